@@ -0,0 +1,17 @@
+fn main() {
+    #[cfg(feature = "remote")]
+    remote::generate();
+}
+
+/// Generates the messages and services of `proto/usig.proto` without needing `protoc`
+#[cfg(feature = "remote")]
+mod remote {
+    pub fn generate() {
+        println!("cargo:rerun-if-changed=proto/usig.proto");
+        let descriptors =
+            protox::compile(["usig.proto"], ["proto"]).expect("proto/usig.proto is valid");
+        tonic_build::configure()
+            .compile_fds(descriptors)
+            .expect("services can be generated");
+    }
+}
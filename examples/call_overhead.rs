@@ -0,0 +1,89 @@
+//! Compares signing and verifying on a bare backend with the same calls through the layers above it
+//!
+//! Usage: `cargo run --release --example call_overhead --features async`
+//!
+//! "bare" calls the concrete halves on the caller's thread. "direct" makes the same calls
+//! through the async traits with [usig::asynchronous::Direct], the fast path selected at
+//! compile time, and "blocking" through [usig::asynchronous::Blocking], which hops to the
+//! blocking thread pool for every call. Without the `async` feature only the bare path
+//! and the opt-in wrappers, e.g. the lock of the counter tracking of a
+//! [usig::monotonic::MonotonicVerifyHalf], are measured.
+
+use std::{hint::black_box, time::Instant};
+
+use hmac::Hmac;
+use sha2::Sha256;
+use usig::{
+    hmac::UsigHmac, revocation::RevocationVerifyHalf, ReplicaId, SignHalf, Usig, VerifyHalf,
+};
+
+type HmacUsig = UsigHmac<Hmac<Sha256>>;
+
+const ITERATIONS: u32 = 200_000;
+const ID: ReplicaId = ReplicaId::first();
+const MESSAGE: &[u8] = b"message";
+
+fn bench<S: SignHalf, V: VerifyHalf<Signature = S::Signature, Attestation = S::Attestation>>(
+    name: &str,
+    mut sign: S,
+    mut verify: V,
+) {
+    assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let signature = sign.sign(black_box(MESSAGE)).unwrap();
+        verify.verify(ID, black_box(MESSAGE), &signature).unwrap();
+    }
+    let per_call = start.elapsed() / ITERATIONS;
+    println!("{:<12}{:>12?}", name, per_call);
+}
+
+#[cfg(feature = "async")]
+fn bench_async<S, V>(name: &str, runtime: &tokio::runtime::Runtime, mut sign: S, mut verify: V)
+where
+    S: usig::asynchronous::AsyncSignHalf,
+    V: usig::asynchronous::AsyncVerifyHalf<Signature = S::Signature, Attestation = S::Attestation>,
+{
+    let per_call = runtime.block_on(async {
+        verify
+            .try_add_remote_party(ID, sign.attest().await.unwrap())
+            .await
+            .unwrap();
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            let signature = sign.sign(black_box(MESSAGE)).await.unwrap();
+            verify
+                .verify(ID, black_box(MESSAGE), &signature)
+                .await
+                .unwrap();
+        }
+        start.elapsed() / ITERATIONS
+    });
+    println!("{:<12}{:>12?}", name, per_call);
+}
+
+fn usig() -> HmacUsig {
+    HmacUsig::try_new(Box::new([7; 16])).unwrap()
+}
+
+fn main() {
+    let (sign, verify) = usig().split();
+    bench("bare", sign, verify);
+
+    #[cfg(feature = "async")]
+    {
+        use usig::asynchronous::{AsyncUsig, Blocking, Direct};
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let (sign, verify) = AsyncUsig::split(Direct(usig()));
+        bench_async("direct", &runtime, sign, verify);
+        let (sign, verify) = AsyncUsig::split(Blocking::new(usig()));
+        bench_async("blocking", &runtime, sign, verify);
+    }
+
+    let (sign, verify) = usig().split();
+    bench("revocation", sign, RevocationVerifyHalf::new(verify));
+
+    let (sign, verify) = usig().split();
+    bench("monotonic", sign, verify.enforce_monotonicity());
+}
@@ -0,0 +1,76 @@
+//! Writes the fuzz corpus of every backend to a directory
+//!
+//! Usage: `cargo run --example fuzz_corpus -- <output directory>`
+
+use std::{env, fs, io, path::Path};
+
+use hmac::Hmac;
+use rand::{rngs::OsRng, RngCore};
+use sha2::Sha256;
+#[cfg(feature = "bls")]
+use usig::bls::new_bls;
+use usig::{
+    cmac::UsigCmacAes128,
+    corpus::{self, Entry},
+    hmac::UsigHmac,
+    noop::UsigNoOp,
+    signature::{new_ed25519, new_ed448, new_p256, new_secp256k1},
+    siphash::UsigSipHash,
+};
+
+fn write(dir: &Path, backend: &str, entries: &[Entry]) -> io::Result<()> {
+    let dir = dir.join(backend);
+    fs::create_dir_all(&dir)?;
+    for entry in entries {
+        fs::write(dir.join(entry.name()), &entry.bytes)?;
+    }
+    println!("{}: {} entries", backend, entries.len());
+    Ok(())
+}
+
+fn main() -> io::Result<()> {
+    let Some(dir) = env::args().nth(1) else {
+        eprintln!("usage: fuzz_corpus <output directory>");
+        std::process::exit(2);
+    };
+    let dir = Path::new(&dir);
+
+    let mut key = [0u8; 16];
+    OsRng.fill_bytes(&mut key);
+
+    write(
+        dir,
+        "noop",
+        &corpus::generate(&mut UsigNoOp::default()).unwrap(),
+    )?;
+    write(
+        dir,
+        "hmac-sha256",
+        &corpus::generate(&mut UsigHmac::<Hmac<Sha256>>::try_new(Box::new(key)).unwrap()).unwrap(),
+    )?;
+    write(
+        dir,
+        "ed25519",
+        &corpus::generate(&mut new_ed25519()).unwrap(),
+    )?;
+    write(
+        dir,
+        "secp256k1",
+        &corpus::generate(&mut new_secp256k1()).unwrap(),
+    )?;
+    write(dir, "p256", &corpus::generate(&mut new_p256()).unwrap())?;
+    write(dir, "ed448", &corpus::generate(&mut new_ed448()).unwrap())?;
+    #[cfg(feature = "bls")]
+    write(dir, "bls", &corpus::generate(&mut new_bls()).unwrap())?;
+    write(
+        dir,
+        "cmac-aes128",
+        &corpus::generate(&mut UsigCmacAes128::try_new(Box::new(key)).unwrap()).unwrap(),
+    )?;
+    write(
+        dir,
+        "siphash",
+        &corpus::generate(&mut UsigSipHash::new(key)).unwrap(),
+    )?;
+    Ok(())
+}
@@ -0,0 +1,114 @@
+//! Compares the verification hot path with keys in a hash map and in a table indexed by replica id
+//!
+//! Usage: `cargo run --release --example party_table`
+//!
+//! Every round pre-validates and verifies one fresh signature of each party, so the lookup
+//! cost is paid just like on the hot path of a replica. With the table the pre-validation
+//! also checks the counter expected next from the party.
+
+use std::time::Instant;
+
+use hmac::Hmac;
+use sha2::Sha256;
+use usig::{
+    directory::{MemoryDirectory, PartyDirectory, TableDirectory},
+    hmac::{UsigHmac, UsigHmacVerifyHalf, VerifyKey},
+    signature::{new_ed25519, UsigSignatureVerifyHalf},
+    siphash::{UsigSipHash, UsigSipHashVerifyHalf, VerifyKey as SipKey},
+    ReplicaId, SignHalf, Usig, VerifyHalf,
+};
+
+type Mac = Hmac<Sha256>;
+type Ed25519VerifyHalf<D> =
+    UsigSignatureVerifyHalf<ed25519_dalek::Signature, ed25519_dalek::VerifyingKey, D>;
+
+const ROUNDS: usize = 200;
+const MESSAGE: &[u8] = b"message";
+
+fn bench<V, S>(
+    backend: &str,
+    layout: &str,
+    parties: u64,
+    mut verify: V,
+    new_sign: impl Fn(u64) -> S,
+) where
+    V: VerifyHalf,
+    S: SignHalf<Signature = V::Signature, Attestation = V::Attestation>,
+{
+    let mut signers: Vec<_> = (0..parties)
+        .map(|id| {
+            let mut sign = new_sign(id);
+            let id = ReplicaId::from_u64(id);
+            assert!(verify.add_remote_party(id, sign.attest().unwrap()));
+            (id, sign)
+        })
+        .collect();
+    let rounds: Vec<Vec<_>> = (0..ROUNDS)
+        .map(|_| {
+            signers
+                .iter_mut()
+                .map(|(id, sign)| (*id, sign.sign(MESSAGE).unwrap()))
+                .collect()
+        })
+        .collect();
+
+    let start = Instant::now();
+    for round in &rounds {
+        for (id, signature) in round {
+            verify.pre_validate(*id, signature).unwrap();
+            verify.verify(*id, MESSAGE, signature).unwrap();
+        }
+    }
+    let per_verify = start.elapsed() / (ROUNDS as u32 * parties as u32);
+    println!("{backend:<10}{layout:<8}{parties:>6} parties{per_verify:>12?}");
+}
+
+fn hmac<D: PartyDirectory<VerifyKey<Mac>>>(layout: &str, parties: u64, directory: D) {
+    bench(
+        "hmac",
+        layout,
+        parties,
+        UsigHmacVerifyHalf::<Mac, D>::with_directory(directory),
+        |id| {
+            UsigHmac::<Mac>::try_new(Box::new([id as u8; 16]))
+                .unwrap()
+                .split()
+                .0
+        },
+    );
+}
+
+fn siphash<D: PartyDirectory<SipKey>>(layout: &str, parties: u64, directory: D) {
+    bench(
+        "siphash",
+        layout,
+        parties,
+        UsigSipHashVerifyHalf::with_directory(directory),
+        |id| UsigSipHash::new([id as u8; 16]).split().0,
+    );
+}
+
+fn ed25519<D: PartyDirectory<ed25519_dalek::VerifyingKey>>(
+    layout: &str,
+    parties: u64,
+    directory: D,
+) {
+    bench(
+        "ed25519",
+        layout,
+        parties,
+        Ed25519VerifyHalf::with_directory(directory),
+        |_| new_ed25519().split().0,
+    );
+}
+
+fn main() {
+    for parties in [4, 16, 64, 128] {
+        hmac("map", parties, MemoryDirectory::default());
+        hmac("table", parties, TableDirectory::default());
+        siphash("map", parties, MemoryDirectory::default());
+        siphash("table", parties, TableDirectory::default());
+        ed25519("map", parties, MemoryDirectory::default());
+        ed25519("table", parties, TableDirectory::default());
+    }
+}
@@ -0,0 +1,48 @@
+//! Verifies a file against its `.usig` sidecar made with an Ed25519 USIG
+//!
+//! Usage: `cargo run --example verify_sidecar -- <attestation file> <file>`
+//!
+//! The attestation file holds the bincode encoded attestation of the signer.
+
+use std::{env, fs, io, process};
+
+use usig::{
+    sidecar::{read_sidecar, verify_file},
+    signature::UsigEd25519,
+    Usig, VerifyHalf,
+};
+
+type Attestation = <UsigEd25519 as Usig>::Attestation;
+type Signature = <UsigEd25519 as Usig>::Signature;
+
+fn main() -> io::Result<()> {
+    let args: Vec<_> = env::args().skip(1).collect();
+    let [attestation, file] = args.as_slice() else {
+        eprintln!("usage: verify_sidecar <attestation file> <file>");
+        process::exit(2);
+    };
+
+    let attestation: Attestation = bincode::deserialize(&fs::read(attestation)?)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let replica = read_sidecar::<Signature>(file)?.replica;
+
+    let (_, mut verifier) = usig::signature::new_ed25519().split();
+    verifier
+        .try_add_remote_party(replica, attestation.clone())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    match verify_file(&verifier, &attestation, file) {
+        Ok(sidecar) => {
+            println!(
+                "valid: replica {} counter {}",
+                sidecar.replica.as_u64(),
+                sidecar.counter
+            );
+            Ok(())
+        }
+        Err(error) => {
+            eprintln!("invalid: {}", error);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use std::fmt;
+
+/// The SHA-2 implementation used by the HMAC backend and pre-hash paths
+///
+/// `sha2` detects CPU features at runtime and dispatches on every compression call.
+/// Building with the `force-software-sha` feature disables the detection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShaBackend {
+    /// Portable implementation
+    Software,
+    /// x86 SHA extensions
+    ShaNi,
+    /// x86 AVX2 vector instructions
+    Avx2,
+}
+
+impl fmt::Display for ShaBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaBackend::Software => write!(f, "software"),
+            ShaBackend::ShaNi => write!(f, "SHA-NI"),
+            ShaBackend::Avx2 => write!(f, "AVX2"),
+        }
+    }
+}
+
+/// Whether hardware acceleration was disabled at build time
+pub const fn force_software() -> bool {
+    cfg!(feature = "force-software-sha")
+}
+
+/// The implementation SHA-256 dispatches to on this CPU
+pub fn sha256_backend() -> ShaBackend {
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        not(feature = "force-software-sha")
+    ))]
+    if is_x86_feature_detected!("sha")
+        && is_x86_feature_detected!("sse2")
+        && is_x86_feature_detected!("ssse3")
+        && is_x86_feature_detected!("sse4.1")
+    {
+        return ShaBackend::ShaNi;
+    }
+    ShaBackend::Software
+}
+
+/// The implementation SHA-512 dispatches to on this CPU
+pub fn sha512_backend() -> ShaBackend {
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        not(feature = "force-software-sha")
+    ))]
+    if is_x86_feature_detected!("avx2") {
+        return ShaBackend::Avx2;
+    }
+    ShaBackend::Software
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detection() {
+        let (sha256, sha512) = (sha256_backend(), sha512_backend());
+        assert!(matches!(sha256, ShaBackend::Software | ShaBackend::ShaNi));
+        assert!(matches!(sha512, ShaBackend::Software | ShaBackend::Avx2));
+        if force_software() {
+            assert_eq!(sha256, ShaBackend::Software);
+            assert_eq!(sha512, ShaBackend::Software);
+        }
+    }
+}
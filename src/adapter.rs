@@ -0,0 +1,249 @@
+use std::sync::Mutex;
+
+#[cfg(feature = "async")]
+use async_signature::AsyncSigner;
+use shared_ids::ReplicaId;
+use signature::{Error, Signer, Verifier};
+
+#[cfg(feature = "async")]
+use crate::asynchronous::{AsyncSignHalf, AsyncVerifyHalf};
+use crate::{PartyId, SignHalf, VerifyHalf};
+
+/// A [SignHalf] usable wherever a [Signer] is expected
+///
+/// Every signature takes the next counter of the sign half. [Signer::try_sign] only borrows
+/// the signer, so the sign half is kept behind a lock. Failures, e.g. an exhausted counter,
+/// are passed on as the source of the [Error].
+///
+/// `async-signature` implements its `AsyncSigner` for every [Signer], so this serves async
+/// callers too, but signs on their thread. Backends waiting on I/O are better wrapped in
+/// an [AsyncUsigSigner] (feature `async`).
+#[derive(Debug)]
+pub struct UsigSigner<S> {
+    inner: Mutex<S>,
+}
+
+impl<S: SignHalf> UsigSigner<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner: Mutex::new(inner),
+        }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner.into_inner().unwrap()
+    }
+}
+
+impl<S> Signer<S::Signature> for UsigSigner<S>
+where
+    S: SignHalf,
+{
+    fn try_sign(&self, message: &[u8]) -> Result<S::Signature, Error> {
+        self.inner
+            .lock()
+            .unwrap()
+            .sign(message)
+            .map_err(Error::from_source)
+    }
+}
+
+/// A [VerifyHalf] usable wherever a [Verifier] is expected, for the signatures of one party
+#[derive(Debug)]
+pub struct UsigVerifier<V, Id = ReplicaId> {
+    inner: V,
+    remote_usig_id: Id,
+}
+
+impl<V: VerifyHalf<Id>, Id: PartyId> UsigVerifier<V, Id> {
+    pub fn new(inner: V, remote_usig_id: Id) -> Self {
+        Self {
+            inner,
+            remote_usig_id,
+        }
+    }
+
+    /// The party whose signatures are verified
+    pub fn remote_usig_id(&self) -> &Id {
+        &self.remote_usig_id
+    }
+
+    pub fn into_inner(self) -> V {
+        self.inner
+    }
+}
+
+impl<V: VerifyHalf<Id>, Id: PartyId> Verifier<V::Signature> for UsigVerifier<V, Id> {
+    fn verify(&self, message: &[u8], signature: &V::Signature) -> Result<(), Error> {
+        self.inner
+            .verify(self.remote_usig_id.clone(), message, signature)
+            .map_err(|_| Error::new())
+    }
+}
+
+/// An [AsyncSignHalf] usable wherever an [AsyncSigner] is expected
+///
+/// Like [UsigSigner], but the sign half is kept behind an async lock, so concurrent callers
+/// wait for their turn without blocking the executor.
+///
+/// [AsyncSigner] is implemented for the signatures of the backends of this crate only,
+/// `async-signature` implementing it for every [Signer] rules out doing so for all of them.
+#[cfg(feature = "async")]
+#[derive(Debug)]
+pub struct AsyncUsigSigner<S> {
+    inner: tokio::sync::Mutex<S>,
+}
+
+#[cfg(feature = "async")]
+impl<S: AsyncSignHalf> AsyncUsigSigner<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner: tokio::sync::Mutex::new(inner),
+        }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner.into_inner()
+    }
+
+    async fn sign(&self, message: &[u8]) -> Result<S::Signature, Error> {
+        self.inner
+            .lock()
+            .await
+            .sign(message)
+            .await
+            .map_err(Error::from_source)
+    }
+}
+
+macro_rules! async_signer {
+    ($signature:ty $(, $param:ident: $bound:path)?) => {
+        #[cfg(feature = "async")]
+        impl<S $(, $param: $bound)?> AsyncSigner<$signature> for AsyncUsigSigner<S>
+        where
+            S: AsyncSignHalf<Signature = $signature>,
+        {
+            async fn sign_async(&self, message: &[u8]) -> Result<$signature, Error> {
+                self.sign(message).await
+            }
+        }
+    };
+}
+
+async_signer!(crate::noop::Signature);
+async_signer!(crate::siphash::Signature);
+async_signer!(crate::hmac::Signature<L>, L: generic_array::ArrayLength<u8>);
+async_signer!(crate::signature::Signature<Q>, Q: crate::signature::SignatureType);
+
+/// An [AsyncVerifyHalf] verifying the signatures of one party
+///
+/// `async-signature` has no counterpart of [Verifier], so [AsyncUsigVerifier::verify_async]
+/// mirrors [Verifier::verify].
+#[cfg(feature = "async")]
+#[derive(Debug)]
+pub struct AsyncUsigVerifier<V, Id = ReplicaId> {
+    inner: V,
+    remote_usig_id: Id,
+}
+
+#[cfg(feature = "async")]
+impl<V: AsyncVerifyHalf<Id>, Id: PartyId> AsyncUsigVerifier<V, Id> {
+    pub fn new(inner: V, remote_usig_id: Id) -> Self {
+        Self {
+            inner,
+            remote_usig_id,
+        }
+    }
+
+    /// The party whose signatures are verified
+    pub fn remote_usig_id(&self) -> &Id {
+        &self.remote_usig_id
+    }
+
+    pub fn into_inner(self) -> V {
+        self.inner
+    }
+
+    pub async fn verify_async(&self, message: &[u8], signature: &V::Signature) -> Result<(), Error>
+    where
+        Id: Send,
+    {
+        self.inner
+            .verify(self.remote_usig_id.clone(), message, signature)
+            .await
+            .map_err(|_| Error::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::Hmac;
+    use sha2::Sha256;
+
+    use super::*;
+    use crate::{hmac::UsigHmac, Count, Counter, Usig};
+
+    const ID: ReplicaId = ReplicaId::first();
+    const MESSAGE: &[u8] = b"message";
+
+    /// Written against the signature crate only
+    fn sign_and_verify<Q>(signer: &impl Signer<Q>, verifier: &impl Verifier<Q>) -> Q {
+        let signature = signer.sign(MESSAGE);
+        assert!(verifier.verify(MESSAGE, &signature).is_ok());
+        assert!(verifier.verify(b"forged", &signature).is_err());
+        signature
+    }
+
+    #[test]
+    fn adapters() {
+        let usig = UsigHmac::<Hmac<Sha256>>::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap();
+        let (mut sign, mut verify) = usig.split();
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+        let signer = UsigSigner::new(sign);
+        let verifier = UsigVerifier::new(verify, ID);
+        assert_eq!(sign_and_verify(&signer, &verifier).counter(), Count(0));
+        assert_eq!(sign_and_verify(&signer, &verifier).counter(), Count(1));
+        assert_eq!(verifier.remote_usig_id(), &ID);
+    }
+
+    #[test]
+    fn party_ids() {
+        use crate::party::PartyVerifyHalf;
+
+        let usig = UsigHmac::<Hmac<Sha256>>::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap();
+        let (mut sign, verify) = usig.split();
+        let mut verify = PartyVerifyHalf::<_, String>::new(verify);
+        let party = "replica-a".to_owned();
+        verify
+            .try_add_remote_party(party.clone(), sign.attest().unwrap())
+            .unwrap();
+        let signer = UsigSigner::new(sign);
+        let verifier = UsigVerifier::new(verify, party.clone());
+        sign_and_verify(&signer, &verifier);
+        assert_eq!(verifier.remote_usig_id(), &party);
+
+        let other = UsigVerifier::new(verifier.into_inner(), "replica-b".to_owned());
+        assert!(other.verify(MESSAGE, &signer.sign(MESSAGE)).is_err());
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn async_adapters() {
+        use crate::asynchronous::{AsyncUsig, Blocking};
+
+        let usig = UsigHmac::<Hmac<Sha256>>::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap();
+        let (mut sign, mut verify) = AsyncUsig::split(Blocking::new(usig));
+        let attestation = AsyncSignHalf::attest(&mut sign).await.unwrap();
+        verify.try_add_remote_party(ID, attestation).await.unwrap();
+        let signer = AsyncUsigSigner::new(sign);
+        let verifier = AsyncUsigVerifier::new(verify, ID);
+
+        for counter in 0..2 {
+            let signature = signer.sign_async(MESSAGE).await.unwrap();
+            assert_eq!(signature.counter(), Count(counter));
+            assert!(verifier.verify_async(MESSAGE, &signature).await.is_ok());
+            assert!(verifier.verify_async(b"forged", &signature).await.is_err());
+        }
+        assert_eq!(verifier.remote_usig_id(), &ID);
+    }
+}
@@ -0,0 +1,189 @@
+use std::{
+    num::NonZeroUsize,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use shared_ids::ReplicaId;
+
+use crate::{checked::AttestationVerifier, UsigError, VerifyHalf};
+
+/// The outcome of [admit_all]
+#[derive(Debug, Default)]
+pub struct AdmissionReport {
+    /// The parties that were added, in the order they were passed in
+    pub admitted: Vec<ReplicaId>,
+    /// The parties that were rejected with the reason, in the order they were passed in
+    pub rejected: Vec<(ReplicaId, UsigError)>,
+}
+
+impl AdmissionReport {
+    pub fn all_admitted(&self) -> bool {
+        self.rejected.is_empty()
+    }
+}
+
+/// Admit many parties at once, e.g. on cluster bootstrap or reconfiguration
+///
+/// The attestations are checked against `policy` on up to `concurrency` threads, the valid
+/// ones are then added to `verify_half` one after another. A rejected party does not stop
+/// the others.
+pub fn admit_all<V, P>(
+    verify_half: &mut V,
+    policy: &P,
+    parties: Vec<(ReplicaId, V::Attestation)>,
+    concurrency: NonZeroUsize,
+) -> AdmissionReport
+where
+    V: VerifyHalf,
+    V::Attestation: Sync,
+    P: AttestationVerifier<V::Attestation> + Sync,
+{
+    let results = validate_all(policy, &parties, concurrency);
+    let mut report = AdmissionReport::default();
+    for ((id, attestation), result) in parties.into_iter().zip(results) {
+        match result.and_then(|()| verify_half.try_add_remote_party(id, attestation)) {
+            Ok(()) => report.admitted.push(id),
+            Err(e) => report.rejected.push((id, e)),
+        }
+    }
+    report
+}
+
+fn validate_all<A: Sync>(
+    policy: &(impl AttestationVerifier<A> + Sync),
+    parties: &[(ReplicaId, A)],
+    concurrency: NonZeroUsize,
+) -> Vec<Result<(), UsigError>> {
+    let workers = concurrency.get().min(parties.len());
+    // wasm32-unknown-unknown can not spawn threads
+    if workers <= 1 || cfg!(all(target_arch = "wasm32", target_os = "unknown")) {
+        return parties
+            .iter()
+            .map(|(id, attestation)| policy.verify_attestation(*id, attestation))
+            .collect();
+    }
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<_> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some((id, attestation)) = parties.get(index) else {
+                            return results;
+                        };
+                        results.push((index, policy.verify_attestation(*id, attestation)));
+                    }
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("attestation validation panicked"))
+            .collect()
+    });
+    results.sort_unstable_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        checked::AcceptAll,
+        signature::{new_ed25519, UsigEd25519},
+        SignHalf, Usig,
+    };
+
+    fn parties(n: u64) -> Vec<(ReplicaId, <UsigEd25519 as Usig>::Attestation)> {
+        (0..n)
+            .map(|i| (ReplicaId::from_u64(i), new_ed25519().attest().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn admit() {
+        let (_, mut verify) = new_ed25519().split();
+        let reject_odd = |id: ReplicaId, _: &_| {
+            if id.as_u64().is_multiple_of(2) {
+                Ok(())
+            } else {
+                Err(UsigError::RemoteAttestationFailed)
+            }
+        };
+        let report = admit_all(
+            &mut verify,
+            &reject_odd,
+            parties(10),
+            NonZeroUsize::new(3).unwrap(),
+        );
+        assert!(!report.all_admitted());
+        assert_eq!(
+            report.admitted,
+            (0..10)
+                .step_by(2)
+                .map(ReplicaId::from_u64)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(report.rejected.len(), 5);
+        assert!(report
+            .rejected
+            .iter()
+            .all(|(_, e)| matches!(e, UsigError::RemoteAttestationFailed)));
+
+        let (mut sign, _) = new_ed25519().split();
+        assert!(verify
+            .pre_validate(ReplicaId::from_u64(4), &sign.sign(b"message").unwrap())
+            .is_ok());
+        assert!(matches!(
+            verify.pre_validate(ReplicaId::from_u64(5), &sign.sign(b"message").unwrap()),
+            Err(UsigError::UnknownId(_))
+        ));
+    }
+
+    #[test]
+    fn bounded_concurrency() {
+        let running = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let slow = |_: ReplicaId, _: &_| {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(5));
+            running.fetch_sub(1, Ordering::SeqCst);
+            Ok(())
+        };
+        let (_, mut verify) = new_ed25519().split();
+        let report = admit_all(
+            &mut verify,
+            &slow,
+            parties(20),
+            NonZeroUsize::new(4).unwrap(),
+        );
+        assert!(report.all_admitted());
+        assert_eq!(report.admitted.len(), 20);
+        assert!(peak.load(Ordering::SeqCst) <= 4);
+    }
+
+    #[test]
+    fn invalid_attestation() {
+        let (_, mut verify) = new_ed25519().split();
+        let mut parties = parties(2);
+        // an attestation relabeled for another id
+        parties[1].1.signer = Some(ReplicaId::from_u64(7));
+        let report = admit_all(
+            &mut verify,
+            &AcceptAll,
+            parties,
+            NonZeroUsize::new(2).unwrap(),
+        );
+        assert_eq!(report.admitted, vec![ReplicaId::from_u64(0)]);
+        assert!(matches!(
+            report.rejected[..],
+            [(_, UsigError::RemoteAttestationFailed)]
+        ));
+    }
+}
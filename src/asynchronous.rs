@@ -0,0 +1,849 @@
+use std::{
+    collections::BTreeMap,
+    future::{ready, Future},
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant},
+};
+
+use shared_ids::ReplicaId;
+
+use crate::{Counter, PartyId, SignHalf, Usig, UsigError, VerifyHalf};
+
+/// [Usig] for backends that wait on I/O, e.g. an HSM, a KMS or a remote daemon
+///
+/// The futures are [Send], so they can be awaited on a multi-threaded executor without
+/// blocking it. Wrap a [Usig] in [Blocking] to use it as an [AsyncUsig], or in [Direct]
+/// if it neither blocks nor does I/O.
+pub trait AsyncUsig<Id: PartyId = ReplicaId>: Send {
+    type Signature: Counter + Send + Sync;
+    type Attestation: Send;
+
+    fn sign(
+        &mut self,
+        message: impl AsRef<[u8]> + Send,
+    ) -> impl Future<Output = Result<Self::Signature, UsigError>> + Send;
+
+    fn attest(&mut self) -> impl Future<Output = Result<Self::Attestation, UsigError>> + Send;
+
+    fn verify(
+        &self,
+        remote_usig_id: Id,
+        message: impl AsRef<[u8]> + Send,
+        signature: &Self::Signature,
+    ) -> impl Future<Output = Result<(), UsigError>> + Send;
+
+    fn pre_validate(
+        &self,
+        remote_usig_id: Id,
+        signature: &Self::Signature,
+    ) -> impl Future<Output = Result<(), UsigError>> + Send;
+
+    fn try_add_remote_party(
+        &mut self,
+        remote_usig_id: Id,
+        attestation: Self::Attestation,
+    ) -> impl Future<Output = Result<(), UsigError>> + Send;
+
+    fn remove_remote_party(
+        &mut self,
+        remote_usig_id: Id,
+    ) -> impl Future<Output = Result<bool, UsigError>> + Send;
+
+    fn remote_parties(&self) -> impl Future<Output = Result<Vec<Id>, UsigError>> + Send;
+
+    fn contains(&self, remote_usig_id: Id) -> impl Future<Output = Result<bool, UsigError>> + Send;
+
+    type SignHalf: AsyncSignHalf<Id, Signature = Self::Signature, Attestation = Self::Attestation>;
+    type VerifyHalf: AsyncVerifyHalf<
+        Id,
+        Signature = Self::Signature,
+        Attestation = Self::Attestation,
+    >;
+
+    fn split(self) -> (Self::SignHalf, Self::VerifyHalf);
+}
+
+/// [SignHalf] for backends that wait on I/O, see [AsyncUsig]
+pub trait AsyncSignHalf<Id: PartyId = ReplicaId>: Send {
+    type Signature: Counter + Send + Sync;
+    type Attestation: Send;
+
+    fn sign(
+        &mut self,
+        message: impl AsRef<[u8]> + Send,
+    ) -> impl Future<Output = Result<Self::Signature, UsigError>> + Send;
+
+    fn attest(&mut self) -> impl Future<Output = Result<Self::Attestation, UsigError>> + Send;
+
+    fn local_id(&self) -> impl Future<Output = Result<Option<Id>, UsigError>> + Send;
+}
+
+/// [VerifyHalf] for backends that wait on I/O, see [AsyncUsig]
+pub trait AsyncVerifyHalf<Id: PartyId = ReplicaId>: Send + Sync {
+    type Signature: Counter + Send + Sync;
+    type Attestation: Send;
+
+    fn verify(
+        &self,
+        remote_usig_id: Id,
+        message: impl AsRef<[u8]> + Send,
+        signature: &Self::Signature,
+    ) -> impl Future<Output = Result<(), UsigError>> + Send;
+
+    fn pre_validate(
+        &self,
+        remote_usig_id: Id,
+        signature: &Self::Signature,
+    ) -> impl Future<Output = Result<(), UsigError>> + Send;
+
+    fn try_add_remote_party(
+        &mut self,
+        remote_usig_id: Id,
+        attestation: Self::Attestation,
+    ) -> impl Future<Output = Result<(), UsigError>> + Send;
+
+    fn remove_remote_party(
+        &mut self,
+        remote_usig_id: Id,
+    ) -> impl Future<Output = Result<bool, UsigError>> + Send;
+
+    fn remote_parties(&self) -> impl Future<Output = Result<Vec<Id>, UsigError>> + Send;
+
+    fn contains(&self, remote_usig_id: Id) -> impl Future<Output = Result<bool, UsigError>> + Send;
+}
+
+/// The calls of a [Blocking] that did not complete yet
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueStats {
+    /// Calls waiting for the blocking thread pool or running on it
+    pub pending: usize,
+    /// How long the oldest pending call is waiting, zero if there is none
+    pub oldest_pending: Duration,
+}
+
+#[derive(Debug, Default)]
+struct Queue {
+    next: u64,
+    /// The start of every pending call, by the order they were made in
+    pending: BTreeMap<u64, Instant>,
+}
+
+/// A call of a [Blocking] in its [Queue], leaving it when the call completes or is dropped
+struct Pending {
+    queue: Arc<Mutex<Queue>>,
+    call: u64,
+}
+
+impl Pending {
+    fn enqueue(queue: &Arc<Mutex<Queue>>) -> Self {
+        let mut locked = queue.lock().unwrap();
+        let call = locked.next;
+        locked.next += 1;
+        locked.pending.insert(call, Instant::now());
+        Self {
+            queue: Arc::clone(queue),
+            call,
+        }
+    }
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        self.queue.lock().unwrap().pending.remove(&self.call);
+    }
+}
+
+/// Spawn `call` on the blocking thread pool, as a task named `name` for tokio-console
+fn spawn_blocking<R: Send + 'static>(
+    name: &'static str,
+    call: impl FnOnce() -> R + Send + 'static,
+) -> Result<tokio::task::JoinHandle<R>, UsigError> {
+    #[cfg(all(feature = "console", tokio_unstable))]
+    return tokio::task::Builder::new()
+        .name(name)
+        .spawn_blocking(call)
+        .map_err(UsigError::Io);
+    #[cfg(not(all(feature = "console", tokio_unstable)))]
+    {
+        let _ = name;
+        Ok(tokio::task::spawn_blocking(call))
+    }
+}
+
+/// Runs a [Usig], [SignHalf] or [VerifyHalf] on the blocking thread pool of tokio
+///
+/// Every call is moved to [tokio::task::spawn_blocking], so a backend blocking on I/O does
+/// not stall the executor. Messages and signatures are copied for that. Calls taking
+/// `&mut self` are serialized, the others, e.g. verifies, share the wrapped value and run
+/// concurrently. Must be awaited within a tokio runtime.
+///
+/// [Blocking::queue] reports the calls piling up in front of a stalled backend. Built with
+/// the `console` feature and `--cfg tokio_unstable`, every call is a task named after the
+/// operation, e.g. `usig sign`, in tokio-console.
+#[derive(Debug)]
+pub struct Blocking<T> {
+    /// Taken by [AsyncUsig::split], calls cancelled before they ran find it empty
+    inner: Arc<RwLock<Option<T>>>,
+    queue: Arc<Mutex<Queue>>,
+}
+
+impl<T: Send + Sync + 'static> Blocking<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(Some(inner))),
+            queue: Arc::default(),
+        }
+    }
+
+    /// Wait for running calls and return the wrapped value
+    pub fn into_inner(self) -> T {
+        self.inner
+            .write()
+            .unwrap()
+            .take()
+            .expect("only taken when consumed")
+    }
+
+    /// The depth of the queue of calls and the age of the oldest one
+    pub fn queue(&self) -> QueueStats {
+        let queue = self.queue.lock().unwrap();
+        QueueStats {
+            pending: queue.pending.len(),
+            oldest_pending: queue
+                .pending
+                .values()
+                .next()
+                .map(Instant::elapsed)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Run `call` with shared access, concurrently with other shared calls
+    async fn run<R: Send + 'static>(
+        &self,
+        name: &'static str,
+        call: impl FnOnce(&T) -> R + Send + 'static,
+    ) -> Result<R, UsigError> {
+        let inner = Arc::clone(&self.inner);
+        self.spawn(name, move || inner.read().unwrap().as_ref().map(call))
+            .await
+    }
+
+    /// Run `call` with exclusive access
+    async fn run_mut<R: Send + 'static>(
+        &mut self,
+        name: &'static str,
+        call: impl FnOnce(&mut T) -> R + Send + 'static,
+    ) -> Result<R, UsigError> {
+        let inner = Arc::clone(&self.inner);
+        self.spawn(name, move || inner.write().unwrap().as_mut().map(call))
+            .await
+    }
+
+    async fn spawn<R: Send + 'static>(
+        &self,
+        name: &'static str,
+        call: impl FnOnce() -> Option<R> + Send + 'static,
+    ) -> Result<R, UsigError> {
+        let pending = Pending::enqueue(&self.queue);
+        spawn_blocking(name, move || {
+            let result = call();
+            drop(pending);
+            result
+        })?
+        .await
+        .map_err(|e| UsigError::Backend(Box::new(e)))?
+        .ok_or(UsigError::Inactive)
+    }
+}
+
+impl<U, Id> AsyncUsig<Id> for Blocking<U>
+where
+    U: Usig<Id> + Send + Sync + 'static,
+    U::Signature: Clone + Send + Sync + 'static,
+    U::Attestation: Send + 'static,
+    U::SignHalf: Send + Sync + 'static,
+    U::VerifyHalf: Send + Sync + 'static,
+    Id: PartyId + Send + Sync + 'static,
+{
+    type Signature = U::Signature;
+    type Attestation = U::Attestation;
+
+    async fn sign(
+        &mut self,
+        message: impl AsRef<[u8]> + Send,
+    ) -> Result<Self::Signature, UsigError> {
+        let message = message.as_ref().to_vec();
+        self.run_mut("usig sign", move |usig| usig.sign(message))
+            .await?
+    }
+
+    async fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        self.run_mut("usig attest", |usig| usig.attest()).await?
+    }
+
+    async fn verify(
+        &self,
+        id: Id,
+        message: impl AsRef<[u8]> + Send,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        let message = message.as_ref().to_vec();
+        let signature = signature.clone();
+        self.run("usig verify", move |usig| {
+            usig.verify(id, message, &signature)
+        })
+        .await?
+    }
+
+    async fn pre_validate(&self, id: Id, signature: &Self::Signature) -> Result<(), UsigError> {
+        let signature = signature.clone();
+        self.run("usig pre_validate", move |usig| {
+            usig.pre_validate(id, &signature)
+        })
+        .await?
+    }
+
+    async fn try_add_remote_party(
+        &mut self,
+        id: Id,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        self.run_mut("usig add_remote_party", move |usig| {
+            usig.try_add_remote_party(id, attestation)
+        })
+        .await?
+    }
+
+    async fn remove_remote_party(&mut self, id: Id) -> Result<bool, UsigError> {
+        self.run_mut("usig remove_remote_party", move |usig| {
+            usig.remove_remote_party(id)
+        })
+        .await
+    }
+
+    async fn remote_parties(&self) -> Result<Vec<Id>, UsigError> {
+        self.run("usig remote_parties", |usig| {
+            usig.remote_parties().collect()
+        })
+        .await
+    }
+
+    async fn contains(&self, id: Id) -> Result<bool, UsigError> {
+        self.run("usig contains", move |usig| usig.contains(id))
+            .await
+    }
+
+    type SignHalf = Blocking<U::SignHalf>;
+    type VerifyHalf = Blocking<U::VerifyHalf>;
+
+    fn split(self) -> (Self::SignHalf, Self::VerifyHalf) {
+        let (sign_half, verify_half) = self.into_inner().split();
+        (Blocking::new(sign_half), Blocking::new(verify_half))
+    }
+}
+
+impl<S, Id> AsyncSignHalf<Id> for Blocking<S>
+where
+    S: SignHalf<Id> + Send + Sync + 'static,
+    S::Signature: Send + Sync + 'static,
+    S::Attestation: Send + 'static,
+    Id: PartyId + Send + Sync + 'static,
+{
+    type Signature = S::Signature;
+    type Attestation = S::Attestation;
+
+    async fn sign(
+        &mut self,
+        message: impl AsRef<[u8]> + Send,
+    ) -> Result<Self::Signature, UsigError> {
+        let message = message.as_ref().to_vec();
+        self.run_mut("usig sign", move |sign_half| sign_half.sign(message))
+            .await?
+    }
+
+    async fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        self.run_mut("usig attest", |sign_half| sign_half.attest())
+            .await?
+    }
+
+    async fn local_id(&self) -> Result<Option<Id>, UsigError> {
+        self.run("usig local_id", |sign_half| sign_half.local_id())
+            .await
+    }
+}
+
+impl<V, Id> AsyncVerifyHalf<Id> for Blocking<V>
+where
+    V: VerifyHalf<Id> + Send + Sync + 'static,
+    V::Signature: Clone + Send + Sync + 'static,
+    V::Attestation: Send + 'static,
+    Id: PartyId + Send + Sync + 'static,
+{
+    type Signature = V::Signature;
+    type Attestation = V::Attestation;
+
+    async fn verify(
+        &self,
+        id: Id,
+        message: impl AsRef<[u8]> + Send,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        let message = message.as_ref().to_vec();
+        let signature = signature.clone();
+        self.run("usig verify", move |verify_half| {
+            verify_half.verify(id, message, &signature)
+        })
+        .await?
+    }
+
+    async fn pre_validate(&self, id: Id, signature: &Self::Signature) -> Result<(), UsigError> {
+        let signature = signature.clone();
+        self.run("usig pre_validate", move |verify_half| {
+            verify_half.pre_validate(id, &signature)
+        })
+        .await?
+    }
+
+    async fn try_add_remote_party(
+        &mut self,
+        id: Id,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        self.run_mut("usig add_remote_party", move |verify_half| {
+            verify_half.try_add_remote_party(id, attestation)
+        })
+        .await?
+    }
+
+    async fn remove_remote_party(&mut self, id: Id) -> Result<bool, UsigError> {
+        self.run_mut("usig remove_remote_party", move |verify_half| {
+            verify_half.remove_remote_party(id)
+        })
+        .await
+    }
+
+    async fn remote_parties(&self) -> Result<Vec<Id>, UsigError> {
+        self.run("usig remote_parties", |verify_half| {
+            verify_half.remote_parties().collect()
+        })
+        .await
+    }
+
+    async fn contains(&self, id: Id) -> Result<bool, UsigError> {
+        self.run("usig contains", move |verify_half| verify_half.contains(id))
+            .await
+    }
+}
+
+/// Runs a [Usig], [SignHalf] or [VerifyHalf] inline on the task awaiting it
+///
+/// The fast path for in-memory backends like the MACs: there is no thread hop, no channel
+/// and nothing is copied, the futures are ready as soon as they are created. Choosing
+/// between [Direct] and [Blocking] is a choice of type, so it is made at compile time.
+/// A backend blocking on I/O stalls the executor, use [Blocking] for those.
+#[derive(Debug, Clone, Default)]
+pub struct Direct<T>(pub T);
+
+impl<T> Direct<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<U, Id> AsyncUsig<Id> for Direct<U>
+where
+    U: Usig<Id> + Send,
+    U::Signature: Send + Sync,
+    U::Attestation: Send,
+    U::SignHalf: Send,
+    U::VerifyHalf: Send + Sync,
+    Id: PartyId + Send,
+{
+    type Signature = U::Signature;
+    type Attestation = U::Attestation;
+
+    fn sign(
+        &mut self,
+        message: impl AsRef<[u8]> + Send,
+    ) -> impl Future<Output = Result<Self::Signature, UsigError>> + Send {
+        ready(self.0.sign(message))
+    }
+
+    fn attest(&mut self) -> impl Future<Output = Result<Self::Attestation, UsigError>> + Send {
+        ready(self.0.attest())
+    }
+
+    fn verify(
+        &self,
+        id: Id,
+        message: impl AsRef<[u8]> + Send,
+        signature: &Self::Signature,
+    ) -> impl Future<Output = Result<(), UsigError>> + Send {
+        ready(self.0.verify(id, message, signature))
+    }
+
+    fn pre_validate(
+        &self,
+        id: Id,
+        signature: &Self::Signature,
+    ) -> impl Future<Output = Result<(), UsigError>> + Send {
+        ready(self.0.pre_validate(id, signature))
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        id: Id,
+        attestation: Self::Attestation,
+    ) -> impl Future<Output = Result<(), UsigError>> + Send {
+        ready(self.0.try_add_remote_party(id, attestation))
+    }
+
+    fn remove_remote_party(
+        &mut self,
+        id: Id,
+    ) -> impl Future<Output = Result<bool, UsigError>> + Send {
+        ready(Ok(self.0.remove_remote_party(id)))
+    }
+
+    fn remote_parties(&self) -> impl Future<Output = Result<Vec<Id>, UsigError>> + Send {
+        ready(Ok(self.0.remote_parties().collect()))
+    }
+
+    fn contains(&self, id: Id) -> impl Future<Output = Result<bool, UsigError>> + Send {
+        ready(Ok(self.0.contains(id)))
+    }
+
+    type SignHalf = Direct<U::SignHalf>;
+    type VerifyHalf = Direct<U::VerifyHalf>;
+
+    fn split(self) -> (Self::SignHalf, Self::VerifyHalf) {
+        let (sign_half, verify_half) = self.0.split();
+        (Direct(sign_half), Direct(verify_half))
+    }
+}
+
+impl<S, Id> AsyncSignHalf<Id> for Direct<S>
+where
+    S: SignHalf<Id> + Send,
+    S::Signature: Send + Sync,
+    S::Attestation: Send,
+    Id: PartyId + Send,
+{
+    type Signature = S::Signature;
+    type Attestation = S::Attestation;
+
+    fn sign(
+        &mut self,
+        message: impl AsRef<[u8]> + Send,
+    ) -> impl Future<Output = Result<Self::Signature, UsigError>> + Send {
+        ready(self.0.sign(message))
+    }
+
+    fn attest(&mut self) -> impl Future<Output = Result<Self::Attestation, UsigError>> + Send {
+        ready(self.0.attest())
+    }
+
+    fn local_id(&self) -> impl Future<Output = Result<Option<Id>, UsigError>> + Send {
+        ready(Ok(self.0.local_id()))
+    }
+}
+
+impl<V, Id> AsyncVerifyHalf<Id> for Direct<V>
+where
+    V: VerifyHalf<Id> + Send + Sync,
+    V::Signature: Send + Sync,
+    V::Attestation: Send,
+    Id: PartyId + Send,
+{
+    type Signature = V::Signature;
+    type Attestation = V::Attestation;
+
+    fn verify(
+        &self,
+        id: Id,
+        message: impl AsRef<[u8]> + Send,
+        signature: &Self::Signature,
+    ) -> impl Future<Output = Result<(), UsigError>> + Send {
+        ready(self.0.verify(id, message, signature))
+    }
+
+    fn pre_validate(
+        &self,
+        id: Id,
+        signature: &Self::Signature,
+    ) -> impl Future<Output = Result<(), UsigError>> + Send {
+        ready(self.0.pre_validate(id, signature))
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        id: Id,
+        attestation: Self::Attestation,
+    ) -> impl Future<Output = Result<(), UsigError>> + Send {
+        ready(self.0.try_add_remote_party(id, attestation))
+    }
+
+    fn remove_remote_party(
+        &mut self,
+        id: Id,
+    ) -> impl Future<Output = Result<bool, UsigError>> + Send {
+        ready(Ok(self.0.remove_remote_party(id)))
+    }
+
+    fn remote_parties(&self) -> impl Future<Output = Result<Vec<Id>, UsigError>> + Send {
+        ready(Ok(self.0.remote_parties().collect()))
+    }
+
+    fn contains(&self, id: Id) -> impl Future<Output = Result<bool, UsigError>> + Send {
+        ready(Ok(self.0.contains(id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::Hmac;
+    use sha2::Sha256;
+
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    };
+
+    use super::*;
+    use crate::{
+        hmac::UsigHmac,
+        noop::{UsigNoOp, UsigNoOpSignHalf, UsigNoOpVerifyHalf},
+        party::PartyVerifyHalf,
+    };
+
+    const ID: ReplicaId = ReplicaId::first();
+    const MESSAGE: &[u8] = b"message";
+
+    fn new_usig() -> UsigHmac<Hmac<Sha256>> {
+        UsigHmac::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap()
+    }
+
+    /// Written against the async traits only, like a consensus protocol on an executor
+    async fn round_trip<
+        Id: PartyId,
+        S: AsyncSignHalf<Id>,
+        V: AsyncVerifyHalf<Id, Signature = S::Signature, Attestation = S::Attestation>,
+    >(
+        id: Id,
+        sign: &mut S,
+        verify: &mut V,
+    ) -> Result<S::Signature, UsigError> {
+        verify
+            .try_add_remote_party(id.clone(), sign.attest().await?)
+            .await?;
+        let signature = sign.sign(MESSAGE).await?;
+        verify.pre_validate(id.clone(), &signature).await?;
+        verify.verify(id, MESSAGE, &signature).await?;
+        Ok(signature)
+    }
+
+    #[tokio::test]
+    async fn blocking_halves() {
+        let (mut sign, mut verify) = AsyncUsig::split(Blocking::new(new_usig()));
+        let signature = round_trip(ID, &mut sign, &mut verify).await.unwrap();
+        assert!(matches!(
+            verify.verify(ID, b"forged", &signature).await,
+            Err(UsigError::InvalidSignature)
+        ));
+        assert_eq!(verify.remote_parties().await.unwrap(), vec![ID]);
+        assert!(verify.remove_remote_party(ID).await.unwrap());
+        assert!(!verify.contains(ID).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn direct_halves() {
+        let (mut sign, mut verify) = AsyncUsig::split(Direct(new_usig()));
+        let signature = round_trip(ID, &mut sign, &mut verify).await.unwrap();
+        assert!(matches!(
+            verify.verify(ID, b"forged", &signature).await,
+            Err(UsigError::InvalidSignature)
+        ));
+        assert_eq!(verify.remote_parties().await.unwrap(), vec![ID]);
+        assert!(verify.remove_remote_party(ID).await.unwrap());
+        assert!(!verify.contains(ID).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn blocking_usig() {
+        let mut usig = Blocking::new(UsigNoOp::default());
+        usig.try_add_remote_party(ID, ()).await.unwrap();
+        let signature = usig.sign(MESSAGE).await.unwrap();
+        assert!(usig.pre_validate(ID, &signature).await.is_ok());
+        assert!(usig.verify(ID, MESSAGE, &signature).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn party_ids() {
+        let (sign, verify) = new_usig().split();
+        let mut sign = Blocking::new(sign);
+        let mut verify = Blocking::new(PartyVerifyHalf::<_, String>::new(verify));
+        let party = "replica-a".to_owned();
+        verify
+            .try_add_remote_party(
+                party.clone(),
+                AsyncSignHalf::attest(&mut sign).await.unwrap(),
+            )
+            .await
+            .unwrap();
+        let signature = AsyncSignHalf::sign(&mut sign, MESSAGE).await.unwrap();
+        assert!(verify.verify(party, MESSAGE, &signature).await.is_ok());
+        assert!(matches!(
+            verify
+                .pre_validate("replica-b".to_owned(), &signature)
+                .await,
+            Err(UsigError::UnknownParty(_))
+        ));
+    }
+
+    /// A sign half whose [SignHalf::local_id] waits for a go
+    struct Stalled {
+        inner: UsigNoOpSignHalf,
+        go: Mutex<mpsc::Receiver<()>>,
+    }
+
+    impl SignHalf for Stalled {
+        type Signature = <UsigNoOpSignHalf as SignHalf>::Signature;
+        type Attestation = <UsigNoOpSignHalf as SignHalf>::Attestation;
+
+        fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+            self.inner.sign(message)
+        }
+
+        fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+            self.inner.attest()
+        }
+
+        fn local_id(&self) -> Option<ReplicaId> {
+            self.go.lock().unwrap().recv().unwrap();
+            None
+        }
+    }
+
+    #[tokio::test]
+    async fn queue() {
+        let (go, receiver) = mpsc::channel();
+        let sign = Arc::new(Blocking::new(Stalled {
+            inner: UsigNoOp::default().split().0,
+            go: Mutex::new(receiver),
+        }));
+        assert_eq!(sign.queue(), QueueStats::default());
+
+        let calls: Vec<_> = (0..2)
+            .map(|_| {
+                let sign = Arc::clone(&sign);
+                tokio::spawn(async move { AsyncSignHalf::<ReplicaId>::local_id(&*sign).await })
+            })
+            .collect();
+        while sign.queue().pending < 2 {
+            tokio::task::yield_now().await;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+        assert!(sign.queue().oldest_pending >= Duration::from_millis(10));
+
+        go.send(()).unwrap();
+        go.send(()).unwrap();
+        for call in calls {
+            assert_eq!(call.await.unwrap().unwrap(), None);
+        }
+        assert_eq!(sign.queue(), QueueStats::default());
+    }
+
+    /// A verify half whose [VerifyHalf::verify] waits for a second verify running alongside,
+    /// and whose [VerifyHalf::contains] panics
+    struct Rendezvous {
+        inner: UsigNoOpVerifyHalf,
+        verifying: AtomicUsize,
+    }
+
+    impl VerifyHalf for Rendezvous {
+        type Signature = <UsigNoOpVerifyHalf as VerifyHalf>::Signature;
+        type Attestation = <UsigNoOpVerifyHalf as VerifyHalf>::Attestation;
+
+        fn verify(
+            &self,
+            id: ReplicaId,
+            message: impl AsRef<[u8]>,
+            signature: &Self::Signature,
+        ) -> Result<(), UsigError> {
+            self.verifying.fetch_add(1, Ordering::SeqCst);
+            let start = Instant::now();
+            while self.verifying.load(Ordering::SeqCst) < 2 {
+                if start.elapsed() > Duration::from_secs(10) {
+                    return Err(UsigError::Inactive);
+                }
+                std::thread::yield_now();
+            }
+            self.inner.verify(id, message, signature)
+        }
+
+        fn pre_validate(
+            &self,
+            id: ReplicaId,
+            signature: &Self::Signature,
+        ) -> Result<(), UsigError> {
+            self.inner.pre_validate(id, signature)
+        }
+
+        fn try_add_remote_party(
+            &mut self,
+            id: ReplicaId,
+            attestation: Self::Attestation,
+        ) -> Result<(), UsigError> {
+            self.inner.try_add_remote_party(id, attestation)
+        }
+
+        fn remove_remote_party(&mut self, id: ReplicaId) -> bool {
+            self.inner.remove_remote_party(id)
+        }
+
+        fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+            self.inner.remote_parties()
+        }
+
+        fn contains(&self, _id: ReplicaId) -> bool {
+            panic!("contains failed")
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrent_verifies() {
+        let (mut sign, verify) = UsigNoOp::default().split();
+        let mut verify = Blocking::new(Rendezvous {
+            inner: verify,
+            verifying: AtomicUsize::new(0),
+        });
+        AsyncVerifyHalf::try_add_remote_party(&mut verify, ID, ())
+            .await
+            .unwrap();
+        let signatures = [sign.sign(MESSAGE).unwrap(), sign.sign(MESSAGE).unwrap()];
+        let (first, second) = tokio::join!(
+            verify.verify(ID, MESSAGE, &signatures[0]),
+            verify.verify(ID, MESSAGE, &signatures[1])
+        );
+        assert!(first.is_ok());
+        assert!(second.is_ok());
+    }
+
+    #[tokio::test]
+    async fn join_error() {
+        let verify = Blocking::new(Rendezvous {
+            inner: UsigNoOp::default().split().1,
+            verifying: AtomicUsize::new(0),
+        });
+        assert!(matches!(
+            AsyncVerifyHalf::contains(&verify, ID).await,
+            Err(UsigError::Backend(_))
+        ));
+        // a panicking shared call leaves the verify half usable
+        assert!(AsyncVerifyHalf::remote_parties(&verify)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,291 @@
+use std::any::Any;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use shared_ids::ReplicaId;
+
+use crate::{
+    encoding::with_context, Count, Counter, MemoryReport, SignHalf, UsigError, VerifyHalf,
+};
+
+/// Context under which an [AuditedSignHalf] signs message digests
+pub const AUDIT_CONTEXT: &[u8] = b"usig audited";
+
+/// SHA-256 of a signed message
+pub type MessageDigest = [u8; 32];
+
+fn audited_message(digest: &MessageDigest) -> Vec<u8> {
+    with_context(AUDIT_CONTEXT, digest)
+}
+
+/// One signature issued by an [AuditedSignHalf]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry<S> {
+    pub digest: MessageDigest,
+    pub signature: S,
+}
+
+/// A sign half that signs the digest of each message and logs every signature
+///
+/// Because only digests are signed, the log alone is enough to re-validate the
+/// whole history of the replica with [replay]. Its signatures are verified with
+/// an [AuditedVerifyHalf].
+#[derive(Debug)]
+pub struct AuditedSignHalf<S: SignHalf> {
+    inner: S,
+    log: Vec<AuditEntry<S::Signature>>,
+}
+
+impl<S: SignHalf> AuditedSignHalf<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            log: Vec::new(),
+        }
+    }
+
+    /// Take all entries logged since the last call
+    pub fn take_log(&mut self) -> Vec<AuditEntry<S::Signature>> {
+        std::mem::take(&mut self.log)
+    }
+}
+
+impl<S: SignHalf> SignHalf for AuditedSignHalf<S>
+where
+    S::Signature: Clone,
+{
+    type Signature = S::Signature;
+    type Attestation = S::Attestation;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        let digest = Sha256::digest(message).into();
+        let signature = self.inner.sign(audited_message(&digest))?;
+        self.log.push(AuditEntry {
+            digest,
+            signature: signature.clone(),
+        });
+        Ok(signature)
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        self.inner.attest()
+    }
+
+    fn local_id(&self) -> Option<ReplicaId> {
+        self.inner.local_id()
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any()
+    }
+
+    /// `None`, signatures of the backend would be missing from the audit log
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        None
+    }
+}
+
+/// Verifies the signatures of an [AuditedSignHalf]
+#[derive(Debug, Default)]
+pub struct AuditedVerifyHalf<V> {
+    inner: V,
+}
+
+impl<V: VerifyHalf> AuditedVerifyHalf<V> {
+    pub fn new(inner: V) -> Self {
+        Self { inner }
+    }
+
+    pub fn inner(&self) -> &V {
+        &self.inner
+    }
+}
+
+impl<V: VerifyHalf> VerifyHalf for AuditedVerifyHalf<V> {
+    type Signature = V::Signature;
+    type Attestation = V::Attestation;
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        let digest = Sha256::digest(message).into();
+        self.inner.verify(id, audited_message(&digest), signature)
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.inner.pre_validate(id, signature)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        self.inner.memory_usage()
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        remote_usig_id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        self.inner.try_add_remote_party(remote_usig_id, attestation)
+    }
+
+    fn remove_remote_party(&mut self, remote_usig_id: ReplicaId) -> bool {
+        self.inner.remove_remote_party(remote_usig_id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.inner.remote_parties()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.inner.contains(remote_usig_id)
+    }
+
+    fn last_counter(&self, id: ReplicaId) -> Option<Count> {
+        self.inner.last_counter(id)
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any()
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any_mut()
+    }
+}
+
+/// A problem found in an audit log
+#[derive(Debug)]
+pub enum Inconsistency {
+    /// The entry is not signed by the replica
+    Signature(UsigError),
+    /// The counter did not increase over the previous entry
+    Regression { previous: Count },
+    /// Counters between the previous entry and this one are missing from the log
+    Gap { expected: Count },
+}
+
+/// The outcome of replaying an audit log
+#[derive(Debug, Default)]
+pub struct ReplayReport {
+    /// Number of entries with a valid signature
+    pub verified: usize,
+    /// Index into the log and the problem found there
+    pub inconsistencies: Vec<(usize, Inconsistency)>,
+}
+
+impl ReplayReport {
+    pub fn is_consistent(&self) -> bool {
+        self.inconsistencies.is_empty()
+    }
+}
+
+/// Re-validate the audit log of `id` offline
+///
+/// Every signature is checked against the attestation `verifier` knows for `id`,
+/// and the counters must form a contiguous sequence starting at `first`.
+/// Checking continues past problems, so the report lists all of them.
+pub fn replay<V: VerifyHalf>(
+    verifier: &V,
+    id: ReplicaId,
+    first: Count,
+    log: &[AuditEntry<V::Signature>],
+) -> ReplayReport {
+    let mut report = ReplayReport::default();
+    let mut expected = first;
+    let mut previous = None;
+    for (index, entry) in log.iter().enumerate() {
+        match verifier.verify(id, audited_message(&entry.digest), &entry.signature) {
+            Ok(()) => report.verified += 1,
+            Err(error) => {
+                report
+                    .inconsistencies
+                    .push((index, Inconsistency::Signature(error)));
+                continue;
+            }
+        }
+        let counter = entry.signature.counter();
+        match previous {
+            Some(previous) if counter <= previous => {
+                report
+                    .inconsistencies
+                    .push((index, Inconsistency::Regression { previous }));
+                continue;
+            }
+            _ if counter != expected => report
+                .inconsistencies
+                .push((index, Inconsistency::Gap { expected })),
+            _ => {}
+        }
+        previous = Some(counter);
+        // After the last counter every entry is a regression
+        expected = counter.checked_add(1).unwrap_or(counter);
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{signature::new_ed25519, Usig};
+
+    const ID: ReplicaId = ReplicaId::first();
+
+    #[test]
+    fn audited_roundtrip() {
+        let (sign, verify) = new_ed25519().split();
+        let mut sign = AuditedSignHalf::new(sign);
+        let mut verify = AuditedVerifyHalf::new(verify);
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+
+        let signature = sign.sign(b"message").unwrap();
+        assert!(verify.verify(ID, b"message", &signature).is_ok());
+        assert!(verify.verify(ID, b"other", &signature).is_err());
+    }
+
+    #[test]
+    fn replay_history() {
+        let (sign, mut verify) = new_ed25519().split();
+        let mut sign = AuditedSignHalf::new(sign);
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+        for i in 0..5u8 {
+            sign.sign([i]).unwrap();
+        }
+        let log = sign.take_log();
+        assert!(sign.take_log().is_empty());
+
+        let report = replay(&verify, ID, Count(0), &log);
+        assert!(report.is_consistent());
+        assert_eq!(report.verified, 5);
+
+        let mut tampered = log.clone();
+        tampered[1].digest = [0; 32];
+        tampered.remove(3);
+        tampered.push(log[0].clone());
+        let report = replay(&verify, ID, Count(0), &tampered);
+        assert_eq!(report.verified, 4);
+        assert!(matches!(
+            report.inconsistencies.as_slice(),
+            [
+                (1, Inconsistency::Signature(UsigError::InvalidSignature)),
+                (2, Inconsistency::Gap { expected: Count(1) }),
+                (3, Inconsistency::Gap { expected: Count(3) }),
+                (4, Inconsistency::Regression { previous: Count(4) }),
+            ]
+        ));
+    }
+}
@@ -0,0 +1,343 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use shared_ids::ReplicaId;
+
+use crate::{encoding::with_context, Count, Counter, SignHalf, UsigError, VerifyHalf};
+
+/// Context of the commitment signed for a [BeaconCommitment]
+pub const BEACON_CONTEXT: &[u8] = b"usig beacon";
+
+fn commitment_message(round: u64, commitment: &[u8; 32]) -> Vec<u8> {
+    let mut message = round.to_be_bytes().to_vec();
+    message.extend_from_slice(commitment);
+    with_context(BEACON_CONTEXT, &message)
+}
+
+fn commitment(round: u64, reveal: &BeaconReveal) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(with_context(BEACON_CONTEXT, &round.to_be_bytes()));
+    hasher.update(reveal.salt);
+    hasher.update(&reveal.input);
+    hasher.finalize().into()
+}
+
+/// The signed commitment of one party to its input to one round of a shared-randomness beacon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BeaconCommitment<S> {
+    pub round: u64,
+    /// Hash of the salted input, opened by the [BeaconReveal]
+    pub commitment: [u8; 32],
+    pub signature: S,
+}
+
+impl<S: Counter> BeaconCommitment<S> {
+    pub fn counter(&self) -> Count {
+        self.signature.counter()
+    }
+}
+
+/// Opens a [BeaconCommitment], published once the round is closed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BeaconReveal {
+    pub input: Vec<u8>,
+    pub salt: [u8; 32],
+}
+
+/// Commit to `input` as the contribution to `round`
+///
+/// Publish the commitment right away and keep the reveal secret until the commitments of
+/// the round are closed, see [BeaconTally::close].
+pub fn commit<S: SignHalf>(
+    sign_half: &mut S,
+    round: u64,
+    input: impl Into<Vec<u8>>,
+) -> Result<(BeaconCommitment<S::Signature>, BeaconReveal), UsigError> {
+    let reveal = BeaconReveal {
+        input: input.into(),
+        salt: rand::random(),
+    };
+    let commitment = commitment(round, &reveal);
+    let signature = sign_half.sign(commitment_message(round, &commitment))?;
+    Ok((
+        BeaconCommitment {
+            round,
+            commitment,
+            signature,
+        },
+        reveal,
+    ))
+}
+
+#[derive(Debug, Clone)]
+struct Round<S> {
+    commitments: BTreeMap<ReplicaId, BeaconCommitment<S>>,
+    reveals: BTreeMap<ReplicaId, BeaconReveal>,
+    closed: bool,
+}
+
+impl<S> Default for Round<S> {
+    fn default() -> Self {
+        Self {
+            commitments: BTreeMap::new(),
+            reveals: BTreeMap::new(),
+            closed: false,
+        }
+    }
+}
+
+/// Runs the commit-reveal rounds of a beacon
+///
+/// Each party first commits to its input, then the protocol agrees to close the round,
+/// and only then the inputs are revealed. Nobody knows any input while the set of
+/// contributors can still change, so the last contributor can not pick its input to bias
+/// the output. A party can still withhold its reveal, which stalls the round and is
+/// reported by [BeaconTally::missing_reveals], but it can not choose among outputs.
+///
+/// Accepts at most one commitment per party and round, and each counter of a party for
+/// one commitment only. A second, different commitment is reported as
+/// [UsigError::Equivocation].
+#[derive(Debug, Clone)]
+pub struct BeaconTally<S> {
+    rounds: BTreeMap<u64, Round<S>>,
+    counters: HashMap<(ReplicaId, Count), u64>,
+}
+
+impl<S> Default for BeaconTally<S> {
+    fn default() -> Self {
+        Self {
+            rounds: BTreeMap::new(),
+            counters: HashMap::new(),
+        }
+    }
+}
+
+impl<S: Counter> BeaconTally<S> {
+    /// Verify a commitment of `id` and add it to its round
+    ///
+    /// Returns whether it was new, a repeated identical commitment is ignored. Fails with
+    /// [UsigError::Outdated] once the round is closed.
+    pub fn add<V: VerifyHalf<Signature = S>>(
+        &mut self,
+        verifier: &V,
+        id: ReplicaId,
+        commitment: BeaconCommitment<S>,
+    ) -> Result<bool, UsigError> {
+        verifier.verify(
+            id,
+            commitment_message(commitment.round, &commitment.commitment),
+            &commitment.signature,
+        )?;
+        let counter = commitment.counter();
+        let round = self.rounds.entry(commitment.round).or_default();
+        match round.commitments.get(&id) {
+            Some(known)
+                if known.counter() == counter && known.commitment == commitment.commitment =>
+            {
+                return Ok(false)
+            }
+            Some(_) => return Err(UsigError::Equivocation(id)),
+            None => {}
+        }
+        if round.closed {
+            return Err(UsigError::Outdated);
+        }
+        if self.counters.contains_key(&(id, counter)) {
+            return Err(UsigError::Equivocation(id));
+        }
+        self.counters.insert((id, counter), commitment.round);
+        round.commitments.insert(id, commitment);
+        Ok(true)
+    }
+
+    /// Accept no more commitments for `round`, the parties may reveal from now on
+    ///
+    /// All parties have to close the round with the same commitments, e.g. those the
+    /// protocol agreed on, to compute the same output.
+    pub fn close(&mut self, round: u64) {
+        self.rounds.entry(round).or_default().closed = true;
+    }
+
+    /// Add the reveal of `id`, which has to open its commitment to `round`
+    ///
+    /// Returns whether it was new. A reveal that does not match the commitment is reported
+    /// as [UsigError::Equivocation].
+    pub fn reveal(
+        &mut self,
+        round: u64,
+        id: ReplicaId,
+        reveal: BeaconReveal,
+    ) -> Result<bool, UsigError> {
+        let round_state = self
+            .rounds
+            .get_mut(&round)
+            .filter(|state| state.commitments.contains_key(&id))
+            .ok_or(UsigError::UnknownId(id))?;
+        if round_state.commitments[&id].commitment != commitment(round, &reveal) {
+            return Err(UsigError::Equivocation(id));
+        }
+        Ok(round_state.reveals.insert(id, reveal).is_none())
+    }
+
+    /// The parties that committed to `round`
+    pub fn contributors(&self, round: u64) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.rounds
+            .get(&round)
+            .into_iter()
+            .flat_map(|round| round.commitments.keys().copied())
+    }
+
+    /// The parties that committed to `round` but did not reveal yet
+    pub fn missing_reveals(&self, round: u64) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.rounds.get(&round).into_iter().flat_map(|round| {
+            round
+                .commitments
+                .keys()
+                .filter(|id| !round.reveals.contains_key(id))
+                .copied()
+        })
+    }
+
+    /// Combine the revealed inputs of `round` into its beacon output
+    ///
+    /// Deterministic in the set of commitments, so every party that closed the round with
+    /// the same commitments computes the same output. [None] until the round is closed and
+    /// every committed party revealed, or if nobody committed.
+    pub fn output(&self, round: u64) -> Option<[u8; 32]> {
+        let state = self.rounds.get(&round)?;
+        if !state.closed
+            || state.commitments.is_empty()
+            || state.reveals.len() != state.commitments.len()
+        {
+            return None;
+        }
+        let mut hasher = Sha256::new();
+        hasher.update(with_context(BEACON_CONTEXT, &round.to_be_bytes()));
+        for (id, reveal) in &state.reveals {
+            hasher.update(id.as_u64().to_be_bytes());
+            hasher.update((reveal.input.len() as u64).to_be_bytes());
+            hasher.update(&reveal.input);
+        }
+        Some(hasher.finalize().into())
+    }
+
+    /// Forget all rounds before `round`
+    pub fn prune_below(&mut self, round: u64) {
+        self.rounds = self.rounds.split_off(&round);
+        self.counters.retain(|_, r| *r >= round);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{signature::new_ed25519, Usig};
+
+    const ID: ReplicaId = ReplicaId::first();
+
+    #[test]
+    fn one_commitment_per_round() {
+        let (mut sign, mut verify) = new_ed25519().split();
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+        let mut tally = BeaconTally::default();
+
+        let (first, _) = commit(&mut sign, 1, b"a".to_vec()).unwrap();
+        assert!(tally.add(&verify, ID, first.clone()).unwrap());
+        assert!(!tally.add(&verify, ID, first).unwrap());
+
+        let (second, _) = commit(&mut sign, 1, b"b".to_vec()).unwrap();
+        assert!(matches!(
+            tally.add(&verify, ID, second),
+            Err(UsigError::Equivocation(ID))
+        ));
+
+        let (mut forged, _) = commit(&mut sign, 2, b"c".to_vec()).unwrap();
+        forged.round = 3;
+        assert!(matches!(
+            tally.add(&verify, ID, forged),
+            Err(UsigError::InvalidSignature)
+        ));
+
+        assert_eq!(tally.contributors(1).collect::<Vec<_>>(), vec![ID]);
+        assert_eq!(tally.contributors(2).count(), 0);
+    }
+
+    #[test]
+    fn commit_reveal() {
+        let ids = [ReplicaId::from_u64(0), ReplicaId::from_u64(1)];
+        let mut verify = new_ed25519().split().1;
+        let mut signers: Vec<_> = ids.iter().map(|_| new_ed25519().split().0).collect();
+        for (id, sign) in ids.iter().zip(&mut signers) {
+            assert!(verify.add_remote_party(*id, sign.attest().unwrap()));
+        }
+
+        let mut tallies = [BeaconTally::default(), BeaconTally::default()];
+        let (commitments, reveals): (Vec<_>, Vec<_>) = signers
+            .iter_mut()
+            .map(|sign| commit(sign, 7, rand::random::<[u8; 32]>().to_vec()).unwrap())
+            .unzip();
+        for (id, commitment) in ids.iter().zip(&commitments) {
+            tallies[0].add(&verify, *id, commitment.clone()).unwrap();
+        }
+        for (id, commitment) in ids.iter().zip(&commitments).rev() {
+            tallies[1].add(&verify, *id, commitment.clone()).unwrap();
+        }
+        for tally in &mut tallies {
+            tally.close(7);
+            assert_eq!(tally.output(7), None);
+            tally.reveal(7, ids[0], reveals[0].clone()).unwrap();
+            assert_eq!(tally.missing_reveals(7).collect::<Vec<_>>(), [ids[1]]);
+            assert_eq!(tally.output(7), None);
+            tally.reveal(7, ids[1], reveals[1].clone()).unwrap();
+        }
+        assert!(tallies[0].output(7).is_some());
+        assert_eq!(tallies[0].output(7), tallies[1].output(7));
+        assert_eq!(tallies[0].output(8), None);
+
+        tallies[0].prune_below(8);
+        assert_eq!(tallies[0].output(7), None);
+    }
+
+    #[test]
+    fn reveal_is_bound() {
+        let (mut sign, mut verify) = new_ed25519().split();
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+        let mut tally = BeaconTally::default();
+
+        let (commitment, mut reveal) = commit(&mut sign, 1, b"a".to_vec()).unwrap();
+        tally.add(&verify, ID, commitment).unwrap();
+        tally.close(1);
+
+        // an input chosen after the others were revealed
+        reveal.input = b"b".to_vec();
+        assert!(matches!(
+            tally.reveal(1, ID, reveal),
+            Err(UsigError::Equivocation(ID))
+        ));
+        assert!(matches!(
+            tally.reveal(2, ID, commit(&mut sign, 2, b"c".to_vec()).unwrap().1),
+            Err(UsigError::UnknownId(ID))
+        ));
+    }
+
+    #[test]
+    fn closed_round() {
+        let ids = [ReplicaId::from_u64(0), ReplicaId::from_u64(1)];
+        let mut verify = new_ed25519().split().1;
+        let mut signers: Vec<_> = ids.iter().map(|_| new_ed25519().split().0).collect();
+        for (id, sign) in ids.iter().zip(&mut signers) {
+            assert!(verify.add_remote_party(*id, sign.attest().unwrap()));
+        }
+        let mut tally = BeaconTally::default();
+        let (first, _) = commit(&mut signers[0], 1, b"a".to_vec()).unwrap();
+        tally.add(&verify, ids[0], first).unwrap();
+        tally.close(1);
+        let (late, _) = commit(&mut signers[1], 1, b"b".to_vec()).unwrap();
+        assert!(matches!(
+            tally.add(&verify, ids[1], late),
+            Err(UsigError::Outdated)
+        ));
+        assert_eq!(tally.contributors(1).count(), 1);
+    }
+}
@@ -0,0 +1,211 @@
+use blst::{min_pk, BLST_ERROR};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use shared_ids::ReplicaId;
+use signature::{Signer, Verifier};
+
+use crate::{
+    directory::PartyDirectory,
+    encoding::CounterEncoding,
+    signature::{
+        BatchVerifier, Signature, SignatureParameters, UsigSignature, UsigSignatureVerifyHalf,
+    },
+    AlgorithmParameters, Count, Counter, UsigError,
+};
+
+/// Domain separation tag of the message augmentation scheme, which prefixes every
+/// message with the public key of the signer so aggregation is safe against rogue keys
+const DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_AUG_";
+
+impl SignatureParameters for min_pk::Signature {
+    fn parameters() -> AlgorithmParameters {
+        AlgorithmParameters {
+            scheme: "BLS12-381 min-pk aug".to_owned(),
+            output_length: 96,
+            pre_hash: None,
+            counter_encoding: CounterEncoding::default(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct BlsSigningKey {
+    secret: min_pk::SecretKey,
+    public: min_pk::PublicKey,
+    /// Prefixed to every message, compressed once instead of for every signature
+    compressed: [u8; 48],
+}
+
+impl BlsSigningKey {
+    pub fn generate() -> Self {
+        let mut ikm = [0; 32];
+        OsRng.fill_bytes(&mut ikm);
+        let secret = min_pk::SecretKey::key_gen(&ikm, &[]).expect("ikm is long enough");
+        let public = secret.sk_to_pk();
+        Self {
+            secret,
+            public,
+            compressed: public.compress(),
+        }
+    }
+
+    pub fn verifying_key(&self) -> BlsVerifyingKey {
+        BlsVerifyingKey(self.public)
+    }
+}
+
+impl Signer<min_pk::Signature> for BlsSigningKey {
+    fn try_sign(&self, msg: &[u8]) -> Result<min_pk::Signature, signature::Error> {
+        Ok(self.secret.sign(msg, DST, &self.compressed))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct BlsVerifyingKey(pub(crate) min_pk::PublicKey);
+
+impl Verifier<min_pk::Signature> for BlsVerifyingKey {
+    fn verify(&self, msg: &[u8], signature: &min_pk::Signature) -> Result<(), signature::Error> {
+        match signature.verify(true, msg, DST, &self.0.compress(), &self.0, true) {
+            BLST_ERROR::BLST_SUCCESS => Ok(()),
+            _ => Err(signature::Error::new()),
+        }
+    }
+}
+
+impl BatchVerifier<min_pk::Signature> for BlsVerifyingKey {}
+
+pub type UsigBls = UsigSignature<min_pk::Signature, BlsSigningKey, BlsVerifyingKey>;
+
+pub fn new_bls() -> UsigBls {
+    let private_key = BlsSigningKey::generate();
+    let public_key = private_key.verifying_key();
+    UsigSignature::new(private_key, public_key)
+}
+
+/// USIG signatures of multiple replicas over the same message combined into one
+///
+/// Each replica keeps its own counter, only the BLS signatures are aggregated.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AggregateSignature {
+    pub counters: Vec<(ReplicaId, Count)>,
+    pub signature: min_pk::Signature,
+}
+
+/// Combine the signatures of distinct replicas over the same message
+pub fn aggregate(
+    signatures: &[(ReplicaId, &Signature<min_pk::Signature>)],
+) -> Result<AggregateSignature, UsigError> {
+    let inner: Vec<_> = signatures.iter().map(|(_, s)| s.inner()).collect();
+    let signature = min_pk::AggregateSignature::aggregate(&inner, true)
+        .map_err(|_| UsigError::InvalidSignature)?
+        .to_signature();
+    Ok(AggregateSignature {
+        counters: signatures
+            .iter()
+            .map(|(id, signature)| (*id, signature.counter()))
+            .collect(),
+        signature,
+    })
+}
+
+/// Verify an aggregate of the signatures over `message` against the attested keys
+pub fn verify_aggregate<D: PartyDirectory<BlsVerifyingKey>>(
+    verify_half: &UsigSignatureVerifyHalf<min_pk::Signature, BlsVerifyingKey, D>,
+    message: impl AsRef<[u8]>,
+    aggregate: &AggregateSignature,
+) -> Result<(), UsigError> {
+    let mut ids: Vec<_> = aggregate.counters.iter().map(|(id, _)| *id).collect();
+    ids.sort();
+    ids.dedup();
+    if ids.is_empty() || ids.len() != aggregate.counters.len() {
+        return Err(UsigError::InvalidSignature);
+    }
+
+    let encoding = verify_half.counter_encoding();
+    let mut keys = Vec::with_capacity(aggregate.counters.len());
+    let mut messages = Vec::with_capacity(aggregate.counters.len());
+    for (id, counter) in &aggregate.counters {
+        let key = verify_half
+            .remote_key(*id)
+            .ok_or(UsigError::UnknownId(*id))?
+            .0;
+        let mut augmented = key.compress().to_vec();
+        augmented.extend(encoding.preimage(counter.0, message.as_ref()));
+        keys.push(key);
+        messages.push(augmented);
+    }
+    let keys: Vec<_> = keys.iter().collect();
+    let messages: Vec<_> = messages.iter().map(Vec::as_slice).collect();
+    match aggregate
+        .signature
+        .aggregate_verify(true, &messages, DST, &keys, true)
+    {
+        BLST_ERROR::BLST_SUCCESS => Ok(()),
+        _ => Err(UsigError::InvalidSignature),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::new_bls;
+    use crate as usig;
+    use crate::tests;
+
+    tests!(new_bls());
+
+    mod aggregate {
+        use super::super::*;
+        use crate::{SignHalf, Usig, VerifyHalf};
+
+        const MESSAGE: &[u8] = b"message";
+
+        #[test]
+        fn quorum() {
+            let mut verify = new_bls().split().1;
+            let mut signatures = Vec::new();
+            for i in 0..4 {
+                let (mut sign, _) = new_bls().split();
+                let id = ReplicaId::from_u64(i);
+                assert!(verify.add_remote_party(id, sign.attest().unwrap()));
+                for _ in 0..i {
+                    sign.sign(b"other").unwrap();
+                }
+                signatures.push((id, sign.sign(MESSAGE).unwrap()));
+            }
+            let refs: Vec<_> = signatures.iter().map(|(id, s)| (*id, s)).collect();
+            let aggregate = aggregate(&refs).unwrap();
+            assert!(verify_aggregate(&verify, MESSAGE, &aggregate).is_ok());
+            assert!(matches!(
+                verify_aggregate(&verify, b"other", &aggregate),
+                Err(UsigError::InvalidSignature)
+            ));
+
+            let mut moved = aggregate.clone();
+            moved.counters[1].1 = Count(7);
+            assert!(matches!(
+                verify_aggregate(&verify, MESSAGE, &moved),
+                Err(UsigError::InvalidSignature)
+            ));
+
+            let mut duplicate = aggregate;
+            duplicate.counters[1].0 = duplicate.counters[0].0;
+            assert!(matches!(
+                verify_aggregate(&verify, MESSAGE, &duplicate),
+                Err(UsigError::InvalidSignature)
+            ));
+        }
+
+        #[test]
+        fn unknown_party() {
+            let verify = new_bls().split().1;
+            let (mut sign, _) = new_bls().split();
+            let signature = sign.sign(MESSAGE).unwrap();
+            let aggregate = aggregate(&[(ReplicaId::first(), &signature)]).unwrap();
+            assert!(matches!(
+                verify_aggregate(&verify, MESSAGE, &aggregate),
+                Err(UsigError::UnknownId(_))
+            ));
+        }
+    }
+}
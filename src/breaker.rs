@@ -0,0 +1,340 @@
+use std::{any::Any, collections::HashMap, fmt::Debug, sync::Mutex, time::Duration};
+
+use derivative::Derivative;
+use shared_ids::ReplicaId;
+
+use crate::{
+    clock::{Clock, SystemClock},
+    Count, MemoryReport, UsigError, VerifyHalf,
+};
+
+/// When a [BreakerVerifyHalf] quarantines a party
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BreakerPolicy {
+    /// Number of invalid signatures sent by a party within `window` that trip the breaker
+    pub max_failures: u32,
+    pub window: Duration,
+    /// How long verifications of the party are short-circuited once tripped
+    pub quarantine: Duration,
+}
+
+/// Counters of a [BreakerVerifyHalf] over all parties
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BreakerMetrics {
+    /// Invalid signatures charged to their sender
+    pub failures: u64,
+    /// Times a party was quarantined
+    pub trips: u64,
+    /// Verifications short-circuited because the party was quarantined
+    pub rejected: u64,
+}
+
+#[derive(Debug, Default)]
+struct PartyState {
+    window_start: Duration,
+    failures: u32,
+    quarantined_until: Option<Duration>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    parties: HashMap<ReplicaId, PartyState>,
+    metrics: BreakerMetrics,
+}
+
+/// A verify half that stops verifying signatures of parties flooding it with invalid ones
+///
+/// Protects the CPU from a byzantine peer. The id a signature claims can be forged, so
+/// invalid signatures are only charged to the sender authenticated by the transport, see
+/// [BreakerVerifyHalf::verify_from]. A quarantined party is rejected with
+/// [UsigError::PartyQuarantined] until the quarantine expires or [BreakerVerifyHalf::reset] is called.
+#[derive(Derivative)]
+#[derivative(Debug(bound = "V: Debug, C: Debug"))]
+pub struct BreakerVerifyHalf<V, C = SystemClock> {
+    inner: V,
+    policy: BreakerPolicy,
+    clock: C,
+    state: Mutex<State>,
+}
+
+impl<V: VerifyHalf> BreakerVerifyHalf<V> {
+    pub fn new(inner: V, policy: BreakerPolicy) -> Self {
+        Self::with_clock(inner, policy, SystemClock)
+    }
+}
+
+impl<V: VerifyHalf, C: Clock> BreakerVerifyHalf<V, C> {
+    pub fn with_clock(inner: V, policy: BreakerPolicy, clock: C) -> Self {
+        Self {
+            inner,
+            policy,
+            clock,
+            state: Mutex::default(),
+        }
+    }
+
+    pub fn is_quarantined(&self, id: ReplicaId) -> bool {
+        let now = self.clock.now();
+        self.state
+            .lock()
+            .unwrap()
+            .parties
+            .get(&id)
+            .and_then(|party| party.quarantined_until)
+            .is_some_and(|until| now < until)
+    }
+
+    /// Verify a signature of `id` in a message received from `sender`
+    ///
+    /// `sender` is the peer the message came from as authenticated by the transport, e.g.
+    /// with mutual TLS. An invalid signature is charged to `sender`, never to the claimed
+    /// `id`, so forged messages can not get an honest party quarantined. Messages of a
+    /// quarantined `sender` are rejected without being verified.
+    pub fn verify_from(
+        &self,
+        sender: ReplicaId,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &V::Signature,
+    ) -> Result<(), UsigError> {
+        let now = self.clock.now();
+        self.check_quarantine(sender, now)?;
+        self.check_quarantine(id, now)?;
+        let result = self.inner.verify(id, message, signature);
+        if let Err(UsigError::InvalidSignature) = result {
+            self.record_failure(&mut self.state.lock().unwrap(), sender, now);
+        }
+        result
+    }
+
+    /// Lift the quarantine of `id` and forget its recent failures
+    pub fn reset(&self, id: ReplicaId) {
+        self.state.lock().unwrap().parties.remove(&id);
+    }
+
+    pub fn metrics(&self) -> BreakerMetrics {
+        self.state.lock().unwrap().metrics
+    }
+
+    fn check_quarantine(&self, id: ReplicaId, now: Duration) -> Result<(), UsigError> {
+        let mut state = self.state.lock().unwrap();
+        let until = state.parties.get(&id).and_then(|p| p.quarantined_until);
+        if until.is_some_and(|until| now < until) {
+            state.metrics.rejected += 1;
+            return Err(UsigError::PartyQuarantined(id));
+        }
+        Ok(())
+    }
+
+    fn record_failure(&self, state: &mut State, id: ReplicaId, now: Duration) {
+        state.metrics.failures += 1;
+        let party = state.parties.entry(id).or_default();
+        if now.saturating_sub(party.window_start) >= self.policy.window {
+            party.window_start = now;
+            party.failures = 0;
+        }
+        party.failures += 1;
+        if party.failures >= self.policy.max_failures {
+            party.quarantined_until = Some(now.saturating_add(self.policy.quarantine));
+            party.failures = 0;
+            state.metrics.trips += 1;
+        }
+    }
+}
+
+impl<V: VerifyHalf, C: Clock> VerifyHalf for BreakerVerifyHalf<V, C> {
+    type Signature = V::Signature;
+    type Attestation = V::Attestation;
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        // without an authenticated sender a failure can not be attributed to anyone
+        self.check_quarantine(id, self.clock.now())?;
+        self.inner.verify(id, message, signature)
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        if self.is_quarantined(id) {
+            return Err(UsigError::PartyQuarantined(id));
+        }
+        self.inner.pre_validate(id, signature)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        let state = self.state.lock().unwrap();
+        let mut report = self.inner.memory_usage();
+        report.evidence += size_of_val(&state.parties)
+            + state.parties.capacity() * (size_of::<(ReplicaId, PartyState)>() + 1);
+        report
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        remote_usig_id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        self.inner.try_add_remote_party(remote_usig_id, attestation)
+    }
+
+    fn remove_remote_party(&mut self, remote_usig_id: ReplicaId) -> bool {
+        self.state
+            .get_mut()
+            .unwrap()
+            .parties
+            .remove(&remote_usig_id);
+        self.inner.remove_remote_party(remote_usig_id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.inner.remote_parties()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.inner.contains(remote_usig_id)
+    }
+
+    fn last_counter(&self, id: ReplicaId) -> Option<Count> {
+        self.inner.last_counter(id)
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any()
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::Hmac;
+    use sha2::Sha256;
+
+    use super::*;
+    use crate::{
+        clock::ManualClock,
+        hmac::{UsigHmac, UsigHmacVerifyHalf},
+        SignHalf, Usig,
+    };
+
+    type HmacUsig = UsigHmac<Hmac<Sha256>>;
+    type HmacVerifyHalf = UsigHmacVerifyHalf<Hmac<Sha256>>;
+    type Signature = <HmacUsig as Usig>::Signature;
+
+    const ID: ReplicaId = ReplicaId::first();
+    const MESSAGE: &[u8] = b"message";
+    const POLICY: BreakerPolicy = BreakerPolicy {
+        max_failures: 3,
+        window: Duration::from_secs(10),
+        quarantine: Duration::from_secs(60),
+    };
+
+    fn setup(clock: &ManualClock) -> (BreakerVerifyHalf<HmacVerifyHalf, &ManualClock>, Signature) {
+        let usig = HmacUsig::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap();
+        let (mut sign, verify) = usig.split();
+        let mut verify = BreakerVerifyHalf::with_clock(verify, POLICY, clock);
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+        (verify, sign.sign(MESSAGE).unwrap())
+    }
+
+    #[test]
+    fn quarantine() {
+        let clock = ManualClock::new(Duration::ZERO);
+        let (verify, signature) = setup(&clock);
+
+        for _ in 0..3 {
+            assert!(matches!(
+                verify.verify_from(ID, ID, b"forged", &signature),
+                Err(UsigError::InvalidSignature)
+            ));
+        }
+        assert!(verify.is_quarantined(ID));
+        assert!(matches!(
+            verify.verify(ID, MESSAGE, &signature),
+            Err(UsigError::PartyQuarantined(ID))
+        ));
+        assert_eq!(
+            verify.metrics(),
+            BreakerMetrics {
+                failures: 3,
+                trips: 1,
+                rejected: 1
+            }
+        );
+
+        clock.advance(POLICY.quarantine);
+        assert!(verify.verify(ID, MESSAGE, &signature).is_ok());
+
+        for _ in 0..3 {
+            let _ = verify.verify_from(ID, ID, b"forged", &signature);
+        }
+        assert!(verify.is_quarantined(ID));
+        verify.reset(ID);
+        assert!(verify.verify(ID, MESSAGE, &signature).is_ok());
+    }
+
+    #[test]
+    fn failures_expire() {
+        let clock = ManualClock::new(Duration::ZERO);
+        let (verify, signature) = setup(&clock);
+
+        for _ in 0..10 {
+            let _ = verify.verify_from(ID, ID, b"forged", &signature);
+            let _ = verify.verify_from(ID, ID, b"forged", &signature);
+            clock.advance(POLICY.window);
+        }
+        assert!(!verify.is_quarantined(ID));
+        assert_eq!(verify.metrics().trips, 0);
+    }
+
+    #[test]
+    fn forged_id() {
+        let clock = ManualClock::new(Duration::ZERO);
+        let (verify, signature) = setup(&clock);
+        let attacker = ReplicaId::from_u64(7);
+
+        for _ in 0..3 {
+            assert!(matches!(
+                verify.verify(ID, b"forged", &signature),
+                Err(UsigError::InvalidSignature)
+            ));
+            let _ = verify.verify_from(attacker, ID, b"forged", &signature);
+        }
+        assert!(!verify.is_quarantined(ID));
+        assert!(verify.is_quarantined(attacker));
+        assert!(verify.verify(ID, MESSAGE, &signature).is_ok());
+        assert!(matches!(
+            verify.verify_from(attacker, ID, MESSAGE, &signature),
+            Err(UsigError::PartyQuarantined(id)) if id == attacker
+        ));
+    }
+
+    #[test]
+    fn endless_quarantine() {
+        let clock = ManualClock::new(Duration::from_secs(1));
+        let policy = BreakerPolicy {
+            quarantine: Duration::MAX,
+            ..POLICY
+        };
+        let usig = HmacUsig::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap();
+        let (mut sign, verify) = usig.split();
+        let mut verify = BreakerVerifyHalf::with_clock(verify, policy, &clock);
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+        let signature = sign.sign(MESSAGE).unwrap();
+        for _ in 0..3 {
+            let _ = verify.verify_from(ID, ID, b"forged", &signature);
+        }
+        assert!(verify.is_quarantined(ID));
+    }
+}
@@ -0,0 +1,318 @@
+use std::{any::Any, collections::BTreeMap};
+
+use ed25519_dalek::{Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use shared_ids::ReplicaId;
+
+use crate::{encoding::with_context, Count, MemoryReport, UsigError, VerifyHalf};
+
+/// Context of the bundle the deployment authority signs for a [SignedBundle]
+pub const BUNDLE_CONTEXT: &[u8] = b"usig attestation bundle";
+
+/// The attestations of all parties of a cluster, as provisioned by its operator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttestationBundle<A> {
+    /// Increases with every bundle the operator issues, older bundles are rejected
+    pub version: u64,
+    pub parties: BTreeMap<ReplicaId, A>,
+}
+
+impl<A: Serialize> AttestationBundle<A> {
+    /// Sign the bundle with the key of the deployment authority
+    pub fn sign(self, authority: &SigningKey) -> SignedBundle<A> {
+        let signature = authority.sign(&bundle_message(&self));
+        SignedBundle {
+            bundle: self,
+            signature,
+        }
+    }
+}
+
+fn bundle_message<A: Serialize>(bundle: &AttestationBundle<A>) -> Vec<u8> {
+    let bundle = bincode::serialize(bundle).expect("serialization to memory does not fail");
+    with_context(BUNDLE_CONTEXT, &bundle)
+}
+
+/// An [AttestationBundle] signed by the deployment authority
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedBundle<A> {
+    pub bundle: AttestationBundle<A>,
+    pub signature: ed25519_dalek::Signature,
+}
+
+impl<A: Serialize> SignedBundle<A> {
+    /// Check the bundle was signed by `authority` and return it
+    pub fn verify(self, authority: &VerifyingKey) -> Result<AttestationBundle<A>, UsigError> {
+        authority
+            .verify(&bundle_message(&self.bundle), &self.signature)
+            .map_err(|_| UsigError::InvalidSignature)?;
+        Ok(self.bundle)
+    }
+}
+
+/// A verify half that only admits parties from bundles signed by the deployment authority
+///
+/// For closed clusters the operator vouches for every party, so individual attestations
+/// are not evaluated against a policy and [VerifyHalf::try_add_remote_party] is refused
+/// with [UsigError::RemoteAttestationFailed]. Use [BundleVerifyHalf::admit] instead.
+#[derive(Debug)]
+pub struct BundleVerifyHalf<V: VerifyHalf> {
+    inner: V,
+    authority: VerifyingKey,
+    version: Option<u64>,
+    /// The attestations of the admitted parties, to restore them if a bundle is rejected
+    admitted: BTreeMap<ReplicaId, V::Attestation>,
+}
+
+impl<V: VerifyHalf> BundleVerifyHalf<V>
+where
+    V::Attestation: Serialize + Clone,
+{
+    pub fn new(inner: V, authority: VerifyingKey) -> Self {
+        Self {
+            inner,
+            authority,
+            version: None,
+            admitted: BTreeMap::new(),
+        }
+    }
+
+    /// The version of the last admitted bundle
+    pub fn version(&self) -> Option<u64> {
+        self.version
+    }
+
+    /// Replace the admitted parties by those of a bundle signed by the authority
+    ///
+    /// Parties missing from the bundle are removed. The bundle is admitted as a whole: if
+    /// one of its attestations is rejected, the error is returned and the parties of the
+    /// last bundle stay admitted. Fails with [UsigError::Outdated] if the bundle is not
+    /// newer than the last one.
+    pub fn admit(&mut self, bundle: SignedBundle<V::Attestation>) -> Result<(), UsigError> {
+        let bundle = bundle.verify(&self.authority)?;
+        if self
+            .version
+            .is_some_and(|version| bundle.version <= version)
+        {
+            return Err(UsigError::Outdated);
+        }
+        let mut added = Vec::with_capacity(bundle.parties.len());
+        for (&id, attestation) in &bundle.parties {
+            if let Err(error) = self.inner.try_add_remote_party(id, attestation.clone()) {
+                self.restore(added);
+                return Err(error);
+            }
+            added.push(id);
+        }
+        for id in self.admitted.keys() {
+            if !bundle.parties.contains_key(id) {
+                self.inner.remove_remote_party(*id);
+            }
+        }
+        self.admitted = bundle.parties;
+        self.version = Some(bundle.version);
+        Ok(())
+    }
+
+    /// Undo adding the parties `added` of a rejected bundle
+    fn restore(&mut self, added: Vec<ReplicaId>) {
+        for id in added {
+            self.inner.remove_remote_party(id);
+            if let Some(attestation) = self.admitted.get(&id) {
+                // was accepted before, so it is accepted again
+                let _ = self.inner.try_add_remote_party(id, attestation.clone());
+            }
+        }
+    }
+}
+
+impl<V: VerifyHalf> VerifyHalf for BundleVerifyHalf<V>
+where
+    V::Attestation: Serialize + Clone,
+{
+    type Signature = V::Signature;
+    type Attestation = V::Attestation;
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        self.inner.verify(id, message, signature)
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.inner.pre_validate(id, signature)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        self.inner.memory_usage()
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        _remote_usig_id: ReplicaId,
+        _attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        Err(UsigError::RemoteAttestationFailed)
+    }
+
+    fn remove_remote_party(&mut self, remote_usig_id: ReplicaId) -> bool {
+        self.admitted.remove(&remote_usig_id);
+        self.inner.remove_remote_party(remote_usig_id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.inner.remote_parties()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.inner.contains(remote_usig_id)
+    }
+
+    fn last_counter(&self, id: ReplicaId) -> Option<Count> {
+        self.inner.last_counter(id)
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any()
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::OsRng;
+
+    use super::*;
+    use crate::{signature::new_ed25519, SignHalf, Usig};
+
+    const MESSAGE: &[u8] = b"message";
+
+    #[test]
+    fn bootstrap() {
+        let authority = SigningKey::generate(&mut OsRng);
+        let (mut sign_0, _) = new_ed25519().split();
+        let (mut sign_1, verify) = new_ed25519().split();
+        let bundle = AttestationBundle {
+            version: 1,
+            parties: [
+                (ReplicaId::from_u64(0), sign_0.attest().unwrap()),
+                (ReplicaId::from_u64(1), sign_1.attest().unwrap()),
+            ]
+            .into(),
+        };
+
+        let mut verify = BundleVerifyHalf::new(verify, authority.verifying_key());
+        verify.admit(bundle.sign(&authority)).unwrap();
+        assert_eq!(verify.version(), Some(1));
+        let signature = sign_1.sign(MESSAGE).unwrap();
+        assert!(verify
+            .verify(ReplicaId::from_u64(1), MESSAGE, &signature)
+            .is_ok());
+        assert!(matches!(
+            verify.try_add_remote_party(ReplicaId::from_u64(2), new_ed25519().attest().unwrap()),
+            Err(UsigError::RemoteAttestationFailed)
+        ));
+    }
+
+    #[test]
+    fn forged_and_stale() {
+        let authority = SigningKey::generate(&mut OsRng);
+        let (_, verify) = new_ed25519().split();
+        let mut verify = BundleVerifyHalf::new(verify, authority.verifying_key());
+        let bundle = |version| AttestationBundle {
+            version,
+            parties: [(ReplicaId::from_u64(0), new_ed25519().attest().unwrap())].into(),
+        };
+
+        let forged = bundle(1).sign(&SigningKey::generate(&mut OsRng));
+        assert!(matches!(
+            verify.admit(forged),
+            Err(UsigError::InvalidSignature)
+        ));
+        assert_eq!(verify.version(), None);
+
+        let mut tampered = bundle(1).sign(&authority);
+        tampered
+            .bundle
+            .parties
+            .insert(ReplicaId::from_u64(1), new_ed25519().attest().unwrap());
+        assert!(matches!(
+            verify.admit(tampered),
+            Err(UsigError::InvalidSignature)
+        ));
+
+        verify.admit(bundle(2).sign(&authority)).unwrap();
+        assert!(matches!(
+            verify.admit(bundle(2).sign(&authority)),
+            Err(UsigError::Outdated)
+        ));
+    }
+
+    #[test]
+    fn replace_parties() {
+        let authority = SigningKey::generate(&mut OsRng);
+        let (mut sign_0, _) = new_ed25519().split();
+        let (mut sign_1, verify) = new_ed25519().split();
+        let mut verify = BundleVerifyHalf::new(verify, authority.verifying_key());
+        let (id_0, id_1) = (ReplicaId::from_u64(0), ReplicaId::from_u64(1));
+        let first = AttestationBundle {
+            version: 1,
+            parties: [(id_0, sign_0.attest().unwrap())].into(),
+        };
+        verify.admit(first.sign(&authority)).unwrap();
+
+        let second = AttestationBundle {
+            version: 2,
+            parties: [(id_1, sign_1.attest().unwrap())].into(),
+        };
+        verify.admit(second.sign(&authority)).unwrap();
+        assert_eq!(verify.remote_parties().collect::<Vec<_>>(), [id_1]);
+    }
+
+    #[test]
+    fn all_or_nothing() {
+        let authority = SigningKey::generate(&mut OsRng);
+        let (id_0, id_1) = (ReplicaId::from_u64(0), ReplicaId::from_u64(1));
+        let (sign_0, _) = new_ed25519().split();
+        let mut sign_0 = sign_0.with_local_id(id_0);
+        let (mut sign_1, verify) = new_ed25519().split();
+        let mut verify = BundleVerifyHalf::new(verify.require_signer(), authority.verifying_key());
+        let first = AttestationBundle {
+            version: 1,
+            parties: [(id_0, sign_0.attest().unwrap())].into(),
+        };
+        verify.admit(first.sign(&authority)).unwrap();
+
+        // the second party is not bound to its id, so the whole bundle is rejected
+        let (other_0, _) = new_ed25519().split();
+        let mut other_0 = other_0.with_local_id(id_0);
+        let rejected = AttestationBundle {
+            version: 2,
+            parties: [
+                (id_0, other_0.attest().unwrap()),
+                (id_1, sign_1.attest().unwrap()),
+            ]
+            .into(),
+        };
+        assert!(matches!(
+            verify.admit(rejected.sign(&authority)),
+            Err(UsigError::IdentityMismatch { .. })
+        ));
+        assert_eq!(verify.version(), Some(1));
+        assert_eq!(verify.remote_parties().collect::<Vec<_>>(), [id_0]);
+        let signature = sign_0.sign(MESSAGE).unwrap();
+        assert!(verify.verify(id_0, MESSAGE, &signature).is_ok());
+    }
+}
@@ -0,0 +1,265 @@
+use std::any::Any;
+
+use shared_ids::ReplicaId;
+
+use crate::{Count, MemoryReport, Usig, UsigError, VerifyHalf};
+
+/// A policy an attestation has to satisfy before its party is added
+///
+/// Most backends accept any well formed attestation, a policy adds the checks of the
+/// deployment, e.g. an allow-list of enclave measurements, pinned keys or certificates or
+/// an expiry date. Reject an attestation with [UsigError::RemoteAttestationFailed].
+/// [crate::admission::admit_all] checks many attestations against a policy in parallel.
+pub trait AttestationVerifier<A> {
+    fn verify_attestation(&self, id: ReplicaId, attestation: &A) -> Result<(), UsigError>;
+}
+
+impl<A, F: Fn(ReplicaId, &A) -> Result<(), UsigError>> AttestationVerifier<A> for F {
+    fn verify_attestation(&self, id: ReplicaId, attestation: &A) -> Result<(), UsigError> {
+        self(id, attestation)
+    }
+}
+
+/// The policy of the backend alone, every attestation it accepts is accepted
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AcceptAll;
+
+impl<A> AttestationVerifier<A> for AcceptAll {
+    fn verify_attestation(&self, _: ReplicaId, _: &A) -> Result<(), UsigError> {
+        Ok(())
+    }
+}
+
+/// A verify half checking every attestation with an [AttestationVerifier] before the
+/// backend loads it
+#[derive(Debug)]
+pub struct CheckedVerifyHalf<V, P> {
+    inner: V,
+    policy: P,
+}
+
+impl<V: VerifyHalf, P: AttestationVerifier<V::Attestation>> CheckedVerifyHalf<V, P> {
+    pub fn new(inner: V, policy: P) -> Self {
+        Self { inner, policy }
+    }
+
+    pub fn into_inner(self) -> V {
+        self.inner
+    }
+}
+
+impl<V: VerifyHalf, P: AttestationVerifier<V::Attestation>> VerifyHalf for CheckedVerifyHalf<V, P> {
+    type Signature = V::Signature;
+    type Attestation = V::Attestation;
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        self.inner.verify(id, message, signature)
+    }
+
+    fn verify_batch(
+        &self,
+        batch: &[(ReplicaId, &[u8], &Self::Signature)],
+    ) -> Vec<Result<(), UsigError>> {
+        self.inner.verify_batch(batch)
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.inner.pre_validate(id, signature)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        self.inner.memory_usage()
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        remote_usig_id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        self.policy
+            .verify_attestation(remote_usig_id, &attestation)?;
+        self.inner.try_add_remote_party(remote_usig_id, attestation)
+    }
+
+    fn remove_remote_party(&mut self, remote_usig_id: ReplicaId) -> bool {
+        self.inner.remove_remote_party(remote_usig_id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.inner.remote_parties()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.inner.contains(remote_usig_id)
+    }
+
+    fn last_counter(&self, id: ReplicaId) -> Option<Count> {
+        self.inner.last_counter(id)
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any()
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any_mut()
+    }
+}
+
+/// A USIG checking every attestation with an [AttestationVerifier], see [CheckedVerifyHalf]
+///
+/// The policy moves to the verify half on [Usig::split].
+#[derive(Debug)]
+pub struct CheckedUsig<U, P> {
+    inner: U,
+    policy: P,
+}
+
+impl<U: Usig, P: AttestationVerifier<U::Attestation>> CheckedUsig<U, P> {
+    pub fn new(inner: U, policy: P) -> Self {
+        Self { inner, policy }
+    }
+
+    pub fn into_inner(self) -> U {
+        self.inner
+    }
+}
+
+impl<U: Usig, P: AttestationVerifier<U::Attestation>> Usig for CheckedUsig<U, P> {
+    type Signature = U::Signature;
+    type Attestation = U::Attestation;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        self.inner.sign(message)
+    }
+
+    fn sign_batch(
+        &mut self,
+        messages: &[impl AsRef<[u8]>],
+    ) -> Result<Vec<Self::Signature>, UsigError> {
+        self.inner.sign_batch(messages)
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        self.inner.attest()
+    }
+
+    fn local_id(&self) -> Option<ReplicaId> {
+        self.inner.local_id()
+    }
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        self.inner.verify(id, message, signature)
+    }
+
+    fn verify_batch(
+        &self,
+        batch: &[(ReplicaId, &[u8], &Self::Signature)],
+    ) -> Vec<Result<(), UsigError>> {
+        self.inner.verify_batch(batch)
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.inner.pre_validate(id, signature)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        self.inner.memory_usage()
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        remote_usig_id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        self.policy
+            .verify_attestation(remote_usig_id, &attestation)?;
+        self.inner.try_add_remote_party(remote_usig_id, attestation)
+    }
+
+    fn remove_remote_party(&mut self, remote_usig_id: ReplicaId) -> bool {
+        self.inner.remove_remote_party(remote_usig_id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.inner.remote_parties()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.inner.contains(remote_usig_id)
+    }
+
+    type SignHalf = U::SignHalf;
+    type VerifyHalf = CheckedVerifyHalf<U::VerifyHalf, P>;
+
+    fn split(self) -> (Self::SignHalf, Self::VerifyHalf) {
+        let (sign_half, verify_half) = self.inner.split();
+        (sign_half, CheckedVerifyHalf::new(verify_half, self.policy))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::VerifyingKey;
+
+    use super::*;
+    use crate::{signature::new_ed25519, Attestation, SignHalf};
+
+    const ID: ReplicaId = ReplicaId::first();
+    const MESSAGE: &[u8] = b"message";
+
+    #[test]
+    fn pinned_keys() {
+        let (mut pinned, _) = new_ed25519().split();
+        let (mut unknown, _) = new_ed25519().split();
+        let keys = [pinned.attest().unwrap().payload];
+        let policy = |_: ReplicaId, attestation: &Attestation<VerifyingKey>| {
+            if keys.contains(&attestation.payload) {
+                Ok(())
+            } else {
+                Err(UsigError::RemoteAttestationFailed)
+            }
+        };
+
+        let mut usig = CheckedUsig::new(new_ed25519(), policy);
+        assert!(matches!(
+            usig.try_add_remote_party(ID, unknown.attest().unwrap()),
+            Err(UsigError::RemoteAttestationFailed)
+        ));
+        assert!(!usig.contains(ID));
+        assert!(usig.add_remote_party(ID, pinned.attest().unwrap()));
+        assert!(usig
+            .verify(ID, MESSAGE, &pinned.sign(MESSAGE).unwrap())
+            .is_ok());
+
+        let (_, mut verify) = usig.split();
+        assert!(!verify.add_remote_party(ReplicaId::from_u64(1), unknown.attest().unwrap()));
+        assert!(verify.add_remote_party(ReplicaId::from_u64(1), pinned.attest().unwrap()));
+    }
+
+    #[test]
+    fn accept_all() {
+        let (mut sign, verify) = new_ed25519().split();
+        let mut verify = CheckedVerifyHalf::new(verify, AcceptAll);
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+        assert!(verify
+            .verify(ID, MESSAGE, &sign.sign(MESSAGE).unwrap())
+            .is_ok());
+    }
+}
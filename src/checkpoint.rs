@@ -0,0 +1,279 @@
+use std::{any::Any, collections::HashMap};
+
+use serde::{Deserialize, Serialize};
+use shared_ids::ReplicaId;
+
+use crate::{
+    encoding::with_context,
+    lazy::{digest, AttestationDigest},
+    Count, Counter, SignHalf, UsigError, VerifyHalf,
+};
+
+/// Context of the anchor and predecessor a [Checkpoint] signs
+pub const CHECKPOINT_CONTEXT: &[u8] = b"usig checkpoint";
+
+fn checkpoint_message(anchor: &AttestationDigest, previous: Option<Count>) -> Vec<u8> {
+    let mut message = anchor.to_vec();
+    match previous {
+        Some(Count(previous)) => {
+            message.push(1);
+            message.extend_from_slice(&previous.to_be_bytes());
+        }
+        None => message.push(0),
+    }
+    with_context(CHECKPOINT_CONTEXT, &message)
+}
+
+/// A cheap statement of the counter chained to the last full attestation
+///
+/// Each checkpoint names the counter of its predecessor, so a peer seeing the whole
+/// chain knows how many signatures were issued in between without a new quote.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint<S> {
+    /// Digest of the full attestation the chain starts at
+    pub anchor: AttestationDigest,
+    /// Counter of the previous checkpoint, [None] for the first one after the anchor
+    pub previous: Option<Count>,
+    pub signature: S,
+}
+
+impl<S: Counter> Checkpoint<S> {
+    pub fn counter(&self) -> Count {
+        self.signature.counter()
+    }
+}
+
+/// A sign half that issues [Checkpoint]s between full attestations
+///
+/// Every call to [SignHalf::attest] produces a full attestation the chain is anchored at
+/// from then on. The chain carries on, the first checkpoint after it names the last one
+/// before it, so a new attestation can not hide the signatures issued since.
+#[derive(Debug)]
+pub struct CheckpointSignHalf<S> {
+    inner: S,
+    anchor: Option<AttestationDigest>,
+    previous: Option<Count>,
+}
+
+impl<S: SignHalf> CheckpointSignHalf<S>
+where
+    S::Attestation: Serialize,
+{
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            anchor: None,
+            previous: None,
+        }
+    }
+
+    /// Issue the next checkpoint of the chain
+    ///
+    /// Fails with [UsigError::SigningFailed] before the first full attestation.
+    pub fn checkpoint(&mut self) -> Result<Checkpoint<S::Signature>, UsigError> {
+        let anchor = self.anchor.ok_or(UsigError::SigningFailed)?;
+        let signature = self
+            .inner
+            .sign(checkpoint_message(&anchor, self.previous))?;
+        let checkpoint = Checkpoint {
+            anchor,
+            previous: self.previous,
+            signature,
+        };
+        self.previous = Some(checkpoint.counter());
+        Ok(checkpoint)
+    }
+}
+
+impl<S: SignHalf> SignHalf for CheckpointSignHalf<S>
+where
+    S::Attestation: Serialize,
+{
+    type Signature = S::Signature;
+    type Attestation = S::Attestation;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        self.inner.sign(message)
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        let attestation = self.inner.attest()?;
+        self.anchor = Some(digest(&attestation));
+        Ok(attestation)
+    }
+
+    fn local_id(&self) -> Option<ReplicaId> {
+        self.inner.local_id()
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any()
+    }
+
+    /// `None`, signatures of the backend would be missing from the chain
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        None
+    }
+}
+
+/// Follows the checkpoint chain of each remote party
+#[derive(Debug, Clone, Default)]
+pub struct CheckpointChains {
+    chains: HashMap<ReplicaId, (AttestationDigest, Option<Count>)>,
+}
+
+impl CheckpointChains {
+    /// Anchor the chain of `id` at a full attestation the caller already verified
+    ///
+    /// The chain carries on from the last accepted checkpoint, if any.
+    pub fn anchor<A: Serialize>(&mut self, id: ReplicaId, attestation: &A) {
+        let previous = self.latest(id);
+        self.chains.insert(id, (digest(attestation), previous));
+    }
+
+    /// Forget the chain of `id`, e.g. when the party is removed
+    pub fn remove(&mut self, id: ReplicaId) -> bool {
+        self.chains.remove(&id).is_some()
+    }
+
+    /// Counter of the last checkpoint of `id` accepted in the current chain
+    pub fn latest(&self, id: ReplicaId) -> Option<Count> {
+        self.chains.get(&id).and_then(|(_, previous)| *previous)
+    }
+
+    /// Verify the next checkpoint of `id` and advance its chain
+    ///
+    /// A checkpoint that does not follow the last accepted one is rejected with
+    /// [UsigError::StaleCounter] if it goes back, and with
+    /// [UsigError::RemoteAttestationFailed] if the chain is broken otherwise,
+    /// in which case a new full attestation is needed.
+    pub fn verify<V: VerifyHalf>(
+        &mut self,
+        verifier: &V,
+        id: ReplicaId,
+        checkpoint: &Checkpoint<V::Signature>,
+    ) -> Result<Count, UsigError> {
+        let (anchor, previous) = self.chains.get(&id).ok_or(UsigError::UnknownId(id))?;
+        if checkpoint.anchor != *anchor {
+            return Err(UsigError::RemoteAttestationFailed);
+        }
+        let counter = checkpoint.counter();
+        if let Some(last) = previous.filter(|previous| counter <= *previous) {
+            return Err(UsigError::StaleCounter { id, last, counter });
+        }
+        if checkpoint.previous != *previous
+            || checkpoint
+                .previous
+                .is_some_and(|previous| counter <= previous)
+        {
+            return Err(UsigError::RemoteAttestationFailed);
+        }
+        verifier.verify(
+            id,
+            checkpoint_message(&checkpoint.anchor, checkpoint.previous),
+            &checkpoint.signature,
+        )?;
+        self.chains.insert(id, (*anchor, Some(counter)));
+        Ok(counter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{signature::new_ed25519, Usig};
+
+    const ID: ReplicaId = ReplicaId::first();
+    const MESSAGE: &[u8] = b"message";
+
+    #[test]
+    fn chain() {
+        let (sign, mut verify) = new_ed25519().split();
+        let mut sign = CheckpointSignHalf::new(sign);
+        assert!(matches!(sign.checkpoint(), Err(UsigError::SigningFailed)));
+
+        let attestation = sign.attest().unwrap();
+        let mut chains = CheckpointChains::default();
+        chains.anchor(ID, &attestation);
+        assert!(verify.add_remote_party(ID, attestation));
+
+        let first = sign.checkpoint().unwrap();
+        sign.sign(MESSAGE).unwrap();
+        sign.sign(MESSAGE).unwrap();
+        let second = sign.checkpoint().unwrap();
+        assert_eq!(second.previous, Some(first.counter()));
+
+        assert_eq!(chains.verify(&verify, ID, &first).unwrap(), first.counter());
+        assert!(matches!(
+            chains.verify(&verify, ID, &first),
+            Err(UsigError::StaleCounter { .. })
+        ));
+        assert_eq!(
+            chains.verify(&verify, ID, &second).unwrap(),
+            second.counter()
+        );
+        assert_eq!(chains.latest(ID), Some(second.counter()));
+    }
+
+    #[test]
+    fn attest_carries_chain() {
+        let (sign, mut verify) = new_ed25519().split();
+        let mut sign = CheckpointSignHalf::new(sign);
+        let attestation = sign.attest().unwrap();
+        let mut chains = CheckpointChains::default();
+        chains.anchor(ID, &attestation);
+        assert!(verify.add_remote_party(ID, attestation));
+
+        let first = sign.checkpoint().unwrap();
+        assert!(chains.verify(&verify, ID, &first).is_ok());
+        let attestation = sign.attest().unwrap();
+        chains.anchor(ID, &attestation);
+        let second = sign.checkpoint().unwrap();
+        assert_eq!(second.previous, Some(first.counter()));
+        assert!(chains.verify(&verify, ID, &second).is_ok());
+
+        // A checkpoint hidden behind a new attestation still breaks the chain
+        sign.checkpoint().unwrap();
+        let attestation = sign.attest().unwrap();
+        chains.anchor(ID, &attestation);
+        let after = sign.checkpoint().unwrap();
+        assert!(matches!(
+            chains.verify(&verify, ID, &after),
+            Err(UsigError::RemoteAttestationFailed)
+        ));
+        assert_eq!(chains.latest(ID), Some(second.counter()));
+
+        assert!(chains.remove(ID));
+        assert_eq!(chains.latest(ID), None);
+    }
+
+    #[test]
+    fn broken_chain() {
+        let (sign, mut verify) = new_ed25519().split();
+        let mut sign = CheckpointSignHalf::new(sign);
+        let attestation = sign.attest().unwrap();
+        let mut chains = CheckpointChains::default();
+        chains.anchor(ID, &attestation);
+        assert!(verify.add_remote_party(ID, attestation));
+
+        sign.checkpoint().unwrap();
+        let skipped = sign.checkpoint().unwrap();
+        assert!(matches!(
+            chains.verify(&verify, ID, &skipped),
+            Err(UsigError::RemoteAttestationFailed)
+        ));
+
+        let mut forged = skipped;
+        forged.previous = None;
+        assert!(matches!(
+            chains.verify(&verify, ID, &forged),
+            Err(UsigError::InvalidSignature)
+        ));
+    }
+}
@@ -0,0 +1,100 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// The source of time for all time dependent features
+///
+/// Deterministic simulation frameworks can plug in their own clock to control time.
+pub trait Clock {
+    /// The current time as the duration since the epoch of the clock
+    fn now(&self) -> Duration;
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> Duration {
+        (**self).now()
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    fn now(&self) -> Duration {
+        (**self).now()
+    }
+}
+
+/// The wall clock of the system, its epoch is the UNIX epoch
+///
+/// There is no system clock on wasm32-unknown-unknown, plug in a clock backed by the
+/// host, e.g. `Date.now()` in browsers, there.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+    }
+}
+
+/// A clock that only advances when told to
+///
+/// Clones share the same time.
+#[derive(Debug, Default, Clone)]
+pub struct ManualClock {
+    nanos: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    pub fn new(now: Duration) -> Self {
+        let clock = Self::default();
+        clock.set(now);
+        clock
+    }
+
+    /// Set the current time
+    pub fn set(&self, now: Duration) {
+        self.nanos.store(
+            now.as_nanos().try_into().unwrap_or(u64::MAX),
+            Ordering::SeqCst,
+        );
+    }
+
+    /// Move the current time forward
+    pub fn advance(&self, by: Duration) {
+        self.set(self.now().saturating_add(by));
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::SeqCst))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manual() {
+        let clock = ManualClock::new(Duration::from_secs(10));
+        let shared = clock.clone();
+        assert_eq!(clock.now(), Duration::from_secs(10));
+        shared.advance(Duration::from_millis(5));
+        assert_eq!(clock.now(), Duration::from_millis(10_005));
+        clock.set(Duration::ZERO);
+        assert_eq!(shared.now(), Duration::ZERO);
+    }
+
+    #[test]
+    fn system() {
+        let before = SystemClock.now();
+        assert!(before > Duration::ZERO);
+        assert!(SystemClock.now() >= before);
+    }
+}
@@ -0,0 +1,56 @@
+//! USIG built on AES-CMAC, for targets with AES but without fast SHA-2 hardware
+//!
+//! The MAC backend of [crate::hmac] is generic over the MAC, so this module only
+//! names its instantiations with CMAC.
+
+use aes::{Aes128, Aes256};
+use cmac::Cmac;
+
+use crate::{
+    directory::MemoryDirectory,
+    hmac::{UsigHmac, UsigHmacSignHalf, UsigHmacVerifyHalf, VerifyKey},
+};
+
+pub type UsigCmac<C, D = MemoryDirectory<VerifyKey<Cmac<C>>>> = UsigHmac<Cmac<C>, D>;
+pub type UsigCmacSignHalf<C> = UsigHmacSignHalf<Cmac<C>>;
+pub type UsigCmacVerifyHalf<C, D = MemoryDirectory<VerifyKey<Cmac<C>>>> =
+    UsigHmacVerifyHalf<Cmac<C>, D>;
+
+pub type UsigCmacAes128 = UsigCmac<Aes128>;
+pub type UsigCmacAes256 = UsigCmac<Aes256>;
+
+#[cfg(test)]
+mod tests {
+    use crate::tests;
+
+    use crate as usig;
+
+    use super::{UsigCmacAes128, UsigCmacAes256};
+
+    use rand::{rngs::OsRng, RngCore};
+
+    fn new_key<const N: usize>() -> Box<[u8]> {
+        let mut key = [0u8; N];
+        OsRng.fill_bytes(&mut key);
+        Box::new(key)
+    }
+
+    tests!(UsigCmacAes128::try_new(new_key::<16>()).unwrap());
+
+    #[test]
+    fn key_length() {
+        assert!(UsigCmacAes128::try_new(new_key::<32>()).is_err());
+        assert!(UsigCmacAes256::try_new(new_key::<32>()).is_ok());
+    }
+
+    #[test]
+    fn key_size_mismatch() {
+        let mut usig_1 = UsigCmacAes128::try_new(new_key::<16>()).unwrap();
+        let mut usig_2 = UsigCmacAes256::try_new(new_key::<32>()).unwrap();
+        assert!(matches!(
+            usig_2.try_add_remote_party(ID, usig_1.attest().unwrap()),
+            Err(UsigError::ParameterMismatch { expected, actual })
+                if expected.scheme == "Cmac<Aes256>" && actual.scheme == "Cmac<Aes128>"
+        ));
+    }
+}
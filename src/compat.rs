@@ -0,0 +1,413 @@
+use std::collections::BTreeSet;
+
+use shared_ids::ReplicaId;
+
+use crate::{MemoryReport, SignHalf, Usig, UsigError, VerifyHalf};
+
+/// The traits as of usig 0.10, before the verify side learned to explain its decisions
+///
+/// Every current implementation also implements these, so code written against them keeps
+/// working. Implementations of these can be used as current ones through [Compat].
+pub mod v0_10 {
+    use std::fmt::Debug;
+
+    use shared_ids::ReplicaId;
+
+    use crate::{Counter, UsigError};
+
+    pub trait Usig {
+        type Signature: Debug + Counter;
+        type Attestation: Debug;
+
+        fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError>;
+
+        fn attest(&mut self) -> Result<Self::Attestation, UsigError>;
+
+        fn verify(
+            &self,
+            remote_usig_id: ReplicaId,
+            message: impl AsRef<[u8]>,
+            signature: &Self::Signature,
+        ) -> Result<(), UsigError>;
+
+        fn add_remote_party(
+            &mut self,
+            remote_usig_id: ReplicaId,
+            attestation: Self::Attestation,
+        ) -> bool;
+
+        type SignHalf: SignHalf<Signature = Self::Signature, Attestation = Self::Attestation>;
+        type VerifyHalf: VerifyHalf<Signature = Self::Signature, Attestation = Self::Attestation>;
+
+        fn split(self) -> (Self::SignHalf, Self::VerifyHalf);
+    }
+
+    pub trait SignHalf {
+        type Signature: Counter;
+        type Attestation;
+
+        fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError>;
+
+        fn attest(&mut self) -> Result<Self::Attestation, UsigError>;
+    }
+
+    pub trait VerifyHalf {
+        type Signature: Counter;
+        type Attestation;
+
+        fn verify(
+            &self,
+            remote_usig_id: ReplicaId,
+            message: impl AsRef<[u8]>,
+            signature: &Self::Signature,
+        ) -> Result<(), UsigError>;
+
+        fn add_remote_party(
+            &mut self,
+            remote_usig_id: ReplicaId,
+            attestation: Self::Attestation,
+        ) -> bool;
+    }
+}
+
+impl<U: Usig> v0_10::Usig for U {
+    type Signature = U::Signature;
+    type Attestation = U::Attestation;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        Usig::sign(self, message)
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        Usig::attest(self)
+    }
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        Usig::verify(self, id, message, signature)
+    }
+
+    fn add_remote_party(&mut self, id: ReplicaId, attestation: Self::Attestation) -> bool {
+        Usig::add_remote_party(self, id, attestation)
+    }
+
+    type SignHalf = U::SignHalf;
+    type VerifyHalf = U::VerifyHalf;
+
+    fn split(self) -> (Self::SignHalf, Self::VerifyHalf) {
+        Usig::split(self)
+    }
+}
+
+impl<S: SignHalf> v0_10::SignHalf for S {
+    type Signature = S::Signature;
+    type Attestation = S::Attestation;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        SignHalf::sign(self, message)
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        SignHalf::attest(self)
+    }
+}
+
+impl<V: VerifyHalf> v0_10::VerifyHalf for V {
+    type Signature = V::Signature;
+    type Attestation = V::Attestation;
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        VerifyHalf::verify(self, id, message, signature)
+    }
+
+    fn add_remote_party(&mut self, id: ReplicaId, attestation: Self::Attestation) -> bool {
+        VerifyHalf::add_remote_party(self, id, attestation)
+    }
+}
+
+/// Makes an implementation of the [v0_10] traits usable as a current one
+///
+/// The old traits can not tell why an attestation was rejected, so it is reported as
+/// [UsigError::RemoteAttestationFailed]. They can neither enumerate nor remove parties, so
+/// the wrapper keeps the registry of the parties added through it. Pre-validation checks
+/// that registry, and removed parties are rejected by the wrapper before they reach the
+/// wrapped implementation, which still holds their keys.
+#[derive(Debug, Clone, Default)]
+pub struct Compat<T> {
+    inner: T,
+    parties: BTreeSet<ReplicaId>,
+}
+
+impl<T> Compat<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            parties: BTreeSet::new(),
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn known(&self, id: ReplicaId) -> Result<(), UsigError> {
+        if self.parties.contains(&id) {
+            Ok(())
+        } else {
+            Err(UsigError::UnknownId(id))
+        }
+    }
+
+    fn added(&mut self, id: ReplicaId, added: bool) -> Result<(), UsigError> {
+        if added {
+            self.parties.insert(id);
+            Ok(())
+        } else {
+            Err(UsigError::RemoteAttestationFailed)
+        }
+    }
+
+    fn memory_report(&self) -> MemoryReport {
+        MemoryReport {
+            party_registry: self.parties.len() * size_of::<ReplicaId>(),
+            ..Default::default()
+        }
+    }
+}
+
+impl<U: v0_10::Usig> Usig for Compat<U> {
+    type Signature = U::Signature;
+    type Attestation = U::Attestation;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        self.inner.sign(message)
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        self.inner.attest()
+    }
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        self.known(id)?;
+        self.inner.verify(id, message, signature)
+    }
+
+    fn pre_validate(&self, id: ReplicaId, _signature: &Self::Signature) -> Result<(), UsigError> {
+        self.known(id)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        self.memory_report()
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        let added = self.inner.add_remote_party(id, attestation);
+        self.added(id, added)
+    }
+
+    fn remove_remote_party(&mut self, id: ReplicaId) -> bool {
+        self.parties.remove(&id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.parties.iter().copied()
+    }
+
+    type SignHalf = Compat<U::SignHalf>;
+    type VerifyHalf = Compat<U::VerifyHalf>;
+
+    fn split(self) -> (Self::SignHalf, Self::VerifyHalf) {
+        let (sign_half, verify_half) = self.inner.split();
+        (
+            Compat::new(sign_half),
+            Compat {
+                inner: verify_half,
+                parties: self.parties,
+            },
+        )
+    }
+}
+
+impl<S: v0_10::SignHalf> SignHalf for Compat<S> {
+    type Signature = S::Signature;
+    type Attestation = S::Attestation;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        self.inner.sign(message)
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        self.inner.attest()
+    }
+}
+
+impl<V: v0_10::VerifyHalf> VerifyHalf for Compat<V> {
+    type Signature = V::Signature;
+    type Attestation = V::Attestation;
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        self.known(id)?;
+        self.inner.verify(id, message, signature)
+    }
+
+    fn pre_validate(&self, id: ReplicaId, _signature: &Self::Signature) -> Result<(), UsigError> {
+        self.known(id)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        self.memory_report()
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        let added = self.inner.add_remote_party(id, attestation);
+        self.added(id, added)
+    }
+
+    fn remove_remote_party(&mut self, id: ReplicaId) -> bool {
+        self.parties.remove(&id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.parties.iter().copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::{hmac::UsigHmac, noop::Signature};
+
+    const ID: ReplicaId = ReplicaId::first();
+    const MESSAGE: &[u8] = b"message";
+
+    /// A backend written against the old traits, signing with plain counters
+    #[derive(Debug, Default)]
+    struct Legacy {
+        counter: u64,
+        parties: HashSet<ReplicaId>,
+    }
+
+    impl v0_10::SignHalf for Legacy {
+        type Signature = Signature;
+        type Attestation = ();
+
+        fn sign(&mut self, _message: impl AsRef<[u8]>) -> Result<Signature, UsigError> {
+            self.counter += 1;
+            Ok(Signature::fake(self.counter - 1))
+        }
+
+        fn attest(&mut self) -> Result<(), UsigError> {
+            Ok(())
+        }
+    }
+
+    impl v0_10::VerifyHalf for Legacy {
+        type Signature = Signature;
+        type Attestation = ();
+
+        fn verify(
+            &self,
+            id: ReplicaId,
+            _message: impl AsRef<[u8]>,
+            _signature: &Signature,
+        ) -> Result<(), UsigError> {
+            if self.parties.contains(&id) {
+                Ok(())
+            } else {
+                Err(UsigError::UnknownId(id))
+            }
+        }
+
+        fn add_remote_party(&mut self, id: ReplicaId, _attestation: ()) -> bool {
+            self.parties.insert(id)
+        }
+    }
+
+    fn legacy_sign<S: v0_10::SignHalf>(sign_half: &mut S) -> S::Signature {
+        sign_half.sign(MESSAGE).unwrap()
+    }
+
+    #[test]
+    fn old_implementation_as_current() {
+        let mut verify = Compat::new(Legacy::default()).enforce_monotonicity();
+        let mut sign = Compat::new(Legacy::default());
+        SignHalf::attest(&mut sign).unwrap();
+        assert!(verify.add_remote_party(ID, ()));
+        assert!(matches!(
+            verify.try_add_remote_party(ID, ()),
+            Err(UsigError::RemoteAttestationFailed)
+        ));
+
+        let signature = SignHalf::sign(&mut sign, MESSAGE).unwrap();
+        assert!(VerifyHalf::verify(&verify, ID, MESSAGE, &signature).is_ok());
+        assert!(matches!(
+            VerifyHalf::verify(&verify, ID, MESSAGE, &signature),
+            Err(UsigError::StaleCounter { .. })
+        ));
+    }
+
+    #[test]
+    fn registry() {
+        let mut verify = Compat::new(Legacy::default());
+        let signature = Signature::fake(0);
+        assert!(matches!(
+            verify.pre_validate(ID, &signature),
+            Err(UsigError::UnknownId(ID))
+        ));
+        assert!(!verify.contains(ID));
+
+        assert!(verify.add_remote_party(ID, ()));
+        assert!(verify.contains(ID));
+        assert_eq!(verify.remote_parties().collect::<Vec<_>>(), [ID]);
+        assert!(verify.pre_validate(ID, &signature).is_ok());
+
+        assert!(verify.remove_remote_party(ID));
+        assert!(!verify.contains(ID));
+        assert!(matches!(
+            VerifyHalf::verify(&verify, ID, MESSAGE, &signature),
+            Err(UsigError::UnknownId(ID))
+        ));
+    }
+
+    #[test]
+    fn current_implementation_as_old() {
+        let usig = UsigHmac::<hmac::Hmac<sha2::Sha256>>::try_new(Box::new([3; 16])).unwrap();
+        let (mut sign, mut verify) = v0_10::Usig::split(usig);
+        assert!(v0_10::VerifyHalf::add_remote_party(
+            &mut verify,
+            ID,
+            v0_10::SignHalf::attest(&mut sign).unwrap()
+        ));
+        let signature = legacy_sign(&mut sign);
+        assert!(v0_10::VerifyHalf::verify(&verify, ID, MESSAGE, &signature).is_ok());
+    }
+}
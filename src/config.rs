@@ -0,0 +1,164 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use shared_ids::ReplicaId;
+
+use crate::{
+    encoding::with_context, AlgorithmParameters, Count, Counter, SignHalf, UsigError, VerifyHalf,
+};
+
+/// Context of the config digest a [ConfigBeacon] signs
+pub const CONFIG_CONTEXT: &[u8] = b"usig config beacon";
+
+/// SHA-256 of a [ClusterConfig]
+pub type ConfigDigest = [u8; 32];
+
+fn config_message(digest: &ConfigDigest) -> Vec<u8> {
+    with_context(CONFIG_CONTEXT, digest)
+}
+
+/// The configuration all replicas of a cluster have to agree on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClusterConfig {
+    pub members: BTreeSet<ReplicaId>,
+    pub parameters: AlgorithmParameters,
+    /// Sizes of windows and other tunables by name, e.g. the watermark window
+    pub settings: BTreeMap<String, u64>,
+}
+
+impl ClusterConfig {
+    pub fn digest(&self) -> ConfigDigest {
+        let encoded = bincode::serialize(self).expect("serialization to memory does not fail");
+        Sha256::digest(encoded).into()
+    }
+}
+
+/// The signed digest of the configuration a replica is running with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigBeacon<S> {
+    pub digest: ConfigDigest,
+    pub signature: S,
+}
+
+/// Sign the digest of `config` to announce it to the peers
+pub fn announce<S: SignHalf>(
+    sign_half: &mut S,
+    config: &ClusterConfig,
+) -> Result<ConfigBeacon<S::Signature>, UsigError> {
+    let digest = config.digest();
+    let signature = sign_half.sign(config_message(&digest))?;
+    Ok(ConfigBeacon { digest, signature })
+}
+
+/// Compares the beacons of the peers with the local configuration
+///
+/// Keeps the latest beacon of every peer, so a peer that diverged is flagged until it
+/// announces the local configuration again.
+#[derive(Debug, Clone)]
+pub struct ConfigMonitor {
+    local: ConfigDigest,
+    latest: BTreeMap<ReplicaId, (Count, ConfigDigest)>,
+}
+
+impl ConfigMonitor {
+    pub fn new(local: &ClusterConfig) -> Self {
+        Self {
+            local: local.digest(),
+            latest: BTreeMap::new(),
+        }
+    }
+
+    /// Switch to a new local configuration, e.g. after a reconfiguration
+    pub fn reconfigure(&mut self, local: &ClusterConfig) {
+        self.local = local.digest();
+    }
+
+    /// Verify a beacon of `id` and return whether it agrees with the local configuration
+    ///
+    /// Beacons older than the latest one seen of `id` are rejected with [UsigError::StaleCounter].
+    pub fn observe<S: Counter, V: VerifyHalf<Signature = S>>(
+        &mut self,
+        verifier: &V,
+        id: ReplicaId,
+        beacon: &ConfigBeacon<S>,
+    ) -> Result<bool, UsigError> {
+        verifier.verify(id, config_message(&beacon.digest), &beacon.signature)?;
+        let counter = beacon.signature.counter();
+        if let Some((latest, _)) = self.latest.get(&id) {
+            if counter <= *latest {
+                return Err(UsigError::StaleCounter {
+                    id,
+                    last: *latest,
+                    counter,
+                });
+            }
+        }
+        self.latest.insert(id, (counter, beacon.digest));
+        Ok(beacon.digest == self.local)
+    }
+
+    /// The peers whose latest beacon announced a different configuration
+    pub fn divergent(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.latest
+            .iter()
+            .filter(|(_, (_, digest))| *digest != self.local)
+            .map(|(id, _)| *id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{detached::Algorithm, signature::new_ed25519, Usig};
+
+    const ID: ReplicaId = ReplicaId::first();
+
+    fn config(window: u64) -> ClusterConfig {
+        ClusterConfig {
+            members: (0..4).map(ReplicaId::from_u64).collect(),
+            parameters: Algorithm::Ed25519.parameters(),
+            settings: [("window".to_owned(), window)].into(),
+        }
+    }
+
+    #[test]
+    fn divergence() {
+        let (mut sign, mut verify) = new_ed25519().split();
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+        let mut monitor = ConfigMonitor::new(&config(100));
+
+        let beacon = announce(&mut sign, &config(100)).unwrap();
+        assert!(monitor.observe(&verify, ID, &beacon).unwrap());
+        assert_eq!(monitor.divergent().count(), 0);
+
+        let beacon = announce(&mut sign, &config(200)).unwrap();
+        assert!(!monitor.observe(&verify, ID, &beacon).unwrap());
+        assert_eq!(monitor.divergent().collect::<Vec<_>>(), vec![ID]);
+
+        monitor.reconfigure(&config(200));
+        assert_eq!(monitor.divergent().count(), 0);
+    }
+
+    #[test]
+    fn stale_and_forged() {
+        let (mut sign, mut verify) = new_ed25519().split();
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+        let mut monitor = ConfigMonitor::new(&config(100));
+
+        let old = announce(&mut sign, &config(100)).unwrap();
+        let new = announce(&mut sign, &config(200)).unwrap();
+        assert!(!monitor.observe(&verify, ID, &new).unwrap());
+        assert!(matches!(
+            monitor.observe(&verify, ID, &old),
+            Err(UsigError::StaleCounter { .. })
+        ));
+
+        let mut forged = announce(&mut sign, &config(200)).unwrap();
+        forged.digest = config(100).digest();
+        assert!(matches!(
+            monitor.observe(&verify, ID, &forged),
+            Err(UsigError::InvalidSignature)
+        ));
+    }
+}
@@ -0,0 +1,372 @@
+use std::{
+    fmt,
+    panic::{self, AssertUnwindSafe},
+};
+
+use shared_ids::ReplicaId;
+
+use crate::{Counter, SignHalf, Usig, UsigError, VerifyHalf};
+
+const MESSAGE_EMPTY: &[u8] = b"";
+const MESSAGE_1: &[u8] = b"message one";
+const MESSAGE_2: &[u8] = b"message two";
+const ID: ReplicaId = ReplicaId::first();
+
+/// A single requirement every [Usig] implementation has to meet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Requirement {
+    /// A short stable identifier
+    pub id: &'static str,
+    pub description: &'static str,
+}
+
+/// The result of checking a [Requirement]
+#[derive(Debug, Clone)]
+pub struct RequirementResult {
+    pub requirement: Requirement,
+    /// Why the requirement is not met, [None] if it is
+    pub failure: Option<String>,
+}
+
+impl RequirementResult {
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// The results of checking all requirements against a backend
+#[derive(Debug, Clone)]
+pub struct ConformanceReport {
+    pub results: Vec<RequirementResult>,
+}
+
+impl ConformanceReport {
+    /// Whether all requirements are met
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(RequirementResult::passed)
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &RequirementResult> {
+        self.results.iter().filter(|r| !r.passed())
+    }
+}
+
+impl fmt::Display for ConformanceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for result in &self.results {
+            let Requirement { id, description } = result.requirement;
+            match &result.failure {
+                None => writeln!(f, "PASS {}: {}", id, description)?,
+                Some(failure) => writeln!(f, "FAIL {}: {}: {}", id, description, failure)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+type Check<U> = fn(&dyn Fn() -> U) -> Result<(), String>;
+
+macro_rules! ensure {
+    ($cond:expr, $($msg:tt)+) => {
+        if !$cond {
+            return Err(format!($($msg)+));
+        }
+    };
+}
+
+fn ok<T>(result: Result<T, UsigError>, what: &str) -> Result<T, String> {
+    result.map_err(|e| format!("{} failed: {}", what, e))
+}
+
+fn registered<U: Usig>(factory: &dyn Fn() -> U) -> Result<U, String> {
+    let mut usig = factory();
+    let attestation = ok(usig.attest(), "attest")?;
+    ok(
+        usig.try_add_remote_party(ID, attestation),
+        "add_remote_party",
+    )?;
+    Ok(usig)
+}
+
+fn is_invalid(result: Result<(), UsigError>) -> bool {
+    matches!(result, Err(UsigError::InvalidSignature))
+}
+
+fn is_unknown(result: Result<(), UsigError>, id: ReplicaId) -> bool {
+    matches!(result, Err(UsigError::UnknownId(unknown)) if unknown == id)
+}
+
+fn sign_verify<U: Usig>(factory: &dyn Fn() -> U) -> Result<(), String> {
+    let mut usig = registered(factory)?;
+    let signature = ok(usig.sign(MESSAGE_1), "sign")?;
+    ok(usig.verify(ID, MESSAGE_1, &signature), "verify")
+}
+
+fn empty_message<U: Usig>(factory: &dyn Fn() -> U) -> Result<(), String> {
+    let mut usig = registered(factory)?;
+    let signature = ok(usig.sign(MESSAGE_EMPTY), "sign")?;
+    ok(usig.verify(ID, MESSAGE_EMPTY, &signature), "verify")
+}
+
+fn consecutive_counters<U: Usig>(factory: &dyn Fn() -> U) -> Result<(), String> {
+    let mut usig = factory();
+    let mut previous = ok(usig.sign(MESSAGE_1), "sign")?.counter();
+    for _ in 0..100 {
+        let counter = ok(usig.sign(MESSAGE_1), "sign")?.counter();
+        ensure!(
+            counter == previous + 1,
+            "counter {} followed {}",
+            counter,
+            previous
+        );
+        previous = counter;
+    }
+    Ok(())
+}
+
+fn attest_after_sign<U: Usig>(factory: &dyn Fn() -> U) -> Result<(), String> {
+    let mut usig = factory();
+    let signature = ok(usig.sign(MESSAGE_1), "sign")?;
+    let attestation = ok(usig.attest(), "attest")?;
+    ok(
+        usig.try_add_remote_party(ID, attestation),
+        "add_remote_party",
+    )?;
+    ok(usig.verify(ID, MESSAGE_1, &signature), "verify")
+}
+
+fn unknown_id<U: Usig>(factory: &dyn Fn() -> U) -> Result<(), String> {
+    let mut usig = factory();
+    let signature = ok(usig.sign(MESSAGE_1), "sign")?;
+    ensure!(
+        is_unknown(usig.verify(ID, MESSAGE_1, &signature), ID),
+        "verification for an unregistered party did not fail with UnknownId"
+    );
+    Ok(())
+}
+
+fn wrong_id<U: Usig>(factory: &dyn Fn() -> U) -> Result<(), String> {
+    let other = ReplicaId::from_u64(1);
+    let mut usig = factory();
+    let attestation = ok(usig.attest(), "attest")?;
+    ok(
+        usig.try_add_remote_party(other, attestation),
+        "add_remote_party",
+    )?;
+    let signature = ok(usig.sign(MESSAGE_1), "sign")?;
+    ok(usig.verify(other, MESSAGE_1, &signature), "verify")?;
+    ensure!(
+        is_unknown(usig.verify(ID, MESSAGE_1, &signature), ID),
+        "verification under another id did not fail with UnknownId"
+    );
+    Ok(())
+}
+
+fn wrong_key<U: Usig>(factory: &dyn Fn() -> U) -> Result<(), String> {
+    let mut signer = factory();
+    let verifier = registered(factory)?;
+    let signature = ok(signer.sign(MESSAGE_1), "sign")?;
+    ensure!(
+        is_invalid(verifier.verify(ID, MESSAGE_1, &signature)),
+        "signature of another USIG did not fail with InvalidSignature"
+    );
+    Ok(())
+}
+
+fn wrong_message<U: Usig>(factory: &dyn Fn() -> U) -> Result<(), String> {
+    let mut usig = registered(factory)?;
+    let signature = ok(usig.sign(MESSAGE_1), "sign")?;
+    ensure!(
+        is_invalid(usig.verify(ID, MESSAGE_2, &signature)),
+        "signature over another message did not fail with InvalidSignature"
+    );
+    Ok(())
+}
+
+fn id_overwrite<U: Usig>(factory: &dyn Fn() -> U) -> Result<(), String> {
+    let mut usig_1 = factory();
+    let mut usig_2 = factory();
+    let mut verifier = factory();
+    let signature_1 = ok(usig_1.sign(MESSAGE_1), "sign")?;
+    let signature_2 = ok(usig_2.sign(MESSAGE_2), "sign")?;
+
+    let attestation = ok(usig_1.attest(), "attest")?;
+    ok(
+        verifier.try_add_remote_party(ID, attestation),
+        "add_remote_party",
+    )?;
+    ok(verifier.verify(ID, MESSAGE_1, &signature_1), "verify")?;
+
+    let attestation = ok(usig_2.attest(), "attest")?;
+    ok(
+        verifier.try_add_remote_party(ID, attestation),
+        "add_remote_party",
+    )?;
+    ok(verifier.verify(ID, MESSAGE_2, &signature_2), "verify")?;
+    ensure!(
+        is_invalid(verifier.verify(ID, MESSAGE_1, &signature_1)),
+        "the replaced attestation is still used"
+    );
+    Ok(())
+}
+
+fn mixed_parties<U: Usig>(factory: &dyn Fn() -> U) -> Result<(), String> {
+    let (id_1, id_2) = (ReplicaId::from_u64(1), ReplicaId::from_u64(2));
+    let mut usig_1 = factory();
+    let mut usig_2 = factory();
+    let mut verifier = factory();
+    let attestation = ok(usig_1.attest(), "attest")?;
+    ok(
+        verifier.try_add_remote_party(id_1, attestation),
+        "add_remote_party",
+    )?;
+    let attestation = ok(usig_2.attest(), "attest")?;
+    ok(
+        verifier.try_add_remote_party(id_2, attestation),
+        "add_remote_party",
+    )?;
+
+    let signature_1 = ok(usig_1.sign(MESSAGE_1), "sign")?;
+    let signature_2 = ok(usig_2.sign(MESSAGE_2), "sign")?;
+    ok(verifier.verify(id_1, MESSAGE_1, &signature_1), "verify")?;
+    ok(verifier.verify(id_2, MESSAGE_2, &signature_2), "verify")?;
+    ensure!(
+        is_invalid(verifier.verify(id_2, MESSAGE_1, &signature_1)),
+        "signature accepted for the wrong party"
+    );
+    ensure!(
+        is_invalid(verifier.verify(id_1, MESSAGE_2, &signature_2)),
+        "signature accepted for the wrong party"
+    );
+    Ok(())
+}
+
+fn split<U: Usig>(factory: &dyn Fn() -> U) -> Result<(), String> {
+    let mut usig = factory();
+    let before = ok(usig.sign(MESSAGE_1), "sign")?;
+    let (mut sign, mut verify) = usig.split();
+    let attestation = ok(sign.attest(), "attest")?;
+    ok(
+        verify.try_add_remote_party(ID, attestation),
+        "add_remote_party",
+    )?;
+    let after = ok(sign.sign(MESSAGE_2), "sign")?;
+    ensure!(
+        before.counter().checked_add(1) == Some(after.counter()),
+        "counter {} after split followed {}",
+        after.counter(),
+        before.counter()
+    );
+    ok(verify.verify(ID, MESSAGE_1, &before), "verify")?;
+    ok(verify.verify(ID, MESSAGE_2, &after), "verify")
+}
+
+fn requirements<U: Usig>() -> Vec<(Requirement, Check<U>)> {
+    macro_rules! requirement {
+        ($id:literal, $description:literal, $check:ident) => {
+            (
+                Requirement {
+                    id: $id,
+                    description: $description,
+                },
+                $check::<U> as Check<U>,
+            )
+        };
+    }
+
+    vec![
+        requirement!("sign-verify", "own signatures verify", sign_verify),
+        requirement!(
+            "empty-message",
+            "signatures over empty messages verify",
+            empty_message
+        ),
+        requirement!(
+            "consecutive-counters",
+            "each signature uses the next counter value",
+            consecutive_counters
+        ),
+        requirement!(
+            "attest-after-sign",
+            "signatures created before attesting verify",
+            attest_after_sign
+        ),
+        requirement!(
+            "unknown-id",
+            "unregistered parties are reported as unknown",
+            unknown_id
+        ),
+        requirement!(
+            "wrong-id",
+            "attestations only apply to the id they were added for",
+            wrong_id
+        ),
+        requirement!(
+            "wrong-key",
+            "signatures of other USIGs are invalid",
+            wrong_key
+        ),
+        requirement!(
+            "wrong-message",
+            "signatures do not verify for other messages",
+            wrong_message
+        ),
+        requirement!(
+            "id-overwrite",
+            "adding a party again replaces its attestation",
+            id_overwrite
+        ),
+        requirement!(
+            "mixed-parties",
+            "signatures only verify for their own party",
+            mixed_parties
+        ),
+        requirement!(
+            "split",
+            "split halves continue the counter and verify",
+            split
+        ),
+    ]
+}
+
+/// Check all requirements against the backend created by `factory`
+///
+/// Each requirement gets fresh instances, panics are reported as failures.
+pub fn run_conformance<U: Usig>(factory: impl Fn() -> U) -> ConformanceReport {
+    let results = requirements::<U>()
+        .into_iter()
+        .map(|(requirement, check)| {
+            let failure = match panic::catch_unwind(AssertUnwindSafe(|| check(&factory))) {
+                Ok(Ok(())) => None,
+                Ok(Err(failure)) => Some(failure),
+                Err(_) => Some("panicked".to_owned()),
+            };
+            RequirementResult {
+                requirement,
+                failure,
+            }
+        })
+        .collect();
+    ConformanceReport { results }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::noop::UsigNoOp;
+
+    #[test]
+    fn noop_violates_key_requirements() {
+        let report = run_conformance(UsigNoOp::default);
+        let failed: Vec<_> = report.failures().map(|r| r.requirement.id).collect();
+        assert_eq!(
+            failed,
+            [
+                "wrong-key",
+                "wrong-message",
+                "id-overwrite",
+                "mixed-parties"
+            ]
+        );
+    }
+}
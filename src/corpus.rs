@@ -0,0 +1,377 @@
+use std::{
+    fmt,
+    panic::{self, AssertUnwindSafe},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+use shared_ids::ReplicaId;
+
+use crate::{Usig, UsigError};
+
+/// The message all corpus signatures are created for
+pub const MESSAGE: &[u8] = b"usig fuzz corpus message";
+
+/// The id the original attestation of a corpus is registered under by [check]
+pub const SIGNER: ReplicaId = ReplicaId::from_u64(0);
+
+/// The id mutated attestations are registered under by [check]
+pub const PROBE: ReplicaId = ReplicaId::from_u64(1);
+
+/// What a corpus entry contains
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Signature,
+    Attestation,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Kind::Signature => write!(f, "signature"),
+            Kind::Attestation => write!(f, "attestation"),
+        }
+    }
+}
+
+/// How a corpus entry was derived from the valid serialization
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mutation {
+    /// The valid serialization itself
+    None,
+    /// A single bit flipped
+    BitFlip { bit: usize },
+    /// Cut down to `len` bytes
+    Truncate { len: usize },
+    /// `len` copies of `byte` appended
+    Extend { len: usize, byte: u8 },
+}
+
+impl fmt::Display for Mutation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mutation::None => write!(f, "valid"),
+            Mutation::BitFlip { bit } => write!(f, "flip-{}", bit),
+            Mutation::Truncate { len } => write!(f, "truncate-{}", len),
+            Mutation::Extend { len, byte } => write!(f, "extend-{}-{:02x}", len, byte),
+        }
+    }
+}
+
+/// A serialized signature or attestation, valid or near-valid
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub kind: Kind,
+    pub mutation: Mutation,
+    pub bytes: Vec<u8>,
+}
+
+impl Entry {
+    /// A file name describing the entry
+    pub fn name(&self) -> String {
+        format!("{}-{}.bin", self.kind, self.mutation)
+    }
+}
+
+/// All near-valid variants of `bytes`: bit flips, truncations and extensions
+pub fn mutations(bytes: &[u8]) -> Vec<(Mutation, Vec<u8>)> {
+    let mut mutations = Vec::new();
+    for bit in 0..bytes.len() * 8 {
+        let mut mutated = bytes.to_vec();
+        mutated[bit / 8] ^= 1 << (bit % 8);
+        mutations.push((Mutation::BitFlip { bit }, mutated));
+    }
+    for len in 0..bytes.len() {
+        mutations.push((Mutation::Truncate { len }, bytes[..len].to_vec()));
+    }
+    for len in [1, 8, 64] {
+        for byte in [0x00, 0xff] {
+            let mut mutated = bytes.to_vec();
+            mutated.resize(bytes.len() + len, byte);
+            mutations.push((Mutation::Extend { len, byte }, mutated));
+        }
+    }
+    mutations
+}
+
+/// Generate the corpus of a backend from a signature over [MESSAGE] and an attestation of `usig`
+pub fn generate<U: Usig>(usig: &mut U) -> Result<Vec<Entry>, UsigError>
+where
+    U::Signature: Serialize,
+    U::Attestation: Serialize,
+{
+    let signature = encode(&usig.sign(MESSAGE)?);
+    let attestation = encode(&usig.attest()?);
+
+    let mut entries = Vec::new();
+    for (kind, bytes) in [
+        (Kind::Signature, signature),
+        (Kind::Attestation, attestation),
+    ] {
+        entries.extend(
+            mutations(&bytes)
+                .into_iter()
+                .map(|(mutation, bytes)| Entry {
+                    kind,
+                    mutation,
+                    bytes,
+                }),
+        );
+        entries.push(Entry {
+            kind,
+            mutation: Mutation::None,
+            bytes,
+        });
+    }
+    Ok(entries)
+}
+
+/// How a backend handled a corpus entry
+#[derive(Debug)]
+pub enum Outcome {
+    /// Decoded and accepted
+    ///
+    /// A signature is `correct` if it is the original signature.
+    /// An attestation is `correct` if the original signature verifies against it
+    /// exactly when it is the original attestation.
+    Accepted { correct: bool },
+    /// Failed to decode
+    Malformed,
+    /// Decoded but rejected by the backend
+    Rejected(UsigError),
+    /// The backend panicked
+    Panicked,
+}
+
+/// Feed all `entries` of a corpus generated by [generate] to the fresh `usig`
+///
+/// The outcomes are in the same order as the entries. Fails if `entries` lack a valid
+/// signature or attestation, or if `usig` does not accept them.
+pub fn check<U: Usig>(usig: &mut U, entries: &[Entry]) -> Result<Vec<Outcome>, UsigError>
+where
+    U::Signature: Serialize + DeserializeOwned,
+    U::Attestation: Serialize + DeserializeOwned,
+{
+    let original = |kind| {
+        entries
+            .iter()
+            .find(|e| e.kind == kind && e.mutation == Mutation::None)
+            .map(|e| e.bytes.as_slice())
+            .ok_or_else(|| {
+                UsigError::Serialization(Box::new(bincode::ErrorKind::Custom(format!(
+                    "corpus lacks the valid {}",
+                    kind
+                ))))
+            })
+    };
+    let original_signature = original(Kind::Signature)?;
+    let original_attestation = original(Kind::Attestation)?;
+    let signature: U::Signature = bincode::deserialize(original_signature)?;
+    let attestation: U::Attestation = bincode::deserialize(original_attestation)?;
+    usig.try_add_remote_party(SIGNER, attestation)?;
+
+    Ok(entries
+        .iter()
+        .map(|entry| {
+            panic::catch_unwind(AssertUnwindSafe(|| match entry.kind {
+                Kind::Signature => check_signature(usig, original_signature, &entry.bytes),
+                Kind::Attestation => {
+                    check_attestation(usig, &signature, original_attestation, &entry.bytes)
+                }
+            }))
+            .unwrap_or(Outcome::Panicked)
+        })
+        .collect())
+}
+
+fn check_signature<U: Usig>(usig: &U, original: &[u8], bytes: &[u8]) -> Outcome
+where
+    U::Signature: Serialize + DeserializeOwned,
+{
+    let Some(signature) = decode::<U::Signature>(bytes) else {
+        return Outcome::Malformed;
+    };
+    match usig.verify(SIGNER, MESSAGE, &signature) {
+        Ok(()) => Outcome::Accepted {
+            correct: encode(&signature) == original,
+        },
+        Err(e) => Outcome::Rejected(e),
+    }
+}
+
+fn check_attestation<U: Usig>(
+    usig: &mut U,
+    signature: &U::Signature,
+    original: &[u8],
+    bytes: &[u8],
+) -> Outcome
+where
+    U::Attestation: Serialize + DeserializeOwned,
+{
+    let Some(attestation) = decode::<U::Attestation>(bytes) else {
+        return Outcome::Malformed;
+    };
+    let is_original = encode(&attestation) == original;
+    match usig.try_add_remote_party(PROBE, attestation) {
+        Ok(()) => Outcome::Accepted {
+            correct: usig.verify(PROBE, MESSAGE, signature).is_ok() == is_original,
+        },
+        Err(e) => Outcome::Rejected(e),
+    }
+}
+
+fn encode<T: Serialize>(value: &T) -> Vec<u8> {
+    bincode::serialize(value).expect("serialization to memory does not fail")
+}
+
+fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Option<T> {
+    bincode::deserialize(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::Hmac;
+    use sha2::Sha256;
+
+    use super::*;
+    use crate::{
+        cmac::UsigCmacAes128,
+        hmac::UsigHmac,
+        noop::UsigNoOp,
+        signature::{new_ed25519, new_ed448, new_p256, new_secp256k1},
+        siphash::UsigSipHash,
+    };
+
+    fn assert_outcomes(entries: &[Entry], outcomes: &[Outcome], authenticated: bool) {
+        assert_eq!(entries.len(), outcomes.len());
+        for (entry, outcome) in entries.iter().zip(outcomes) {
+            match outcome {
+                Outcome::Panicked => panic!("{} panicked", entry.name()),
+                Outcome::Accepted { correct: false } if authenticated => {
+                    panic!("{} accepted but not correct", entry.name())
+                }
+                _ => {}
+            }
+        }
+        for kind in [Kind::Signature, Kind::Attestation] {
+            let valid = entries
+                .iter()
+                .position(|e| e.kind == kind && e.mutation == Mutation::None)
+                .unwrap();
+            assert!(matches!(
+                outcomes[valid],
+                Outcome::Accepted { correct: true }
+            ));
+        }
+    }
+
+    #[test]
+    fn noop() {
+        let entries = generate(&mut UsigNoOp::default()).unwrap();
+        let outcomes = check(&mut UsigNoOp::default(), &entries).unwrap();
+        // the no-op backend accepts every well formed signature
+        assert_outcomes(&entries, &outcomes, false);
+    }
+
+    #[test]
+    fn hmac() {
+        let new_usig = || UsigHmac::<Hmac<Sha256>>::try_new(Box::new([7; 16])).unwrap();
+        let entries = generate(&mut new_usig()).unwrap();
+        let outcomes = check(&mut new_usig(), &entries).unwrap();
+        assert_outcomes(&entries, &outcomes, true);
+    }
+
+    #[test]
+    fn ed25519() {
+        let entries = generate(&mut new_ed25519()).unwrap();
+        let outcomes = check(&mut new_ed25519(), &entries).unwrap();
+        assert_outcomes(&entries, &outcomes, true);
+    }
+
+    #[test]
+    fn secp256k1() {
+        let entries = generate(&mut new_secp256k1()).unwrap();
+        let outcomes = check(&mut new_secp256k1(), &entries).unwrap();
+        assert_outcomes(&entries, &outcomes, true);
+    }
+
+    #[test]
+    fn p256() {
+        let entries = generate(&mut new_p256()).unwrap();
+        let outcomes = check(&mut new_p256(), &entries).unwrap();
+        assert_outcomes(&entries, &outcomes, true);
+    }
+
+    #[test]
+    fn ed448() {
+        let entries = generate(&mut new_ed448()).unwrap();
+        let outcomes = check(&mut new_ed448(), &entries).unwrap();
+        assert_outcomes(&entries, &outcomes, true);
+    }
+
+    #[cfg(feature = "bls")]
+    #[test]
+    fn bls() {
+        use crate::bls::new_bls;
+
+        let entries = generate(&mut new_bls()).unwrap();
+        let outcomes = check(&mut new_bls(), &entries).unwrap();
+        assert_outcomes(&entries, &outcomes, true);
+    }
+
+    #[test]
+    fn cmac() {
+        let new_usig = || UsigCmacAes128::try_new(Box::new([7; 16])).unwrap();
+        let entries = generate(&mut new_usig()).unwrap();
+        let outcomes = check(&mut new_usig(), &entries).unwrap();
+        assert_outcomes(&entries, &outcomes, true);
+    }
+
+    #[test]
+    fn siphash() {
+        let entries = generate(&mut UsigSipHash::new([7; 16])).unwrap();
+        let outcomes = check(&mut UsigSipHash::new([7; 16]), &entries).unwrap();
+        assert_outcomes(&entries, &outcomes, true);
+    }
+
+    #[test]
+    fn malformed_corpus() {
+        let entries = generate(&mut new_ed25519()).unwrap();
+
+        let without_valid: Vec<_> = entries
+            .iter()
+            .filter(|e| !(e.kind == Kind::Signature && e.mutation == Mutation::None))
+            .cloned()
+            .collect();
+        assert!(matches!(
+            check(&mut new_ed25519(), &without_valid),
+            Err(UsigError::Serialization(_))
+        ));
+
+        let mut truncated = entries.clone();
+        for entry in &mut truncated {
+            if entry.kind == Kind::Attestation && entry.mutation == Mutation::None {
+                entry.bytes.truncate(1);
+            }
+        }
+        assert!(matches!(
+            check(&mut new_ed25519(), &truncated),
+            Err(UsigError::Serialization(_))
+        ));
+
+        // the valid attestation of another backend
+        let mut foreign = entries;
+        let hmac =
+            generate(&mut UsigHmac::<Hmac<Sha256>>::try_new(Box::new([7; 16])).unwrap()).unwrap();
+        for entry in &mut foreign {
+            if entry.kind == Kind::Attestation && entry.mutation == Mutation::None {
+                entry.bytes = hmac
+                    .iter()
+                    .find(|e| e.kind == Kind::Attestation && e.mutation == Mutation::None)
+                    .unwrap()
+                    .bytes
+                    .clone();
+            }
+        }
+        assert!(check(&mut new_ed25519(), &foreign).is_err());
+    }
+}
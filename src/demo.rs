@@ -0,0 +1,484 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::mpsc::{channel, Receiver, Sender},
+};
+
+use serde::{Deserialize, Serialize};
+use shared_ids::ReplicaId;
+
+use crate::{encoding::with_context, Count, Counter, Usig, UsigError};
+
+/// Context of the statements a [Replica] signs
+pub const DEMO_CONTEXT: &[u8] = b"usig demo";
+
+/// What a replica of the replicated log states in a [Message]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Statement {
+    /// The primary of `view` proposes to append `entry`
+    Prepare { view: u64, entry: Vec<u8> },
+    /// A backup accepted the prepare with counter `prepare` of the primary
+    Commit {
+        view: u64,
+        prepare: Count,
+        entry: Vec<u8>,
+    },
+    /// The sender suspects the primary and wants to move to `view`
+    ///
+    /// `last_prepare` is the counter of the last prepare the sender accepted from the old primary.
+    /// As the primary can not skip counters, it proves which prepares the old view may have committed.
+    ViewChange {
+        view: u64,
+        last_prepare: Option<Count>,
+    },
+}
+
+impl Statement {
+    fn signed_message(&self) -> Vec<u8> {
+        let statement = bincode::serialize(self).expect("serialization to memory does not fail");
+        with_context(DEMO_CONTEXT, &statement)
+    }
+}
+
+/// A statement signed with the USIG of its sender
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Message<S> {
+    pub from: ReplicaId,
+    pub statement: Statement,
+    pub signature: S,
+}
+
+#[derive(Debug)]
+struct Pending {
+    entry: Vec<u8>,
+    votes: HashSet<ReplicaId>,
+}
+
+/// One replica of a tiny MinBFT-style replicated log
+///
+/// Every message is signed with the USIG of its sender and processed in counter order,
+/// so a faulty primary can neither send different prepares to different backups nor hide one.
+/// An entry is committed once `f + 1` replicas accepted it.
+#[derive(Debug)]
+pub struct Replica<U: Usig> {
+    id: ReplicaId,
+    replicas: u64,
+    usig: U,
+    view: u64,
+    last_counter: HashMap<ReplicaId, Count>,
+    held: BTreeMap<(ReplicaId, Count), Message<U::Signature>>,
+    pending: BTreeMap<Count, Pending>,
+    committed: Option<Count>,
+    last_prepare: Option<Count>,
+    view_changes: BTreeMap<u64, HashMap<ReplicaId, Option<Count>>>,
+    log: Vec<Vec<u8>>,
+    inbox: Receiver<Message<U::Signature>>,
+    peers: Vec<Sender<Message<U::Signature>>>,
+}
+
+impl<U: Usig> Replica<U>
+where
+    U::Signature: Clone,
+{
+    pub fn id(&self) -> ReplicaId {
+        self.id
+    }
+
+    pub fn view(&self) -> u64 {
+        self.view
+    }
+
+    pub fn primary(&self) -> ReplicaId {
+        ReplicaId::from_u64(self.view % self.replicas)
+    }
+
+    /// The committed entries in order
+    pub fn log(&self) -> &[Vec<u8>] {
+        &self.log
+    }
+
+    fn quorum(&self) -> usize {
+        ((self.replicas - 1) / 2 + 1) as usize
+    }
+
+    fn broadcast(&mut self, statement: Statement) -> Result<(), UsigError> {
+        let signature = self.usig.sign(statement.signed_message())?;
+        let message = Message {
+            from: self.id,
+            statement,
+            signature,
+        };
+        for peer in &self.peers {
+            // a replica that shut down is just not listening anymore
+            let _ = peer.send(message.clone());
+        }
+        Ok(())
+    }
+
+    /// Propose `entry` to be appended to the log, only the primary may do so
+    pub fn propose(&mut self, entry: impl Into<Vec<u8>>) -> Result<(), UsigError> {
+        if self.primary() != self.id {
+            return Err(UsigError::SigningFailed);
+        }
+        self.broadcast(Statement::Prepare {
+            view: self.view,
+            entry: entry.into(),
+        })
+    }
+
+    /// Ask to replace the current primary
+    pub fn suspect_primary(&mut self) -> Result<(), UsigError> {
+        self.broadcast(Statement::ViewChange {
+            view: self.view + 1,
+            last_prepare: self.last_prepare,
+        })
+    }
+
+    /// Process all messages received so far, returns the number of messages taken from the inbox
+    pub fn process(&mut self) -> Result<usize, UsigError> {
+        let mut processed = 0;
+        while let Ok(message) = self.inbox.try_recv() {
+            processed += 1;
+            self.receive(message)?;
+        }
+        Ok(processed)
+    }
+
+    /// Verify a message and process it once all earlier messages of its sender were
+    fn receive(&mut self, message: Message<U::Signature>) -> Result<(), UsigError> {
+        let from = message.from;
+        let counter = message.signature.counter();
+        let last = self.last_counter.get(&from).copied();
+        if let Some(last) = last.filter(|last| counter <= *last) {
+            return Err(UsigError::StaleCounter {
+                id: from,
+                last,
+                counter,
+            });
+        }
+        // Below `counter`, so it does not overflow
+        let expected = last.map_or(Count(0), |last| Count(last.0 + 1));
+        if counter > expected {
+            self.held.insert((from, counter), message);
+            return Ok(());
+        }
+        self.usig
+            .verify(from, message.statement.signed_message(), &message.signature)?;
+        self.last_counter.insert(from, counter);
+        self.apply(from, counter, message.statement)?;
+        let next = counter
+            .checked_add(1)
+            .and_then(|next| self.held.remove(&(from, next)));
+        match next {
+            Some(next) => self.receive(next),
+            None => Ok(()),
+        }
+    }
+
+    fn apply(
+        &mut self,
+        from: ReplicaId,
+        counter: Count,
+        statement: Statement,
+    ) -> Result<(), UsigError> {
+        match statement {
+            Statement::Prepare { view, entry } => {
+                if view != self.view || from != self.primary() {
+                    return Ok(());
+                }
+                self.last_prepare = Some(counter);
+                self.vote(from, counter, entry.clone());
+                if self.id != from {
+                    self.broadcast(Statement::Commit {
+                        view,
+                        prepare: counter,
+                        entry,
+                    })?;
+                }
+            }
+            Statement::Commit {
+                view,
+                prepare,
+                entry,
+            } => {
+                if view == self.view {
+                    self.vote(from, prepare, entry);
+                }
+            }
+            Statement::ViewChange { view, last_prepare } => {
+                if view > self.view {
+                    let quorum = self.quorum();
+                    let votes = self.view_changes.entry(view).or_default();
+                    votes.insert(from, last_prepare);
+                    if votes.len() >= quorum {
+                        self.enter_view(view);
+                    }
+                }
+            }
+        }
+        self.commit_ready();
+        Ok(())
+    }
+
+    fn vote(&mut self, from: ReplicaId, prepare: Count, entry: Vec<u8>) {
+        if self.committed.is_some_and(|committed| prepare <= committed) {
+            return;
+        }
+        let pending = self.pending.entry(prepare).or_insert_with(|| Pending {
+            entry: entry.clone(),
+            votes: HashSet::new(),
+        });
+        if pending.entry == entry {
+            pending.votes.insert(from);
+        }
+    }
+
+    fn commit_ready(&mut self) {
+        let quorum = self.quorum();
+        while let Some(pending) = self.pending.first_entry() {
+            if pending.get().votes.len() < quorum {
+                break;
+            }
+            let (prepare, pending) = pending.remove_entry();
+            self.log.push(pending.entry);
+            self.committed = Some(prepare);
+        }
+    }
+
+    /// Commit what the old primary provably prepared and discard everything after it
+    fn enter_view(&mut self, view: u64) {
+        let votes = self.view_changes.remove(&view).unwrap_or_default();
+        let last_prepare = votes.into_values().flatten().max();
+        let pending = std::mem::take(&mut self.pending);
+        for (prepare, pending) in pending {
+            if last_prepare.is_some_and(|last| prepare <= last) {
+                self.log.push(pending.entry);
+            }
+        }
+        self.view_changes
+            .retain(|&pending_view, _| pending_view > view);
+        self.view = view;
+        self.committed = None;
+        self.last_prepare = None;
+    }
+}
+
+/// A replicated log of replicas connected by in-memory channels
+#[derive(Debug)]
+pub struct Cluster<U: Usig> {
+    replicas: Vec<Replica<U>>,
+}
+
+impl<U: Usig> Cluster<U>
+where
+    U::Signature: Clone,
+    U::Attestation: Clone,
+{
+    /// Connect one replica per USIG and exchange their attestations
+    pub fn new(usigs: Vec<U>) -> Result<Self, UsigError> {
+        let (senders, inboxes): (Vec<_>, Vec<_>) = usigs.iter().map(|_| channel()).unzip();
+        let mut usigs = usigs;
+        let attestations = usigs
+            .iter_mut()
+            .map(|usig| usig.attest())
+            .collect::<Result<Vec<_>, _>>()?;
+        let replicas = usigs.len() as u64;
+        let replicas = usigs
+            .into_iter()
+            .zip(inboxes)
+            .enumerate()
+            .map(|(index, (mut usig, inbox))| {
+                for (peer, attestation) in attestations.iter().enumerate() {
+                    usig.try_add_remote_party(
+                        ReplicaId::from_u64(peer as u64),
+                        attestation.clone(),
+                    )?;
+                }
+                Ok(Replica {
+                    id: ReplicaId::from_u64(index as u64),
+                    replicas,
+                    usig,
+                    view: 0,
+                    last_counter: HashMap::new(),
+                    held: BTreeMap::new(),
+                    pending: BTreeMap::new(),
+                    committed: None,
+                    last_prepare: None,
+                    view_changes: BTreeMap::new(),
+                    log: Vec::new(),
+                    inbox,
+                    peers: senders.clone(),
+                })
+            })
+            .collect::<Result<_, UsigError>>()?;
+        Ok(Self { replicas })
+    }
+}
+
+impl<U: Usig> Cluster<U>
+where
+    U::Signature: Clone,
+{
+    pub fn replica(&self, id: ReplicaId) -> &Replica<U> {
+        &self.replicas[id.as_u64() as usize]
+    }
+
+    pub fn replica_mut(&mut self, id: ReplicaId) -> &mut Replica<U> {
+        &mut self.replicas[id.as_u64() as usize]
+    }
+
+    /// Let the primary propose `entry`
+    pub fn submit(&mut self, entry: impl Into<Vec<u8>>) -> Result<(), UsigError> {
+        let primary = self.replicas[0].primary();
+        self.replica_mut(primary).propose(entry)
+    }
+
+    /// Deliver messages until no replica receives any more
+    pub fn run(&mut self) -> Result<(), UsigError> {
+        loop {
+            let mut processed = 0;
+            for replica in &mut self.replicas {
+                processed += replica.process()?;
+            }
+            if processed == 0 {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::Hmac;
+    use sha2::Sha256;
+
+    use super::*;
+    use crate::{hmac::UsigHmac, signature::new_ed25519};
+
+    const REPLICAS: u64 = 4;
+
+    fn ed25519_cluster() -> Cluster<crate::signature::UsigEd25519> {
+        Cluster::new((0..REPLICAS).map(|_| new_ed25519()).collect()).unwrap()
+    }
+
+    fn assert_logs(cluster: &Cluster<impl Usig<Signature: Clone>>, expected: &[&[u8]]) {
+        for id in 0..REPLICAS {
+            assert_eq!(cluster.replica(ReplicaId::from_u64(id)).log(), expected);
+        }
+    }
+
+    #[test]
+    fn replicate() {
+        let mut cluster = ed25519_cluster();
+        cluster.submit(b"one".as_slice()).unwrap();
+        cluster.submit(b"two".as_slice()).unwrap();
+        cluster.run().unwrap();
+        cluster.submit(b"three".as_slice()).unwrap();
+        cluster.run().unwrap();
+        assert_logs(&cluster, &[b"one", b"two", b"three"]);
+    }
+
+    #[test]
+    fn hmac() {
+        let usigs = (0..REPLICAS)
+            .map(|_| UsigHmac::<Hmac<Sha256>>::try_new(Box::new(rand::random::<[u8; 16]>())))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let mut cluster = Cluster::new(usigs).unwrap();
+        cluster.submit(b"entry".as_slice()).unwrap();
+        cluster.run().unwrap();
+        assert_logs(&cluster, &[b"entry"]);
+    }
+
+    #[test]
+    fn backup_can_not_propose() {
+        let mut cluster = ed25519_cluster();
+        assert!(matches!(
+            cluster
+                .replica_mut(ReplicaId::from_u64(1))
+                .propose(b"entry".as_slice()),
+            Err(UsigError::SigningFailed)
+        ));
+    }
+
+    #[test]
+    fn view_change() {
+        let mut cluster = ed25519_cluster();
+        cluster.submit(b"one".as_slice()).unwrap();
+        cluster.run().unwrap();
+
+        cluster.submit(b"lost".as_slice()).unwrap();
+        // the prepare only reaches the primary itself
+        cluster
+            .replica_mut(ReplicaId::from_u64(0))
+            .process()
+            .unwrap();
+        for id in 1..REPLICAS {
+            while cluster
+                .replica_mut(ReplicaId::from_u64(id))
+                .inbox
+                .try_recv()
+                .is_ok()
+            {}
+        }
+
+        for id in 1..REPLICAS {
+            cluster
+                .replica_mut(ReplicaId::from_u64(id))
+                .suspect_primary()
+                .unwrap();
+        }
+        cluster.run().unwrap();
+        for id in 0..REPLICAS {
+            assert_eq!(cluster.replica(ReplicaId::from_u64(id)).view(), 1);
+        }
+
+        cluster.submit(b"two".as_slice()).unwrap();
+        cluster.run().unwrap();
+        assert_logs(&cluster, &[b"one", b"two"]);
+    }
+
+    #[test]
+    fn equivocating_primary() {
+        let mut cluster = ed25519_cluster();
+        cluster.submit(b"entry".as_slice()).unwrap();
+        let backup = cluster.replica_mut(ReplicaId::from_u64(1));
+        let mut message = backup.inbox.try_recv().unwrap();
+        message.statement = Statement::Prepare {
+            view: 0,
+            entry: b"forged".to_vec(),
+        };
+        assert!(matches!(
+            backup.receive(message.clone()),
+            Err(UsigError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn replayed_message() {
+        let mut cluster = ed25519_cluster();
+        cluster.submit(b"entry".as_slice()).unwrap();
+        let backup = cluster.replica_mut(ReplicaId::from_u64(1));
+        let message = backup.inbox.try_recv().unwrap();
+        backup.receive(message.clone()).unwrap();
+        assert!(matches!(
+            backup.receive(message),
+            Err(UsigError::StaleCounter { .. })
+        ));
+    }
+
+    #[test]
+    fn reordered_messages() {
+        let mut cluster = ed25519_cluster();
+        cluster.submit(b"one".as_slice()).unwrap();
+        cluster.submit(b"two".as_slice()).unwrap();
+        let backup = cluster.replica_mut(ReplicaId::from_u64(1));
+        let first = backup.inbox.try_recv().unwrap();
+        let second = backup.inbox.try_recv().unwrap();
+        backup.receive(second).unwrap();
+        assert_eq!(backup.last_prepare, None);
+        backup.receive(first).unwrap();
+        assert_eq!(backup.last_prepare, Some(Count(1)));
+        cluster.run().unwrap();
+        assert_logs(&cluster, &[b"one", b"two"]);
+    }
+}
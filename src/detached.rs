@@ -0,0 +1,249 @@
+use ::signature::Verifier;
+#[cfg(feature = "bls")]
+use blst::min_pk;
+
+#[cfg(feature = "bls")]
+use crate::bls::BlsVerifyingKey;
+use crate::{
+    encoding::CounterEncoding, signature::SignatureParameters, AlgorithmParameters, Count,
+    UsigError,
+};
+
+/// The public key schemes [verify_detached] supports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Algorithm {
+    Ed25519,
+    Ed448,
+    Secp256k1,
+    P256,
+    #[cfg(feature = "bls")]
+    Bls,
+}
+
+impl Algorithm {
+    pub const ALL: &[Algorithm] = &[
+        Algorithm::Ed25519,
+        Algorithm::Ed448,
+        Algorithm::Secp256k1,
+        Algorithm::P256,
+        #[cfg(feature = "bls")]
+        Algorithm::Bls,
+    ];
+
+    pub fn parameters(self) -> AlgorithmParameters {
+        match self {
+            Algorithm::Ed25519 => ed25519_dalek::Signature::parameters(),
+            Algorithm::Ed448 => ed448_goldilocks_plus::Signature::parameters(),
+            Algorithm::Secp256k1 => k256::ecdsa::Signature::parameters(),
+            Algorithm::P256 => p256::ecdsa::Signature::parameters(),
+            #[cfg(feature = "bls")]
+            Algorithm::Bls => min_pk::Signature::parameters(),
+        }
+    }
+
+    /// The algorithm of an attestation, [None] for MACs and unknown schemes
+    pub fn from_parameters(parameters: &AlgorithmParameters) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|algorithm| algorithm.parameters() == *parameters)
+    }
+}
+
+fn verify_with<K: Verifier<S>, S>(
+    key: Option<K>,
+    signature: Option<S>,
+    data: &[u8],
+) -> Result<(), UsigError> {
+    let key = key.ok_or(UsigError::RemoteAttestationFailed)?;
+    let signature = signature.ok_or(UsigError::InvalidSignature)?;
+    key.verify(data, &signature)
+        .map_err(|_| UsigError::InvalidSignature)
+}
+
+/// Check a single USIG signature given only the raw public key of its signer
+///
+/// Needs no verify half, but also checks nothing beyond the signature itself,
+/// in particular neither the attestation of the key nor the counter sequence.
+/// The counter is expected in the default [CounterEncoding].
+/// Malformed keys are reported as [UsigError::RemoteAttestationFailed].
+pub fn verify_detached(
+    algorithm: Algorithm,
+    public_key: &[u8],
+    counter: Count,
+    message: impl AsRef<[u8]>,
+    signature: &[u8],
+) -> Result<(), UsigError> {
+    let data = CounterEncoding::default().preimage(counter.0, message.as_ref());
+    match algorithm {
+        Algorithm::Ed25519 => verify_with(
+            public_key
+                .try_into()
+                .ok()
+                .and_then(|key| ed25519_dalek::VerifyingKey::from_bytes(key).ok()),
+            ed25519_dalek::Signature::from_slice(signature).ok(),
+            &data,
+        ),
+        Algorithm::Ed448 => verify_with(
+            public_key
+                .try_into()
+                .ok()
+                .and_then(|key| ed448_goldilocks_plus::VerifyingKey::from_bytes(key).ok()),
+            ed448_goldilocks_plus::Signature::try_from(signature).ok(),
+            &data,
+        ),
+        Algorithm::Secp256k1 => verify_with(
+            k256::ecdsa::VerifyingKey::from_sec1_bytes(public_key).ok(),
+            k256::ecdsa::Signature::from_slice(signature).ok(),
+            &data,
+        ),
+        Algorithm::P256 => verify_with(
+            p256::ecdsa::VerifyingKey::from_sec1_bytes(public_key).ok(),
+            p256::ecdsa::Signature::from_slice(signature).ok(),
+            &data,
+        ),
+        #[cfg(feature = "bls")]
+        Algorithm::Bls => verify_with(
+            min_pk::PublicKey::key_validate(public_key)
+                .ok()
+                .map(BlsVerifyingKey),
+            min_pk::Signature::from_bytes(signature).ok(),
+            &data,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        signature::{new_ed25519, new_p256},
+        Counter, SignHalf, Usig,
+    };
+
+    const MESSAGE: &[u8] = b"message";
+
+    #[test]
+    fn ed25519() {
+        let (mut sign, _) = new_ed25519().split();
+        let key = sign.attest().unwrap().payload.to_bytes();
+        sign.sign(MESSAGE).unwrap();
+        let signature = sign.sign(MESSAGE).unwrap();
+        let bytes = signature.inner().to_bytes();
+
+        assert!(verify_detached(
+            Algorithm::Ed25519,
+            &key,
+            signature.counter(),
+            MESSAGE,
+            &bytes
+        )
+        .is_ok());
+        assert!(matches!(
+            verify_detached(Algorithm::Ed25519, &key, Count(0), MESSAGE, &bytes),
+            Err(UsigError::InvalidSignature)
+        ));
+        assert!(matches!(
+            verify_detached(Algorithm::Ed25519, &key[1..], Count(1), MESSAGE, &bytes),
+            Err(UsigError::RemoteAttestationFailed)
+        ));
+        assert!(matches!(
+            verify_detached(Algorithm::Ed25519, &key, Count(1), MESSAGE, &bytes[1..]),
+            Err(UsigError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn p256() {
+        let (mut sign, _) = new_p256().split();
+        let key = sign.attest().unwrap().payload.to_sec1_bytes();
+        let signature = sign.sign(MESSAGE).unwrap();
+        let bytes = signature.inner().to_bytes();
+        assert!(
+            verify_detached(Algorithm::P256, &key, signature.counter(), MESSAGE, &bytes).is_ok()
+        );
+        assert!(verify_detached(
+            Algorithm::Secp256k1,
+            &key,
+            signature.counter(),
+            MESSAGE,
+            &bytes
+        )
+        .is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "bls")]
+    fn bls() {
+        let (mut sign, _) = crate::bls::new_bls().split();
+        let key = sign.attest().unwrap().payload.0.compress();
+        let signature = sign.sign(MESSAGE).unwrap();
+        let bytes = signature.inner().compress();
+        assert!(
+            verify_detached(Algorithm::Bls, &key, signature.counter(), MESSAGE, &bytes).is_ok()
+        );
+        assert!(
+            verify_detached(Algorithm::Bls, &key, signature.counter(), b"other", &bytes).is_err()
+        );
+    }
+
+    #[test]
+    fn from_parameters() {
+        for &algorithm in Algorithm::ALL {
+            assert_eq!(
+                Algorithm::from_parameters(&algorithm.parameters()),
+                Some(algorithm)
+            );
+        }
+        let (mut sign, _) = new_ed25519().split();
+        let parameters = sign.attest().unwrap().parameters;
+        assert_eq!(
+            Algorithm::from_parameters(&parameters),
+            Some(Algorithm::Ed25519)
+        );
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32", target_os = "unknown"))]
+mod wasm {
+    use hmac::Hmac;
+    use sha2::Sha256;
+    use shared_ids::ReplicaId;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+    use crate::{hmac::UsigHmac, signature::new_ed25519, Counter, SignHalf, Usig, VerifyHalf};
+
+    const ID: ReplicaId = ReplicaId::first();
+    const MESSAGE: &[u8] = b"message";
+
+    #[wasm_bindgen_test]
+    fn light_verifier() {
+        let (mut sign, mut verify) = new_ed25519().split();
+        let attestation = sign.attest().unwrap();
+        let key = attestation.payload.to_bytes();
+        let signature = sign.sign(MESSAGE).unwrap();
+
+        assert!(verify_detached(
+            Algorithm::Ed25519,
+            &key,
+            signature.counter(),
+            MESSAGE,
+            &signature.inner().to_bytes()
+        )
+        .is_ok());
+        assert!(verify.add_remote_party(ID, attestation));
+        assert!(verify.verify(ID, MESSAGE, &signature).is_ok());
+    }
+
+    #[wasm_bindgen_test]
+    fn hmac() {
+        let mut usig =
+            UsigHmac::<Hmac<Sha256>>::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap();
+        let attestation = usig.attest().unwrap();
+        assert!(usig.add_remote_party(ID, attestation));
+        let signature = usig.sign(MESSAGE).unwrap();
+        assert!(usig.verify(ID, MESSAGE, &signature).is_ok());
+        assert!(usig.verify(ID, b"other", &signature).is_err());
+    }
+}
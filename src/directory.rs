@@ -0,0 +1,863 @@
+#[cfg(any(feature = "http", feature = "ldap"))]
+use std::sync::Arc;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fmt::Debug,
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, Payload},
+    Aes256Gcm, Nonce,
+};
+use derivative::Derivative;
+use rand::rngs::OsRng;
+use serde::{de::DeserializeOwned, Serialize};
+use shared_ids::ReplicaId;
+use zeroize::Zeroizing;
+
+use crate::{Count, UsigError, VerifyHalf};
+
+/// Lookup of the verification material of remote parties by their [ReplicaId]
+///
+/// The verify halves use a directory for all key lookups, so the way keys are
+/// distributed can be swapped without touching the verification logic.
+pub trait PartyDirectory<K: Clone> {
+    /// Get the verification material registered for a party
+    fn get(&self, id: ReplicaId) -> Option<Cow<'_, K>>;
+
+    /// Register the verification material of a party, replacing any previous one
+    fn insert(&mut self, id: ReplicaId, key: K) -> Result<(), UsigError>;
+
+    /// Remove the verification material of a party, `false` if there was none
+    fn remove(&mut self, id: ReplicaId) -> bool;
+
+    /// The parties with verification material, in no particular order
+    fn ids(&self) -> impl Iterator<Item = ReplicaId> + '_;
+
+    /// Estimated number of bytes held in memory, not counting heap allocations owned by keys
+    ///
+    /// Defaults to the size of the ids and keys, without any overhead of the directory.
+    fn memory_usage(&self) -> usize {
+        self.ids().count() * size_of::<(ReplicaId, K)>()
+    }
+
+    /// The message-independent checks of [crate::VerifyHalf::pre_validate] for a signature
+    /// of `id` with `counter`
+    ///
+    /// Checks that the party is registered. Directories tracking the counter expected next
+    /// from each party, like [TableDirectory], also reject counters below it with
+    /// [UsigError::StaleCounter]. This is the one place where the choice of directory
+    /// changes the behavior of a verify half: its `pre_validate` rejects a replayed
+    /// signature that it accepts on any other directory, which leave replays to the caller.
+    fn precheck(&self, id: ReplicaId, counter: Count) -> Result<(), UsigError> {
+        let _ = counter;
+        self.get(id).map(|_| ()).ok_or(UsigError::UnknownId(id))
+    }
+
+    /// Record that a signature of `id` with `counter` was verified, see [PartyDirectory::precheck]
+    fn observe(&self, id: ReplicaId, counter: Count) {
+        let _ = (id, counter);
+    }
+}
+
+/// Bytes owned on the heap by the keys in `directory`, which [PartyDirectory::memory_usage] leaves out
+pub(crate) fn key_heap_usage<K: Clone>(
+    directory: &impl PartyDirectory<K>,
+    heap_size: impl Fn(&K) -> usize,
+) -> usize {
+    directory
+        .ids()
+        .filter_map(|id| directory.get(id))
+        .map(|key| heap_size(&key))
+        .sum()
+}
+
+/// Estimated number of bytes used by a key map
+fn map_memory_usage<K>(keys: &HashMap<ReplicaId, K>) -> usize {
+    size_of_val(keys) + keys.capacity() * (size_of::<(ReplicaId, K)>() + 1)
+}
+
+/// A directory that only keeps the keys in memory
+#[derive(Derivative)]
+#[derivative(
+    Debug(bound = "K: Debug"),
+    Default(bound = ""),
+    Clone(bound = "K: Clone")
+)]
+pub struct MemoryDirectory<K> {
+    keys: HashMap<ReplicaId, K>,
+}
+
+impl<K: Clone> PartyDirectory<K> for MemoryDirectory<K> {
+    fn get(&self, id: ReplicaId) -> Option<Cow<'_, K>> {
+        self.keys.get(&id).map(Cow::Borrowed)
+    }
+
+    fn insert(&mut self, id: ReplicaId, key: K) -> Result<(), UsigError> {
+        self.keys.insert(id, key);
+        Ok(())
+    }
+
+    fn remove(&mut self, id: ReplicaId) -> bool {
+        self.keys.remove(&id).is_some()
+    }
+
+    fn ids(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.keys.keys().copied()
+    }
+
+    fn memory_usage(&self) -> usize {
+        map_memory_usage(&self.keys)
+    }
+}
+
+/// The highest [ReplicaId] a [TableDirectory] accepts
+pub const TABLE_LIMIT: u64 = u16::MAX as u64;
+
+/// Number of parties per page of a [TableDirectory]
+const TABLE_PAGE: usize = 64;
+
+/// The precomputed verification context of one party in a [TableDirectory]
+#[derive(Debug)]
+struct PartyContext<K> {
+    /// The parsed key, e.g. the keyed MAC state of [crate::hmac::VerifyKey]
+    key: K,
+    /// One above the highest verified counter, zero before the first one
+    next: AtomicU64,
+}
+
+type TablePage<K> = Box<[Option<PartyContext<K>>; TABLE_PAGE]>;
+
+/// A directory keeping the verification context of each party in a table indexed by [ReplicaId]
+///
+/// Replica ids are small and dense in practice, so a lookup is an index into the table
+/// instead of hashing the id. Next to the key, which the backends store already parsed,
+/// each entry holds the counter expected next from the party. [PartyDirectory::precheck]
+/// rejects counters below it with [UsigError::StaleCounter] in the same lookup, so
+/// replays are dropped before any cryptography. Only use it where a party's messages are
+/// verified in counter order, e.g. behind a [crate::monotonic::MonotonicVerifyHalf].
+///
+/// The table is allocated in pages of 64 parties, so a high id does not allocate the slots
+/// of all ids below it. Ids above [TABLE_LIMIT] are rejected with [UsigError::DirectoryFailed].
+#[derive(Derivative)]
+#[derivative(Debug(bound = "K: Debug"), Default(bound = ""))]
+pub struct TableDirectory<K> {
+    pages: Vec<Option<TablePage<K>>>,
+}
+
+impl<K> TableDirectory<K> {
+    fn context(&self, id: ReplicaId) -> Option<&PartyContext<K>> {
+        let index = usize::try_from(id.as_u64()).ok()?;
+        self.pages
+            .get(index / TABLE_PAGE)?
+            .as_ref()?
+            .get(index % TABLE_PAGE)?
+            .as_ref()
+    }
+
+    /// The counter expected next from `id`, [None] for unknown parties
+    pub fn expected_counter(&self, id: ReplicaId) -> Option<Count> {
+        self.context(id)
+            .map(|context| Count(context.next.load(Ordering::Acquire)))
+    }
+}
+
+impl<K: Clone> PartyDirectory<K> for TableDirectory<K> {
+    fn get(&self, id: ReplicaId) -> Option<Cow<'_, K>> {
+        self.context(id).map(|context| Cow::Borrowed(&context.key))
+    }
+
+    fn insert(&mut self, id: ReplicaId, key: K) -> Result<(), UsigError> {
+        if id.as_u64() > TABLE_LIMIT {
+            return Err(UsigError::DirectoryFailed);
+        }
+        let index = id.as_u64() as usize;
+        let page = index / TABLE_PAGE;
+        if page >= self.pages.len() {
+            self.pages.resize_with(page + 1, || None);
+        }
+        let page = self.pages[page].get_or_insert_with(|| Box::new(std::array::from_fn(|_| None)));
+        // a new key starts a new counter sequence
+        page[index % TABLE_PAGE] = Some(PartyContext {
+            key,
+            next: AtomicU64::new(0),
+        });
+        Ok(())
+    }
+
+    fn remove(&mut self, id: ReplicaId) -> bool {
+        let Ok(index) = usize::try_from(id.as_u64()) else {
+            return false;
+        };
+        let Some(Some(page)) = self.pages.get_mut(index / TABLE_PAGE) else {
+            return false;
+        };
+        let removed = page[index % TABLE_PAGE].take().is_some();
+        if page.iter().all(Option::is_none) {
+            self.pages[index / TABLE_PAGE] = None;
+        }
+        removed
+    }
+
+    fn ids(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.pages
+            .iter()
+            .enumerate()
+            .filter_map(|(page, entries)| Some((page, entries.as_ref()?)))
+            .flat_map(|(page, entries)| {
+                entries
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, context)| context.is_some())
+                    .map(move |(offset, _)| {
+                        ReplicaId::from_u64((page * TABLE_PAGE + offset) as u64)
+                    })
+            })
+    }
+
+    fn memory_usage(&self) -> usize {
+        let pages = self.pages.iter().flatten().count();
+        size_of_val(&self.pages)
+            + self.pages.capacity() * size_of::<Option<TablePage<K>>>()
+            + pages * TABLE_PAGE * size_of::<Option<PartyContext<K>>>()
+    }
+
+    fn precheck(&self, id: ReplicaId, counter: Count) -> Result<(), UsigError> {
+        let context = self.context(id).ok_or(UsigError::UnknownId(id))?;
+        match context.next.load(Ordering::Acquire) {
+            0 => Ok(()),
+            next if counter.0 < next => Err(UsigError::StaleCounter {
+                id,
+                last: Count(next - 1),
+                counter,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn observe(&self, id: ReplicaId, counter: Count) {
+        if let Some(context) = self.context(id) {
+            context
+                .next
+                .fetch_max(counter.0.saturating_add(1), Ordering::AcqRel);
+        }
+    }
+}
+
+/// The key a [FileDirectory] encrypts its file with
+pub type DirectoryKey = [u8; 32];
+
+/// Associated data binding the encrypted file to a [FileDirectory]
+const DIRECTORY_DOMAIN: &[u8] = b"usig directory";
+
+const NONCE_LENGTH: usize = 12;
+
+/// A directory that persists all keys to a file
+///
+/// The file is encrypted with a [DirectoryKey], as the keys of MAC backends are secrets,
+/// rewritten atomically on every insert and loaded again by [FileDirectory::open].
+#[derive(Derivative)]
+#[derivative(Debug(bound = "K: Debug"))]
+pub struct FileDirectory<K> {
+    path: PathBuf,
+    #[derivative(Debug = "ignore")]
+    cipher: Aes256Gcm,
+    keys: HashMap<ReplicaId, K>,
+}
+
+impl<K: Serialize + DeserializeOwned> FileDirectory<K> {
+    /// Open the directory stored at `path`, starting empty if the file does not exist yet
+    ///
+    /// Fails with [io::ErrorKind::InvalidData] if the file was not written with `key` or
+    /// was tampered with.
+    pub fn open(path: impl AsRef<Path>, key: &DirectoryKey) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+        let cipher = Aes256Gcm::new(key.into());
+        let keys = match File::open(&path) {
+            Ok(mut file) => {
+                let mut sealed = Vec::new();
+                file.read_to_end(&mut sealed)?;
+                Self::unseal(&cipher, &sealed)?
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Self { path, cipher, keys })
+    }
+
+    fn unseal(cipher: &Aes256Gcm, sealed: &[u8]) -> io::Result<HashMap<ReplicaId, K>> {
+        let invalid = |error| io::Error::new(io::ErrorKind::InvalidData, error);
+        if sealed.len() < NONCE_LENGTH {
+            return Err(invalid("directory file is truncated"));
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LENGTH);
+        let plaintext = Zeroizing::new(
+            cipher
+                .decrypt(
+                    Nonce::from_slice(nonce),
+                    Payload {
+                        msg: ciphertext,
+                        aad: DIRECTORY_DOMAIN,
+                    },
+                )
+                .map_err(|_| invalid("directory file can not be decrypted"))?,
+        );
+        bincode::deserialize(&plaintext).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    fn persist(&self) -> io::Result<()> {
+        let plaintext = Zeroizing::new(bincode::serialize(&self.keys).map_err(io::Error::other)?);
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: &plaintext,
+                    aad: DIRECTORY_DOMAIN,
+                },
+            )
+            .map_err(|_| io::Error::other("directory can not be encrypted"))?;
+        let tmp = self.path.with_extension("tmp");
+        let mut file = File::create(&tmp)?;
+        file.write_all(&nonce)?;
+        file.write_all(&ciphertext)?;
+        file.sync_all()?;
+        fs::rename(tmp, &self.path)?;
+        // the rename itself is only durable once the directory is synced
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            File::open(parent)?.sync_all()?;
+        }
+        Ok(())
+    }
+}
+
+impl<K: Clone + Serialize + DeserializeOwned> PartyDirectory<K> for FileDirectory<K> {
+    fn get(&self, id: ReplicaId) -> Option<Cow<'_, K>> {
+        self.keys.get(&id).map(Cow::Borrowed)
+    }
+
+    fn insert(&mut self, id: ReplicaId, key: K) -> Result<(), UsigError> {
+        let previous = self.keys.insert(id, key);
+        self.persist().map_err(|_| {
+            match previous {
+                Some(previous) => self.keys.insert(id, previous),
+                None => self.keys.remove(&id),
+            };
+            UsigError::DirectoryFailed
+        })
+    }
+
+    /// Keeps the party if the file can not be rewritten
+    fn remove(&mut self, id: ReplicaId) -> bool {
+        let Some(previous) = self.keys.remove(&id) else {
+            return false;
+        };
+        if self.persist().is_err() {
+            self.keys.insert(id, previous);
+            return false;
+        }
+        true
+    }
+
+    fn ids(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.keys.keys().copied()
+    }
+
+    fn memory_usage(&self) -> usize {
+        size_of_val(&self.path) + self.path.as_os_str().len() + map_memory_usage(&self.keys)
+    }
+}
+
+/// Fetches the key of a party from external key distribution infrastructure (LDAP, HTTP, ...)
+///
+/// Fetch attestations to register parties through a [RemoteDirectory].
+pub trait KeyFetcher<K> {
+    /// Fetch the key of a party, [None] if the source does not know the party
+    ///
+    /// Failures to reach or understand the source are errors, not unknown parties.
+    fn fetch(&self, id: ReplicaId) -> Result<Option<K>, UsigError>;
+}
+
+impl<K, F: Fn(ReplicaId) -> Result<Option<K>, UsigError>> KeyFetcher<K> for F {
+    fn fetch(&self, id: ReplicaId) -> Result<Option<K>, UsigError> {
+        self(id)
+    }
+}
+
+/// Registers the parties known to a [KeyFetcher] with a verify half
+///
+/// The fetched attestations are added with [VerifyHalf::try_add_remote_party], so they pass
+/// the same parameter, attestation and admission checks as any other, and the keys end up
+/// in the directory of the verify half. Lookups never wait for the network, so
+/// verification can not be stalled or amplified by the source. Parties are fetched by
+/// [RemoteDirectory::fetch] and [RemoteDirectory::refresh] off the verification path, e.g.
+/// when a party joins or on a timer.
+#[derive(Debug)]
+pub struct RemoteDirectory<F> {
+    fetcher: F,
+}
+
+impl<F> RemoteDirectory<F> {
+    pub fn new(fetcher: F) -> Self {
+        Self { fetcher }
+    }
+
+    /// Fetch the attestation of `id` and add the party, returns whether the source knows it
+    ///
+    /// A party the source no longer knows is removed. Fails like
+    /// [VerifyHalf::try_add_remote_party] if the attestation is rejected, the previously
+    /// registered key, if any, is kept then.
+    pub fn fetch<V>(&self, verify_half: &mut V, id: ReplicaId) -> Result<bool, UsigError>
+    where
+        V: VerifyHalf,
+        F: KeyFetcher<V::Attestation>,
+    {
+        match self.fetcher.fetch(id)? {
+            Some(attestation) => {
+                verify_half.try_add_remote_party(id, attestation)?;
+                Ok(true)
+            }
+            None => {
+                verify_half.remove_remote_party(id);
+                Ok(false)
+            }
+        }
+    }
+
+    /// Fetch all registered parties again, e.g. to pick up rotated keys
+    ///
+    /// Returns the parties that could not be fetched or added, their key is kept.
+    pub fn refresh<V>(&self, verify_half: &mut V) -> Vec<(ReplicaId, UsigError)>
+    where
+        V: VerifyHalf,
+        F: KeyFetcher<V::Attestation>,
+    {
+        let ids: Vec<_> = verify_half.remote_parties().collect();
+        ids.into_iter()
+            .filter_map(|id| self.fetch(verify_half, id).err().map(|e| (id, e)))
+            .collect()
+    }
+}
+
+/// The certificate authorities a [HttpFetcher] or [crate::ldap::LdapFetcher] trusts
+///
+/// Replaces the system roots, so only servers certified by the deployment are accepted.
+#[cfg(any(feature = "http", feature = "ldap"))]
+#[derive(Debug, Clone)]
+pub struct PinnedTrust {
+    roots: Vec<rustls::pki_types::CertificateDer<'static>>,
+}
+
+#[cfg(any(feature = "http", feature = "ldap"))]
+impl PinnedTrust {
+    /// Trust the DER encoded CA certificates `roots` and nothing else
+    pub fn new(roots: impl IntoIterator<Item = Vec<u8>>) -> Self {
+        Self {
+            roots: roots.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    pub(crate) fn client_config(&self) -> Result<Arc<rustls::ClientConfig>, UsigError> {
+        let mut store = rustls::RootCertStore::empty();
+        for root in &self.roots {
+            store
+                .add(root.clone())
+                .map_err(|e| UsigError::Backend(Box::new(e)))?;
+        }
+        let config = rustls::ClientConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| UsigError::Backend(Box::new(e)))?
+        .with_root_certificates(store)
+        .with_no_client_auth();
+        Ok(Arc::new(config))
+    }
+}
+
+/// Largest key a fetcher accepts, so a misbehaving source can not exhaust memory
+#[cfg(any(feature = "http", feature = "ldap"))]
+pub(crate) const MAX_FETCHED_KEY: u64 = 64 * 1024;
+
+/// Fetches keys over HTTPS from `{base_url}/{replica id}`, expecting a bincode encoded body
+///
+/// Serve attestations to register the parties through a [RemoteDirectory].
+///
+/// The server has to be certified by the [PinnedTrust]. A `404 Not Found` is an unknown
+/// party, any other failure is returned as an error.
+#[cfg(feature = "http")]
+#[derive(Derivative, Clone)]
+#[derivative(Debug)]
+pub struct HttpFetcher {
+    base_url: String,
+    #[derivative(Debug = "ignore")]
+    agent: ureq::Agent,
+}
+
+#[cfg(feature = "http")]
+impl HttpFetcher {
+    /// Fails with [io::ErrorKind::InvalidInput] if `base_url` is not an `https` URL
+    pub fn new(base_url: impl Into<String>, trust: &PinnedTrust) -> Result<Self, UsigError> {
+        let base_url = base_url.into();
+        if !base_url.starts_with("https://") {
+            return Err(UsigError::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "keys are only fetched over https",
+            )));
+        }
+        let agent = ureq::AgentBuilder::new()
+            .tls_config(trust.client_config()?)
+            .https_only(true)
+            .redirects(0)
+            .build();
+        Ok(Self { base_url, agent })
+    }
+}
+
+#[cfg(feature = "http")]
+impl<K: DeserializeOwned> KeyFetcher<K> for HttpFetcher {
+    fn fetch(&self, id: ReplicaId) -> Result<Option<K>, UsigError> {
+        let url = format!("{}/{}", self.base_url.trim_end_matches('/'), id.as_u64());
+        let response = match self.agent.get(&url).call() {
+            Ok(response) => response,
+            Err(ureq::Error::Status(404, _)) => return Ok(None),
+            Err(e) => return Err(UsigError::Backend(Box::new(e))),
+        };
+        let mut body = Vec::new();
+        response
+            .into_reader()
+            .take(MAX_FETCHED_KEY + 1)
+            .read_to_end(&mut body)?;
+        if body.len() as u64 > MAX_FETCHED_KEY {
+            return Err(UsigError::Io(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "fetched key is too large",
+            )));
+        }
+        Ok(Some(bincode::deserialize(&body)?))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    };
+
+    use super::*;
+
+    const ID: ReplicaId = ReplicaId::first();
+
+    /// The configuration of a TLS server for `localhost` and the trust in its certificate
+    #[cfg(any(feature = "http", feature = "ldap"))]
+    pub(crate) fn tls_server() -> (Arc<rustls::ServerConfig>, PinnedTrust) {
+        use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+
+        let certified = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+        let certificate = certified.cert.der().to_vec();
+        let key = PrivatePkcs8KeyDer::from(certified.key_pair.serialize_der());
+        let config = rustls::ServerConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![CertificateDer::from(certificate.clone())],
+            PrivateKeyDer::Pkcs8(key),
+        )
+        .unwrap();
+        (Arc::new(config), PinnedTrust::new([certificate]))
+    }
+
+    #[test]
+    fn memory() {
+        let mut directory = MemoryDirectory::default();
+        assert!(directory.get(ID).is_none());
+        directory.insert(ID, 1u8).unwrap();
+        assert_eq!(directory.get(ID).as_deref(), Some(&1));
+        directory.insert(ID, 2u8).unwrap();
+        assert_eq!(directory.get(ID).as_deref(), Some(&2));
+        assert!(directory.remove(ID));
+        assert!(!directory.remove(ID));
+        assert_eq!(directory.ids().count(), 0);
+        assert!(directory.get(ID).is_none());
+    }
+
+    #[test]
+    fn memory_usage_grows() {
+        let mut directory = MemoryDirectory::default();
+        let empty = directory.memory_usage();
+        for id in 0..100 {
+            directory
+                .insert(ReplicaId::from_u64(id), [0u8; 32])
+                .unwrap();
+        }
+        assert!(directory.memory_usage() >= empty + 100 * 32);
+    }
+
+    #[test]
+    fn table() {
+        let mut directory = TableDirectory::default();
+        assert!(directory.get(ID).is_none());
+        directory.insert(ReplicaId::from_u64(3), 1u8).unwrap();
+        assert!(directory.get(ID).is_none());
+        assert_eq!(directory.get(ReplicaId::from_u64(3)).as_deref(), Some(&1));
+        directory.insert(ID, 2u8).unwrap();
+        assert_eq!(directory.get(ID).as_deref(), Some(&2));
+        assert!(directory.get(ReplicaId::from_u64(4)).is_none());
+        assert_eq!(
+            directory.ids().collect::<Vec<_>>(),
+            [ID, ReplicaId::from_u64(3)]
+        );
+        assert!(matches!(
+            directory.insert(ReplicaId::from_u64(TABLE_LIMIT + 1), 3u8),
+            Err(UsigError::DirectoryFailed)
+        ));
+    }
+
+    #[test]
+    fn table_sparse() {
+        let mut directory = TableDirectory::default();
+        let empty = directory.memory_usage();
+        let last = ReplicaId::from_u64(TABLE_LIMIT);
+        directory.insert(last, 1u64).unwrap();
+        assert!(directory.memory_usage() - empty < 16 * 1024);
+        assert_eq!(directory.ids().collect::<Vec<_>>(), [last]);
+        assert!(directory.remove(last));
+        assert_eq!(directory.ids().count(), 0);
+    }
+
+    #[test]
+    fn table_counters() {
+        let mut directory = TableDirectory::default();
+        assert!(matches!(
+            directory.precheck(ID, Count(0)),
+            Err(UsigError::UnknownId(ID))
+        ));
+        directory.insert(ID, ()).unwrap();
+        assert_eq!(directory.expected_counter(ID), Some(Count(0)));
+        directory.precheck(ID, Count(5)).unwrap();
+        directory.observe(ID, Count(5));
+        directory.observe(ID, Count(3));
+        assert_eq!(directory.expected_counter(ID), Some(Count(6)));
+        assert!(matches!(
+            directory.precheck(ID, Count(5)),
+            Err(UsigError::StaleCounter {
+                last: Count(5),
+                counter: Count(5),
+                ..
+            })
+        ));
+        directory.precheck(ID, Count(6)).unwrap();
+
+        // a new key starts over
+        directory.insert(ID, ()).unwrap();
+        directory.precheck(ID, Count(0)).unwrap();
+    }
+
+    #[test]
+    fn table_prechecks_replays() {
+        use crate::{
+            signature::{new_ed25519, UsigSignatureVerifyHalf},
+            SignHalf, Usig, VerifyHalf,
+        };
+
+        let (mut sign, _) = new_ed25519().split();
+        let mut verify = UsigSignatureVerifyHalf::<
+            ed25519_dalek::Signature,
+            ed25519_dalek::VerifyingKey,
+            _,
+        >::with_directory(TableDirectory::default());
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+        let first = sign.sign(b"message").unwrap();
+        verify.pre_validate(ID, &first).unwrap();
+        verify.verify(ID, b"message", &first).unwrap();
+        assert!(matches!(
+            verify.pre_validate(ID, &first),
+            Err(UsigError::StaleCounter { .. })
+        ));
+        verify
+            .pre_validate(ID, &sign.sign(b"message").unwrap())
+            .unwrap();
+    }
+
+    #[test]
+    fn file_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("directory");
+
+        let key = rand::random();
+
+        let mut directory = FileDirectory::open(&path, &key).unwrap();
+        assert!(directory.get(ID).is_none());
+        directory.insert(ID, vec![1u8, 2, 3]).unwrap();
+        drop(directory);
+
+        let directory = FileDirectory::<Vec<u8>>::open(&path, &key).unwrap();
+        assert_eq!(directory.get(ID).as_deref(), Some(&vec![1, 2, 3]));
+        assert!(directory.get(ReplicaId::from_u64(1)).is_none());
+    }
+
+    #[test]
+    fn file_encrypted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("directory");
+        let key = rand::random();
+
+        let secret = [0x5au8; 32];
+        let mut directory = FileDirectory::open(&path, &key).unwrap();
+        directory.insert(ID, secret).unwrap();
+        let file = fs::read(&path).unwrap();
+        assert!(!file.windows(secret.len()).any(|window| window == secret));
+
+        assert_eq!(
+            FileDirectory::<[u8; 32]>::open(&path, &rand::random())
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn file_unwritable() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut directory =
+            FileDirectory::open(dir.path().join("missing/directory"), &rand::random()).unwrap();
+        assert!(matches!(
+            directory.insert(ID, 1u8),
+            Err(UsigError::DirectoryFailed)
+        ));
+        assert!(directory.get(ID).is_none());
+    }
+
+    #[test]
+    fn remote_registers() {
+        use hmac::Hmac;
+        use sha2::Sha256;
+
+        use crate::{hmac::UsigHmac, SignHalf, Usig};
+
+        type Hmac256 = UsigHmac<Hmac<Sha256>>;
+
+        let new_usig = || Hmac256::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap();
+        let (mut sign, mut verify) = new_usig().split();
+        let attestation = Mutex::new(sign.attest().unwrap());
+        let fetched = AtomicUsize::new(0);
+        let directory = RemoteDirectory::new(|id: ReplicaId| {
+            fetched.fetch_add(1, Ordering::SeqCst);
+            match id.as_u64() {
+                0 => Ok(Some(attestation.lock().unwrap().clone())),
+                1 => Ok(None),
+                _ => Err(UsigError::DirectoryFailed),
+            }
+        });
+
+        assert!(directory.fetch(&mut verify, ID).unwrap());
+        let signature = sign.sign(b"message").unwrap();
+        assert!(verify.verify(ID, b"message", &signature).is_ok());
+        assert_eq!(fetched.load(Ordering::SeqCst), 1);
+        assert!(!directory
+            .fetch(&mut verify, ReplicaId::from_u64(1))
+            .unwrap());
+        assert!(!verify.contains(ReplicaId::from_u64(1)));
+        assert!(matches!(
+            directory.fetch(&mut verify, ReplicaId::from_u64(2)),
+            Err(UsigError::DirectoryFailed)
+        ));
+
+        // a rotated key is picked up
+        let (mut rotated, _) = new_usig().split();
+        *attestation.lock().unwrap() = rotated.attest().unwrap();
+        assert!(directory.refresh(&mut verify).is_empty());
+        let signature = rotated.sign(b"message").unwrap();
+        assert!(verify.verify(ID, b"message", &signature).is_ok());
+
+        // a fetched attestation is checked like any other, the registered key is kept
+        attestation.lock().unwrap().binding[0] ^= 1;
+        assert!(matches!(
+            directory.refresh(&mut verify).as_slice(),
+            [(ID, UsigError::RemoteAttestationFailed)]
+        ));
+        assert!(verify.verify(ID, b"message", &signature).is_ok());
+    }
+
+    #[cfg(feature = "http")]
+    mod http {
+        use std::{
+            io::{BufRead, BufReader, Write},
+            net::TcpListener,
+            thread,
+        };
+
+        use super::*;
+
+        /// Answer one request per response over TLS, returns the base URL and its trust
+        fn serve(responses: Vec<(u16, Vec<u8>)>) -> (String, PinnedTrust) {
+            let (config, trust) = tls_server();
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let port = listener.local_addr().unwrap().port();
+            thread::spawn(move || {
+                for (status, body) in responses {
+                    let (tcp, _) = listener.accept().unwrap();
+                    let connection = rustls::ServerConnection::new(config.clone()).unwrap();
+                    let mut stream = rustls::StreamOwned::new(connection, tcp);
+                    let mut reader = BufReader::new(&mut stream);
+                    let mut line = String::new();
+                    while reader.read_line(&mut line).is_ok_and(|read| read > 2) {
+                        line.clear();
+                    }
+                    let head = format!(
+                        "HTTP/1.1 {status} Status\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        body.len()
+                    );
+                    let _ = stream.write_all(head.as_bytes());
+                    let _ = stream.write_all(&body);
+                    stream.conn.send_close_notify();
+                    let _ = stream.flush();
+                }
+            });
+            (format!("https://localhost:{port}/keys"), trust)
+        }
+
+        #[test]
+        fn fetch() {
+            let key = bincode::serialize(&7u64).unwrap();
+            let (url, trust) = serve(vec![(200, key), (404, Vec::new()), (500, Vec::new())]);
+            let fetcher = HttpFetcher::new(url, &trust).unwrap();
+            assert_eq!(fetcher.fetch(ID).unwrap(), Some(7u64));
+            assert_eq!(KeyFetcher::<u64>::fetch(&fetcher, ID).unwrap(), None);
+            assert!(matches!(
+                KeyFetcher::<u64>::fetch(&fetcher, ID),
+                Err(UsigError::Backend(_))
+            ));
+        }
+
+        #[test]
+        fn untrusted() {
+            let (url, _) = serve(vec![(200, Vec::new())]);
+            let (_, other) = tls_server();
+            let fetcher = HttpFetcher::new(url, &other).unwrap();
+            assert!(matches!(
+                KeyFetcher::<u64>::fetch(&fetcher, ID),
+                Err(UsigError::Backend(_))
+            ));
+        }
+
+        #[test]
+        fn plain_http() {
+            let (_, trust) = tls_server();
+            assert!(matches!(
+                HttpFetcher::new("http://localhost/keys", &trust),
+                Err(UsigError::Io(e)) if e.kind() == io::ErrorKind::InvalidInput
+            ));
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use shared_ids::ReplicaId;
+
+use crate::{encoding::with_context, Count, Counter, SignHalf, UsigError, VerifyHalf};
+
+/// Context of the field count and tree root a [FieldsSignature] signs
+pub const FIELDS_CONTEXT: &[u8] = b"usig fields";
+
+/// A node of the hash tree over the fields of a message
+pub type FieldHash = [u8; 32];
+
+fn leaf_hash(field: &[u8]) -> FieldHash {
+    Sha256::new()
+        .chain_update([0])
+        .chain_update(field)
+        .finalize()
+        .into()
+}
+
+fn node_hash(left: &FieldHash, right: &FieldHash) -> FieldHash {
+    Sha256::new()
+        .chain_update([1])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+/// The next level of the tree, a node without sibling is promoted unchanged
+fn next_level(level: &[FieldHash]) -> Vec<FieldHash> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => node_hash(left, right),
+            [single] => *single,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+fn levels(fields: &[impl AsRef<[u8]>]) -> Vec<Vec<FieldHash>> {
+    let mut levels = vec![fields
+        .iter()
+        .map(|f| leaf_hash(f.as_ref()))
+        .collect::<Vec<_>>()];
+    while levels.last().unwrap().len() > 1 {
+        levels.push(next_level(levels.last().unwrap()));
+    }
+    levels
+}
+
+/// The root of the hash tree over `fields`
+pub fn root(fields: &[impl AsRef<[u8]>]) -> FieldHash {
+    levels(fields)
+        .pop()
+        .and_then(|root| root.first().copied())
+        .unwrap_or_else(|| leaf_hash(&[]))
+}
+
+fn fields_message(field_count: u64, root: &FieldHash) -> Vec<u8> {
+    let mut message = field_count.to_be_bytes().to_vec();
+    message.extend_from_slice(root);
+    with_context(FIELDS_CONTEXT, &message)
+}
+
+/// Proves that a single field is part of the tree a [FieldsSignature] covers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldProof {
+    pub index: u64,
+    /// The siblings on the path from the field to the root, bottom up
+    pub siblings: Vec<FieldHash>,
+}
+
+/// Create the proof for the field at `index`, [None] if there is no such field
+pub fn prove(fields: &[impl AsRef<[u8]>], index: usize) -> Option<FieldProof> {
+    if index >= fields.len() {
+        return None;
+    }
+    let mut siblings = Vec::new();
+    let mut position = index;
+    for level in levels(fields) {
+        if let Some(sibling) = level.get(position ^ 1) {
+            siblings.push(*sibling);
+        }
+        position /= 2;
+    }
+    Some(FieldProof {
+        index: index as u64,
+        siblings,
+    })
+}
+
+/// A USIG signature over the hash tree of a message made of multiple fields
+///
+/// The counter covers the whole message, while single fields can be disclosed
+/// and verified later on with a [FieldProof].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldsSignature<S> {
+    pub field_count: u64,
+    pub root: FieldHash,
+    pub signature: S,
+}
+
+impl<S: Counter> Counter for FieldsSignature<S> {
+    fn counter(&self) -> Count {
+        self.signature.counter()
+    }
+}
+
+/// Sign the hash tree over `fields`
+pub fn sign_fields<S: SignHalf>(
+    sign_half: &mut S,
+    fields: &[impl AsRef<[u8]>],
+) -> Result<FieldsSignature<S::Signature>, UsigError> {
+    let field_count = fields.len() as u64;
+    let root = root(fields);
+    let signature = sign_half.sign(fields_message(field_count, &root))?;
+    Ok(FieldsSignature {
+        field_count,
+        root,
+        signature,
+    })
+}
+
+impl<S> FieldsSignature<S> {
+    /// Verify the signature of `id` over the whole message
+    pub fn verify<V: VerifyHalf<Signature = S>>(
+        &self,
+        verifier: &V,
+        id: ReplicaId,
+        fields: &[impl AsRef<[u8]>],
+    ) -> Result<(), UsigError> {
+        if fields.len() as u64 != self.field_count || root(fields) != self.root {
+            return Err(UsigError::InvalidSignature);
+        }
+        self.verify_root(verifier, id)
+    }
+
+    /// Verify the signature of `id` and that `field` is the disclosed field of `proof`
+    pub fn verify_field<V: VerifyHalf<Signature = S>>(
+        &self,
+        verifier: &V,
+        id: ReplicaId,
+        field: impl AsRef<[u8]>,
+        proof: &FieldProof,
+    ) -> Result<(), UsigError> {
+        if !self.covers(field.as_ref(), proof) {
+            return Err(UsigError::InvalidSignature);
+        }
+        self.verify_root(verifier, id)
+    }
+
+    fn verify_root<V: VerifyHalf<Signature = S>>(
+        &self,
+        verifier: &V,
+        id: ReplicaId,
+    ) -> Result<(), UsigError> {
+        verifier.verify(
+            id,
+            fields_message(self.field_count, &self.root),
+            &self.signature,
+        )
+    }
+
+    fn covers(&self, field: &[u8], proof: &FieldProof) -> bool {
+        if proof.index >= self.field_count {
+            return false;
+        }
+        let mut siblings = proof.siblings.iter();
+        let mut hash = leaf_hash(field);
+        let mut position = proof.index;
+        let mut width = self.field_count;
+        while width > 1 {
+            if position % 2 == 1 {
+                let Some(sibling) = siblings.next() else {
+                    return false;
+                };
+                hash = node_hash(sibling, &hash);
+            } else if position + 1 < width {
+                let Some(sibling) = siblings.next() else {
+                    return false;
+                };
+                hash = node_hash(&hash, sibling);
+            }
+            position /= 2;
+            width = width.div_ceil(2);
+        }
+        siblings.next().is_none() && hash == self.root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::Hmac;
+
+    use super::*;
+    use crate::{
+        hmac::{UsigHmac, UsigHmacVerifyHalf},
+        Usig,
+    };
+
+    type HmacUsig = UsigHmac<Hmac<Sha256>>;
+    type HmacSignature = <HmacUsig as Usig>::Signature;
+
+    const ID: ReplicaId = ReplicaId::first();
+    const FIELDS: [&[u8]; 5] = [b"view", b"sequence", b"digest", b"client", b"payload"];
+
+    fn setup() -> (
+        FieldsSignature<HmacSignature>,
+        UsigHmacVerifyHalf<Hmac<Sha256>>,
+    ) {
+        let usig = HmacUsig::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap();
+        let (mut sign, mut verify) = usig.split();
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+        (sign_fields(&mut sign, &FIELDS).unwrap(), verify)
+    }
+
+    #[test]
+    fn disclose_each_field() {
+        let (signature, verify) = setup();
+        assert!(signature.verify(&verify, ID, &FIELDS).is_ok());
+        for (index, field) in FIELDS.iter().enumerate() {
+            let proof = prove(&FIELDS, index).unwrap();
+            assert!(signature.verify_field(&verify, ID, field, &proof).is_ok());
+        }
+        assert!(prove(&FIELDS, FIELDS.len()).is_none());
+    }
+
+    #[test]
+    fn wrong_field() {
+        let (signature, verify) = setup();
+        let proof = prove(&FIELDS, 2).unwrap();
+        assert!(matches!(
+            signature.verify_field(&verify, ID, b"other", &proof),
+            Err(UsigError::InvalidSignature)
+        ));
+        let moved = FieldProof { index: 3, ..proof };
+        assert!(matches!(
+            signature.verify_field(&verify, ID, FIELDS[2], &moved),
+            Err(UsigError::InvalidSignature)
+        ));
+        assert!(matches!(
+            signature.verify(&verify, ID, &FIELDS[..4]),
+            Err(UsigError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn tree_shapes() {
+        for count in 1..=9 {
+            let fields: Vec<_> = (0..count).map(|i: u8| vec![i]).collect();
+            let root = root(&fields);
+            let signature = FieldsSignature {
+                field_count: count as u64,
+                root,
+                signature: (),
+            };
+            for (index, field) in fields.iter().enumerate() {
+                assert!(signature.covers(field, &prove(&fields, index).unwrap()));
+            }
+        }
+    }
+}
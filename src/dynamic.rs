@@ -0,0 +1,352 @@
+use std::{any::Any, fmt::Debug};
+
+use shared_ids::ReplicaId;
+
+use crate::{Count, Counter, MemoryReport, PartyId, SignHalf, Usig, UsigError, VerifyHalf};
+
+/// A signature of any backend, see [DynUsig]
+pub trait DynSignature: Debug + Send + Sync {
+    fn counter(&self) -> Count;
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<S: Counter + Debug + Send + Sync + 'static> DynSignature for S {
+    fn counter(&self) -> Count {
+        Counter::counter(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Counter for dyn DynSignature {
+    fn counter(&self) -> Count {
+        DynSignature::counter(self)
+    }
+}
+
+/// An attestation of any backend, see [DynUsig]
+pub trait DynAttestation: Debug + Send {
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<A: Debug + Send + 'static> DynAttestation for A {
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+pub type BoxedSignature = Box<dyn DynSignature>;
+pub type BoxedAttestation = Box<dyn DynAttestation>;
+
+/// Object safe version of [SignHalf], see [DynUsig]
+pub trait DynSignHalf<Id: PartyId = ReplicaId>: Send {
+    fn sign(&mut self, message: &[u8]) -> Result<BoxedSignature, UsigError>;
+
+    fn attest(&mut self) -> Result<BoxedAttestation, UsigError>;
+
+    fn local_id(&self) -> Option<Id>;
+}
+
+/// Object safe version of [VerifyHalf], see [DynUsig]
+///
+/// A signature or attestation of another backend is rejected with
+/// [UsigError::InvalidSignature] or [UsigError::RemoteAttestationFailed].
+pub trait DynVerifyHalf<Id: PartyId = ReplicaId>: Send + Sync {
+    fn verify(
+        &self,
+        remote_usig_id: Id,
+        message: &[u8],
+        signature: &dyn DynSignature,
+    ) -> Result<(), UsigError>;
+
+    fn pre_validate(
+        &self,
+        remote_usig_id: Id,
+        signature: &dyn DynSignature,
+    ) -> Result<(), UsigError>;
+
+    fn try_add_remote_party(
+        &mut self,
+        remote_usig_id: Id,
+        attestation: BoxedAttestation,
+    ) -> Result<(), UsigError>;
+
+    fn remove_remote_party(&mut self, remote_usig_id: Id) -> bool;
+
+    fn remote_parties(&self) -> Vec<Id>;
+
+    fn contains(&self, remote_usig_id: Id) -> bool;
+
+    fn last_counter(&self, remote_usig_id: Id) -> Option<Count>;
+
+    fn memory_usage(&self) -> MemoryReport;
+}
+
+/// Object safe version of [Usig] operating on byte slices and boxed signatures and attestations
+///
+/// Lets applications select a backend at runtime, e.g. from the configuration, and keep it
+/// as a `Box<dyn DynUsig>`. Every [Usig] is a [DynUsig] for the same party ids.
+pub trait DynUsig<Id: PartyId = ReplicaId>: Send + Sync {
+    fn sign(&mut self, message: &[u8]) -> Result<BoxedSignature, UsigError>;
+
+    fn attest(&mut self) -> Result<BoxedAttestation, UsigError>;
+
+    fn local_id(&self) -> Option<Id>;
+
+    fn verify(
+        &self,
+        remote_usig_id: Id,
+        message: &[u8],
+        signature: &dyn DynSignature,
+    ) -> Result<(), UsigError>;
+
+    fn pre_validate(
+        &self,
+        remote_usig_id: Id,
+        signature: &dyn DynSignature,
+    ) -> Result<(), UsigError>;
+
+    fn try_add_remote_party(
+        &mut self,
+        remote_usig_id: Id,
+        attestation: BoxedAttestation,
+    ) -> Result<(), UsigError>;
+
+    fn remove_remote_party(&mut self, remote_usig_id: Id) -> bool;
+
+    fn remote_parties(&self) -> Vec<Id>;
+
+    fn contains(&self, remote_usig_id: Id) -> bool;
+
+    fn memory_usage(&self) -> MemoryReport;
+
+    fn split(self: Box<Self>) -> (Box<dyn DynSignHalf<Id>>, Box<dyn DynVerifyHalf<Id>>);
+}
+
+fn downcast_signature<S: 'static>(signature: &dyn DynSignature) -> Result<&S, UsigError> {
+    signature
+        .as_any()
+        .downcast_ref()
+        .ok_or(UsigError::InvalidSignature)
+}
+
+fn downcast_attestation<A: 'static>(attestation: BoxedAttestation) -> Result<A, UsigError> {
+    attestation
+        .into_any()
+        .downcast()
+        .map(|attestation| *attestation)
+        .map_err(|_| UsigError::RemoteAttestationFailed)
+}
+
+impl<S, Id: PartyId> DynSignHalf<Id> for S
+where
+    S: SignHalf<Id> + Send,
+    S::Signature: Debug + Send + Sync + 'static,
+    S::Attestation: Debug + Send + 'static,
+{
+    fn sign(&mut self, message: &[u8]) -> Result<BoxedSignature, UsigError> {
+        Ok(Box::new(SignHalf::sign(self, message)?))
+    }
+
+    fn attest(&mut self) -> Result<BoxedAttestation, UsigError> {
+        Ok(Box::new(SignHalf::attest(self)?))
+    }
+
+    fn local_id(&self) -> Option<Id> {
+        SignHalf::local_id(self)
+    }
+}
+
+impl<V, Id: PartyId> DynVerifyHalf<Id> for V
+where
+    V: VerifyHalf<Id> + Send + Sync,
+    V::Signature: 'static,
+    V::Attestation: 'static,
+{
+    fn verify(
+        &self,
+        id: Id,
+        message: &[u8],
+        signature: &dyn DynSignature,
+    ) -> Result<(), UsigError> {
+        VerifyHalf::verify(self, id, message, downcast_signature(signature)?)
+    }
+
+    fn pre_validate(&self, id: Id, signature: &dyn DynSignature) -> Result<(), UsigError> {
+        VerifyHalf::pre_validate(self, id, downcast_signature(signature)?)
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        id: Id,
+        attestation: BoxedAttestation,
+    ) -> Result<(), UsigError> {
+        VerifyHalf::try_add_remote_party(self, id, downcast_attestation(attestation)?)
+    }
+
+    fn remove_remote_party(&mut self, id: Id) -> bool {
+        VerifyHalf::remove_remote_party(self, id)
+    }
+
+    fn remote_parties(&self) -> Vec<Id> {
+        VerifyHalf::remote_parties(self).collect()
+    }
+
+    fn contains(&self, id: Id) -> bool {
+        VerifyHalf::contains(self, id)
+    }
+
+    fn last_counter(&self, id: Id) -> Option<Count> {
+        VerifyHalf::last_counter(self, id)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        VerifyHalf::memory_usage(self)
+    }
+}
+
+impl<U, Id: PartyId> DynUsig<Id> for U
+where
+    U: Usig<Id> + Send + Sync,
+    U::Signature: Send + Sync + 'static,
+    U::Attestation: Send + 'static,
+    U::SignHalf: DynSignHalf<Id> + 'static,
+    U::VerifyHalf: DynVerifyHalf<Id> + 'static,
+{
+    fn sign(&mut self, message: &[u8]) -> Result<BoxedSignature, UsigError> {
+        Ok(Box::new(Usig::sign(self, message)?))
+    }
+
+    fn attest(&mut self) -> Result<BoxedAttestation, UsigError> {
+        Ok(Box::new(Usig::attest(self)?))
+    }
+
+    fn local_id(&self) -> Option<Id> {
+        Usig::local_id(self)
+    }
+
+    fn verify(
+        &self,
+        id: Id,
+        message: &[u8],
+        signature: &dyn DynSignature,
+    ) -> Result<(), UsigError> {
+        Usig::verify(self, id, message, downcast_signature(signature)?)
+    }
+
+    fn pre_validate(&self, id: Id, signature: &dyn DynSignature) -> Result<(), UsigError> {
+        Usig::pre_validate(self, id, downcast_signature(signature)?)
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        id: Id,
+        attestation: BoxedAttestation,
+    ) -> Result<(), UsigError> {
+        Usig::try_add_remote_party(self, id, downcast_attestation(attestation)?)
+    }
+
+    fn remove_remote_party(&mut self, id: Id) -> bool {
+        Usig::remove_remote_party(self, id)
+    }
+
+    fn remote_parties(&self) -> Vec<Id> {
+        Usig::remote_parties(self).collect()
+    }
+
+    fn contains(&self, id: Id) -> bool {
+        Usig::contains(self, id)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        Usig::memory_usage(self)
+    }
+
+    fn split(self: Box<Self>) -> (Box<dyn DynSignHalf<Id>>, Box<dyn DynVerifyHalf<Id>>) {
+        let (sign_half, verify_half) = Usig::split(*self);
+        (Box::new(sign_half), Box::new(verify_half))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::Hmac;
+    use sha2::Sha256;
+
+    use super::*;
+    use crate::{hmac::UsigHmac, noop::UsigNoOp, party::PartyVerifyHalf, signature::new_ed25519};
+
+    const ID: ReplicaId = ReplicaId::first();
+    const MESSAGE: &[u8] = b"message";
+
+    fn backend(name: &str) -> Box<dyn DynUsig> {
+        match name {
+            "hmac" => Box::new(
+                UsigHmac::<Hmac<Sha256>>::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap(),
+            ),
+            "ed25519" => Box::new(new_ed25519()),
+            _ => Box::new(UsigNoOp::default()),
+        }
+    }
+
+    #[test]
+    fn selected_at_runtime() {
+        for name in ["hmac", "ed25519", "noop"] {
+            let mut usig = backend(name);
+            let attestation = usig.attest().unwrap();
+            usig.try_add_remote_party(ID, attestation).unwrap();
+            let signature = usig.sign(MESSAGE).unwrap();
+            assert_eq!(signature.counter(), Count(0));
+            assert!(usig.verify(ID, MESSAGE, signature.as_ref()).is_ok());
+
+            let (mut sign, mut verify) = usig.split();
+            verify
+                .try_add_remote_party(ID, sign.attest().unwrap())
+                .unwrap();
+            let signature = sign.sign(MESSAGE).unwrap();
+            assert!(verify.pre_validate(ID, signature.as_ref()).is_ok());
+            assert!(verify.verify(ID, MESSAGE, signature.as_ref()).is_ok());
+        }
+    }
+
+    #[test]
+    fn foreign_backend() {
+        let (mut hmac, _) = backend("hmac").split();
+        let (_, mut ed25519) = backend("ed25519").split();
+        assert!(matches!(
+            ed25519.try_add_remote_party(ID, hmac.attest().unwrap()),
+            Err(UsigError::RemoteAttestationFailed)
+        ));
+        assert!(matches!(
+            ed25519.verify(ID, MESSAGE, hmac.sign(MESSAGE).unwrap().as_ref()),
+            Err(UsigError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn party_ids() {
+        let (sign, verify) =
+            UsigHmac::<Hmac<Sha256>>::try_new(Box::new(rand::random::<[u8; 16]>()))
+                .unwrap()
+                .split();
+        let mut sign: Box<dyn DynSignHalf> = Box::new(sign);
+        let mut verify: Box<dyn DynVerifyHalf<String>> =
+            Box::new(PartyVerifyHalf::<_, String>::new(verify));
+        let party = "replica-a".to_owned();
+        verify
+            .try_add_remote_party(party.clone(), sign.attest().unwrap())
+            .unwrap();
+        let signature = sign.sign(MESSAGE).unwrap();
+        assert!(verify
+            .verify(party.clone(), MESSAGE, signature.as_ref())
+            .is_ok());
+        assert_eq!(verify.remote_parties(), vec![party]);
+        assert!(matches!(
+            verify.verify("replica-b".to_owned(), MESSAGE, signature.as_ref()),
+            Err(UsigError::UnknownParty(_))
+        ));
+    }
+}
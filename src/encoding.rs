@@ -0,0 +1,142 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Byte order of the counter and the length frame
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Endianness {
+    #[default]
+    Big,
+    Little,
+}
+
+/// Where the counter is placed relative to the message
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum CounterPosition {
+    #[default]
+    Prefix,
+    Suffix,
+}
+
+/// How the message is delimited from the counter
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Framing {
+    /// The message bytes as they are
+    #[default]
+    None,
+    /// The message preceded by its length as a 64 bit integer
+    LengthPrefixed,
+}
+
+/// How the counter is mixed into the data the MAC or signature is computed over
+///
+/// The default, a big endian counter followed by the bare message, is what this crate
+/// always used. Other layouts exist to be wire compatible with other USIG implementations.
+/// The encoding is part of the [crate::AlgorithmParameters] of an attestation, so parties
+/// configured with different encodings reject each other on registration.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct CounterEncoding {
+    pub endianness: Endianness,
+    pub position: CounterPosition,
+    pub framing: Framing,
+}
+
+impl CounterEncoding {
+    fn int(&self, value: u64) -> [u8; 8] {
+        match self.endianness {
+            Endianness::Big => value.to_be_bytes(),
+            Endianness::Little => value.to_le_bytes(),
+        }
+    }
+
+    /// Feed the encoded counter and message to `update` piece by piece
+    pub fn write(&self, counter: u64, message: &[u8], mut update: impl FnMut(&[u8])) {
+        let counter = self.int(counter);
+        if self.position == CounterPosition::Prefix {
+            update(&counter);
+        }
+        if self.framing == Framing::LengthPrefixed {
+            update(&self.int(message.len() as u64));
+        }
+        update(message);
+        if self.position == CounterPosition::Suffix {
+            update(&counter);
+        }
+    }
+
+    /// The encoded counter and message as one buffer
+    pub fn preimage(&self, counter: u64, message: &[u8]) -> Vec<u8> {
+        let mut data = Vec::with_capacity(16 + message.len());
+        self.write(counter, message, |bytes| data.extend_from_slice(bytes));
+        data
+    }
+}
+
+/// The message preceded by the big endian length of `context` and `context` itself
+///
+/// The length keeps the context from being confused with the start of the message, see
+/// [crate::Usig::sign_with_context].
+pub fn with_context(context: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(8 + context.len() + message.len());
+    data.extend_from_slice(&(context.len() as u64).to_be_bytes());
+    data.extend_from_slice(context);
+    data.extend_from_slice(message);
+    data
+}
+
+impl fmt::Display for CounterEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let endianness = match self.endianness {
+            Endianness::Big => "big endian",
+            Endianness::Little => "little endian",
+        };
+        let position = match self.position {
+            CounterPosition::Prefix => "prefix",
+            CounterPosition::Suffix => "suffix",
+        };
+        write!(f, "{} counter {}", endianness, position)?;
+        if self.framing == Framing::LengthPrefixed {
+            write!(f, ", length prefixed")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_layout() {
+        assert_eq!(
+            CounterEncoding::default().preimage(1, b"ab"),
+            [0, 0, 0, 0, 0, 0, 0, 1, b'a', b'b']
+        );
+    }
+
+    #[test]
+    fn other_layout() {
+        let encoding = CounterEncoding {
+            endianness: Endianness::Little,
+            position: CounterPosition::Suffix,
+            framing: Framing::LengthPrefixed,
+        };
+        assert_eq!(
+            encoding.preimage(1, b"ab"),
+            [2, 0, 0, 0, 0, 0, 0, 0, b'a', b'b', 1, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            encoding.to_string(),
+            "little endian counter suffix, length prefixed"
+        );
+    }
+
+    #[test]
+    fn context() {
+        assert_eq!(
+            with_context(b"ab", b"c"),
+            [0, 0, 0, 0, 0, 0, 0, 2, b'a', b'b', b'c']
+        );
+        assert_ne!(with_context(b"ab", b"c"), with_context(b"a", b"bc"));
+    }
+}
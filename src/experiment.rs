@@ -0,0 +1,305 @@
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use shared_ids::ReplicaId;
+use signature::{Signer, Verifier};
+
+use crate::{
+    monotonic::MonotonicVerifyHalf,
+    persistence::{CounterStorage, PersistentSignHalf},
+    standby::ResumableSignHalf,
+    SignHalf, Usig, UsigError, VerifyHalf,
+};
+
+const ID: ReplicaId = ReplicaId::first();
+
+/// The messages signed and verified in an experiment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Workload {
+    pub messages: usize,
+    pub message_size: usize,
+}
+
+impl Workload {
+    /// Messages are the little endian index padded to `message_size`
+    ///
+    /// They are longer than `message_size` if it is too short to tell all of them apart.
+    fn messages(&self) -> Vec<Vec<u8>> {
+        let index_size = (usize::BITS - self.messages.saturating_sub(1).leading_zeros())
+            .div_ceil(u8::BITS) as usize;
+        let message_size = self.message_size.max(index_size);
+        (0..self.messages)
+            .map(|i| {
+                let mut message = vec![0; message_size];
+                for (byte, i) in message.iter_mut().zip(i.to_le_bytes()) {
+                    *byte = i;
+                }
+                message
+            })
+            .collect()
+    }
+}
+
+/// Time spent running a [Workload]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Measurement {
+    /// Attesting and registering the signer, zero for plain signers
+    pub setup: Duration,
+    pub sign: Duration,
+    pub verify: Duration,
+}
+
+impl Measurement {
+    pub fn total(&self) -> Duration {
+        self.setup + self.sign + self.verify
+    }
+}
+
+/// Run `workload` against `usig`, verifying its own signatures
+///
+/// Whatever the backend does besides creating signatures (counter management,
+/// persisting its directory, tracking replays) is part of the measurement, see
+/// [run_breakdown] to tell these apart.
+pub fn run_usig<U: Usig>(usig: &mut U, workload: &Workload) -> Result<Measurement, UsigError> {
+    let messages = workload.messages();
+
+    let start = Instant::now();
+    let attestation = usig.attest()?;
+    usig.try_add_remote_party(ID, attestation)?;
+    let setup = start.elapsed();
+
+    let start = Instant::now();
+    let signatures = messages
+        .iter()
+        .map(|message| usig.sign(message))
+        .collect::<Result<Vec<_>, _>>()?;
+    let sign = start.elapsed();
+
+    let start = Instant::now();
+    for (message, signature) in messages.iter().zip(&signatures) {
+        usig.verify(ID, message, signature)?;
+    }
+    let verify = start.elapsed();
+
+    Ok(Measurement {
+        setup,
+        sign,
+        verify,
+    })
+}
+
+/// Run `workload` against a plain counter-less signer
+pub fn run_plain<Q>(
+    signer: &impl Signer<Q>,
+    verifier: &impl Verifier<Q>,
+    workload: &Workload,
+) -> Result<Measurement, signature::Error> {
+    let messages = workload.messages();
+
+    let start = Instant::now();
+    let signatures = messages
+        .iter()
+        .map(|message| signer.try_sign(message))
+        .collect::<Result<Vec<_>, _>>()?;
+    let sign = start.elapsed();
+
+    let start = Instant::now();
+    for (message, signature) in messages.iter().zip(&signatures) {
+        verifier.verify(message, signature)?;
+    }
+    let verify = start.elapsed();
+
+    Ok(Measurement {
+        setup: Duration::ZERO,
+        sign,
+        verify,
+    })
+}
+
+/// The same [Workload] run against a USIG and a plain signer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Comparison {
+    pub workload: Workload,
+    pub usig: Measurement,
+    pub plain: Measurement,
+}
+
+impl Comparison {
+    /// Additional time the USIG spent signing
+    pub fn sign_overhead(&self) -> Duration {
+        self.usig.sign.saturating_sub(self.plain.sign)
+    }
+
+    /// Additional time the USIG spent verifying
+    pub fn verify_overhead(&self) -> Duration {
+        self.usig.verify.saturating_sub(self.plain.verify)
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Workload {
+            messages,
+            message_size,
+        } = self.workload;
+        writeln!(f, "{} messages of {} bytes", messages, message_size)?;
+        writeln!(f, "{:<8}{:>14}{:>14}{:>14}", "", "setup", "sign", "verify")?;
+        for (name, m) in [("usig", self.usig), ("plain", self.plain)] {
+            writeln!(
+                f,
+                "{:<8}{:>14?}{:>14?}{:>14?}",
+                name, m.setup, m.sign, m.verify
+            )?;
+        }
+        writeln!(
+            f,
+            "{:<8}{:>14}{:>14?}{:>14?}",
+            "overhead",
+            "",
+            self.sign_overhead(),
+            self.verify_overhead()
+        )
+    }
+}
+
+/// Where the time a USIG spends beyond a plain signer goes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Breakdown {
+    /// Signing and verifying with counters, without persistence or replay protection
+    pub counter: Duration,
+    /// Persisting the counter before signing
+    pub persistence: Duration,
+    /// Rejecting replayed and regressing counters on verification
+    pub replay: Duration,
+}
+
+impl Breakdown {
+    pub fn total(&self) -> Duration {
+        self.counter + self.persistence + self.replay
+    }
+}
+
+impl fmt::Display for Breakdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<12}{:>14?}", "counter", self.counter)?;
+        writeln!(f, "{:<12}{:>14?}", "persistence", self.persistence)?;
+        writeln!(f, "{:<12}{:>14?}", "replay", self.replay)
+    }
+}
+
+/// Run `workload` against fresh USIGs with each layer added in turn
+///
+/// The bare USIG is compared against `plain`, the [Measurement] of a plain signer on
+/// the same workload. Persisting counters to `storage` and the replay protection of a
+/// [MonotonicVerifyHalf] are compared against the bare USIG.
+pub fn run_breakdown<U>(
+    mut new_usig: impl FnMut() -> U,
+    storage: impl CounterStorage,
+    plain: &Measurement,
+    workload: &Workload,
+) -> Result<Breakdown, UsigError>
+where
+    U: Usig,
+    U::SignHalf: ResumableSignHalf,
+{
+    let bare = run_usig(&mut new_usig(), workload)?;
+    let messages = workload.messages();
+
+    let (sign_half, verify_half) = new_usig().split();
+    let mut sign_half = PersistentSignHalf::open(sign_half, storage)?;
+    let mut verify_half = MonotonicVerifyHalf::new(verify_half);
+    verify_half.try_add_remote_party(ID, sign_half.attest()?)?;
+
+    let start = Instant::now();
+    let signatures = messages
+        .iter()
+        .map(|message| sign_half.sign(message))
+        .collect::<Result<Vec<_>, _>>()?;
+    let sign = start.elapsed();
+
+    let start = Instant::now();
+    for (message, signature) in messages.iter().zip(&signatures) {
+        verify_half.verify(ID, message, signature)?;
+    }
+    let verify = start.elapsed();
+
+    Ok(Breakdown {
+        counter: (bare.sign + bare.verify).saturating_sub(plain.sign + plain.verify),
+        persistence: sign.saturating_sub(bare.sign),
+        replay: verify.saturating_sub(bare.verify),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::SigningKey;
+    use rand::rngs::OsRng;
+
+    use hmac::Hmac;
+    use sha2::Sha256;
+
+    use super::*;
+    use crate::{hmac::UsigHmac, persistence::FileCounterStorage, signature::new_ed25519};
+
+    const WORKLOAD: Workload = Workload {
+        messages: 10,
+        message_size: 32,
+    };
+
+    #[test]
+    fn ed25519() {
+        let key = SigningKey::generate(&mut OsRng);
+        let usig = run_usig(&mut new_ed25519(), &WORKLOAD).unwrap();
+        let plain = run_plain(&key, &key.verifying_key(), &WORKLOAD).unwrap();
+        let comparison = Comparison {
+            workload: WORKLOAD,
+            usig,
+            plain,
+        };
+        assert_eq!(comparison.to_string().lines().count(), 5);
+    }
+
+    #[test]
+    fn breakdown() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = FileCounterStorage::new(dir.path().join("counter"));
+        let key = SigningKey::generate(&mut OsRng);
+        let plain = run_plain(&key, &key.verifying_key(), &WORKLOAD).unwrap();
+        let breakdown = run_breakdown(
+            || UsigHmac::<Hmac<Sha256>>::try_new(Box::new([0; 16])).unwrap(),
+            storage,
+            &plain,
+            &WORKLOAD,
+        )
+        .unwrap();
+        // one flush per signature dwarfs a MAC
+        assert!(breakdown.persistence > Duration::ZERO);
+        assert_eq!(breakdown.to_string().lines().count(), 3);
+    }
+
+    #[test]
+    fn distinct_messages() {
+        let messages = WORKLOAD.messages();
+        for (i, message) in messages.iter().enumerate() {
+            assert_eq!(message.len(), WORKLOAD.message_size);
+            assert!(!messages[..i].contains(message));
+        }
+    }
+
+    #[test]
+    fn distinct_short_messages() {
+        for message_size in [0, 1] {
+            let workload = Workload {
+                messages: 300,
+                message_size,
+            };
+            let messages = workload.messages();
+            for (i, message) in messages.iter().enumerate() {
+                assert_eq!(message.len(), 2);
+                assert!(!messages[..i].contains(message));
+            }
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use std::{collections::HashSet, io};
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, Payload},
+    Aes256Gcm, Nonce,
+};
+use rand::rngs::OsRng;
+use serde::{de::DeserializeOwned, Serialize};
+use zeroize::Zeroizing;
+
+use crate::UsigError;
+
+/// The key exported sign half states are encrypted with
+///
+/// It has to be shared between the old and the new host of a replica, e.g. through the
+/// secret store of the deployment, and must not be known to anyone else.
+pub type ExportKey = [u8; 32];
+
+/// The random id of one export, so every exported state can only be imported once
+pub type TransferId = [u8; 16];
+
+/// The [TransferId]s of the states imported so far
+///
+/// Importing the same state twice yields two sign halves with the same key and counter,
+/// which can sign different messages with the same counter. The log has to be shared by
+/// all hosts that may import the states of a replica and survive their restarts, e.g. in
+/// the database of the deployment, otherwise it can not prevent this.
+pub trait ImportLog {
+    /// Record `transfer`, returns `false` if it was recorded before
+    fn insert(&mut self, transfer: TransferId) -> Result<bool, UsigError>;
+}
+
+/// An in-memory log, only suitable if all imports happen in this process
+impl ImportLog for HashSet<TransferId> {
+    fn insert(&mut self, transfer: TransferId) -> Result<bool, UsigError> {
+        Ok(HashSet::insert(self, transfer))
+    }
+}
+
+/// Associated data binding exported states to this crate, followed by the backend name
+const EXPORT_DOMAIN: &[u8] = b"usig export ";
+
+const NONCE_LENGTH: usize = 12;
+
+pub(crate) fn invalid_data(error: &str) -> UsigError {
+    UsigError::Io(io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+/// Serialize and encrypt the state of a sign half of `backend`
+pub(crate) fn seal<T: Serialize>(
+    key: &ExportKey,
+    backend: &str,
+    state: &T,
+) -> Result<Vec<u8>, UsigError> {
+    let transfer: TransferId = rand::random();
+    let plaintext = Zeroizing::new(bincode::serialize(&(transfer, state))?);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = Aes256Gcm::new(key.into())
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: &plaintext,
+                aad: &[EXPORT_DOMAIN, backend.as_bytes()].concat(),
+            },
+        )
+        .map_err(|_| invalid_data("state can not be encrypted"))?;
+    let mut blob = nonce.to_vec();
+    blob.extend_from_slice(&ciphertext);
+    Ok(blob)
+}
+
+/// Decrypt and deserialize a state written by [seal] for the same `backend`
+///
+/// Fails with [io::ErrorKind::InvalidData] if the blob was not exported with `key` for
+/// `backend`, was tampered with or holds a state of another layout, and with [io::ErrorKind::AlreadyExists] if `log`
+/// recorded its import before.
+pub(crate) fn open<T: DeserializeOwned>(
+    key: &ExportKey,
+    backend: &str,
+    blob: &[u8],
+    log: &mut impl ImportLog,
+) -> Result<T, UsigError> {
+    if blob.len() < NONCE_LENGTH {
+        return Err(invalid_data("exported state is truncated"));
+    }
+    let (nonce, ciphertext) = blob.split_at(NONCE_LENGTH);
+    let plaintext = Zeroizing::new(
+        Aes256Gcm::new(key.into())
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: &[EXPORT_DOMAIN, backend.as_bytes()].concat(),
+                },
+            )
+            .map_err(|_| invalid_data("exported state can not be decrypted"))?,
+    );
+    let (transfer, state): (TransferId, T) = bincode::deserialize(&plaintext)
+        .map_err(|e| UsigError::Io(io::Error::new(io::ErrorKind::InvalidData, e)))?;
+    if !log.insert(transfer)? {
+        return Err(UsigError::Io(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "exported state was already imported",
+        )));
+    }
+    Ok(state)
+}
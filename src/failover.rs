@@ -0,0 +1,697 @@
+use std::{
+    any::Any,
+    collections::{BTreeSet, HashMap},
+    fmt::Debug,
+    sync::Mutex,
+};
+
+use derivative::Derivative;
+use serde::{Deserialize, Serialize};
+use shared_ids::ReplicaId;
+
+use crate::{
+    encoding::with_context, Count, Counter, MemoryReport, SignHalf, Usig, UsigError, VerifyHalf,
+};
+
+/// Offset of the counters of the secondary backend of a [UsigFailover]
+///
+/// Places every signature of the secondary after all signatures of the primary.
+pub const SECONDARY_EPOCH: u64 = 1 << 63;
+
+/// Context of the handoff the primary signs before the secondary takes over
+pub const HANDOFF_CONTEXT: &[u8] = b"usig failover handoff";
+
+/// The handoff to the secondary attested with `secondary`, whose counters start at `epoch`
+///
+/// Naming the secondary keeps a handoff from being replayed for another key or epoch.
+fn handoff_message(epoch: u64, secondary: &impl Serialize) -> Result<Vec<u8>, UsigError> {
+    let handoff = bincode::serialize(&(epoch, secondary))?;
+    Ok(with_context(HANDOFF_CONTEXT, &handoff))
+}
+
+/// The counter of a secondary signature, `None` if it does not fit after [SECONDARY_EPOCH]
+fn secondary_counter(signature: &impl Counter) -> Option<Count> {
+    signature.counter().checked_add(SECONDARY_EPOCH)
+}
+
+/// A signature made with either backend of a [UsigFailover]
+///
+/// A signature of the secondary carries the handoff of the primary, a signature of the
+/// primary over the attestation of the secondary whose counter is the last one the
+/// primary issued.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum FailoverSignature<P, S> {
+    Primary(P),
+    Secondary { handoff: P, signature: S },
+}
+
+impl<P: Counter, S: Counter> Counter for FailoverSignature<P, S> {
+    /// Saturates for secondary counters beyond [SECONDARY_EPOCH], such signatures are
+    /// rejected by [FailoverVerifyHalf]
+    fn counter(&self) -> Count {
+        match self {
+            FailoverSignature::Primary(signature) => signature.counter(),
+            FailoverSignature::Secondary { signature, .. } => {
+                secondary_counter(signature).unwrap_or(Count(u64::MAX))
+            }
+        }
+    }
+}
+
+/// The attestations of both backends of a [UsigFailover]
+///
+/// A [FailoverSignHalf] attests both backends once and hands out the same attestation
+/// afterwards, as the handoff names the attestation of the secondary.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailoverAttestation<A, B> {
+    pub primary: A,
+    pub secondary: B,
+}
+
+/// A sign half switching to its secondary backend once the primary fails to sign
+///
+/// The switch happens when the primary fails with [UsigError::SigningFailed], e.g. when an
+/// HSM goes offline, or on [FailoverSignHalf::fail_over], and is permanent: the primary is
+/// never used again afterwards. Before switching the primary signs a handoff naming its
+/// last counter and the attestation of the secondary, if that fails as well the error is
+/// returned and the primary stays in use, as the secondary can not prove that the primary
+/// stopped signing. For the same reason attesting fails while the primary is offline,
+/// until it has handed over.
+#[derive(Derivative)]
+#[derivative(Debug(
+    bound = "P: Debug, S: Debug, P::Signature: Debug, P::Attestation: Debug, S::Attestation: Debug"
+))]
+pub struct FailoverSignHalf<P: SignHalf, S: SignHalf> {
+    primary: P,
+    secondary: S,
+    attestation: Option<FailoverAttestation<P::Attestation, S::Attestation>>,
+    handoff: Option<P::Signature>,
+}
+
+impl<P: SignHalf, S: SignHalf> FailoverSignHalf<P, S> {
+    pub fn new(primary: P, secondary: S) -> Self {
+        Self {
+            primary,
+            secondary,
+            attestation: None,
+            handoff: None,
+        }
+    }
+
+    /// Whether the primary handed over and the secondary is signing
+    pub fn is_failed_over(&self) -> bool {
+        self.handoff.is_some()
+    }
+}
+
+impl<P: SignHalf, S: SignHalf> FailoverSignHalf<P, S>
+where
+    P::Attestation: Clone,
+    S::Attestation: Clone + Serialize,
+{
+    /// Switch to the secondary, e.g. before the primary is taken down for maintenance
+    ///
+    /// Fails if the primary can not attest or sign the handoff.
+    pub fn fail_over(&mut self) -> Result<(), UsigError> {
+        if self.handoff.is_none() {
+            let secondary = self.attestation()?.secondary;
+            let handoff = handoff_message(SECONDARY_EPOCH, &secondary)?;
+            self.handoff = Some(self.primary.sign(handoff)?);
+        }
+        Ok(())
+    }
+
+    fn attestation(
+        &mut self,
+    ) -> Result<FailoverAttestation<P::Attestation, S::Attestation>, UsigError> {
+        if self.attestation.is_none() {
+            self.attestation = Some(FailoverAttestation {
+                primary: self.primary.attest()?,
+                secondary: self.secondary.attest()?,
+            });
+        }
+        Ok(self.attestation.clone().expect("attested above"))
+    }
+}
+
+impl<P: SignHalf, S: SignHalf> SignHalf for FailoverSignHalf<P, S>
+where
+    P::Signature: Clone,
+    P::Attestation: Clone,
+    S::Attestation: Clone + Serialize,
+{
+    type Signature = FailoverSignature<P::Signature, S::Signature>;
+    type Attestation = FailoverAttestation<P::Attestation, S::Attestation>;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        let handoff = match &self.handoff {
+            Some(handoff) => handoff.clone(),
+            None => match self.primary.sign(message.as_ref()) {
+                Err(UsigError::SigningFailed) => {
+                    self.fail_over()?;
+                    self.handoff.clone().expect("handed over above")
+                }
+                result => return result.map(FailoverSignature::Primary),
+            },
+        };
+        let signature = self.secondary.sign(message)?;
+        if secondary_counter(&signature).is_none() {
+            return Err(UsigError::CounterExhausted);
+        }
+        Ok(FailoverSignature::Secondary { handoff, signature })
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        self.attestation()
+    }
+
+    fn local_id(&self) -> Option<ReplicaId> {
+        self.primary
+            .local_id()
+            .or_else(|| self.secondary.local_id())
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        if self.is_failed_over() {
+            self.secondary.as_any()
+        } else {
+            self.primary.as_any()
+        }
+    }
+
+    /// `None`, the primary could sign past its handoff
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        None
+    }
+}
+
+/// A verify half accepting signatures of both backends of a [UsigFailover]
+///
+/// A secondary signature is only accepted with a valid handoff to the registered
+/// secondary. Once one is accepted, every later primary signature of the party is
+/// rejected, with [UsigError::Equivocation] if its counter is after the handoff.
+#[derive(Debug)]
+pub struct FailoverVerifyHalf<P, S> {
+    primary: P,
+    secondary: S,
+    /// The handoff message expected from each party, naming its registered secondary
+    handoffs: HashMap<ReplicaId, Vec<u8>>,
+    /// The counter of the accepted handoff of each party that switched to its secondary
+    handed_over: Mutex<HashMap<ReplicaId, Count>>,
+}
+
+impl<P: VerifyHalf, S: VerifyHalf> FailoverVerifyHalf<P, S> {
+    pub fn new(primary: P, secondary: S) -> Self {
+        Self {
+            primary,
+            secondary,
+            handoffs: HashMap::new(),
+            handed_over: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The counter of the handoff of `id`, if a secondary signature of it was accepted
+    pub fn handoff_counter(&self, id: ReplicaId) -> Option<Count> {
+        self.handed_over.lock().unwrap().get(&id).copied()
+    }
+
+    fn check_primary(&self, id: ReplicaId, signature: &P::Signature) -> Result<(), UsigError> {
+        match self.handoff_counter(id) {
+            Some(last) if signature.counter() > last => Err(UsigError::Equivocation(id)),
+            Some(_) => Err(UsigError::Outdated),
+            None => Ok(()),
+        }
+    }
+
+    /// Check the handoff of a secondary signature, returns its counter
+    fn check_handoff(&self, id: ReplicaId, handoff: &P::Signature) -> Result<Count, UsigError> {
+        match self.handoff_counter(id) {
+            Some(last) if last == handoff.counter() => {}
+            // The primary signed two handoffs, at least one after it handed over
+            Some(_) => return Err(UsigError::Equivocation(id)),
+            None => {
+                let message = self.handoffs.get(&id).ok_or(UsigError::UnknownId(id))?;
+                self.primary.verify(id, message, handoff)?
+            }
+        }
+        Ok(handoff.counter())
+    }
+}
+
+impl<P: VerifyHalf, S: VerifyHalf> VerifyHalf for FailoverVerifyHalf<P, S>
+where
+    S::Attestation: Serialize,
+{
+    type Signature = FailoverSignature<P::Signature, S::Signature>;
+    type Attestation = FailoverAttestation<P::Attestation, S::Attestation>;
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        match signature {
+            FailoverSignature::Primary(signature) => {
+                self.check_primary(id, signature)?;
+                self.primary.verify(id, message, signature)
+            }
+            FailoverSignature::Secondary { handoff, signature } => {
+                if secondary_counter(signature).is_none() {
+                    return Err(UsigError::InvalidSignature);
+                }
+                let handoff = self.check_handoff(id, handoff)?;
+                self.secondary.verify(id, message, signature)?;
+                self.handed_over.lock().unwrap().insert(id, handoff);
+                Ok(())
+            }
+        }
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        match signature {
+            FailoverSignature::Primary(signature) => {
+                self.check_primary(id, signature)?;
+                self.primary.pre_validate(id, signature)
+            }
+            FailoverSignature::Secondary { signature, .. } => {
+                if secondary_counter(signature).is_none() {
+                    return Err(UsigError::InvalidSignature);
+                }
+                self.secondary.pre_validate(id, signature)
+            }
+        }
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        let (primary, secondary) = (self.primary.memory_usage(), self.secondary.memory_usage());
+        let handed_over = self.handed_over.lock().unwrap();
+        MemoryReport {
+            party_registry: primary.party_registry
+                + secondary.party_registry
+                + self.handoffs.capacity() * (size_of::<(ReplicaId, Vec<u8>)>() + 1)
+                + self.handoffs.values().map(Vec::capacity).sum::<usize>(),
+            replay_cache: primary.replay_cache + secondary.replay_cache,
+            evidence: primary.evidence
+                + secondary.evidence
+                + size_of_val(&*handed_over)
+                + handed_over.capacity() * (size_of::<(ReplicaId, Count)>() + 1),
+        }
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        remote_usig_id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        let handoff = handoff_message(SECONDARY_EPOCH, &attestation.secondary)?;
+        self.primary
+            .try_add_remote_party(remote_usig_id, attestation.primary)?;
+        if let Err(e) = self
+            .secondary
+            .try_add_remote_party(remote_usig_id, attestation.secondary)
+        {
+            self.primary.remove_remote_party(remote_usig_id);
+            return Err(e);
+        }
+        self.handoffs.insert(remote_usig_id, handoff);
+        Ok(())
+    }
+
+    /// Also forgets whether the party handed over to its secondary
+    fn remove_remote_party(&mut self, remote_usig_id: ReplicaId) -> bool {
+        self.handoffs.remove(&remote_usig_id);
+        self.handed_over.get_mut().unwrap().remove(&remote_usig_id);
+        let primary = self.primary.remove_remote_party(remote_usig_id);
+        self.secondary.remove_remote_party(remote_usig_id) | primary
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        let mut parties: BTreeSet<_> = self.primary.remote_parties().collect();
+        parties.extend(self.secondary.remote_parties());
+        parties.into_iter()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.primary.contains(remote_usig_id) || self.secondary.contains(remote_usig_id)
+    }
+}
+
+/// A USIG signing with a primary backend and failing over to a secondary one
+///
+/// See [FailoverSignHalf] for when it switches and [FailoverVerifyHalf] for how the switch
+/// is verified. Signatures of the secondary carry counters offset by [SECONDARY_EPOCH], so
+/// they stay ordered after those of the primary.
+#[derive(Derivative)]
+#[derivative(Debug(
+    bound = "P::SignHalf: Debug, S::SignHalf: Debug, P::VerifyHalf: Debug, S::VerifyHalf: Debug"
+))]
+pub struct UsigFailover<P: Usig, S: Usig> {
+    sign_half: FailoverSignHalf<P::SignHalf, S::SignHalf>,
+    verify_half: FailoverVerifyHalf<P::VerifyHalf, S::VerifyHalf>,
+}
+
+impl<P: Usig, S: Usig> UsigFailover<P, S> {
+    pub fn new(primary: P, secondary: S) -> Self {
+        let (primary_sign, primary_verify) = primary.split();
+        let (secondary_sign, secondary_verify) = secondary.split();
+        Self {
+            sign_half: FailoverSignHalf::new(primary_sign, secondary_sign),
+            verify_half: FailoverVerifyHalf::new(primary_verify, secondary_verify),
+        }
+    }
+
+    /// Whether the primary failed and the secondary is signing
+    pub fn is_failed_over(&self) -> bool {
+        self.sign_half.is_failed_over()
+    }
+}
+
+impl<P: Usig, S: Usig> Usig for UsigFailover<P, S>
+where
+    P::Signature: Clone,
+    P::Attestation: Clone,
+    S::Attestation: Clone + Serialize,
+{
+    type Signature = FailoverSignature<P::Signature, S::Signature>;
+    type Attestation = FailoverAttestation<P::Attestation, S::Attestation>;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        self.sign_half.sign(message)
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        self.sign_half.attest()
+    }
+
+    fn local_id(&self) -> Option<ReplicaId> {
+        self.sign_half.local_id()
+    }
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        self.verify_half.verify(id, message, signature)
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.verify_half.pre_validate(id, signature)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        self.verify_half.memory_usage()
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        self.verify_half.try_add_remote_party(id, attestation)
+    }
+
+    fn remove_remote_party(&mut self, id: ReplicaId) -> bool {
+        self.verify_half.remove_remote_party(id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.verify_half.remote_parties()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.verify_half.contains(remote_usig_id)
+    }
+
+    type SignHalf = FailoverSignHalf<P::SignHalf, S::SignHalf>;
+    type VerifyHalf = FailoverVerifyHalf<P::VerifyHalf, S::VerifyHalf>;
+
+    fn split(self) -> (Self::SignHalf, Self::VerifyHalf) {
+        (self.sign_half, self.verify_half)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    };
+
+    use super::{
+        FailoverSignHalf, FailoverSignature, FailoverVerifyHalf, UsigFailover, SECONDARY_EPOCH,
+    };
+    use crate as usig;
+    use crate::{signature::new_ed25519, tests, Count};
+
+    type Ed25519 = crate::signature::UsigEd25519;
+    type Ed25519SignHalf = <Ed25519 as crate::Usig>::SignHalf;
+    type Ed25519VerifyHalf = <Ed25519 as crate::Usig>::VerifyHalf;
+
+    fn new_failover() -> UsigFailover<Ed25519, Ed25519> {
+        UsigFailover::new(new_ed25519(), new_ed25519())
+    }
+
+    tests!(new_failover());
+
+    /// A sign half whose hardware fails the next `failures` requests
+    struct Flaky {
+        inner: Primary,
+        failures: Arc<AtomicUsize>,
+    }
+
+    impl Flaky {
+        fn fail(&self) -> bool {
+            self.failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok()
+        }
+    }
+
+    impl usig::SignHalf for Flaky {
+        type Signature = <Ed25519SignHalf as usig::SignHalf>::Signature;
+        type Attestation = <Ed25519SignHalf as usig::SignHalf>::Attestation;
+
+        fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+            if self.fail() {
+                return Err(UsigError::SigningFailed);
+            }
+            self.inner.lock().unwrap().sign(message)
+        }
+
+        fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+            if self.fail() {
+                return Err(UsigError::SigningFailed);
+            }
+            self.inner.lock().unwrap().attest()
+        }
+    }
+
+    type Primary = Arc<Mutex<Ed25519SignHalf>>;
+
+    /// The failover halves, the failures of the primary and the primary itself, to sign
+    /// behind the back of the failover
+    fn flaky() -> (
+        FailoverSignHalf<Flaky, Ed25519SignHalf>,
+        FailoverVerifyHalf<Ed25519VerifyHalf, Ed25519VerifyHalf>,
+        Arc<AtomicUsize>,
+        Primary,
+    ) {
+        use crate::Usig;
+
+        let failures = Arc::new(AtomicUsize::new(0));
+        let (primary, primary_verify) = new_ed25519().split();
+        let (secondary, secondary_verify) = new_ed25519().split();
+        let primary = Arc::new(Mutex::new(primary));
+        let flaky = Flaky {
+            inner: primary.clone(),
+            failures: failures.clone(),
+        };
+        (
+            FailoverSignHalf::new(flaky, secondary),
+            FailoverVerifyHalf::new(primary_verify, secondary_verify),
+            failures,
+            primary,
+        )
+    }
+
+    #[test]
+    fn fail_over() {
+        let (mut sign, mut verify, failures, _) = flaky();
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+
+        let before = sign.sign(MESSAGE_1).unwrap();
+        assert!(matches!(before, FailoverSignature::Primary(_)));
+        assert!(!sign.is_failed_over());
+
+        failures.store(1, Ordering::SeqCst);
+        let after = sign.sign(MESSAGE_2).unwrap();
+        let FailoverSignature::Secondary { handoff, .. } = &after else {
+            panic!("expected a secondary signature");
+        };
+        assert_eq!(handoff.counter(), Count(1));
+        assert!(sign.is_failed_over());
+        assert!(after.counter() > before.counter());
+        assert_eq!(after.counter(), Count(super::SECONDARY_EPOCH));
+
+        assert!(verify.verify(ID, MESSAGE_2, &after).is_ok());
+        assert_eq!(verify.handoff_counter(ID), Some(Count(1)));
+        // Primary signatures are rejected once the secondary took over
+        assert!(matches!(
+            verify.verify(ID, MESSAGE_1, &before),
+            Err(UsigError::Outdated)
+        ));
+
+        assert!(matches!(
+            sign.sign(MESSAGE_1).unwrap(),
+            FailoverSignature::Secondary { .. }
+        ));
+    }
+
+    #[test]
+    fn stay_on_primary_without_handoff() {
+        let (mut sign, mut verify, failures, _) = flaky();
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+
+        failures.store(2, Ordering::SeqCst);
+        assert!(matches!(
+            sign.sign(MESSAGE_1),
+            Err(UsigError::SigningFailed)
+        ));
+        assert!(!sign.is_failed_over());
+
+        let signature = sign.sign(MESSAGE_1).unwrap();
+        assert!(matches!(signature, FailoverSignature::Primary(_)));
+        assert!(verify.verify(ID, MESSAGE_1, &signature).is_ok());
+    }
+
+    #[test]
+    fn forged_handoff() {
+        let (mut sign, mut verify, _, primary) = flaky();
+        let (mut other, _) = crate::Usig::split(new_ed25519());
+        let attestation = sign.attest().unwrap();
+        assert!(verify.add_remote_party(ID, attestation.clone()));
+
+        sign.fail_over().unwrap();
+        let FailoverSignature::Secondary { signature, .. } = sign.sign(MESSAGE_1).unwrap() else {
+            panic!("expected a secondary signature");
+        };
+        let handoff = super::handoff_message(SECONDARY_EPOCH, &attestation.secondary).unwrap();
+        let forged = FailoverSignature::Secondary {
+            handoff: other.sign(&handoff).unwrap(),
+            signature: signature.clone(),
+        };
+        assert!(matches!(
+            verify.verify(ID, MESSAGE_1, &forged),
+            Err(UsigError::InvalidSignature)
+        ));
+
+        // A handoff of the primary to another secondary or epoch does not carry over
+        let other_secondary = other.attest().unwrap();
+        for handoff in [
+            super::handoff_message(SECONDARY_EPOCH, &other_secondary).unwrap(),
+            super::handoff_message(0, &attestation.secondary).unwrap(),
+        ] {
+            let replayed = FailoverSignature::Secondary {
+                handoff: primary.lock().unwrap().sign(handoff).unwrap(),
+                signature: signature.clone(),
+            };
+            assert!(matches!(
+                verify.verify(ID, MESSAGE_1, &replayed),
+                Err(UsigError::InvalidSignature)
+            ));
+        }
+        assert_eq!(verify.handoff_counter(ID), None);
+    }
+
+    #[test]
+    fn primary_after_handoff() {
+        let (mut sign, mut verify, _, primary) = flaky();
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+
+        sign.fail_over().unwrap();
+        let secondary = sign.sign(MESSAGE_1).unwrap();
+        assert!(verify.verify(ID, MESSAGE_1, &secondary).is_ok());
+
+        // The host keeps signing with the primary it handed over
+        let signature =
+            FailoverSignature::Primary(primary.lock().unwrap().sign(MESSAGE_2).unwrap());
+        assert!(matches!(
+            verify.pre_validate(ID, &signature),
+            Err(UsigError::Equivocation(ID))
+        ));
+        assert!(matches!(
+            verify.verify(ID, MESSAGE_2, &signature),
+            Err(UsigError::Equivocation(ID))
+        ));
+
+        // A second handoff is proof of the same
+        let FailoverSignature::Secondary { signature, .. } = sign.sign(MESSAGE_2).unwrap() else {
+            panic!("expected a secondary signature");
+        };
+        let handoff = super::handoff_message(SECONDARY_EPOCH, &sign.attest().unwrap().secondary);
+        let handoff = primary.lock().unwrap().sign(handoff.unwrap()).unwrap();
+        let signature = FailoverSignature::Secondary { handoff, signature };
+        assert!(matches!(
+            verify.verify(ID, MESSAGE_2, &signature),
+            Err(UsigError::Equivocation(ID))
+        ));
+    }
+
+    #[test]
+    fn counter_overflow() {
+        struct At(Count);
+        impl usig::Counter for At {
+            fn counter(&self) -> Count {
+                self.0
+            }
+        }
+
+        let last = At(Count(super::SECONDARY_EPOCH - 1));
+        assert_eq!(super::secondary_counter(&last), Some(Count(u64::MAX)));
+        let overflowing = FailoverSignature::<At, At>::Secondary {
+            handoff: At(Count(0)),
+            signature: At(Count(super::SECONDARY_EPOCH)),
+        };
+        assert_eq!(
+            super::secondary_counter(&At(Count(super::SECONDARY_EPOCH))),
+            None
+        );
+        assert_eq!(overflowing.counter(), Count(u64::MAX));
+    }
+
+    #[test]
+    fn attest_while_offline() {
+        let (mut sign, mut verify, failures, _) = flaky();
+        failures.store(1, Ordering::SeqCst);
+        assert!(matches!(sign.attest(), Err(UsigError::SigningFailed)));
+        assert!(!sign.is_failed_over());
+
+        let attestation = sign.attest().unwrap();
+        assert!(verify.add_remote_party(ID, attestation));
+        // the attestation is kept, so it is handed out while the primary is offline
+        failures.store(1, Ordering::SeqCst);
+        let attestation = sign.attest().unwrap();
+        assert!(!sign.is_failed_over());
+
+        let signature = sign.sign(MESSAGE_1).unwrap();
+        assert!(sign.is_failed_over());
+        assert!(verify.verify(ID, MESSAGE_1, &signature).is_ok());
+
+        // a replica registering the party afterwards verifies the handoff just the same
+        let mut late = FailoverVerifyHalf::new(
+            crate::Usig::split(new_ed25519()).1,
+            crate::Usig::split(new_ed25519()).1,
+        );
+        assert!(late.add_remote_party(ID, attestation));
+        assert!(late.verify(ID, MESSAGE_1, &signature).is_ok());
+    }
+}
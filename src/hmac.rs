@@ -1,21 +1,52 @@
-use std::{collections::HashMap, fmt::Debug};
+use std::{any::Any, fmt::Debug, marker::PhantomData};
 
-use crate::{Count, Counter, SignHalf, UsigError, VerifyHalf};
+use crate::{
+    directory::{key_heap_usage, MemoryDirectory, PartyDirectory},
+    encoding::CounterEncoding,
+    export::{self, ExportKey, ImportLog},
+    standby::ResumableSignHalf,
+    AlgorithmParameters, Attestation, Count, Counter, MemoryReport, SignHalf, UsigError,
+    VerifyHalf,
+};
 
 use super::Usig;
 
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 use hmac::Mac;
 
 use derivative::Derivative;
 
-use generic_array::{ArrayLength, GenericArray};
-use hmac::digest::{InvalidLength, KeyInit};
+use generic_array::{typenum::Unsigned, ArrayLength, GenericArray};
+use hmac::digest::{
+    core_api::{AlgorithmName, CoreProxy},
+    InvalidLength, KeyInit,
+};
 use shared_ids::ReplicaId;
 use trait_alias_macro::pub_trait_alias_macro;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
-pub_trait_alias_macro!(MacType = Mac + Debug + KeyInit + Clone);
+pub_trait_alias_macro!(
+    MacType = Mac + Debug + KeyInit + Clone + CoreProxy<Core: AlgorithmName>
+);
+
+/// The algorithm parameters of a USIG using the MAC `M`
+pub fn parameters<M: MacType>() -> AlgorithmParameters {
+    struct Name<M>(PhantomData<M>);
+
+    impl<M: AlgorithmName> std::fmt::Display for Name<M> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            M::write_alg_name(f)
+        }
+    }
+
+    AlgorithmParameters {
+        scheme: Name::<M::Core>(PhantomData).to_string(),
+        output_length: M::OutputSize::USIZE,
+        pre_hash: None,
+        counter_encoding: CounterEncoding::default(),
+    }
+}
 
 #[derive(Derivative, Clone, Deserialize, Serialize)]
 #[serde(bound = "")]
@@ -31,38 +62,138 @@ impl<L: ArrayLength<u8>> Counter for Signature<L> {
     }
 }
 
+impl<L: ArrayLength<u8>> Signature<L> {
+    /// The big endian counter followed by the MAC, for transports not using serde
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(8 + L::USIZE);
+        bytes.extend_from_slice(&self.counter.to_be_bytes());
+        bytes.extend_from_slice(&self.signature);
+        bytes
+    }
+
+    /// Read a signature written by [Signature::to_bytes]
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<Self, UsigError> {
+        match bytes.split_first_chunk::<8>() {
+            Some((counter, signature)) if signature.len() == L::USIZE => Ok(Self {
+                counter: u64::from_be_bytes(*counter),
+                signature: GenericArray::clone_from_slice(signature),
+            }),
+            _ => Err(UsigError::InvalidSignature),
+        }
+    }
+}
+
 type Key = Box<[u8]>;
 
+/// The MAC keyed with `key`, whose length was checked when the key was taken
+///
+/// The keyed state is derived for every MAC instead of being kept next to the key, as
+/// the MAC types do not wipe it on drop.
+fn keyed<M: MacType>(key: &[u8]) -> M {
+    <M as Mac>::new_from_slice(key).expect("key length is checked on construction")
+}
+
 #[derive(Derivative)]
 #[derivative(Debug(bound = ""))]
 pub struct UsigHmacSignHalf<M: MacType> {
     counter: u64,
-    hmac: M,
-    key: Key,
+    key: Zeroizing<Key>,
+    local_id: Option<ReplicaId>,
+    encoding: CounterEncoding,
+    #[derivative(Debug = "ignore")]
+    phantom_data: PhantomData<M>,
 }
 
 impl<M: MacType> UsigHmacSignHalf<M> {
+    /// The key is wiped from memory when the sign half is dropped
     pub fn try_new(key: Box<[u8]>) -> Result<Self, InvalidLength> {
+        let key = Zeroizing::new(key);
+        <M as Mac>::new_from_slice(&key)?;
         Ok(Self {
             counter: 0,
-            hmac: Mac::new_from_slice(&key)?,
             key,
+            local_id: None,
+            encoding: CounterEncoding::default(),
+            phantom_data: PhantomData,
+        })
+    }
+
+    /// Bind the sign half to its own id, which is embedded in its attestation
+    pub fn with_local_id(mut self, id: ReplicaId) -> Self {
+        self.local_id = Some(id);
+        self
+    }
+
+    /// Mix the counter into the MAC input as `encoding` describes
+    pub fn with_counter_encoding(mut self, encoding: CounterEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Encrypt the key and counter with `key` to move the sign half to another host
+    ///
+    /// The sign half is consumed, so it can not sign with a counter the imported one will
+    /// use again. The export carries a random [export::TransferId], so it can only be
+    /// imported once per [ImportLog].
+    pub fn export_state(self, key: &ExportKey) -> Result<Vec<u8>, UsigError> {
+        let state = HmacState {
+            key: Key::clone(&self.key),
+            counter: self.counter,
+            local_id: self.local_id,
+            encoding: self.encoding,
+        };
+        export::seal(key, &parameters::<M>().scheme, &state)
+    }
+
+    /// Restore a sign half exported with [UsigHmacSignHalf::export_state]
+    ///
+    /// Fails with [UsigError::Io] if the state was not exported with `key` for the MAC `M`
+    /// or `log` recorded its import before.
+    pub fn import_state(
+        state: &[u8],
+        key: &ExportKey,
+        log: &mut impl ImportLog,
+    ) -> Result<Self, UsigError> {
+        let state: HmacState = export::open(key, &parameters::<M>().scheme, state, log)?;
+        <M as Mac>::new_from_slice(&state.key)
+            .map_err(|_| export::invalid_data("exported key has an invalid length"))?;
+        Ok(Self {
+            counter: state.counter,
+            key: Zeroizing::new(state.key.clone()),
+            local_id: state.local_id,
+            encoding: state.encoding,
+            phantom_data: PhantomData,
         })
     }
 }
 
+/// The exported state of a [UsigHmacSignHalf]
+#[derive(Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+struct HmacState {
+    key: Key,
+    counter: u64,
+    #[zeroize(skip)]
+    local_id: Option<ReplicaId>,
+    #[zeroize(skip)]
+    encoding: CounterEncoding,
+}
+
+/// The key wipes itself on drop
+impl<M: MacType> ZeroizeOnDrop for UsigHmacSignHalf<M> {}
+
 impl<M: MacType> SignHalf for UsigHmacSignHalf<M> {
     type Signature = Signature<M::OutputSize>;
-    type Attestation = Key;
+    type Attestation = Attestation<Zeroizing<Key>>;
 
     fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
         let counter = self.counter;
-        self.counter += 1;
+        self.counter = counter.checked_add(1).ok_or(UsigError::CounterExhausted)?;
 
-        let mut hmac = self.hmac.clone();
+        let mut hmac = keyed::<M>(&self.key);
 
-        Mac::update(&mut hmac, &counter.to_be_bytes());
-        Mac::update(&mut hmac, message.as_ref());
+        self.encoding.write(counter, message.as_ref(), |bytes| {
+            Mac::update(&mut hmac, bytes)
+        });
 
         Ok(Signature {
             counter,
@@ -71,19 +202,135 @@ impl<M: MacType> SignHalf for UsigHmacSignHalf<M> {
     }
 
     fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
-        Ok(self.key.clone())
+        let mut attestation = Attestation {
+            parameters: AlgorithmParameters {
+                counter_encoding: self.encoding,
+                ..parameters::<M>()
+            },
+            signer: self.local_id,
+            payload: Zeroizing::new(Key::clone(&self.key)),
+            binding: Vec::new(),
+        };
+        let mut hmac = keyed::<M>(&self.key);
+        Mac::update(&mut hmac, &attestation.bound_message()?);
+        attestation.binding = hmac.finalize().into_bytes().to_vec();
+        Ok(attestation)
+    }
+
+    fn local_id(&self) -> Option<ReplicaId> {
+        self.local_id
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        Some(self)
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        Some(self)
+    }
+}
+
+impl<M: MacType> ResumableSignHalf for UsigHmacSignHalf<M> {
+    fn resume_at(&mut self, next: Count) -> Result<(), UsigError> {
+        if next.0 < self.counter {
+            return Err(UsigError::CounterRegression);
+        }
+        self.counter = next.0;
+        Ok(())
+    }
+
+    fn next_counter(&self) -> Count {
+        Count(self.counter)
+    }
+}
+
+/// The key of a remote party, checked to have a valid length for the MAC `M`
+///
+/// Serializes as the raw key only. The key is wiped from memory on drop.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""), Clone(bound = ""))]
+pub struct VerifyKey<M: MacType> {
+    key: Zeroizing<Key>,
+    #[derivative(Debug = "ignore")]
+    phantom_data: PhantomData<M>,
+}
+
+impl<M: MacType> VerifyKey<M> {
+    pub fn try_new(key: Key) -> Result<Self, InvalidLength> {
+        let key = Zeroizing::new(key);
+        <M as Mac>::new_from_slice(&key)?;
+        Ok(Self {
+            key,
+            phantom_data: PhantomData,
+        })
+    }
+}
+
+/// The key wipes itself on drop
+impl<M: MacType> ZeroizeOnDrop for VerifyKey<M> {}
+
+impl<M: MacType> Serialize for VerifyKey<M> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.key.serialize(serializer)
+    }
+}
+
+impl<'de, M: MacType> Deserialize<'de> for VerifyKey<M> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::try_new(Key::deserialize(deserializer)?).map_err(de::Error::custom)
     }
 }
 
 #[derive(Derivative)]
-#[derivative(Debug(bound = ""), Default(bound = ""))]
-pub struct UsigHmacVerifyHalf<M: MacType> {
-    other_hmacs: HashMap<ReplicaId, M>,
+#[derivative(Debug(bound = "D: Debug"))]
+pub struct UsigHmacVerifyHalf<M: MacType, D = MemoryDirectory<VerifyKey<M>>> {
+    other_hmacs: D,
+    encoding: CounterEncoding,
+    require_signer: bool,
+    #[derivative(Debug = "ignore")]
+    phantom_data: PhantomData<M>,
 }
 
-impl<M: MacType> VerifyHalf for UsigHmacVerifyHalf<M> {
+impl<M: MacType> Default for UsigHmacVerifyHalf<M> {
+    fn default() -> Self {
+        Self::with_directory(MemoryDirectory::default())
+    }
+}
+
+impl<M: MacType, D: PartyDirectory<VerifyKey<M>>> UsigHmacVerifyHalf<M, D> {
+    pub fn with_directory(directory: D) -> Self {
+        Self {
+            other_hmacs: directory,
+            encoding: CounterEncoding::default(),
+            require_signer: false,
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Expect the counter encoding remote parties are attested with
+    pub fn with_counter_encoding(mut self, encoding: CounterEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Reject attestations that are not bound to the id they are added under
+    ///
+    /// Remote parties have to be constructed with their local id then.
+    pub fn require_signer(mut self) -> Self {
+        self.require_signer = true;
+        self
+    }
+}
+
+impl<M: MacType, D: PartyDirectory<VerifyKey<M>>> VerifyHalf for UsigHmacVerifyHalf<M, D> {
     type Signature = Signature<M::OutputSize>;
-    type Attestation = Key;
+    type Attestation = Attestation<Zeroizing<Key>>;
 
     fn verify(
         &self,
@@ -91,35 +338,90 @@ impl<M: MacType> VerifyHalf for UsigHmacVerifyHalf<M> {
         message: impl AsRef<[u8]>,
         signature: &Self::Signature,
     ) -> Result<(), UsigError> {
-        if let Some(hmac) = self.other_hmacs.get(&id) {
+        if let Some(key) = self.other_hmacs.get(id) {
             let Signature { counter, signature } = signature;
-            let mut hmac = hmac.clone();
+            let mut hmac = keyed::<M>(&key.key);
 
-            Mac::update(&mut hmac, &counter.to_be_bytes());
-            Mac::update(&mut hmac, message.as_ref());
+            self.encoding.write(*counter, message.as_ref(), |bytes| {
+                Mac::update(&mut hmac, bytes)
+            });
 
             hmac.verify(signature)
-                .map_err(|_| UsigError::InvalidSignature)
+                .map_err(|_| UsigError::InvalidSignature)?;
+            self.other_hmacs.observe(id, Count(*counter));
+            Ok(())
         } else {
             Err(UsigError::UnknownId(id))
         }
     }
 
-    fn add_remote_party(&mut self, id: ReplicaId, attestation: Self::Attestation) -> bool {
-        if let Ok(hmac) = Mac::new_from_slice(&attestation) {
-            self.other_hmacs.insert(id, hmac);
-            true
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.other_hmacs.precheck(id, signature.counter())
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        MemoryReport {
+            party_registry: self.other_hmacs.memory_usage()
+                + key_heap_usage(&self.other_hmacs, |key| key.key.len()),
+            ..Default::default()
+        }
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        attestation.check_parameters(&AlgorithmParameters {
+            counter_encoding: self.encoding,
+            ..parameters::<M>()
+        })?;
+        let key = VerifyKey::<M>::try_new(Key::clone(&attestation.payload))
+            .map_err(|_| UsigError::RemoteAttestationFailed)?;
+        let mut hmac = keyed::<M>(&key.key);
+        Mac::update(&mut hmac, &attestation.bound_message()?);
+        hmac.verify_slice(&attestation.binding)
+            .map_err(|_| UsigError::RemoteAttestationFailed)?;
+        if self.require_signer {
+            attestation.check_bound_signer(id)?;
         } else {
-            false
+            attestation.check_signer(id)?;
         }
+        self.other_hmacs.insert(id, key)
+    }
+
+    fn remove_remote_party(&mut self, id: ReplicaId) -> bool {
+        self.other_hmacs.remove(id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.other_hmacs.ids()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.other_hmacs.get(remote_usig_id).is_some()
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        Some(self)
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        Some(self)
     }
 }
 
 #[derive(Derivative)]
-#[derivative(Debug(bound = ""))]
-pub struct UsigHmac<M: MacType> {
+#[derivative(Debug(bound = "D: Debug"))]
+pub struct UsigHmac<M: MacType, D = MemoryDirectory<VerifyKey<M>>> {
     sign_half: UsigHmacSignHalf<M>,
-    verify_half: UsigHmacVerifyHalf<M>,
+    verify_half: UsigHmacVerifyHalf<M, D>,
 }
 
 impl<M: MacType> UsigHmac<M> {
@@ -131,9 +433,37 @@ impl<M: MacType> UsigHmac<M> {
     }
 }
 
-impl<M: MacType> Usig for UsigHmac<M> {
+impl<M: MacType, D: PartyDirectory<VerifyKey<M>>> UsigHmac<M, D> {
+    pub fn try_with_directory(key: Box<[u8]>, directory: D) -> Result<Self, InvalidLength> {
+        Ok(Self {
+            sign_half: UsigHmacSignHalf::try_new(key)?,
+            verify_half: UsigHmacVerifyHalf::with_directory(directory),
+        })
+    }
+
+    /// Bind the USIG to its own id, which is embedded in its attestation
+    pub fn with_local_id(mut self, id: ReplicaId) -> Self {
+        self.sign_half = self.sign_half.with_local_id(id);
+        self
+    }
+
+    /// Use `encoding` for the own MACs and expect it from remote parties
+    pub fn with_counter_encoding(mut self, encoding: CounterEncoding) -> Self {
+        self.sign_half = self.sign_half.with_counter_encoding(encoding);
+        self.verify_half = self.verify_half.with_counter_encoding(encoding);
+        self
+    }
+
+    /// Reject attestations that are not bound to the id they are added under
+    pub fn require_signer(mut self) -> Self {
+        self.verify_half = self.verify_half.require_signer();
+        self
+    }
+}
+
+impl<M: MacType, D: PartyDirectory<VerifyKey<M>>> Usig for UsigHmac<M, D> {
     type Signature = Signature<M::OutputSize>;
-    type Attestation = Key;
+    type Attestation = Attestation<Zeroizing<Key>>;
 
     fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
         self.sign_half.sign(message)
@@ -143,6 +473,10 @@ impl<M: MacType> Usig for UsigHmac<M> {
         self.sign_half.attest()
     }
 
+    fn local_id(&self) -> Option<ReplicaId> {
+        self.sign_half.local_id()
+    }
+
     fn verify(
         &self,
         id: ReplicaId,
@@ -152,12 +486,36 @@ impl<M: MacType> Usig for UsigHmac<M> {
         self.verify_half.verify(id, message, signature)
     }
 
-    fn add_remote_party(&mut self, id: ReplicaId, attestation: Self::Attestation) -> bool {
-        self.verify_half.add_remote_party(id, attestation)
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.verify_half.pre_validate(id, signature)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        self.verify_half.memory_usage()
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        self.verify_half.try_add_remote_party(id, attestation)
+    }
+
+    fn remove_remote_party(&mut self, id: ReplicaId) -> bool {
+        self.verify_half.remove_remote_party(id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.verify_half.remote_parties()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.verify_half.contains(remote_usig_id)
     }
 
     type SignHalf = UsigHmacSignHalf<M>;
-    type VerifyHalf = UsigHmacVerifyHalf<M>;
+    type VerifyHalf = UsigHmacVerifyHalf<M, D>;
 
     fn split(self) -> (Self::SignHalf, Self::VerifyHalf) {
         (self.sign_half, self.verify_half)
@@ -173,13 +531,338 @@ mod tests {
     use super::Key;
     use super::UsigHmac;
 
+    use crate::encoding::{CounterEncoding, CounterPosition, Endianness, Framing};
+
+    use crate::directory::FileDirectory;
+    use crate::standby::ResumableSignHalf;
+    use crate::Count;
+
     use hmac::Hmac;
     use rand::{rngs::OsRng, RngCore};
-    use sha2::Sha256;
+    use sha2::{Sha256, Sha512};
 
-    tests!({
+    fn new_key() -> Key {
         let mut key = [0u8; 16];
         OsRng.fill_bytes(&mut key);
-        UsigHmac::<Hmac<Sha256>>::try_new(Key::from(key)).unwrap()
-    });
+        Key::from(key)
+    }
+
+    tests!(UsigHmac::<Hmac<Sha256>>::try_new(new_key()).unwrap());
+
+    mod table_directory {
+        use hmac::Hmac;
+        use sha2::Sha256;
+        use shared_ids::ReplicaId;
+
+        use super::{new_key, UsigHmac};
+        use crate::{
+            directory::TableDirectory, hmac::VerifyKey, Count, Counter, SignHalf, Usig, UsigError,
+            VerifyHalf,
+        };
+
+        const ID: ReplicaId = ReplicaId::first();
+
+        type TableHmac = UsigHmac<Hmac<Sha256>, TableDirectory<VerifyKey<Hmac<Sha256>>>>;
+
+        fn new_usig() -> TableHmac {
+            UsigHmac::try_with_directory(new_key(), TableDirectory::default()).unwrap()
+        }
+
+        #[test]
+        fn verifies() {
+            let (mut sign, mut verify) = new_usig().split();
+            assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+            for counter in 0..3 {
+                let signature = sign.sign(b"message").unwrap();
+                assert_eq!(signature.counter(), Count(counter));
+                verify.pre_validate(ID, &signature).unwrap();
+                verify.verify(ID, b"message", &signature).unwrap();
+            }
+            assert!(matches!(
+                verify.pre_validate(ReplicaId::from_u64(1), &sign.sign(b"message").unwrap()),
+                Err(UsigError::UnknownId(_))
+            ));
+        }
+
+        #[test]
+        fn prechecks_replays() {
+            let (mut sign, mut verify) = new_usig().split();
+            assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+            let first = sign.sign(b"message").unwrap();
+            let second = sign.sign(b"message").unwrap();
+            verify.verify(ID, b"message", &second).unwrap();
+            for replayed in [&first, &second] {
+                assert!(matches!(
+                    verify.pre_validate(ID, replayed),
+                    Err(UsigError::StaleCounter { last: Count(1), .. })
+                ));
+            }
+            // only pre_validate consults the expected counter
+            verify.verify(ID, b"message", &first).unwrap();
+        }
+
+        #[test]
+        fn invalid_signature_not_observed() {
+            let (mut sign, mut verify) = new_usig().split();
+            assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+            let signature = sign.sign(b"message").unwrap();
+            assert!(matches!(
+                verify.verify(ID, b"forged", &signature),
+                Err(UsigError::InvalidSignature)
+            ));
+            verify.pre_validate(ID, &signature).unwrap();
+        }
+
+        #[test]
+        fn new_attestation_starts_over() {
+            let (mut sign, mut verify) = new_usig().split();
+            let attestation = sign.attest().unwrap();
+            assert!(verify.add_remote_party(ID, attestation.clone()));
+            let signature = sign.sign(b"message").unwrap();
+            verify.verify(ID, b"message", &signature).unwrap();
+            assert!(verify.pre_validate(ID, &signature).is_err());
+
+            assert!(verify.add_remote_party(ID, attestation));
+            verify.pre_validate(ID, &signature).unwrap();
+        }
+    }
+
+    #[test]
+    fn bytes_roundtrip() {
+        type Signature = super::Signature<generic_array::typenum::U32>;
+
+        let mut usig = UsigHmac::<Hmac<Sha256>>::try_new(new_key()).unwrap();
+        let attestation = usig.attest().unwrap();
+        assert!(usig.add_remote_party(ID, attestation));
+        let bytes = usig.sign(MESSAGE_1).unwrap().to_bytes();
+        assert_eq!(bytes.len(), 8 + 32);
+        let signature = Signature::try_from_bytes(&bytes).unwrap();
+        assert!(usig.verify(ID, MESSAGE_1, &signature).is_ok());
+        assert!(matches!(
+            Signature::try_from_bytes(&bytes[1..]),
+            Err(UsigError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn memory_counts_keys() {
+        let mut usig = UsigHmac::<Hmac<Sha256>>::try_new(Key::from([0; 64])).unwrap();
+        let empty = usig.memory_usage().party_registry;
+        for id in 0..4 {
+            let attestation = usig.attest().unwrap();
+            assert!(usig.add_remote_party(ReplicaId::from_u64(id), attestation));
+        }
+        assert!(usig.memory_usage().party_registry >= empty + 4 * 64);
+    }
+
+    #[test]
+    fn zeroize_on_drop() {
+        fn assert_zeroize_on_drop<T: zeroize::ZeroizeOnDrop>(_: &T) {}
+        let mut usig = UsigHmac::<Hmac<Sha256>>::try_new(new_key()).unwrap();
+        let attestation = usig.attest().unwrap();
+        assert_zeroize_on_drop(&attestation.payload);
+        let key = super::VerifyKey::<Hmac<Sha256>>::try_new(new_key()).unwrap();
+        assert_zeroize_on_drop(&key);
+        let (sign_half, _) = usig.split();
+        assert_zeroize_on_drop(&sign_half);
+    }
+
+    #[test]
+    fn file_directory_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("directory");
+
+        let mut usig_1 = UsigHmac::<Hmac<Sha256>>::try_new(new_key()).unwrap();
+        let signature = usig_1.sign(MESSAGE_1).unwrap();
+
+        let key = rand::random();
+        let directory = FileDirectory::open(&path, &key).unwrap();
+        let mut usig_2 =
+            UsigHmac::<Hmac<Sha256>, _>::try_with_directory(new_key(), directory).unwrap();
+        assert!(usig_2.add_remote_party(ID, usig_1.attest().unwrap()));
+        drop(usig_2);
+
+        let directory = FileDirectory::open(&path, &key).unwrap();
+        let usig_2 = UsigHmac::<Hmac<Sha256>, _>::try_with_directory(new_key(), directory).unwrap();
+        assert!(usig_2.verify(ID, MESSAGE_1, &signature).is_ok());
+    }
+
+    #[test]
+    fn counter_exhausted() {
+        let (mut sign_half, _) = UsigHmac::<Hmac<Sha256>>::try_new(new_key())
+            .unwrap()
+            .split();
+        sign_half.resume_at(Count(u64::MAX - 1)).unwrap();
+        assert_eq!(
+            sign_half.sign(MESSAGE_1).unwrap().counter(),
+            Count(u64::MAX - 1)
+        );
+        assert!(matches!(
+            sign_half.sign(MESSAGE_1),
+            Err(UsigError::CounterExhausted)
+        ));
+    }
+
+    #[test]
+    fn digest_mismatch() {
+        let mut usig_1 = UsigHmac::<Hmac<Sha256>>::try_new(new_key()).unwrap();
+        let mut usig_2 = UsigHmac::<Hmac<Sha512>>::try_new(new_key()).unwrap();
+        assert!(matches!(
+            usig_2.try_add_remote_party(ID, usig_1.attest().unwrap()),
+            Err(UsigError::ParameterMismatch { expected, actual })
+                if expected.scheme == "Hmac<Sha512_64>" && actual.scheme == "Hmac<Sha256_32>"
+        ));
+        assert!(!usig_2.add_remote_party(ID, usig_1.attest().unwrap()));
+    }
+
+    #[test]
+    fn local_id() {
+        let other = ReplicaId::from_u64(1);
+        let mut usig_1 = UsigHmac::<Hmac<Sha256>>::try_new(new_key())
+            .unwrap()
+            .with_local_id(ID);
+        let mut usig_2 = UsigHmac::<Hmac<Sha256>>::try_new(new_key()).unwrap();
+        assert_eq!(usig_1.local_id(), Some(ID));
+        assert_eq!(usig_2.local_id(), None);
+
+        let attestation = usig_1.attest().unwrap();
+        assert_eq!(attestation.signer, Some(ID));
+        assert!(matches!(
+            usig_2.try_add_remote_party(other, attestation.clone()),
+            Err(UsigError::IdentityMismatch { claimed, attested: Some(attested) })
+                if claimed == other && attested == ID
+        ));
+        usig_2.try_add_remote_party(ID, attestation).unwrap();
+
+        let (sign_half, _) = usig_1.split();
+        assert_eq!(sign_half.local_id(), Some(ID));
+    }
+
+    #[test]
+    fn require_signer() {
+        let other = ReplicaId::from_u64(1);
+        let mut bound = UsigHmac::<Hmac<Sha256>>::try_new(new_key())
+            .unwrap()
+            .with_local_id(ID);
+        let mut unbound = UsigHmac::<Hmac<Sha256>>::try_new(new_key()).unwrap();
+        let mut usig = UsigHmac::<Hmac<Sha256>>::try_new(new_key())
+            .unwrap()
+            .require_signer();
+
+        assert!(matches!(
+            usig.try_add_remote_party(other, unbound.attest().unwrap()),
+            Err(UsigError::IdentityMismatch { claimed, attested: None }) if claimed == other
+        ));
+        assert!(matches!(
+            usig.try_add_remote_party(other, bound.attest().unwrap()),
+            Err(UsigError::IdentityMismatch { claimed, attested: Some(attested) })
+                if claimed == other && attested == ID
+        ));
+        assert!(!usig.contains(other));
+        usig.try_add_remote_party(ID, bound.attest().unwrap())
+            .unwrap();
+        let signature = bound.sign(MESSAGE_1).unwrap();
+        assert!(usig.verify(ID, MESSAGE_1, &signature).is_ok());
+    }
+
+    #[test]
+    fn relabeled_attestation() {
+        let other = ReplicaId::from_u64(1);
+        let mut bound = UsigHmac::<Hmac<Sha256>>::try_new(new_key())
+            .unwrap()
+            .with_local_id(ID);
+        let mut usig = UsigHmac::<Hmac<Sha256>>::try_new(new_key()).unwrap();
+
+        let mut attestation = bound.attest().unwrap();
+        attestation.signer = Some(other);
+        assert!(matches!(
+            usig.try_add_remote_party(other, attestation),
+            Err(UsigError::RemoteAttestationFailed)
+        ));
+        let mut attestation = bound.attest().unwrap();
+        attestation.parameters.counter_encoding.endianness = Endianness::Little;
+        let mut usig_little = UsigHmac::<Hmac<Sha256>>::try_new(new_key())
+            .unwrap()
+            .with_counter_encoding(attestation.parameters.counter_encoding);
+        assert!(matches!(
+            usig_little.try_add_remote_party(ID, attestation),
+            Err(UsigError::RemoteAttestationFailed)
+        ));
+        assert!(!usig.contains(other));
+    }
+
+    #[test]
+    fn counter_encoding() {
+        let encoding = CounterEncoding {
+            endianness: Endianness::Little,
+            position: CounterPosition::Suffix,
+            framing: Framing::LengthPrefixed,
+        };
+        let mut usig_1 = UsigHmac::<Hmac<Sha256>>::try_new(new_key())
+            .unwrap()
+            .with_counter_encoding(encoding);
+        let mut usig_2 = UsigHmac::<Hmac<Sha256>>::try_new(new_key()).unwrap();
+        let mut usig_3 = UsigHmac::<Hmac<Sha256>>::try_new(new_key())
+            .unwrap()
+            .with_counter_encoding(encoding);
+
+        let attestation = usig_1.attest().unwrap();
+        assert_eq!(attestation.parameters.counter_encoding, encoding);
+        assert!(matches!(
+            usig_2.try_add_remote_party(ID, attestation.clone()),
+            Err(UsigError::ParameterMismatch { .. })
+        ));
+        usig_3.try_add_remote_party(ID, attestation).unwrap();
+        let signature = usig_1.sign(MESSAGE_1).unwrap();
+        assert!(usig_3.verify(ID, MESSAGE_1, &signature).is_ok());
+    }
+
+    #[test]
+    fn export_import() {
+        use std::collections::HashSet;
+
+        use super::UsigHmacSignHalf;
+
+        let export_key = [5; 32];
+        let (mut sign_half, mut verify_half) = UsigHmac::<Hmac<Sha256>>::try_new(new_key())
+            .unwrap()
+            .split();
+        assert!(verify_half.add_remote_party(ID, sign_half.attest().unwrap()));
+        sign_half.sign(MESSAGE_1).unwrap();
+        let state = sign_half.export_state(&export_key).unwrap();
+
+        let mut log = HashSet::new();
+        assert!(matches!(
+            UsigHmacSignHalf::<Hmac<Sha256>>::import_state(&state, &[6; 32], &mut log),
+            Err(UsigError::Io(_))
+        ));
+        assert!(
+            UsigHmacSignHalf::<Hmac<Sha512>>::import_state(&state, &export_key, &mut log).is_err()
+        );
+        let mut sign_half =
+            UsigHmacSignHalf::<Hmac<Sha256>>::import_state(&state, &export_key, &mut log).unwrap();
+        let signature = sign_half.sign(MESSAGE_2).unwrap();
+        assert_eq!(signature.counter(), Count(1));
+        assert!(verify_half.verify(ID, MESSAGE_2, &signature).is_ok());
+
+        // A second copy could sign another message with the same counter
+        assert!(matches!(
+            UsigHmacSignHalf::<Hmac<Sha256>>::import_state(&state, &export_key, &mut log),
+            Err(UsigError::Io(error)) if error.kind() == std::io::ErrorKind::AlreadyExists
+        ));
+
+        // Decrypts, but holds no state of this backend
+        let state = crate::export::seal(
+            &export_key,
+            &super::parameters::<Hmac<Sha256>>().scheme,
+            &0u8,
+        )
+        .unwrap();
+        let error = UsigHmacSignHalf::<Hmac<Sha256>>::import_state(&state, &export_key, &mut log)
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            UsigError::Io(error) if error.kind() == std::io::ErrorKind::InvalidData
+        ));
+    }
 }
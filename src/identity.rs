@@ -0,0 +1,146 @@
+use std::{any::Any, collections::HashMap, hash::Hash};
+
+use shared_ids::ReplicaId;
+
+use crate::{Attestation, Count, MemoryReport, UsigError, VerifyHalf};
+
+/// Maps the identity embedded in an attestation to the [ReplicaId] it belongs to
+///
+/// The identity is whatever the attestation binds the key to, e.g. a certificate CN,
+/// SGX report data, a TPM key handle or just the public key.
+pub trait IdentityMapping<A> {
+    /// The canonical id of the attested party, [None] if the identity is not known
+    fn replica_id(&self, attestation: &A) -> Option<ReplicaId>;
+}
+
+impl<A, F: Fn(&A) -> Option<ReplicaId>> IdentityMapping<A> for F {
+    fn replica_id(&self, attestation: &A) -> Option<ReplicaId> {
+        self(attestation)
+    }
+}
+
+/// Maps the attested key itself to the id, for backends without further identity
+impl<P: Hash + Eq> IdentityMapping<Attestation<P>> for HashMap<P, ReplicaId> {
+    fn replica_id(&self, attestation: &Attestation<P>) -> Option<ReplicaId> {
+        self.get(&attestation.payload).copied()
+    }
+}
+
+/// A verify half that only adds remote parties under the id their attestation maps to
+///
+/// Prevents operators from registering an attestation under the wrong id.
+#[derive(Debug)]
+pub struct MappedVerifyHalf<V, M> {
+    inner: V,
+    mapping: M,
+}
+
+impl<V: VerifyHalf, M: IdentityMapping<V::Attestation>> MappedVerifyHalf<V, M> {
+    pub fn new(inner: V, mapping: M) -> Self {
+        Self { inner, mapping }
+    }
+}
+
+impl<V: VerifyHalf, M: IdentityMapping<V::Attestation>> VerifyHalf for MappedVerifyHalf<V, M> {
+    type Signature = V::Signature;
+    type Attestation = V::Attestation;
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        self.inner.verify(id, message, signature)
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.inner.pre_validate(id, signature)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        self.inner.memory_usage()
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        remote_usig_id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        let attested = self.mapping.replica_id(&attestation);
+        if attested != Some(remote_usig_id) {
+            return Err(UsigError::IdentityMismatch {
+                claimed: remote_usig_id,
+                attested,
+            });
+        }
+        self.inner.try_add_remote_party(remote_usig_id, attestation)
+    }
+
+    fn remove_remote_party(&mut self, remote_usig_id: ReplicaId) -> bool {
+        self.inner.remove_remote_party(remote_usig_id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.inner.remote_parties()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.inner.contains(remote_usig_id)
+    }
+
+    fn last_counter(&self, id: ReplicaId) -> Option<Count> {
+        self.inner.last_counter(id)
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any()
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{signature::new_ed25519, SignHalf, Usig};
+
+    const MESSAGE: &[u8] = b"message";
+
+    #[test]
+    fn mislabeled_attestation() {
+        let (id_0, id_1) = (ReplicaId::from_u64(0), ReplicaId::from_u64(1));
+        let (mut sign_0, verify) = new_ed25519().split();
+        let (mut sign_1, _) = new_ed25519().split();
+        let attestation_0 = sign_0.attest().unwrap();
+        let attestation_1 = sign_1.attest().unwrap();
+        let mapping = HashMap::from([(attestation_0.payload, id_0)]);
+        let mut verify = MappedVerifyHalf::new(verify, mapping);
+
+        assert!(matches!(
+            verify.try_add_remote_party(id_1, attestation_0.clone()),
+            Err(UsigError::IdentityMismatch { claimed, attested: Some(attested) })
+                if claimed == id_1 && attested == id_0
+        ));
+        assert!(matches!(
+            verify.try_add_remote_party(id_1, attestation_1),
+            Err(UsigError::IdentityMismatch { attested: None, .. })
+        ));
+
+        verify.try_add_remote_party(id_0, attestation_0).unwrap();
+        let signature = sign_0.sign(MESSAGE).unwrap();
+        assert!(verify.verify(id_0, MESSAGE, &signature).is_ok());
+        assert!(matches!(
+            verify.verify(id_1, MESSAGE, &signature),
+            Err(UsigError::UnknownId(_))
+        ));
+    }
+}
@@ -0,0 +1,321 @@
+use std::{any::Any, io};
+
+use serde::{Deserialize, Serialize};
+use shared_ids::ReplicaId;
+
+use crate::{
+    encoding::with_context, lazy::digest, Count, Counter, MemoryReport, SignHalf, UsigError,
+    VerifyHalf,
+};
+
+/// Context under which messages are signed together with their [Lane]
+pub const LANE_CONTEXT: &[u8] = b"usig lane";
+
+/// One of the two independent counter sequences of a [LaneSignHalf]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Lane {
+    Normal,
+    /// For urgent protocol messages like view changes
+    Priority,
+}
+
+impl Lane {
+    fn tag(self) -> u8 {
+        match self {
+            Lane::Normal => 0,
+            Lane::Priority => 1,
+        }
+    }
+}
+
+fn lane_message(lane: Lane, message: &[u8]) -> Vec<u8> {
+    with_context(LANE_CONTEXT, &[&[lane.tag()], message].concat())
+}
+
+/// The highest counter a lane signs with, so the counters of both lanes fit into one
+const MAX_LANE_COUNTER: Count = Count(u64::MAX >> 1);
+
+/// A USIG signature together with the [Lane] it was created in
+///
+/// The counter of the inner signature is only unique and sequential within its lane, see
+/// [LaneSignature::lane_counter]. [Counter::counter] interleaves the lanes instead, so it is
+/// unique for the signer but not sequential.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaneSignature<S> {
+    pub lane: Lane,
+    pub signature: S,
+}
+
+impl<S: Counter> LaneSignature<S> {
+    /// The lane and the counter within it, the counters of a lane are sequential
+    pub fn lane_counter(&self) -> (Lane, Count) {
+        (self.lane, self.signature.counter())
+    }
+}
+
+impl<S: Counter> Counter for LaneSignature<S> {
+    /// Even counters are of [Lane::Normal], odd ones of [Lane::Priority]
+    ///
+    /// Saturates for lane counters beyond the highest one signed, such signatures are
+    /// rejected by [LaneVerifyHalf].
+    fn counter(&self) -> Count {
+        let Count(counter) = self.signature.counter();
+        Count(
+            counter
+                .saturating_mul(2)
+                .saturating_add(u64::from(self.lane.tag())),
+        )
+    }
+}
+
+fn check_lane_counter(signature: &impl Counter) -> Result<(), UsigError> {
+    if signature.counter() > MAX_LANE_COUNTER {
+        Err(UsigError::InvalidSignature)
+    } else {
+        Ok(())
+    }
+}
+
+/// A sign half with a normal and a priority lane, each with its own counter
+///
+/// Both inner sign halves hold the same key, the attestation is taken from the normal lane.
+/// Messages are domain separated by lane, so a signature can never be replayed in the other lane.
+#[derive(Debug)]
+pub struct LaneSignHalf<S> {
+    normal: S,
+    priority: S,
+}
+
+impl<S: SignHalf> LaneSignHalf<S> {
+    /// Combine the sign halves of both lanes
+    ///
+    /// Fails with [io::ErrorKind::InvalidInput] if their attestations differ, i.e. they do
+    /// not hold the same key and id, so backends attesting with fresh quotes do not fit.
+    pub fn new(mut normal: S, mut priority: S) -> Result<Self, UsigError>
+    where
+        S::Attestation: Serialize,
+    {
+        if digest(&normal.attest()?) != digest(&priority.attest()?) {
+            return Err(UsigError::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the lanes hold different keys",
+            )));
+        }
+        Ok(Self { normal, priority })
+    }
+
+    /// Sign `message` with the next counter of `lane`
+    ///
+    /// Fails with [UsigError::CounterExhausted] once the lane reached the highest counter
+    /// both lanes can be interleaved with.
+    pub fn sign_in(
+        &mut self,
+        lane: Lane,
+        message: impl AsRef<[u8]>,
+    ) -> Result<LaneSignature<S::Signature>, UsigError> {
+        let inner = match lane {
+            Lane::Normal => &mut self.normal,
+            Lane::Priority => &mut self.priority,
+        };
+        let signature = inner.sign(lane_message(lane, message.as_ref()))?;
+        if signature.counter() > MAX_LANE_COUNTER {
+            return Err(UsigError::CounterExhausted);
+        }
+        Ok(LaneSignature { lane, signature })
+    }
+}
+
+impl<S: SignHalf> SignHalf for LaneSignHalf<S> {
+    type Signature = LaneSignature<S::Signature>;
+    type Attestation = S::Attestation;
+
+    /// Sign in the [Lane::Normal] lane
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        self.sign_in(Lane::Normal, message)
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        self.normal.attest()
+    }
+
+    fn local_id(&self) -> Option<ReplicaId> {
+        self.normal.local_id()
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        self.normal.as_any()
+    }
+
+    /// `None`, the backend would sign without the lane, burning counters peers never verify
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        None
+    }
+}
+
+/// Verifies [LaneSignature]s created by a [LaneSignHalf]
+#[derive(Debug, Default)]
+pub struct LaneVerifyHalf<V> {
+    inner: V,
+}
+
+impl<V: VerifyHalf> LaneVerifyHalf<V> {
+    pub fn new(inner: V) -> Self {
+        Self { inner }
+    }
+}
+
+impl<V: VerifyHalf> VerifyHalf for LaneVerifyHalf<V> {
+    type Signature = LaneSignature<V::Signature>;
+    type Attestation = V::Attestation;
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        check_lane_counter(&signature.signature)?;
+        self.inner.verify(
+            id,
+            lane_message(signature.lane, message.as_ref()),
+            &signature.signature,
+        )
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        check_lane_counter(&signature.signature)?;
+        self.inner.pre_validate(id, &signature.signature)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        self.inner.memory_usage()
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        remote_usig_id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        self.inner.try_add_remote_party(remote_usig_id, attestation)
+    }
+
+    fn remove_remote_party(&mut self, remote_usig_id: ReplicaId) -> bool {
+        self.inner.remove_remote_party(remote_usig_id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.inner.remote_parties()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.inner.contains(remote_usig_id)
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any()
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::Hmac;
+    use sha2::Sha256;
+
+    use super::*;
+    use crate::{
+        hmac::{UsigHmacSignHalf, UsigHmacVerifyHalf},
+        standby::ResumableSignHalf,
+    };
+
+    type HmacSignHalf = UsigHmacSignHalf<Hmac<Sha256>>;
+    type HmacVerifyHalf = UsigHmacVerifyHalf<Hmac<Sha256>>;
+
+    const ID: ReplicaId = ReplicaId::first();
+    const MESSAGE: &[u8] = b"message";
+
+    fn setup() -> (LaneSignHalf<HmacSignHalf>, LaneVerifyHalf<HmacVerifyHalf>) {
+        let key: Box<[u8]> = Box::new(rand::random::<[u8; 16]>());
+        let mut sign = LaneSignHalf::new(
+            HmacSignHalf::try_new(key.clone()).unwrap(),
+            HmacSignHalf::try_new(key).unwrap(),
+        )
+        .unwrap();
+        let mut verify = LaneVerifyHalf::default();
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+        (sign, verify)
+    }
+
+    #[test]
+    fn independent_counters() {
+        let (mut sign, verify) = setup();
+        for i in 0..5 {
+            let signature = sign.sign(MESSAGE).unwrap();
+            assert_eq!(signature.lane_counter(), (Lane::Normal, Count(i)));
+            assert_eq!(signature.counter(), Count(2 * i));
+            assert!(verify.verify(ID, MESSAGE, &signature).is_ok());
+        }
+        let urgent = sign.sign_in(Lane::Priority, MESSAGE).unwrap();
+        assert_eq!(urgent.lane_counter(), (Lane::Priority, Count(0)));
+        assert_eq!(urgent.counter(), Count(1));
+        assert!(verify.verify(ID, MESSAGE, &urgent).is_ok());
+        assert_eq!(
+            sign.sign(MESSAGE).unwrap().lane_counter(),
+            (Lane::Normal, Count(5))
+        );
+    }
+
+    #[test]
+    fn different_keys() {
+        let new_sign_half = || HmacSignHalf::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap();
+        assert!(matches!(
+            LaneSignHalf::new(new_sign_half(), new_sign_half()),
+            Err(UsigError::Io(error)) if error.kind() == io::ErrorKind::InvalidInput
+        ));
+    }
+
+    #[test]
+    fn exhausted_lane() {
+        let key: Box<[u8]> = Box::new(rand::random::<[u8; 16]>());
+        let mut priority = HmacSignHalf::try_new(key.clone()).unwrap();
+        priority.resume_at(MAX_LANE_COUNTER).unwrap();
+        let mut sign = LaneSignHalf::new(HmacSignHalf::try_new(key).unwrap(), priority).unwrap();
+        let mut verify = LaneVerifyHalf::<HmacVerifyHalf>::default();
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+
+        let last = sign.sign_in(Lane::Priority, MESSAGE).unwrap();
+        assert_eq!(last.counter(), Count(u64::MAX));
+        assert!(verify.verify(ID, MESSAGE, &last).is_ok());
+        assert!(matches!(
+            sign.sign_in(Lane::Priority, MESSAGE),
+            Err(UsigError::CounterExhausted)
+        ));
+        assert!(sign.sign(MESSAGE).is_ok());
+    }
+
+    #[test]
+    fn no_cross_lane_replay() {
+        let (mut sign, verify) = setup();
+        let mut signature = sign.sign_in(Lane::Priority, MESSAGE).unwrap();
+        signature.lane = Lane::Normal;
+        assert!(matches!(
+            verify.verify(ID, MESSAGE, &signature),
+            Err(UsigError::InvalidSignature)
+        ));
+    }
+}
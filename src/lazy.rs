@@ -0,0 +1,413 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Debug,
+    sync::{Mutex, RwLock},
+    time::Duration,
+};
+
+use derivative::Derivative;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use shared_ids::ReplicaId;
+
+use crate::{
+    clock::{Clock, SystemClock},
+    Count, MemoryReport, UsigError, VerifyHalf,
+};
+
+/// SHA-256 over the bincode serialization of an attestation
+pub type AttestationDigest = [u8; 32];
+
+/// Compute the digest an attestation is registered by
+pub fn digest<A: Serialize>(attestation: &A) -> AttestationDigest {
+    let bytes = bincode::serialize(attestation).expect("serialization to memory does not fail");
+    Sha256::digest(bytes).into()
+}
+
+/// Fetches full attestations out-of-band by their digest
+pub trait AttestationResolver<A> {
+    /// Fetch the attestation of a party, [None] if it is not available
+    fn resolve(&self, id: ReplicaId, digest: &AttestationDigest) -> Option<A>;
+}
+
+impl<A, F: Fn(ReplicaId, &AttestationDigest) -> Option<A>> AttestationResolver<A> for F {
+    fn resolve(&self, id: ReplicaId, digest: &AttestationDigest) -> Option<A> {
+        self(id, digest)
+    }
+}
+
+/// How often a [LazyVerifyHalf] calls its resolver
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResolverLimits {
+    /// How long a failed resolution is remembered before the resolver is asked again
+    pub miss_ttl: Duration,
+    /// Resolver calls allowed per `period` over all parties
+    pub max_calls: u32,
+    pub period: Duration,
+}
+
+impl Default for ResolverLimits {
+    fn default() -> Self {
+        Self {
+            miss_ttl: Duration::from_secs(30),
+            max_calls: 16,
+            period: Duration::from_secs(1),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Throttle {
+    /// Parties whose attestation could not be resolved, with the digest and until when
+    misses: HashMap<ReplicaId, (AttestationDigest, Duration)>,
+    period_start: Duration,
+    calls: u32,
+}
+
+/// A verify half that allows registering remote parties by the digest of their attestation
+///
+/// Large attestations (SGX, TPM) are only fetched through the resolver and validated
+/// when a signature of the party is first verified, or when [LazyVerifyHalf::resolve_all]
+/// is called, so parties that are registered but never heard from take up little memory.
+///
+/// Messages of pending parties trigger resolver calls, so these are bounded by
+/// [ResolverLimits]: a failed resolution is answered from the cache until it expires and
+/// calls beyond the rate are rejected with [UsigError::DirectoryFailed].
+#[derive(Derivative)]
+#[derivative(Debug(bound = "V: Debug, R: Debug, C: Debug"))]
+pub struct LazyVerifyHalf<V, R, C = SystemClock> {
+    inner: RwLock<V>,
+    resolver: R,
+    pending: RwLock<HashMap<ReplicaId, AttestationDigest>>,
+    limits: ResolverLimits,
+    clock: C,
+    throttle: Mutex<Throttle>,
+}
+
+impl<V: VerifyHalf, R: AttestationResolver<V::Attestation>> LazyVerifyHalf<V, R>
+where
+    V::Attestation: Serialize,
+{
+    pub fn new(inner: V, resolver: R) -> Self {
+        Self::with_limits(inner, resolver, ResolverLimits::default(), SystemClock)
+    }
+}
+
+impl<V: VerifyHalf, R: AttestationResolver<V::Attestation>, C: Clock> LazyVerifyHalf<V, R, C>
+where
+    V::Attestation: Serialize,
+{
+    pub fn with_limits(inner: V, resolver: R, limits: ResolverLimits, clock: C) -> Self {
+        Self {
+            inner: RwLock::new(inner),
+            resolver,
+            pending: RwLock::default(),
+            limits,
+            clock,
+            throttle: Mutex::default(),
+        }
+    }
+
+    /// Register a remote party by the digest of its attestation
+    ///
+    /// Replaces any previous registration of the party once the attestation is resolved.
+    pub fn add_remote_digest(&mut self, id: ReplicaId, digest: AttestationDigest) {
+        self.pending.get_mut().unwrap().insert(id, digest);
+        self.throttle.get_mut().unwrap().misses.remove(&id);
+    }
+
+    /// Number of parties whose attestation was not resolved yet
+    pub fn pending(&self) -> usize {
+        self.pending.read().unwrap().len()
+    }
+
+    /// Resolve and validate the attestations of all pending parties
+    ///
+    /// Returns the parties that could not be resolved. Subject to the [ResolverLimits] like
+    /// resolutions on first use.
+    pub fn resolve_all(&self) -> Vec<(ReplicaId, UsigError)> {
+        let ids: Vec<_> = self.pending.read().unwrap().keys().copied().collect();
+        ids.into_iter()
+            .filter_map(|id| self.resolve(id).err().map(|e| (id, e)))
+            .collect()
+    }
+
+    fn resolve(&self, id: ReplicaId) -> Result<(), UsigError> {
+        let Some(expected) = self.pending.read().unwrap().get(&id).copied() else {
+            return Ok(());
+        };
+        self.admit_call(id, &expected)?;
+        let attestation = self
+            .resolver
+            .resolve(id, &expected)
+            .filter(|attestation| digest(attestation) == expected);
+        let Some(attestation) = attestation else {
+            let until = self.clock.now().saturating_add(self.limits.miss_ttl);
+            let mut throttle = self.throttle.lock().unwrap();
+            throttle.misses.insert(id, (expected, until));
+            return Err(UsigError::RemoteAttestationFailed);
+        };
+        let mut pending = self.pending.write().unwrap();
+        if pending.get(&id) == Some(&expected) {
+            self.inner
+                .write()
+                .unwrap()
+                .try_add_remote_party(id, attestation)?;
+            pending.remove(&id);
+        }
+        Ok(())
+    }
+
+    /// Check the cached misses and the rate before calling the resolver for `id`
+    fn admit_call(&self, id: ReplicaId, expected: &AttestationDigest) -> Result<(), UsigError> {
+        let now = self.clock.now();
+        let mut throttle = self.throttle.lock().unwrap();
+        match throttle.misses.get(&id) {
+            Some((digest, until)) if digest == expected && now < *until => {
+                return Err(UsigError::RemoteAttestationFailed)
+            }
+            Some(_) => {
+                throttle.misses.remove(&id);
+            }
+            None => {}
+        }
+        if now.saturating_sub(throttle.period_start) >= self.limits.period {
+            throttle.period_start = now;
+            throttle.calls = 0;
+        }
+        if throttle.calls >= self.limits.max_calls {
+            return Err(UsigError::DirectoryFailed);
+        }
+        throttle.calls += 1;
+        Ok(())
+    }
+}
+
+impl<V, R, C> VerifyHalf for LazyVerifyHalf<V, R, C>
+where
+    V: VerifyHalf,
+    R: AttestationResolver<V::Attestation>,
+    C: Clock,
+    V::Attestation: Serialize,
+{
+    type Signature = V::Signature;
+    type Attestation = V::Attestation;
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        self.resolve(id)?;
+        self.inner.read().unwrap().verify(id, message, signature)
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        if self.pending.read().unwrap().contains_key(&id) {
+            Ok(())
+        } else {
+            self.inner.read().unwrap().pre_validate(id, signature)
+        }
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        let pending = self.pending.read().unwrap();
+        let mut report = self.inner.read().unwrap().memory_usage();
+        let misses = &self.throttle.lock().unwrap().misses;
+        report.party_registry += size_of_val(&*pending)
+            + pending.capacity() * (size_of::<(ReplicaId, AttestationDigest)>() + 1)
+            + misses.capacity() * (size_of::<(ReplicaId, (AttestationDigest, Duration))>() + 1);
+        report
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        self.inner
+            .get_mut()
+            .unwrap()
+            .try_add_remote_party(id, attestation)?;
+        self.pending.get_mut().unwrap().remove(&id);
+        self.throttle.get_mut().unwrap().misses.remove(&id);
+        Ok(())
+    }
+
+    fn remove_remote_party(&mut self, id: ReplicaId) -> bool {
+        self.throttle.get_mut().unwrap().misses.remove(&id);
+        let pending = self.pending.get_mut().unwrap().remove(&id).is_some();
+        self.inner.get_mut().unwrap().remove_remote_party(id) | pending
+    }
+
+    /// Includes the parties whose attestation is not resolved yet
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        let mut parties: BTreeSet<_> = self.inner.read().unwrap().remote_parties().collect();
+        parties.extend(self.pending.read().unwrap().keys());
+        parties.into_iter()
+    }
+
+    fn contains(&self, id: ReplicaId) -> bool {
+        self.pending.read().unwrap().contains_key(&id) || self.inner.read().unwrap().contains(id)
+    }
+
+    fn last_counter(&self, id: ReplicaId) -> Option<Count> {
+        self.inner.read().unwrap().last_counter(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use hmac::Hmac;
+
+    use super::*;
+    use crate::{
+        clock::ManualClock,
+        hmac::{UsigHmac, UsigHmacVerifyHalf},
+        Attestation, SignHalf, Usig,
+    };
+
+    type Key = zeroize::Zeroizing<Box<[u8]>>;
+
+    const MESSAGE: &[u8] = b"message";
+    const ID: ReplicaId = ReplicaId::first();
+
+    fn new_usig() -> UsigHmac<Hmac<Sha256>> {
+        UsigHmac::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap()
+    }
+
+    #[test]
+    fn resolve_on_first_use() {
+        let (mut sign, _) = new_usig().split();
+        let attestation = sign.attest().unwrap();
+        let resolved = AtomicUsize::new(0);
+        let mut verify = LazyVerifyHalf::new(
+            UsigHmacVerifyHalf::<Hmac<Sha256>>::default(),
+            |id, _: &AttestationDigest| {
+                resolved.fetch_add(1, Ordering::SeqCst);
+                (id == ID).then(|| attestation.clone())
+            },
+        );
+        verify.add_remote_digest(ID, digest(&attestation));
+        assert_eq!(verify.pending(), 1);
+
+        let signature = sign.sign(MESSAGE).unwrap();
+        assert!(verify.verify(ID, MESSAGE, &signature).is_ok());
+        assert!(verify.verify(ID, MESSAGE, &signature).is_ok());
+        assert_eq!(resolved.load(Ordering::SeqCst), 1);
+        assert_eq!(verify.pending(), 0);
+
+        assert!(matches!(
+            verify.verify(ReplicaId::from_u64(1), MESSAGE, &signature),
+            Err(UsigError::UnknownId(_))
+        ));
+    }
+
+    #[test]
+    fn digest_mismatch() {
+        let (mut sign, _) = new_usig().split();
+        let (mut other, _) = new_usig().split();
+        let attestation = sign.attest().unwrap();
+        let other = other.attest().unwrap();
+        let mut verify = LazyVerifyHalf::new(
+            UsigHmacVerifyHalf::<Hmac<Sha256>>::default(),
+            |_, _: &AttestationDigest| Some(other.clone()),
+        );
+        verify.add_remote_digest(ID, digest(&attestation));
+
+        let signature = sign.sign(MESSAGE).unwrap();
+        assert!(matches!(
+            verify.verify(ID, MESSAGE, &signature),
+            Err(UsigError::RemoteAttestationFailed)
+        ));
+        assert_eq!(verify.resolve_all().len(), 1);
+        assert_eq!(verify.pending(), 1);
+    }
+
+    #[test]
+    fn full_attestation_replaces_digest() {
+        let (mut sign, _) = new_usig().split();
+        let attestation = sign.attest().unwrap();
+        let mut verify = LazyVerifyHalf::new(
+            UsigHmacVerifyHalf::<Hmac<Sha256>>::default(),
+            |_, _: &AttestationDigest| None::<Attestation<Key>>,
+        );
+        verify.add_remote_digest(ID, digest(&attestation));
+        assert!(verify.add_remote_party(ID, attestation));
+        assert_eq!(verify.pending(), 0);
+
+        let signature = sign.sign(MESSAGE).unwrap();
+        assert!(verify.verify(ID, MESSAGE, &signature).is_ok());
+    }
+
+    #[test]
+    fn cache_misses() {
+        let (mut sign, _) = new_usig().split();
+        let attestation = sign.attest().unwrap();
+        let clock = ManualClock::new(Duration::ZERO);
+        let limits = ResolverLimits::default();
+        let resolved = AtomicUsize::new(0);
+        let mut verify = LazyVerifyHalf::with_limits(
+            UsigHmacVerifyHalf::<Hmac<Sha256>>::default(),
+            |_, _: &AttestationDigest| {
+                resolved.fetch_add(1, Ordering::SeqCst);
+                None::<Attestation<Key>>
+            },
+            limits,
+            &clock,
+        );
+        verify.add_remote_digest(ID, digest(&attestation));
+
+        let signature = sign.sign(MESSAGE).unwrap();
+        for _ in 0..3 {
+            assert!(matches!(
+                verify.verify(ID, MESSAGE, &signature),
+                Err(UsigError::RemoteAttestationFailed)
+            ));
+        }
+        assert_eq!(resolved.load(Ordering::SeqCst), 1);
+
+        clock.advance(limits.miss_ttl);
+        assert!(verify.verify(ID, MESSAGE, &signature).is_err());
+        assert_eq!(resolved.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn rate_limit() {
+        let (mut sign, _) = new_usig().split();
+        let attestation = sign.attest().unwrap();
+        let clock = ManualClock::new(Duration::ZERO);
+        let limits = ResolverLimits {
+            max_calls: 2,
+            ..ResolverLimits::default()
+        };
+        let mut verify = LazyVerifyHalf::with_limits(
+            UsigHmacVerifyHalf::<Hmac<Sha256>>::default(),
+            |id: ReplicaId, _: &AttestationDigest| (id.as_u64() == 3).then(|| attestation.clone()),
+            limits,
+            &clock,
+        );
+        for id in 1..=3 {
+            verify.add_remote_digest(ReplicaId::from_u64(id), digest(&attestation));
+        }
+
+        let signature = sign.sign(MESSAGE).unwrap();
+        let id = ReplicaId::from_u64(3);
+        assert!(verify
+            .verify(ReplicaId::from_u64(1), MESSAGE, &signature)
+            .is_err());
+        assert!(verify
+            .verify(ReplicaId::from_u64(2), MESSAGE, &signature)
+            .is_err());
+        assert!(matches!(
+            verify.verify(id, MESSAGE, &signature),
+            Err(UsigError::DirectoryFailed)
+        ));
+
+        clock.advance(limits.period);
+        assert!(verify.verify(id, MESSAGE, &signature).is_ok());
+    }
+}
@@ -0,0 +1,538 @@
+//! Party keys stored in an LDAP directory, read over TLS
+//!
+//! [LdapFetcher] speaks the few LDAPv3 operations it needs, a simple bind and a search for
+//! one entry, so no LDAP client library is required.
+
+use std::{
+    io::{self, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::Arc,
+    time::Duration,
+};
+
+use derivative::Derivative;
+use rustls::{pki_types::ServerName, ClientConfig, ClientConnection, StreamOwned};
+use serde::de::DeserializeOwned;
+use shared_ids::ReplicaId;
+use zeroize::Zeroizing;
+
+use crate::{
+    directory::{KeyFetcher, PinnedTrust, MAX_FETCHED_KEY},
+    export::invalid_data,
+    UsigError,
+};
+
+const BOOLEAN: u8 = 0x01;
+const INTEGER: u8 = 0x02;
+const OCTET_STRING: u8 = 0x04;
+const ENUMERATED: u8 = 0x0a;
+const SEQUENCE: u8 = 0x30;
+const SET: u8 = 0x31;
+const BIND_REQUEST: u8 = 0x60;
+const BIND_RESPONSE: u8 = 0x61;
+const UNBIND_REQUEST: u8 = 0x42;
+const SEARCH_REQUEST: u8 = 0x63;
+const SEARCH_RESULT_ENTRY: u8 = 0x64;
+const SEARCH_RESULT_DONE: u8 = 0x65;
+const SEARCH_RESULT_REFERENCE: u8 = 0x73;
+const SIMPLE_AUTHENTICATION: u8 = 0x80;
+const EQUALITY_MATCH: u8 = 0xa3;
+
+const LDAP_VERSION: u32 = 3;
+const SCOPE_WHOLE_SUBTREE: u32 = 2;
+const NEVER_DEREF_ALIASES: u32 = 0;
+
+/// Largest response accepted, an entry holds at most one key
+const MAX_MESSAGE: usize = 2 * MAX_FETCHED_KEY as usize;
+
+/// Encode `content` as a BER element with `tag`
+fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut element = vec![tag];
+    if content.len() < 0x80 {
+        element.push(content.len() as u8);
+    } else {
+        let length = content.len().to_be_bytes();
+        let skip = length.iter().take_while(|byte| **byte == 0).count();
+        element.push(0x80 | (length.len() - skip) as u8);
+        element.extend_from_slice(&length[skip..]);
+    }
+    element.extend_from_slice(content);
+    element
+}
+
+/// Encode a non-negative integer or enumeration value
+fn integer(tag: u8, value: u32) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let skip = bytes.iter().take_while(|byte| **byte == 0).count().min(3);
+    let mut content = bytes[skip..].to_vec();
+    if content[0] & 0x80 != 0 {
+        content.insert(0, 0);
+    }
+    tlv(tag, &content)
+}
+
+fn decode_integer(content: &[u8]) -> Result<u32, UsigError> {
+    let content = match content {
+        [0, rest @ ..] if !rest.is_empty() => rest,
+        [first, ..] if first & 0x80 != 0 => return Err(malformed()),
+        content => content,
+    };
+    if content.is_empty() || content.len() > 4 {
+        return Err(malformed());
+    }
+    Ok(content
+        .iter()
+        .fold(0, |value, byte| value << 8 | u32::from(*byte)))
+}
+
+fn malformed() -> UsigError {
+    invalid_data("malformed LDAP message")
+}
+
+/// A cursor over consecutive BER elements
+struct Ber<'a>(&'a [u8]);
+
+impl<'a> Ber<'a> {
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn next(&mut self) -> Result<(u8, &'a [u8]), UsigError> {
+        let (&tag, rest) = self.0.split_first().ok_or_else(malformed)?;
+        let (&first, rest) = rest.split_first().ok_or_else(malformed)?;
+        let (length, rest) = if first < 0x80 {
+            (usize::from(first), rest)
+        } else {
+            let count = usize::from(first & 0x7f);
+            if count == 0 || count > 4 || rest.len() < count {
+                return Err(malformed());
+            }
+            let (length, rest) = rest.split_at(count);
+            let length = length
+                .iter()
+                .fold(0, |length, byte| length << 8 | usize::from(*byte));
+            (length, rest)
+        };
+        if rest.len() < length {
+            return Err(malformed());
+        }
+        let (content, rest) = rest.split_at(length);
+        self.0 = rest;
+        Ok((tag, content))
+    }
+
+    fn expect(&mut self, tag: u8) -> Result<&'a [u8], UsigError> {
+        match self.next()? {
+            (actual, content) if actual == tag => Ok(content),
+            _ => Err(malformed()),
+        }
+    }
+}
+
+/// An LDAPMessage, the operation is identified by its tag
+struct Message {
+    id: u32,
+    operation: u8,
+    body: Vec<u8>,
+}
+
+impl Message {
+    fn encode(id: u32, operation: &[u8]) -> Zeroizing<Vec<u8>> {
+        Zeroizing::new(tlv(
+            SEQUENCE,
+            &[integer(INTEGER, id), operation.to_vec()].concat(),
+        ))
+    }
+
+    fn read(stream: &mut impl Read) -> Result<Self, UsigError> {
+        let mut header = [0; 2];
+        stream.read_exact(&mut header)?;
+        if header[0] != SEQUENCE {
+            return Err(malformed());
+        }
+        let length = if header[1] < 0x80 {
+            usize::from(header[1])
+        } else {
+            let count = usize::from(header[1] & 0x7f);
+            if count == 0 || count > 4 {
+                return Err(malformed());
+            }
+            let mut length = [0; 4];
+            stream.read_exact(&mut length[4 - count..])?;
+            u32::from_be_bytes(length) as usize
+        };
+        if length > MAX_MESSAGE {
+            return Err(invalid_data("LDAP message is too large"));
+        }
+        let mut content = vec![0; length];
+        stream.read_exact(&mut content)?;
+        let mut message = Ber(&content);
+        let id = decode_integer(message.expect(INTEGER)?)?;
+        let (operation, body) = message.next()?;
+        Ok(Self {
+            id,
+            operation,
+            body: body.to_vec(),
+        })
+    }
+
+    /// Fail unless the response answers request `id` with a successful `operation`
+    fn check(&self, id: u32, operation: u8) -> Result<(), UsigError> {
+        if self.id != id || self.operation != operation {
+            return Err(malformed());
+        }
+        let mut result = Ber(&self.body);
+        let code = decode_integer(result.expect(ENUMERATED)?)?;
+        result.expect(OCTET_STRING)?;
+        let diagnostic = result.expect(OCTET_STRING)?;
+        if code != 0 {
+            return Err(UsigError::Backend(
+                format!(
+                    "LDAP operation failed with result code {}: {}",
+                    code,
+                    String::from_utf8_lossy(diagnostic)
+                )
+                .into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The values of `attribute` in a SearchResultEntry
+fn entry_values(body: &[u8], attribute: &str) -> Result<Vec<Vec<u8>>, UsigError> {
+    let mut entry = Ber(body);
+    entry.expect(OCTET_STRING)?;
+    let mut attributes = Ber(entry.expect(SEQUENCE)?);
+    let mut values = Vec::new();
+    while !attributes.is_empty() {
+        let mut partial = Ber(attributes.expect(SEQUENCE)?);
+        let name = partial.expect(OCTET_STRING)?;
+        let mut set = Ber(partial.expect(SET)?);
+        if name.eq_ignore_ascii_case(attribute.as_bytes()) {
+            while !set.is_empty() {
+                values.push(set.expect(OCTET_STRING)?.to_vec());
+            }
+        }
+    }
+    Ok(values)
+}
+
+/// Fetches keys from an LDAP directory over TLS (LDAPS)
+///
+/// Searches below the base DN for the entry whose id attribute is the decimal replica id
+/// and expects the bincode encoded key in its key attribute. No entry is an unknown party,
+/// more than one entry or value is rejected as ambiguous. The server has to be certified
+/// by the [PinnedTrust]. Store attestations to register the parties through a
+/// [crate::directory::RemoteDirectory].
+#[derive(Derivative, Clone)]
+#[derivative(Debug)]
+pub struct LdapFetcher {
+    address: String,
+    server_name: ServerName<'static>,
+    #[derivative(Debug = "ignore")]
+    tls: Arc<ClientConfig>,
+    #[derivative(Debug = "ignore")]
+    bind: Option<(String, Zeroizing<String>)>,
+    base: String,
+    id_attribute: String,
+    key_attribute: String,
+    timeout: Duration,
+}
+
+impl LdapFetcher {
+    /// Search below `base` on the server at `address`, e.g. `ldap.example.org:636`
+    ///
+    /// The server has to present a certificate for `domain`. Searches are anonymous and
+    /// use the attributes `usigReplicaId` and `usigKey` unless configured otherwise.
+    pub fn new(
+        address: impl Into<String>,
+        domain: &str,
+        trust: &PinnedTrust,
+        base: impl Into<String>,
+    ) -> Result<Self, UsigError> {
+        let server_name = ServerName::try_from(domain.to_owned())
+            .map_err(|e| UsigError::Io(io::Error::new(io::ErrorKind::InvalidInput, e)))?;
+        Ok(Self {
+            address: address.into(),
+            server_name,
+            tls: trust.client_config()?,
+            bind: None,
+            base: base.into(),
+            id_attribute: "usigReplicaId".to_owned(),
+            key_attribute: "usigKey".to_owned(),
+            timeout: Duration::from_secs(5),
+        })
+    }
+
+    /// Authenticate with a simple bind as `dn` before searching
+    pub fn with_bind(mut self, dn: impl Into<String>, password: impl Into<String>) -> Self {
+        self.bind = Some((dn.into(), Zeroizing::new(password.into())));
+        self
+    }
+
+    /// The attributes holding the replica id and the key of a party
+    pub fn with_attributes(
+        mut self,
+        id_attribute: impl Into<String>,
+        key_attribute: impl Into<String>,
+    ) -> Self {
+        self.id_attribute = id_attribute.into();
+        self.key_attribute = key_attribute.into();
+        self
+    }
+
+    /// Give up on connecting, reading or writing after `timeout`, five seconds by default
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn connect(&self) -> Result<StreamOwned<ClientConnection, TcpStream>, UsigError> {
+        let address = self.address.to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "LDAP server address does not resolve",
+            )
+        })?;
+        let tcp = TcpStream::connect_timeout(&address, self.timeout)?;
+        tcp.set_read_timeout(Some(self.timeout))?;
+        tcp.set_write_timeout(Some(self.timeout))?;
+        let connection = ClientConnection::new(self.tls.clone(), self.server_name.clone())
+            .map_err(|e| UsigError::Backend(Box::new(e)))?;
+        Ok(StreamOwned::new(connection, tcp))
+    }
+
+    fn search(
+        &self,
+        stream: &mut impl ReadWrite,
+        id: ReplicaId,
+    ) -> Result<Vec<Vec<u8>>, UsigError> {
+        let mut message_id = 1;
+        if let Some((dn, password)) = &self.bind {
+            let bind = Zeroizing::new(tlv(
+                BIND_REQUEST,
+                &[
+                    integer(INTEGER, LDAP_VERSION),
+                    tlv(OCTET_STRING, dn.as_bytes()),
+                    tlv(SIMPLE_AUTHENTICATION, password.as_bytes()),
+                ]
+                .concat(),
+            ));
+            stream.write_all(&Message::encode(message_id, &bind))?;
+            Message::read(stream)?.check(message_id, BIND_RESPONSE)?;
+            message_id += 1;
+        }
+
+        let filter = tlv(
+            EQUALITY_MATCH,
+            &[
+                tlv(OCTET_STRING, self.id_attribute.as_bytes()),
+                tlv(OCTET_STRING, id.as_u64().to_string().as_bytes()),
+            ]
+            .concat(),
+        );
+        let search = tlv(
+            SEARCH_REQUEST,
+            &[
+                tlv(OCTET_STRING, self.base.as_bytes()),
+                integer(ENUMERATED, SCOPE_WHOLE_SUBTREE),
+                integer(ENUMERATED, NEVER_DEREF_ALIASES),
+                // a second entry is enough to tell that the id is ambiguous
+                integer(INTEGER, 2),
+                integer(
+                    INTEGER,
+                    self.timeout.as_secs().try_into().unwrap_or(u32::MAX),
+                ),
+                tlv(BOOLEAN, &[0]),
+                filter,
+                tlv(SEQUENCE, &tlv(OCTET_STRING, self.key_attribute.as_bytes())),
+            ]
+            .concat(),
+        );
+        stream.write_all(&Message::encode(message_id, &search))?;
+        let mut values = Vec::new();
+        loop {
+            let response = Message::read(stream)?;
+            match response.operation {
+                SEARCH_RESULT_ENTRY if response.id == message_id => {
+                    values.extend(entry_values(&response.body, &self.key_attribute)?)
+                }
+                SEARCH_RESULT_REFERENCE if response.id == message_id => {}
+                _ => {
+                    response.check(message_id, SEARCH_RESULT_DONE)?;
+                    break;
+                }
+            }
+        }
+        stream.write_all(&Message::encode(message_id + 1, &tlv(UNBIND_REQUEST, &[])))?;
+        Ok(values)
+    }
+}
+
+trait ReadWrite: Read + Write {}
+
+impl<T: Read + Write> ReadWrite for T {}
+
+impl<K: DeserializeOwned> KeyFetcher<K> for LdapFetcher {
+    fn fetch(&self, id: ReplicaId) -> Result<Option<K>, UsigError> {
+        let mut stream = self.connect()?;
+        let values = self.search(&mut stream, id)?;
+        stream.conn.send_close_notify();
+        // the keys are read, a failing close does not matter
+        let _ = stream.flush();
+        match values.as_slice() {
+            [] => Ok(None),
+            [key] => Ok(Some(bincode::deserialize(key)?)),
+            _ => Err(UsigError::Backend(
+                format!("party {} has more than one key", id.as_u64()).into(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::TcpListener, thread};
+
+    use super::*;
+    use crate::directory::tests::tls_server;
+
+    const DN: &str = "cn=usig,dc=example,dc=org";
+    const PASSWORD: &str = "secret";
+
+    fn result(operation: u8, code: u32) -> Vec<u8> {
+        tlv(
+            operation,
+            &[
+                integer(ENUMERATED, code),
+                tlv(OCTET_STRING, b""),
+                tlv(OCTET_STRING, b""),
+            ]
+            .concat(),
+        )
+    }
+
+    fn entry(id: &str, key: u64) -> Vec<u8> {
+        let value = tlv(OCTET_STRING, &bincode::serialize(&key).unwrap());
+        let attribute = tlv(
+            SEQUENCE,
+            &[tlv(OCTET_STRING, b"usigKey"), tlv(SET, &value)].concat(),
+        );
+        tlv(
+            SEARCH_RESULT_ENTRY,
+            &[
+                tlv(OCTET_STRING, format!("cn={id},{DN}").as_bytes()),
+                tlv(SEQUENCE, &attribute),
+            ]
+            .concat(),
+        )
+    }
+
+    /// Answer a session of [LdapFetcher]: party 0 has key 7, party 2 two keys
+    fn answer(stream: &mut impl ReadWrite) -> Result<(), UsigError> {
+        loop {
+            let request = Message::read(stream)?;
+            let reply = |stream: &mut dyn Write, operation: &[u8]| {
+                stream.write_all(&Message::encode(request.id, operation))
+            };
+            match request.operation {
+                BIND_REQUEST => {
+                    let mut bind = Ber(&request.body);
+                    bind.expect(INTEGER)?;
+                    let valid = bind.expect(OCTET_STRING)? == DN.as_bytes()
+                        && bind.expect(SIMPLE_AUTHENTICATION)? == PASSWORD.as_bytes();
+                    let code = if valid { 0 } else { 49 };
+                    reply(stream, &result(BIND_RESPONSE, code))?;
+                }
+                SEARCH_REQUEST => {
+                    let mut search = Ber(&request.body);
+                    for tag in [
+                        OCTET_STRING,
+                        ENUMERATED,
+                        ENUMERATED,
+                        INTEGER,
+                        INTEGER,
+                        BOOLEAN,
+                    ] {
+                        search.expect(tag)?;
+                    }
+                    let mut filter = Ber(search.expect(EQUALITY_MATCH)?);
+                    assert_eq!(filter.expect(OCTET_STRING)?, b"usigReplicaId");
+                    match filter.expect(OCTET_STRING)? {
+                        b"0" => reply(stream, &entry("0", 7))?,
+                        b"2" => {
+                            reply(stream, &entry("2a", 8))?;
+                            reply(stream, &entry("2b", 9))?;
+                        }
+                        _ => {}
+                    }
+                    reply(stream, &result(SEARCH_RESULT_DONE, 0))?;
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn serve() -> (String, PinnedTrust) {
+        let (config, trust) = tls_server();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || {
+            for tcp in listener.incoming() {
+                let connection = rustls::ServerConnection::new(config.clone()).unwrap();
+                let mut stream = StreamOwned::new(connection, tcp.unwrap());
+                let _ = answer(&mut stream);
+            }
+        });
+        (address, trust)
+    }
+
+    fn fetch(fetcher: &LdapFetcher, id: u64) -> Result<Option<u64>, UsigError> {
+        fetcher.fetch(ReplicaId::from_u64(id))
+    }
+
+    #[test]
+    fn search() {
+        let (address, trust) = serve();
+        let fetcher = LdapFetcher::new(address, "localhost", &trust, DN).unwrap();
+        assert_eq!(fetch(&fetcher, 0).unwrap(), Some(7));
+        assert_eq!(fetch(&fetcher, 1).unwrap(), None);
+        assert!(matches!(fetch(&fetcher, 2), Err(UsigError::Backend(_))));
+    }
+
+    #[test]
+    fn bind() {
+        let (address, trust) = serve();
+        let fetcher = LdapFetcher::new(address, "localhost", &trust, DN).unwrap();
+        assert_eq!(
+            fetch(&fetcher.clone().with_bind(DN, PASSWORD), 0).unwrap(),
+            Some(7)
+        );
+        assert!(matches!(
+            fetch(&fetcher.with_bind(DN, "wrong"), 0),
+            Err(UsigError::Backend(_))
+        ));
+    }
+
+    #[test]
+    fn untrusted() {
+        let (address, _) = serve();
+        let (_, other) = tls_server();
+        let fetcher = LdapFetcher::new(address, "localhost", &other, DN).unwrap();
+        assert!(fetch(&fetcher, 0).is_err());
+    }
+
+    #[test]
+    fn encoding() {
+        assert_eq!(integer(INTEGER, 0), [INTEGER, 1, 0]);
+        assert_eq!(integer(INTEGER, 128), [INTEGER, 2, 0, 128]);
+        assert_eq!(decode_integer(&[0, 128]).unwrap(), 128);
+        let long = tlv(OCTET_STRING, &[1; 300]);
+        assert_eq!(long[..4], [OCTET_STRING, 0x82, 1, 44]);
+        let mut ber = Ber(&long);
+        assert_eq!(ber.expect(OCTET_STRING).unwrap().len(), 300);
+        assert!(ber.is_empty());
+        assert!(Ber(&long[..100]).next().is_err());
+    }
+}
@@ -1,16 +1,84 @@
+pub mod accel;
+pub mod adapter;
+pub mod admission;
+#[cfg(feature = "async")]
+pub mod asynchronous;
+pub mod audit;
+pub mod beacon;
+#[cfg(feature = "bls")]
+pub mod bls;
+pub mod breaker;
+pub mod bundle;
+pub mod checked;
+pub mod checkpoint;
+pub mod clock;
+pub mod cmac;
+pub mod compat;
+pub mod config;
+pub mod conformance;
+pub mod corpus;
+pub mod demo;
+pub mod detached;
+pub mod directory;
+pub mod disclosure;
+pub mod dynamic;
+pub mod encoding;
+pub mod experiment;
+pub mod export;
+pub mod failover;
 pub mod hmac;
+pub mod identity;
+pub mod lanes;
+pub mod lazy;
+#[cfg(feature = "ldap")]
+pub mod ldap;
+pub mod metrics;
+pub mod migration;
+pub mod monotonic;
+pub mod mux;
 pub mod noop;
+pub mod party;
+pub mod persistence;
+pub mod progress;
+pub mod registry;
+#[cfg(feature = "remote")]
+pub mod remote;
+pub mod replay;
+pub mod revocation;
+pub mod semantics;
+pub mod shared;
+pub mod sidecar;
 pub mod signature;
+pub mod siphash;
+pub mod standby;
+pub mod state;
+pub mod stats;
+pub mod stream;
+pub mod tee;
 pub mod test;
+pub mod transcript;
+pub mod watermarks;
+#[cfg(feature = "count128")]
+pub mod wide;
+pub mod window;
+pub mod wire;
 
 use core::fmt;
 use std::{
+    any::Any,
     fmt::Debug,
+    hash::Hash,
+    io,
     ops::{Add, AddAssign},
+    time::Duration,
 };
 
+use clock::Clock;
+pub use detached::verify_detached;
+use encoding::CounterEncoding;
 use serde::{Deserialize, Serialize};
 pub use shared_ids::ReplicaId;
+use stream::{SignContext, VerifyContext};
 use thiserror::Error;
 
 /// A USIG signature counter value
@@ -26,7 +94,152 @@ impl fmt::Display for Count {
     }
 }
 
+impl Count {
+    /// The counter `rhs` values later, [None] on overflow
+    pub const fn checked_add(self, rhs: u64) -> Option<Count> {
+        match self.0.checked_add(rhs) {
+            Some(counter) => Some(Count(counter)),
+            None => None,
+        }
+    }
+
+    /// The counter `rhs` values earlier, [None] below zero
+    pub const fn checked_sub(self, rhs: u64) -> Option<Count> {
+        match self.0.checked_sub(rhs) {
+            Some(counter) => Some(Count(counter)),
+            None => None,
+        }
+    }
+
+    /// The number of steps between two counters, in either direction
+    pub const fn distance(self, other: Count) -> u64 {
+        self.0.abs_diff(other.0)
+    }
+}
+
+impl From<u64> for Count {
+    fn from(counter: u64) -> Self {
+        Count(counter)
+    }
+}
+
+impl From<Count> for u64 {
+    fn from(counter: Count) -> Self {
+        counter.0
+    }
+}
+
+/// The algorithm parameters a USIG is configured with
+///
+/// They are part of every attestation, so a verify half can reject remote parties
+/// configured differently instead of failing every verification later on.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AlgorithmParameters {
+    /// The MAC or signature scheme including its curve, e.g. `Ed25519` or `Hmac<Sha256>`
+    pub scheme: String,
+    /// The length of a MAC or signature in bytes
+    pub output_length: usize,
+    /// The digest the message is hashed with before signing, if any
+    pub pre_hash: Option<String>,
+    /// How the counter is mixed into the signed data
+    pub counter_encoding: CounterEncoding,
+}
+
+impl fmt::Display for AlgorithmParameters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({} bytes", self.scheme, self.output_length)?;
+        if let Some(pre_hash) = &self.pre_hash {
+            write!(f, ", pre-hashed with {}", pre_hash)?;
+        }
+        if self.counter_encoding != CounterEncoding::default() {
+            write!(f, ", {}", self.counter_encoding)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Context of the binding of an [Attestation], see [Attestation::bound_message]
+pub const ATTESTATION_CONTEXT: &[u8] = b"usig attestation";
+
+/// An attestation envelope carrying the algorithm parameters of the attested USIG
+///
+/// The attested USIG signs, or for MACs authenticates, `signer`, `parameters` and
+/// `payload` together into `binding`, so an attestation relabeled for another id or
+/// parameters on its way is rejected with [UsigError::RemoteAttestationFailed]. With
+/// MACs the payload is the key itself, so the binding only holds against parties not
+/// knowing the key, which could forge signatures anyway.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Attestation<A> {
+    pub parameters: AlgorithmParameters,
+    /// The id of the attested USIG, if it was constructed with one
+    pub signer: Option<ReplicaId>,
+    pub payload: A,
+    /// The signature or MAC of the attested USIG over [Attestation::bound_message]
+    pub binding: Vec<u8>,
+}
+
+impl<A: Serialize> Attestation<A> {
+    /// The data authenticated by `binding`: the signer, the parameters and the payload
+    pub fn bound_message(&self) -> Result<Vec<u8>, UsigError> {
+        let fields = bincode::serialize(&(&self.signer, &self.parameters, &self.payload))?;
+        Ok(encoding::with_context(ATTESTATION_CONTEXT, &fields))
+    }
+}
+
+impl<A> Attestation<A> {
+    /// Check that the attested USIG uses the `expected` parameters
+    pub fn check_parameters(&self, expected: &AlgorithmParameters) -> Result<(), UsigError> {
+        if &self.parameters == expected {
+            Ok(())
+        } else {
+            Err(UsigError::ParameterMismatch {
+                expected: Box::new(expected.clone()),
+                actual: Box::new(self.parameters.clone()),
+            })
+        }
+    }
+
+    /// Check that the attested USIG, if bound to an id, is the one `claimed` for it
+    pub fn check_signer(&self, claimed: ReplicaId) -> Result<(), UsigError> {
+        match self.signer {
+            Some(signer) if signer != claimed => Err(UsigError::IdentityMismatch {
+                claimed,
+                attested: Some(signer),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that the attested USIG is bound to an id and that it is the one `claimed`
+    ///
+    /// Unlike [Attestation::check_signer], attestations without an id are rejected, so a
+    /// party that was not constructed with its id can not be registered by mistake.
+    pub fn check_bound_signer(&self, claimed: ReplicaId) -> Result<(), UsigError> {
+        match self.signer {
+            Some(signer) if signer == claimed => Ok(()),
+            attested => Err(UsigError::IdentityMismatch { claimed, attested }),
+        }
+    }
+}
+
+/// What a [UsigError] says about its cause, see [UsigError::kind]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A fault of the local environment, e.g. I/O or an HSM, retrying may succeed
+    Transient,
+    /// Evidence of a misbehaving remote party, e.g. a forged or conflicting signature
+    Byzantine,
+    /// A message or party that is not accepted, e.g. from an unknown party or replayed
+    Rejected,
+    /// Parties or versions that do not fit together, e.g. different algorithm parameters
+    Incompatible,
+    /// The local USIG can not continue signing as it is, e.g. an exhausted counter
+    Fatal,
+}
+
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum UsigError {
     #[error("unknown id '{0:?}'")]
     UnknownId(ReplicaId),
@@ -39,19 +252,152 @@ pub enum UsigError {
 
     #[error("signing failed")]
     SigningFailed,
+
+    #[error("party directory failed")]
+    DirectoryFailed,
+
+    /// The local counter would move backwards, e.g. when resuming from an older state
+    ///
+    /// Stale input of remote parties is reported as [UsigError::StaleCounter],
+    /// [UsigError::Outdated] or [UsigError::RemoteRollback] instead.
+    #[error("counter can not move backwards")]
+    CounterRegression,
+
+    /// The counter reached its maximum, the key has to be rotated to continue signing
+    #[error("counter exhausted")]
+    CounterExhausted,
+
+    #[error("sign half is inactive")]
+    Inactive,
+
+    /// The key of the local sign half was revoked, it never signs again
+    #[error("sign half is revoked")]
+    Revoked,
+
+    #[error("algorithm parameter mismatch: expected {expected}, got {actual}")]
+    ParameterMismatch {
+        expected: Box<AlgorithmParameters>,
+        actual: Box<AlgorithmParameters>,
+    },
+
+    #[error("attestation for '{claimed:?}' identifies '{attested:?}'")]
+    IdentityMismatch {
+        claimed: ReplicaId,
+        attested: Option<ReplicaId>,
+    },
+
+    #[error("party '{0:?}' is quarantined")]
+    PartyQuarantined(ReplicaId),
+
+    #[error("party '{0:?}' signed conflicting statements")]
+    Equivocation(ReplicaId),
+
+    #[error("key of party '{0:?}' is revoked")]
+    PartyRevoked(ReplicaId),
+
+    #[error("counter {counter} of party '{id:?}' does not follow {last}")]
+    StaleCounter {
+        id: ReplicaId,
+        last: Count,
+        counter: Count,
+    },
+
+    /// Signed statements of a remote party prove that it rolled back its counter
+    #[error("party '{0:?}' rolled back its counter")]
+    RemoteRollback(ReplicaId),
+
+    /// A statement older than the latest one accepted, e.g. a delayed or replayed one
+    #[error("statement is older than the latest one accepted")]
+    Outdated,
+
+    #[error("counter {counter} of party '{id:?}' is outside the window from {low} to {high}")]
+    OutsideWindow {
+        id: ReplicaId,
+        counter: Count,
+        low: Count,
+        high: Count,
+    },
+
+    /// A party not identified by a [ReplicaId] is unknown, see [PartyId]
+    #[error("unknown party {0}")]
+    UnknownParty(String),
+
+    /// A value of another backend or of a layout this version does not know, see [wire]
+    #[error("unsupported wire format: algorithm {algorithm}, version {version}")]
+    UnsupportedWireFormat {
+        algorithm: registry::AlgorithmId,
+        version: u8,
+    },
+
+    /// Two backends of a [mux::UsigMux] would tag their values with the same algorithm
+    #[error("algorithm {0} is used by more than one backend")]
+    DuplicateAlgorithm(registry::AlgorithmId),
+
+    #[error("I/O failed")]
+    Io(#[from] io::Error),
+
+    /// A failure of the backend holding the key, e.g. an HSM, a KMS or a remote daemon
+    #[error("backend failed")]
+    Backend(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("serialization failed")]
+    Serialization(#[from] bincode::Error),
+}
+
+impl UsigError {
+    /// Whether the error is a transient fault, evidence of a Byzantine party and so on
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            UsigError::SigningFailed
+            | UsigError::DirectoryFailed
+            | UsigError::Inactive
+            | UsigError::Io(_)
+            | UsigError::Backend(_) => ErrorKind::Transient,
+            UsigError::InvalidSignature
+            | UsigError::RemoteAttestationFailed
+            | UsigError::Equivocation(_)
+            | UsigError::RemoteRollback(_) => ErrorKind::Byzantine,
+            UsigError::UnknownId(_)
+            | UsigError::UnknownParty(_)
+            | UsigError::PartyQuarantined(_)
+            | UsigError::PartyRevoked(_)
+            | UsigError::StaleCounter { .. }
+            | UsigError::Outdated
+            | UsigError::OutsideWindow { .. } => ErrorKind::Rejected,
+            UsigError::ParameterMismatch { .. }
+            | UsigError::IdentityMismatch { .. }
+            | UsigError::UnsupportedWireFormat { .. }
+            | UsigError::DuplicateAlgorithm(_)
+            | UsigError::Serialization(_) => ErrorKind::Incompatible,
+            UsigError::CounterRegression | UsigError::CounterExhausted | UsigError::Revoked => {
+                ErrorKind::Fatal
+            }
+        }
+    }
+
+    /// The counter of the signature the error is about, if it carries one
+    pub fn counter(&self) -> Option<Count> {
+        match self {
+            UsigError::StaleCounter { counter, .. } | UsigError::OutsideWindow { counter, .. } => {
+                Some(*counter)
+            }
+            _ => None,
+        }
+    }
 }
 
+/// Panics on overflow, sign halves fail with [UsigError::CounterExhausted] instead
 impl Add<u64> for Count {
     type Output = Count;
 
     fn add(self, rhs: u64) -> Self::Output {
-        Self(self.0 + rhs)
+        Self(self.0.checked_add(rhs).expect("counter overflow"))
     }
 }
 
 impl AddAssign<u64> for Count {
     fn add_assign(&mut self, rhs: u64) {
-        self.0 += rhs
+        *self = *self + rhs
     }
 }
 
@@ -61,8 +407,56 @@ pub trait Counter {
     fn counter(&self) -> Count;
 }
 
+/// A USIG signature together with timing metadata about its creation
+///
+/// Allows latency budgeting to tell the time spent in the USIG apart from network time.
+#[derive(Debug, Clone)]
+pub struct SignReceipt<S> {
+    /// The created USIG signature
+    pub signature: S,
+    /// When the sign request was issued, according to the [Clock] used
+    pub enqueued_at: Duration,
+    /// How long the request waited before the USIG started signing
+    pub queue_time: Duration,
+    /// How long the USIG (or its hardware) took to produce the signature
+    pub sign_time: Duration,
+}
+
+impl<S> SignReceipt<S> {
+    /// The total time from issuing the request until the signature was available
+    pub fn total_time(&self) -> Duration {
+        self.queue_time + self.sign_time
+    }
+}
+
+/// Estimated memory usage of a verify half in bytes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryReport {
+    /// The registry of remote parties and their keys
+    pub party_registry: usize,
+    /// Caches remembering already verified signatures or counters
+    pub replay_cache: usize,
+    /// Stored evidence of misbehaving parties
+    pub evidence: usize,
+}
+
+impl MemoryReport {
+    pub fn total(&self) -> usize {
+        self.party_registry + self.replay_cache + self.evidence
+    }
+}
+
+/// Identifies a remote party
+///
+/// The traits default to [ReplicaId], systems identifying their peers differently, e.g. by
+/// the hash of a public key or a UUID, can use their own identifier.
+/// See [party::PartyVerifyHalf] to use such an identifier with any verify half.
+pub trait PartyId: Clone + Eq + Hash + Debug {}
+
+impl<T: Clone + Eq + Hash + Debug> PartyId for T {}
+
 /// The main trait that defines a usig service
-pub trait Usig {
+pub trait Usig<Id: PartyId = ReplicaId> {
     /// The type of the USIG signature
     ///
     /// The access to the count is provided by the counter trait
@@ -74,38 +468,180 @@ pub trait Usig {
     /// Sign a message with a USIG signature
     fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError>;
 
+    /// Sign messages with consecutive counters
+    ///
+    /// Stops at the first message that can not be signed and returns the signatures of the
+    /// messages before it, so fewer signatures than messages are no error, like the bytes
+    /// written by [std::io::Write::write]. A signature not following the previous one, e.g.
+    /// because a wrapper switched backends in the middle of the batch, ends the batch as
+    /// well and is dropped. Fails only if the first message can not be signed.
+    fn sign_batch(
+        &mut self,
+        messages: &[impl AsRef<[u8]>],
+    ) -> Result<Vec<Self::Signature>, UsigError> {
+        let mut signatures: Vec<Self::Signature> = Vec::with_capacity(messages.len());
+        for message in messages {
+            let signature = match self.sign(message) {
+                Ok(signature) => signature,
+                Err(error) if signatures.is_empty() => return Err(error),
+                Err(_) => break,
+            };
+            if let Some(last) = signatures.last() {
+                if last.counter().checked_add(1) != Some(signature.counter()) {
+                    break;
+                }
+            }
+            signatures.push(signature);
+        }
+        Ok(signatures)
+    }
+
+    /// Sign a message under a domain `context`, e.g. the message type of the protocol
+    ///
+    /// The signature is only valid under the same context, see
+    /// [Usig::verify_with_context], so a prepare can not be passed off as a commit.
+    fn sign_with_context(
+        &mut self,
+        context: &[u8],
+        message: impl AsRef<[u8]>,
+    ) -> Result<Self::Signature, UsigError> {
+        self.sign(encoding::with_context(context, message.as_ref()))
+    }
+
+    /// Start signing a message passed in parts, e.g. a checkpoint too large for one buffer
+    ///
+    /// The signature has to be verified with [Usig::begin_verify].
+    fn begin_sign(&mut self) -> SignContext<'_, Self, Self::Signature>
+    where
+        Self: Sized,
+    {
+        SignContext::new(self, |signer, message| Usig::sign(signer, message))
+    }
+
+    /// See [SignHalf::sign_with_receipt]
+    fn sign_with_receipt(
+        &mut self,
+        message: impl AsRef<[u8]>,
+        clock: &impl Clock,
+        enqueued_at: Duration,
+    ) -> Result<SignReceipt<Self::Signature>, UsigError> {
+        SignHalf::sign_with_receipt(&mut UsigSignHalf(self), message, clock, enqueued_at)
+    }
+
     /// Get the remote attestation of this USIG
     fn attest(&mut self) -> Result<Self::Attestation, UsigError>;
 
+    /// The id this USIG signs as, if it was constructed with one
+    fn local_id(&self) -> Option<Id> {
+        None
+    }
+
     /// Verify the USIG signature of a message
     ///
     /// Only work if the attestation for the usig is was previously loaded
     fn verify(
         &self,
-        remote_usig_id: ReplicaId,
+        remote_usig_id: Id,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError>;
+
+    /// Verify the USIG signatures of many messages, e.g. of a quorum
+    ///
+    /// Returns the result of every entry in order. Backends override it to verify the
+    /// batch faster than one signature after the other.
+    fn verify_batch(&self, batch: &[(Id, &[u8], &Self::Signature)]) -> Vec<Result<(), UsigError>> {
+        batch
+            .iter()
+            .map(|(id, message, signature)| self.verify(id.clone(), message, signature))
+            .collect()
+    }
+
+    /// Verify the USIG signature of a message signed with [Usig::sign_with_context]
+    fn verify_with_context(
+        &self,
+        remote_usig_id: Id,
+        context: &[u8],
         message: impl AsRef<[u8]>,
         signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        self.verify(
+            remote_usig_id,
+            encoding::with_context(context, message.as_ref()),
+            signature,
+        )
+    }
+
+    /// Start verifying a message passed in parts, signed with [Usig::begin_sign]
+    fn begin_verify(&self, remote_usig_id: Id) -> VerifyContext<'_, Self, Id, Self::Signature>
+    where
+        Self: Sized,
+    {
+        VerifyContext::new(
+            self,
+            |verifier, id, message, signature| Usig::verify(verifier, id, message, signature),
+            remote_usig_id,
+        )
+    }
+
+    /// Cheaply check a signature without verifying it cryptographically
+    ///
+    /// Checks that the signature is well formed and its party is registered,
+    /// so relays can drop obvious garbage before spending the full verification cost.
+    fn pre_validate(
+        &self,
+        remote_usig_id: Id,
+        signature: &Self::Signature,
     ) -> Result<(), UsigError>;
 
+    /// Estimate the memory used for verification
+    ///
+    /// Defaults to an empty report, implementations keeping per-party state should override it.
+    fn memory_usage(&self) -> MemoryReport {
+        MemoryReport::default()
+    }
+
     /// Load a remote attestation of a remote USIG and add the remote party
-    fn add_remote_party(
+    fn add_remote_party(&mut self, remote_usig_id: Id, attestation: Self::Attestation) -> bool {
+        self.try_add_remote_party(remote_usig_id, attestation)
+            .is_ok()
+    }
+
+    /// Load a remote attestation of a remote USIG and add the remote party
+    ///
+    /// Reports why the attestation was rejected
+    fn try_add_remote_party(
         &mut self,
-        remote_usig_id: ReplicaId,
+        remote_usig_id: Id,
         attestation: Self::Attestation,
-    ) -> bool;
+    ) -> Result<(), UsigError>;
+
+    /// Forget a remote party, e.g. when it is evicted by a reconfiguration
+    ///
+    /// Signatures of the party are rejected with [UsigError::UnknownId] afterwards.
+    /// Returns `false` if the party was not known.
+    fn remove_remote_party(&mut self, remote_usig_id: Id) -> bool;
+
+    /// The remote parties whose attestation is loaded, in no particular order
+    fn remote_parties(&self) -> impl Iterator<Item = Id> + '_;
+
+    /// Whether signatures of a remote party can be verified
+    fn contains(&self, remote_usig_id: Id) -> bool {
+        self.remote_parties().any(|id| id == remote_usig_id)
+    }
 
     /// Type of the signing half
-    type SignHalf: SignHalf<Signature = Self::Signature, Attestation = Self::Attestation>;
+    type SignHalf: SignHalf<Id, Signature = Self::Signature, Attestation = Self::Attestation>;
 
     /// Type of the verifying half
-    type VerifyHalf: VerifyHalf<Signature = Self::Signature, Attestation = Self::Attestation>;
+    type VerifyHalf: VerifyHalf<Id, Signature = Self::Signature, Attestation = Self::Attestation>;
 
     /// Split USIG into signing and verifying half's
     fn split(self) -> (Self::SignHalf, Self::VerifyHalf);
 }
 
 /// The signing half of a split usig service
-pub trait SignHalf {
+pub trait SignHalf<Id: PartyId = ReplicaId> {
     /// The type of the USIG signature
     ///
     /// The access to the count is provided by the counter trait
@@ -117,12 +653,130 @@ pub trait SignHalf {
     /// Sign a message with a USIG signature
     fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError>;
 
+    /// Sign messages with consecutive counters
+    ///
+    /// Stops at the first message that can not be signed and returns the signatures of the
+    /// messages before it, so fewer signatures than messages are no error, like the bytes
+    /// written by [std::io::Write::write]. A signature not following the previous one, e.g.
+    /// because a wrapper switched backends in the middle of the batch, ends the batch as
+    /// well and is dropped. Fails only if the first message can not be signed.
+    fn sign_batch(
+        &mut self,
+        messages: &[impl AsRef<[u8]>],
+    ) -> Result<Vec<Self::Signature>, UsigError> {
+        let mut signatures: Vec<Self::Signature> = Vec::with_capacity(messages.len());
+        for message in messages {
+            let signature = match self.sign(message) {
+                Ok(signature) => signature,
+                Err(error) if signatures.is_empty() => return Err(error),
+                Err(_) => break,
+            };
+            if let Some(last) = signatures.last() {
+                if last.counter().checked_add(1) != Some(signature.counter()) {
+                    break;
+                }
+            }
+            signatures.push(signature);
+        }
+        Ok(signatures)
+    }
+
+    /// Sign a message under a domain `context`, e.g. the message type of the protocol
+    ///
+    /// The signature is only valid under the same context, see
+    /// [VerifyHalf::verify_with_context], so a prepare can not be passed off as a commit.
+    fn sign_with_context(
+        &mut self,
+        context: &[u8],
+        message: impl AsRef<[u8]>,
+    ) -> Result<Self::Signature, UsigError> {
+        self.sign(encoding::with_context(context, message.as_ref()))
+    }
+
+    /// Start signing a message passed in parts, e.g. a checkpoint too large for one buffer
+    ///
+    /// The signature has to be verified with [VerifyHalf::begin_verify].
+    fn begin_sign(&mut self) -> SignContext<'_, Self, Self::Signature>
+    where
+        Self: Sized,
+    {
+        SignContext::new(self, |signer, message| SignHalf::sign(signer, message))
+    }
+
+    /// Sign a message with a USIG signature and report how long it took according to `clock`
+    ///
+    /// `enqueued_at` is when the request was issued according to `clock`, e.g. when a queue
+    /// in front of the USIG took it in, the time until signing starts is the queue time.
+    /// Pass `clock.now()` if the request was not queued.
+    fn sign_with_receipt(
+        &mut self,
+        message: impl AsRef<[u8]>,
+        clock: &impl Clock,
+        enqueued_at: Duration,
+    ) -> Result<SignReceipt<Self::Signature>, UsigError> {
+        let started_at = clock.now();
+        let signature = self.sign(message)?;
+        Ok(SignReceipt {
+            signature,
+            enqueued_at,
+            queue_time: started_at.saturating_sub(enqueued_at),
+            sign_time: clock.now().saturating_sub(started_at),
+        })
+    }
+
     /// Get the remote attestation of this USIG
     fn attest(&mut self) -> Result<Self::Attestation, UsigError>;
+
+    /// The id this USIG signs as, if it was constructed with one
+    fn local_id(&self) -> Option<Id> {
+        None
+    }
+
+    /// Access the backend behind this sign half, e.g. to reach a TPM handle
+    ///
+    /// Wrappers forward to the sign half they wrap, so generic code can downcast to the
+    /// backend it expects without knowing about the wrappers in between.
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        None
+    }
+
+    /// Mutably access the backend behind this sign half, see [SignHalf::as_any]
+    ///
+    /// Wrappers guarding what their backend signs, e.g. by persisting its counter, return
+    /// `None`: signing with the backend directly would bypass them.
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        None
+    }
+}
+
+/// A [Usig] borrowed as its [SignHalf], for the defaults of [Usig] to reuse those of [SignHalf]
+struct UsigSignHalf<'a, U: ?Sized>(&'a mut U);
+
+impl<U: Usig<Id> + ?Sized, Id: PartyId> SignHalf<Id> for UsigSignHalf<'_, U> {
+    type Signature = U::Signature;
+    type Attestation = U::Attestation;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        self.0.sign(message)
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        self.0.attest()
+    }
+
+    fn local_id(&self) -> Option<Id> {
+        self.0.local_id()
+    }
 }
 
 /// The verifying half of a split usig service
-pub trait VerifyHalf {
+pub trait VerifyHalf<Id: PartyId = ReplicaId> {
     /// The type of the USIG signature
     ///
     /// The access to the count is provided by the counter trait
@@ -136,15 +790,251 @@ pub trait VerifyHalf {
     /// Only work if the attestation for the usig is was previously loaded
     fn verify(
         &self,
-        remote_usig_id: ReplicaId,
+        remote_usig_id: Id,
         message: impl AsRef<[u8]>,
         signature: &Self::Signature,
     ) -> Result<(), UsigError>;
 
+    /// Verify the USIG signatures of many messages, e.g. of a quorum
+    ///
+    /// Returns the result of every entry in order. Backends override it to verify the
+    /// batch faster than one signature after the other.
+    fn verify_batch(&self, batch: &[(Id, &[u8], &Self::Signature)]) -> Vec<Result<(), UsigError>> {
+        batch
+            .iter()
+            .map(|(id, message, signature)| self.verify(id.clone(), message, signature))
+            .collect()
+    }
+
+    /// Verify the USIG signature of a message signed with [SignHalf::sign_with_context]
+    fn verify_with_context(
+        &self,
+        remote_usig_id: Id,
+        context: &[u8],
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        self.verify(
+            remote_usig_id,
+            encoding::with_context(context, message.as_ref()),
+            signature,
+        )
+    }
+
+    /// Start verifying a message passed in parts, signed with [SignHalf::begin_sign]
+    fn begin_verify(&self, remote_usig_id: Id) -> VerifyContext<'_, Self, Id, Self::Signature>
+    where
+        Self: Sized,
+    {
+        VerifyContext::new(
+            self,
+            |verifier, id, message, signature| VerifyHalf::verify(verifier, id, message, signature),
+            remote_usig_id,
+        )
+    }
+
+    /// Cheaply check a signature without verifying it cryptographically
+    ///
+    /// Checks that the signature is well formed and its party is registered,
+    /// so relays can drop obvious garbage before spending the full verification cost.
+    fn pre_validate(
+        &self,
+        remote_usig_id: Id,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError>;
+
+    /// Estimate the memory used for verification
+    ///
+    /// Defaults to an empty report, implementations keeping per-party state should override it.
+    fn memory_usage(&self) -> MemoryReport {
+        MemoryReport::default()
+    }
+
+    /// Load a remote attestation of a remote USIG and add the remote party
+    fn add_remote_party(&mut self, remote_usig_id: Id, attestation: Self::Attestation) -> bool {
+        self.try_add_remote_party(remote_usig_id, attestation)
+            .is_ok()
+    }
+
     /// Load a remote attestation of a remote USIG and add the remote party
-    fn add_remote_party(
+    ///
+    /// Reports why the attestation was rejected
+    fn try_add_remote_party(
         &mut self,
-        remote_usig_id: ReplicaId,
+        remote_usig_id: Id,
         attestation: Self::Attestation,
-    ) -> bool;
+    ) -> Result<(), UsigError>;
+
+    /// Forget a remote party, e.g. when it is evicted by a reconfiguration
+    ///
+    /// Signatures of the party are rejected with [UsigError::UnknownId] afterwards.
+    /// Returns `false` if the party was not known.
+    fn remove_remote_party(&mut self, remote_usig_id: Id) -> bool;
+
+    /// The remote parties whose attestation is loaded, in no particular order
+    fn remote_parties(&self) -> impl Iterator<Item = Id> + '_;
+
+    /// Whether signatures of a remote party can be verified
+    fn contains(&self, remote_usig_id: Id) -> bool {
+        self.remote_parties().any(|id| id == remote_usig_id)
+    }
+
+    /// The last accepted counter of a remote party
+    ///
+    /// Only known if this verify half tracks counters, e.g. after [VerifyHalf::enforce_monotonicity],
+    /// lets protocols detect holes in the counters received from a party.
+    fn last_counter(&self, _remote_usig_id: Id) -> Option<Count> {
+        None
+    }
+
+    /// Reject duplicate and regressing counters of each party with [UsigError::StaleCounter]
+    fn enforce_monotonicity(self) -> monotonic::MonotonicVerifyHalf<Self>
+    where
+        Self: VerifyHalf + Sized,
+    {
+        monotonic::MonotonicVerifyHalf::new(self)
+    }
+
+    /// Access the backend behind this verify half, see [SignHalf::as_any]
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        None
+    }
+
+    /// Mutably access the backend behind this verify half, see [SignHalf::as_any]
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::{Count, Counter, ErrorKind, ReplicaId, SignHalf, Usig, UsigError, VerifyHalf};
+    use crate::{
+        audit::{AuditedSignHalf, AuditedVerifyHalf},
+        metrics::{InstrumentedSignHalf, UsigMetrics},
+        revocation::{RevocableSignHalf, RevocationVerifyHalf},
+        signature::{new_ed25519, UsigEd25519},
+    };
+
+    type Ed25519SignHalf = <UsigEd25519 as Usig>::SignHalf;
+    type Ed25519VerifyHalf = <UsigEd25519 as Usig>::VerifyHalf;
+
+    #[test]
+    fn downcast_through_wrappers() {
+        let (sign, verify) = new_ed25519().split();
+        let mut sign = RevocableSignHalf::new(AuditedSignHalf::new(sign));
+        let verify = RevocationVerifyHalf::new(AuditedVerifyHalf::new(verify));
+
+        let backend = sign
+            .as_any()
+            .and_then(|any| any.downcast_ref::<Ed25519SignHalf>());
+        assert!(backend.is_some());
+        assert!(sign
+            .as_any()
+            .and_then(|any| any.downcast_ref::<AuditedSignHalf<Ed25519SignHalf>>())
+            .is_none());
+        // Signing with the backend directly would bypass the revocation and the audit log
+        assert!(sign.as_any_mut().is_none());
+
+        let backend = verify
+            .as_any()
+            .and_then(|any| any.downcast_ref::<Ed25519VerifyHalf>());
+        assert!(backend.is_some());
+    }
+
+    #[test]
+    fn downcast_mut_through_transparent_wrappers() {
+        let (sign, _) = new_ed25519().split();
+        struct Ignore;
+        impl UsigMetrics for Ignore {}
+
+        let mut sign = InstrumentedSignHalf::new(sign, Ignore);
+        let backend = sign
+            .as_any_mut()
+            .and_then(|any| any.downcast_mut::<Ed25519SignHalf>());
+        assert!(backend.is_some());
+    }
+
+    #[test]
+    fn partial_batch() {
+        /// Fails to sign after `left` signatures
+        struct Failing {
+            inner: Ed25519SignHalf,
+            left: usize,
+        }
+
+        impl SignHalf for Failing {
+            type Signature = <Ed25519SignHalf as SignHalf>::Signature;
+            type Attestation = <Ed25519SignHalf as SignHalf>::Attestation;
+
+            fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+                self.left = self.left.checked_sub(1).ok_or(UsigError::SigningFailed)?;
+                self.inner.sign(message)
+            }
+
+            fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+                self.inner.attest()
+            }
+        }
+
+        let mut sign = Failing {
+            inner: new_ed25519().split().0,
+            left: 2,
+        };
+        let signatures = sign.sign_batch(&[b"message"; 3]).unwrap();
+        assert_eq!(
+            signatures.iter().map(|s| s.counter()).collect::<Vec<_>>(),
+            [Count(0), Count(1)]
+        );
+        assert!(matches!(
+            sign.sign_batch(&[b"message"]),
+            Err(UsigError::SigningFailed)
+        ));
+    }
+
+    #[test]
+    fn count_arithmetic() {
+        assert_eq!(Count(3).checked_add(2), Some(Count(5)));
+        assert_eq!(Count(u64::MAX).checked_add(1), None);
+        assert_eq!(Count(3).checked_sub(3), Some(Count(0)));
+        assert_eq!(Count(3).checked_sub(4), None);
+        assert_eq!(Count(3).distance(Count(7)), 4);
+        assert_eq!(Count(7).distance(Count(3)), 4);
+        assert_eq!(Count::from(9), Count(9));
+        assert_eq!(u64::from(Count(9)), 9);
+    }
+
+    #[test]
+    fn error_kind() {
+        let id = ReplicaId::first();
+        let io = UsigError::from(io::Error::other("disk full"));
+        assert_eq!(io.kind(), ErrorKind::Transient);
+        assert!(std::error::Error::source(&io).is_some());
+        assert_eq!(UsigError::Equivocation(id).kind(), ErrorKind::Byzantine);
+        let stale = UsigError::StaleCounter {
+            id,
+            last: Count(4),
+            counter: Count(2),
+        };
+        assert_eq!(stale.kind(), ErrorKind::Rejected);
+        assert_eq!(stale.counter(), Some(Count(2)));
+        assert_eq!(UsigError::Outdated.kind(), ErrorKind::Rejected);
+        assert_eq!(UsigError::RemoteRollback(id).kind(), ErrorKind::Byzantine);
+        let mismatch = UsigError::IdentityMismatch {
+            claimed: id,
+            attested: None,
+        };
+        assert_eq!(mismatch.kind(), ErrorKind::Incompatible);
+        assert_eq!(UsigError::CounterExhausted.kind(), ErrorKind::Fatal);
+        assert_eq!(UsigError::CounterExhausted.counter(), None);
+        assert_eq!(UsigError::Revoked.kind(), ErrorKind::Fatal);
+    }
 }
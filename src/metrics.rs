@@ -0,0 +1,427 @@
+use std::{any::Any, sync::Arc, time::Duration};
+
+use shared_ids::ReplicaId;
+
+use crate::{
+    clock::{Clock, SystemClock},
+    Count, Counter, MemoryReport, SignHalf, Usig, UsigError, VerifyHalf,
+};
+
+/// Receives the outcome and latency of every operation of an [InstrumentedUsig]
+///
+/// All methods do nothing by default, implementations only override what they record.
+pub trait UsigMetrics {
+    /// A signature with `counter` was created
+    fn on_sign(&self, counter: Count, duration: Duration) {
+        let _ = (counter, duration);
+    }
+
+    /// A signature of `id` was verified successfully
+    fn on_verify_ok(&self, id: ReplicaId, duration: Duration) {
+        let _ = (id, duration);
+    }
+
+    /// A signature of `id` was rejected with `error`
+    fn on_verify_fail(&self, id: ReplicaId, error: &UsigError, duration: Duration) {
+        let _ = (id, error, duration);
+    }
+}
+
+impl<M: UsigMetrics + ?Sized> UsigMetrics for &M {
+    fn on_sign(&self, counter: Count, duration: Duration) {
+        (**self).on_sign(counter, duration)
+    }
+
+    fn on_verify_ok(&self, id: ReplicaId, duration: Duration) {
+        (**self).on_verify_ok(id, duration)
+    }
+
+    fn on_verify_fail(&self, id: ReplicaId, error: &UsigError, duration: Duration) {
+        (**self).on_verify_fail(id, error, duration)
+    }
+}
+
+impl<M: UsigMetrics + ?Sized> UsigMetrics for Arc<M> {
+    fn on_sign(&self, counter: Count, duration: Duration) {
+        (**self).on_sign(counter, duration)
+    }
+
+    fn on_verify_ok(&self, id: ReplicaId, duration: Duration) {
+        (**self).on_verify_ok(id, duration)
+    }
+
+    fn on_verify_fail(&self, id: ReplicaId, error: &UsigError, duration: Duration) {
+        (**self).on_verify_fail(id, error, duration)
+    }
+}
+
+fn sign_timed<S: Counter>(
+    metrics: &impl UsigMetrics,
+    clock: &impl Clock,
+    sign: impl FnOnce() -> Result<S, UsigError>,
+) -> Result<S, UsigError> {
+    let start = clock.now();
+    let signature = sign()?;
+    metrics.on_sign(signature.counter(), clock.now().saturating_sub(start));
+    Ok(signature)
+}
+
+fn verify_timed(
+    metrics: &impl UsigMetrics,
+    clock: &impl Clock,
+    id: ReplicaId,
+    verify: impl FnOnce() -> Result<(), UsigError>,
+) -> Result<(), UsigError> {
+    let start = clock.now();
+    let result = verify();
+    let duration = clock.now().saturating_sub(start);
+    match &result {
+        Ok(()) => metrics.on_verify_ok(id, duration),
+        Err(e) => metrics.on_verify_fail(id, e, duration),
+    }
+    result
+}
+
+/// A USIG reporting the latency of every signature and verification to [UsigMetrics]
+///
+/// Batches are signed and verified one message after the other, so every operation is
+/// measured on its own. Failed signing attempts are not reported. Splitting it hands a
+/// clone of the metrics and the clock to each half, share them e.g. through an [Arc].
+#[derive(Debug)]
+pub struct InstrumentedUsig<U, M, C = SystemClock> {
+    inner: U,
+    metrics: M,
+    clock: C,
+}
+
+impl<U: Usig, M: UsigMetrics> InstrumentedUsig<U, M> {
+    pub fn new(inner: U, metrics: M) -> Self {
+        Self::with_clock(inner, metrics, SystemClock)
+    }
+}
+
+impl<U: Usig, M: UsigMetrics, C: Clock> InstrumentedUsig<U, M, C> {
+    pub fn with_clock(inner: U, metrics: M, clock: C) -> Self {
+        Self {
+            inner,
+            metrics,
+            clock,
+        }
+    }
+
+    pub fn metrics(&self) -> &M {
+        &self.metrics
+    }
+
+    pub fn into_inner(self) -> U {
+        self.inner
+    }
+}
+
+impl<U: Usig, M: UsigMetrics + Clone, C: Clock + Clone> Usig for InstrumentedUsig<U, M, C> {
+    type Signature = U::Signature;
+    type Attestation = U::Attestation;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        sign_timed(&self.metrics, &self.clock, || self.inner.sign(message))
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        self.inner.attest()
+    }
+
+    fn local_id(&self) -> Option<ReplicaId> {
+        self.inner.local_id()
+    }
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        verify_timed(&self.metrics, &self.clock, id, || {
+            self.inner.verify(id, message, signature)
+        })
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.inner.pre_validate(id, signature)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        self.inner.memory_usage()
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        remote_usig_id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        self.inner.try_add_remote_party(remote_usig_id, attestation)
+    }
+
+    fn remove_remote_party(&mut self, remote_usig_id: ReplicaId) -> bool {
+        self.inner.remove_remote_party(remote_usig_id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.inner.remote_parties()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.inner.contains(remote_usig_id)
+    }
+
+    type SignHalf = InstrumentedSignHalf<U::SignHalf, M, C>;
+    type VerifyHalf = InstrumentedVerifyHalf<U::VerifyHalf, M, C>;
+
+    fn split(self) -> (Self::SignHalf, Self::VerifyHalf) {
+        let (sign_half, verify_half) = self.inner.split();
+        (
+            InstrumentedSignHalf {
+                inner: sign_half,
+                metrics: self.metrics.clone(),
+                clock: self.clock.clone(),
+            },
+            InstrumentedVerifyHalf {
+                inner: verify_half,
+                metrics: self.metrics,
+                clock: self.clock,
+            },
+        )
+    }
+}
+
+/// The sign half of an [InstrumentedUsig]
+#[derive(Debug)]
+pub struct InstrumentedSignHalf<S, M, C = SystemClock> {
+    inner: S,
+    metrics: M,
+    clock: C,
+}
+
+impl<S: SignHalf, M: UsigMetrics> InstrumentedSignHalf<S, M> {
+    pub fn new(inner: S, metrics: M) -> Self {
+        Self::with_clock(inner, metrics, SystemClock)
+    }
+}
+
+impl<S: SignHalf, M: UsigMetrics, C: Clock> InstrumentedSignHalf<S, M, C> {
+    pub fn with_clock(inner: S, metrics: M, clock: C) -> Self {
+        Self {
+            inner,
+            metrics,
+            clock,
+        }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: SignHalf, M: UsigMetrics, C: Clock> SignHalf for InstrumentedSignHalf<S, M, C> {
+    type Signature = S::Signature;
+    type Attestation = S::Attestation;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        sign_timed(&self.metrics, &self.clock, || self.inner.sign(message))
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        self.inner.attest()
+    }
+
+    fn local_id(&self) -> Option<ReplicaId> {
+        self.inner.local_id()
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any()
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any_mut()
+    }
+}
+
+/// The verify half of an [InstrumentedUsig]
+#[derive(Debug)]
+pub struct InstrumentedVerifyHalf<V, M, C = SystemClock> {
+    inner: V,
+    metrics: M,
+    clock: C,
+}
+
+impl<V: VerifyHalf, M: UsigMetrics> InstrumentedVerifyHalf<V, M> {
+    pub fn new(inner: V, metrics: M) -> Self {
+        Self::with_clock(inner, metrics, SystemClock)
+    }
+}
+
+impl<V: VerifyHalf, M: UsigMetrics, C: Clock> InstrumentedVerifyHalf<V, M, C> {
+    pub fn with_clock(inner: V, metrics: M, clock: C) -> Self {
+        Self {
+            inner,
+            metrics,
+            clock,
+        }
+    }
+
+    pub fn into_inner(self) -> V {
+        self.inner
+    }
+}
+
+impl<V: VerifyHalf, M: UsigMetrics, C: Clock> VerifyHalf for InstrumentedVerifyHalf<V, M, C> {
+    type Signature = V::Signature;
+    type Attestation = V::Attestation;
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        verify_timed(&self.metrics, &self.clock, id, || {
+            self.inner.verify(id, message, signature)
+        })
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.inner.pre_validate(id, signature)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        self.inner.memory_usage()
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        remote_usig_id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        self.inner.try_add_remote_party(remote_usig_id, attestation)
+    }
+
+    fn remove_remote_party(&mut self, remote_usig_id: ReplicaId) -> bool {
+        self.inner.remove_remote_party(remote_usig_id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.inner.remote_parties()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.inner.contains(remote_usig_id)
+    }
+
+    fn last_counter(&self, id: ReplicaId) -> Option<Count> {
+        self.inner.last_counter(id)
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any()
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use hmac::Hmac;
+    use sha2::Sha256;
+
+    use super::*;
+    use crate::{clock::ManualClock, hmac::UsigHmac};
+
+    type HmacUsig = UsigHmac<Hmac<Sha256>>;
+
+    const ID: ReplicaId = ReplicaId::first();
+    const MESSAGE: &[u8] = b"message";
+
+    #[derive(Debug, Default)]
+    struct Recorder {
+        signed: Mutex<Vec<Count>>,
+        verified: Mutex<Vec<Result<(), ReplicaId>>>,
+        durations: Mutex<Vec<Duration>>,
+    }
+
+    impl UsigMetrics for Recorder {
+        fn on_sign(&self, counter: Count, duration: Duration) {
+            self.signed.lock().unwrap().push(counter);
+            self.durations.lock().unwrap().push(duration);
+        }
+
+        fn on_verify_ok(&self, _: ReplicaId, duration: Duration) {
+            self.verified.lock().unwrap().push(Ok(()));
+            self.durations.lock().unwrap().push(duration);
+        }
+
+        fn on_verify_fail(&self, id: ReplicaId, error: &UsigError, duration: Duration) {
+            assert!(matches!(error, UsigError::InvalidSignature));
+            self.verified.lock().unwrap().push(Err(id));
+            self.durations.lock().unwrap().push(duration);
+        }
+    }
+
+    /// Advances by a second every time it is read
+    #[derive(Debug, Clone)]
+    struct TickingClock(ManualClock);
+
+    impl Clock for TickingClock {
+        fn now(&self) -> Duration {
+            let now = self.0.now();
+            self.0.set(now + Duration::from_secs(1));
+            now
+        }
+    }
+
+    #[test]
+    fn records() {
+        let recorder = Arc::new(Recorder::default());
+        let usig = HmacUsig::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap();
+        let mut usig = InstrumentedUsig::with_clock(
+            usig,
+            recorder.clone(),
+            TickingClock(ManualClock::default()),
+        );
+        let attestation = usig.attest().unwrap();
+        assert!(usig.add_remote_party(ID, attestation));
+
+        let signatures = usig.sign_batch(&[MESSAGE, MESSAGE]).unwrap();
+        assert!(usig.verify(ID, MESSAGE, &signatures[0]).is_ok());
+        let (mut sign, verify) = usig.split();
+        sign.sign(MESSAGE).unwrap();
+        assert!(verify.verify(ID, b"other", &signatures[1]).is_err());
+
+        assert_eq!(
+            *recorder.signed.lock().unwrap(),
+            [Count(0), Count(1), Count(2)]
+        );
+        assert_eq!(*recorder.verified.lock().unwrap(), [Ok(()), Err(ID)]);
+        assert!(recorder
+            .durations
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|duration| *duration == Duration::from_secs(1)));
+    }
+}
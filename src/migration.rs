@@ -0,0 +1,321 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+use shared_ids::ReplicaId;
+
+use crate::{
+    encoding::with_context,
+    lazy::{digest, AttestationDigest},
+    Count, Counter, MemoryReport, SignHalf, UsigError, VerifyHalf,
+};
+
+/// Context of the successor digest a [Retirement] signs
+pub const RETIREMENT_CONTEXT: &[u8] = b"usig retirement";
+
+fn retirement_message(successor: &AttestationDigest) -> Vec<u8> {
+    with_context(RETIREMENT_CONTEXT, successor)
+}
+
+/// The final statement of a retired USIG naming the attestation of its successor
+///
+/// Its counter is the last one the retired USIG ever used.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Retirement<S> {
+    pub successor: AttestationDigest,
+    pub signature: S,
+}
+
+impl<S: Counter> Retirement<S> {
+    /// The last counter of the retired USIG, later signatures of it must be rejected
+    pub fn final_counter(&self) -> Count {
+        self.signature.counter()
+    }
+}
+
+/// The attestation of a new backend together with the retirement of the old one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Migration<S, A> {
+    pub attestation: A,
+    pub retirement: Retirement<S>,
+}
+
+/// Retire `old` in favor of `new`
+///
+/// Consumes the old sign half, so its counter is burnt by the retirement statement.
+pub fn migrate<O: SignHalf, N: SignHalf>(
+    mut old: O,
+    new: &mut N,
+) -> Result<Migration<O::Signature, N::Attestation>, UsigError>
+where
+    N::Attestation: Serialize,
+{
+    let attestation = new.attest()?;
+    let successor = digest(&attestation);
+    let signature = old.sign(retirement_message(&successor))?;
+    Ok(Migration {
+        attestation,
+        retirement: Retirement {
+            successor,
+            signature,
+        },
+    })
+}
+
+impl<S: Counter, A: Serialize> Migration<S, A> {
+    /// Check that the retirement was signed by the old USIG of `id` and names this attestation
+    pub fn verify<V: VerifyHalf<Signature = S>>(
+        &self,
+        old: &V,
+        id: ReplicaId,
+    ) -> Result<(), UsigError> {
+        if digest(&self.attestation) != self.retirement.successor {
+            return Err(UsigError::RemoteAttestationFailed);
+        }
+        old.verify(
+            id,
+            retirement_message(&self.retirement.successor),
+            &self.retirement.signature,
+        )
+    }
+
+    /// Verify the migration and register the new backend of `id` with `new`
+    ///
+    /// Returns the final counter of the old backend.
+    pub fn apply<V: VerifyHalf<Signature = S>, W: VerifyHalf<Attestation = A>>(
+        self,
+        old: &V,
+        new: &mut W,
+        id: ReplicaId,
+    ) -> Result<Count, UsigError> {
+        self.verify(old, id)?;
+        let final_counter = self.retirement.final_counter();
+        new.try_add_remote_party(id, self.attestation)?;
+        Ok(final_counter)
+    }
+}
+
+/// A signature made with either the old or the new algorithm of a [MigrationVerifyHalf]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum DualSignature<O, N> {
+    Old(O),
+    New(N),
+}
+
+impl<O: Counter, N: Counter> Counter for DualSignature<O, N> {
+    fn counter(&self) -> Count {
+        match self {
+            DualSignature::Old(signature) => signature.counter(),
+            DualSignature::New(signature) => signature.counter(),
+        }
+    }
+}
+
+/// An attestation for either algorithm of a [MigrationVerifyHalf]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum DualAttestation<A, B> {
+    Old(A),
+    New(B),
+}
+
+/// A verify half accepting both algorithms of a party below a cutover counter
+///
+/// A party may be registered with both algorithms. Signatures of the old algorithm are
+/// accepted for counters below `cutover` only and rejected with [UsigError::InvalidSignature]
+/// from there on, so the cluster can rotate algorithms without a flag day.
+#[derive(Debug)]
+pub struct MigrationVerifyHalf<O, N> {
+    old: O,
+    new: N,
+    cutover: Count,
+}
+
+impl<O: VerifyHalf, N: VerifyHalf> MigrationVerifyHalf<O, N> {
+    pub fn new(old: O, new: N, cutover: Count) -> Self {
+        Self { old, new, cutover }
+    }
+
+    pub fn cutover(&self) -> Count {
+        self.cutover
+    }
+
+    /// Finish the migration, keeping only the verify half of the new algorithm
+    pub fn into_new(self) -> N {
+        self.new
+    }
+
+    fn check_cutover(
+        &self,
+        signature: &DualSignature<O::Signature, N::Signature>,
+    ) -> Result<(), UsigError> {
+        match signature {
+            DualSignature::Old(signature) if signature.counter() >= self.cutover => {
+                Err(UsigError::InvalidSignature)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<O: VerifyHalf, N: VerifyHalf> VerifyHalf for MigrationVerifyHalf<O, N> {
+    type Signature = DualSignature<O::Signature, N::Signature>;
+    type Attestation = DualAttestation<O::Attestation, N::Attestation>;
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        self.check_cutover(signature)?;
+        match signature {
+            DualSignature::Old(signature) => self.old.verify(id, message, signature),
+            DualSignature::New(signature) => self.new.verify(id, message, signature),
+        }
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.check_cutover(signature)?;
+        match signature {
+            DualSignature::Old(signature) => self.old.pre_validate(id, signature),
+            DualSignature::New(signature) => self.new.pre_validate(id, signature),
+        }
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        let (old, new) = (self.old.memory_usage(), self.new.memory_usage());
+        MemoryReport {
+            party_registry: old.party_registry + new.party_registry,
+            replay_cache: old.replay_cache + new.replay_cache,
+            evidence: old.evidence + new.evidence,
+        }
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        remote_usig_id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        match attestation {
+            DualAttestation::Old(attestation) => {
+                self.old.try_add_remote_party(remote_usig_id, attestation)
+            }
+            DualAttestation::New(attestation) => {
+                self.new.try_add_remote_party(remote_usig_id, attestation)
+            }
+        }
+    }
+
+    fn remove_remote_party(&mut self, remote_usig_id: ReplicaId) -> bool {
+        let old = self.old.remove_remote_party(remote_usig_id);
+        self.new.remove_remote_party(remote_usig_id) | old
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        let mut parties: BTreeSet<_> = self.old.remote_parties().collect();
+        parties.extend(self.new.remote_parties());
+        parties.into_iter()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.old.contains(remote_usig_id) || self.new.contains(remote_usig_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::Hmac;
+    use sha2::Sha256;
+
+    use super::*;
+    use crate::{hmac::UsigHmac, signature::new_ed25519, standby::ResumableSignHalf, Usig};
+
+    const ID: ReplicaId = ReplicaId::first();
+    const MESSAGE: &[u8] = b"message";
+
+    fn new_hmac() -> UsigHmac<Hmac<Sha256>> {
+        UsigHmac::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap()
+    }
+
+    #[test]
+    fn hmac_to_ed25519() {
+        let (mut old, mut old_verify) = new_hmac().split();
+        assert!(old_verify.add_remote_party(ID, old.attest().unwrap()));
+        for _ in 0..3 {
+            old.sign(MESSAGE).unwrap();
+        }
+
+        let (mut new, mut new_verify) = new_ed25519().split();
+        let migration = migrate(old, &mut new).unwrap();
+        let final_counter = migration.apply(&old_verify, &mut new_verify, ID).unwrap();
+        assert_eq!(final_counter, Count(3));
+
+        let signature = new.sign(MESSAGE).unwrap();
+        assert!(new_verify.verify(ID, MESSAGE, &signature).is_ok());
+    }
+
+    #[test]
+    fn substituted_successor() {
+        let (mut old, mut old_verify) = new_hmac().split();
+        let (mut intended, _) = new_ed25519().split();
+        let (mut other, mut new_verify) = new_ed25519().split();
+        assert!(old_verify.add_remote_party(ID, old.attest().unwrap()));
+
+        let mut migration = migrate(old, &mut intended).unwrap();
+        migration.attestation = other.attest().unwrap();
+        assert!(matches!(
+            migration.apply(&old_verify, &mut new_verify, ID),
+            Err(UsigError::RemoteAttestationFailed)
+        ));
+    }
+
+    #[test]
+    fn forged_retirement() {
+        let (_, mut old_verify) = new_hmac().split();
+        let (impostor, _) = new_hmac().split();
+        let (mut genuine, _) = new_hmac().split();
+        assert!(old_verify.add_remote_party(ID, genuine.attest().unwrap()));
+
+        let (mut new, mut new_verify) = new_ed25519().split();
+        let migration = migrate(impostor, &mut new).unwrap();
+        assert!(matches!(
+            migration.apply(&old_verify, &mut new_verify, ID),
+            Err(UsigError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn dual_stack() {
+        let (mut old, old_verify) = new_hmac().split();
+        let (mut new, new_verify) = new_ed25519().split();
+        let mut verify = MigrationVerifyHalf::new(old_verify, new_verify, Count(2));
+        verify
+            .try_add_remote_party(ID, DualAttestation::Old(old.attest().unwrap()))
+            .unwrap();
+        verify
+            .try_add_remote_party(ID, DualAttestation::New(new.attest().unwrap()))
+            .unwrap();
+
+        for _ in 0..2 {
+            let signature = DualSignature::Old(old.sign(MESSAGE).unwrap());
+            assert!(verify.verify(ID, MESSAGE, &signature).is_ok());
+        }
+        let late = DualSignature::Old(old.sign(MESSAGE).unwrap());
+        assert!(matches!(
+            verify.pre_validate(ID, &late),
+            Err(UsigError::InvalidSignature)
+        ));
+        assert!(matches!(
+            verify.verify(ID, MESSAGE, &late),
+            Err(UsigError::InvalidSignature)
+        ));
+
+        new.resume_at(Count(2)).unwrap();
+        let signature = DualSignature::New(new.sign(MESSAGE).unwrap());
+        assert_eq!(signature.counter(), Count(2));
+        assert!(verify.verify(ID, MESSAGE, &signature).is_ok());
+        assert!(matches!(
+            verify.verify(ReplicaId::from_u64(1), MESSAGE, &signature),
+            Err(UsigError::UnknownId(_))
+        ));
+    }
+}
@@ -0,0 +1,291 @@
+use std::{any::Any, collections::BTreeMap, fmt::Debug, ops::Range, sync::Mutex};
+
+use derivative::Derivative;
+use shared_ids::ReplicaId;
+
+use crate::{
+    semantics::{CounterSemantics, Monotonic},
+    watermarks::HighWatermarks,
+    Count, Counter, MemoryReport, UsigError, VerifyHalf,
+};
+
+/// Notified when a verified counter skips ahead of the last accepted one of a party
+pub trait GapObserver {
+    /// `missing` are the counters between the last accepted one and the new one
+    fn on_gap(&self, id: ReplicaId, missing: Range<Count>);
+}
+
+impl<F: Fn(ReplicaId, Range<Count>)> GapObserver for F {
+    fn on_gap(&self, id: ReplicaId, missing: Range<Count>) {
+        self(id, missing)
+    }
+}
+
+/// A verify half that only accepts counters following the last accepted one of a party
+///
+/// Remembers the last accepted counter per party and rejects duplicates and regressions,
+/// or whatever else `M` considers invalid, with [UsigError::StaleCounter].
+/// The signature is verified first, so a forged counter can not raise the watermark.
+/// Skipped counters are reported to the [GapObserver]s, e.g. to request retransmissions.
+#[derive(Derivative)]
+#[derivative(Debug(bound = "V: Debug, M: Debug"))]
+pub struct MonotonicVerifyHalf<V, M = Monotonic> {
+    inner: V,
+    semantics: M,
+    accepted: Mutex<HighWatermarks>,
+    #[derivative(Debug = "ignore")]
+    observers: Vec<Box<dyn GapObserver + Send + Sync>>,
+}
+
+impl<V: VerifyHalf> MonotonicVerifyHalf<V> {
+    pub fn new(inner: V) -> Self {
+        Self::with_semantics(inner, Monotonic)
+    }
+}
+
+impl<V: VerifyHalf, M: CounterSemantics> MonotonicVerifyHalf<V, M> {
+    pub fn with_semantics(inner: V, semantics: M) -> Self {
+        Self {
+            inner,
+            semantics,
+            accepted: Mutex::default(),
+            observers: Vec::new(),
+        }
+    }
+
+    /// Report counters skipped by a party to `observer`
+    pub fn add_gap_observer(&mut self, observer: impl GapObserver + Send + Sync + 'static) {
+        self.observers.push(Box::new(observer));
+    }
+
+    /// Start from the counters accepted by peers, e.g. from verified
+    /// [crate::watermarks::WatermarkSnapshot]s, see [HighWatermarks::merge]
+    pub fn merge(&self, vouched: &BTreeMap<ReplicaId, HighWatermarks>, f: usize) {
+        self.accepted.lock().unwrap().merge(vouched, f);
+    }
+
+    /// Start from counters this replica accepted itself, e.g. from its own checkpoint
+    pub(crate) fn merge_trusted(&self, watermarks: &HighWatermarks) {
+        self.accepted.lock().unwrap().merge_trusted(watermarks);
+    }
+
+    /// The last accepted counters of all parties
+    pub fn watermarks(&self) -> HighWatermarks {
+        self.accepted.lock().unwrap().clone()
+    }
+
+    pub fn into_inner(self) -> V {
+        self.inner
+    }
+
+    fn stale(&self, id: ReplicaId, last: Option<Count>, counter: Count) -> Result<(), UsigError> {
+        match last {
+            Some(last) if !self.semantics.is_valid_next(last, counter) => {
+                Err(UsigError::StaleCounter { id, last, counter })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<V: VerifyHalf, M: CounterSemantics> VerifyHalf for MonotonicVerifyHalf<V, M> {
+    type Signature = V::Signature;
+    type Attestation = V::Attestation;
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        let counter = signature.counter();
+        self.stale(id, self.last_counter(id), counter)?;
+        self.inner.verify(id, message, signature)?;
+        let last = {
+            let mut accepted = self.accepted.lock().unwrap();
+            let last = accepted.get(id);
+            if !accepted.observe_with(&self.semantics, id, counter) {
+                // a concurrent verification accepted a counter in the meantime
+                let last = accepted.get(id).expect("party was observed");
+                return Err(UsigError::StaleCounter { id, last, counter });
+            }
+            last
+        };
+        if let Some(last) = last.filter(|last| counter.distance(*last) > 1) {
+            for observer in &self.observers {
+                observer.on_gap(id, last + 1..counter);
+            }
+        }
+        Ok(())
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.stale(id, self.last_counter(id), signature.counter())?;
+        self.inner.pre_validate(id, signature)
+    }
+
+    fn last_counter(&self, id: ReplicaId) -> Option<Count> {
+        self.accepted.lock().unwrap().get(id)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        let accepted = self.accepted.lock().unwrap();
+        let mut report = self.inner.memory_usage();
+        report.evidence += accepted.iter().count() * size_of::<(ReplicaId, Count)>();
+        report
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        remote_usig_id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        self.inner.try_add_remote_party(remote_usig_id, attestation)
+    }
+
+    fn remove_remote_party(&mut self, remote_usig_id: ReplicaId) -> bool {
+        self.accepted.get_mut().unwrap().remove(remote_usig_id);
+        self.inner.remove_remote_party(remote_usig_id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.inner.remote_parties()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.inner.contains(remote_usig_id)
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any()
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use hmac::Hmac;
+    use sha2::Sha256;
+
+    use super::*;
+    use crate::{
+        hmac::UsigHmac, revocation::RevocationVerifyHalf, semantics::StrictIncrement, SignHalf,
+        Usig,
+    };
+
+    type HmacUsig = UsigHmac<Hmac<Sha256>>;
+
+    const ID: ReplicaId = ReplicaId::first();
+    const MESSAGE: &[u8] = b"message";
+
+    fn usig() -> HmacUsig {
+        HmacUsig::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap()
+    }
+
+    #[test]
+    fn duplicates_and_regressions() {
+        let (mut sign, verify) = usig().split();
+        let mut verify = verify.enforce_monotonicity();
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+
+        let first = sign.sign(MESSAGE).unwrap();
+        let second = sign.sign(MESSAGE).unwrap();
+        let fourth = {
+            sign.sign(MESSAGE).unwrap();
+            sign.sign(MESSAGE).unwrap()
+        };
+        assert!(verify.verify(ID, MESSAGE, &second).is_ok());
+        assert!(matches!(
+            verify.verify(ID, MESSAGE, &second),
+            Err(UsigError::StaleCounter {
+                last: Count(1),
+                counter: Count(1),
+                ..
+            })
+        ));
+        assert!(matches!(
+            verify.pre_validate(ID, &first),
+            Err(UsigError::StaleCounter { .. })
+        ));
+        assert!(matches!(
+            verify.verify(ID, MESSAGE, &first),
+            Err(UsigError::StaleCounter { .. })
+        ));
+        assert!(verify.verify(ID, MESSAGE, &fourth).is_ok());
+        assert_eq!(verify.last_counter(ID), Some(Count(3)));
+    }
+
+    #[test]
+    fn forged_counter() {
+        let (mut sign, verify) = usig().split();
+        let mut verify = MonotonicVerifyHalf::new(verify);
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+
+        let signature = sign.sign(MESSAGE).unwrap();
+        assert!(matches!(
+            verify.verify(ID, b"forged", &signature),
+            Err(UsigError::InvalidSignature)
+        ));
+        assert_eq!(verify.last_counter(ID), None);
+        assert!(verify.verify(ID, MESSAGE, &signature).is_ok());
+    }
+
+    #[test]
+    fn holes_through_wrapper() {
+        let (mut sign, verify) = usig().split();
+        let mut verify = RevocationVerifyHalf::new(verify.enforce_monotonicity());
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+        assert_eq!(verify.last_counter(ID), None);
+
+        let first = sign.sign(MESSAGE).unwrap();
+        sign.sign(MESSAGE).unwrap();
+        let third = sign.sign(MESSAGE).unwrap();
+        assert!(verify.verify(ID, MESSAGE, &first).is_ok());
+        assert!(verify.verify(ID, MESSAGE, &third).is_ok());
+        assert_eq!(verify.last_counter(ID), Some(third.counter()));
+        assert!(verify.last_counter(ReplicaId::from_u64(1)).is_none());
+    }
+
+    #[test]
+    fn gaps() {
+        let (mut sign, verify) = usig().split();
+        let mut verify = MonotonicVerifyHalf::new(verify);
+        let gaps = Arc::new(Mutex::new(Vec::new()));
+        let reported = gaps.clone();
+        verify.add_gap_observer(move |id, missing| reported.lock().unwrap().push((id, missing)));
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+
+        let signatures: Vec<_> = (0..6).map(|_| sign.sign(MESSAGE).unwrap()).collect();
+        for i in [1, 2, 5] {
+            assert!(verify.verify(ID, MESSAGE, &signatures[i]).is_ok());
+        }
+        assert_eq!(*gaps.lock().unwrap(), vec![(ID, Count(3)..Count(5))]);
+    }
+
+    #[test]
+    fn custom_semantics() {
+        let (mut sign, verify) = usig().split();
+        let mut verify = MonotonicVerifyHalf::with_semantics(verify, StrictIncrement);
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+
+        let first = sign.sign(MESSAGE).unwrap();
+        sign.sign(MESSAGE).unwrap();
+        let third = sign.sign(MESSAGE).unwrap();
+        assert!(verify.verify(ID, MESSAGE, &first).is_ok());
+        assert!(matches!(
+            verify.verify(ID, MESSAGE, &third),
+            Err(UsigError::StaleCounter { .. })
+        ));
+    }
+}
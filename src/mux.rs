@@ -0,0 +1,686 @@
+use std::{any::Any, collections::HashMap, fmt, marker::PhantomData};
+
+use derivative::Derivative;
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use shared_ids::ReplicaId;
+
+use crate::{
+    registry::{AlgorithmId, AlgorithmIdentifier},
+    Count, Counter, MemoryReport, SignHalf, Usig, UsigError, VerifyHalf,
+};
+
+/// A value of either backend, as serialized by [MuxSignature] and [MuxAttestation]
+enum Tagged<X, Y> {
+    First(X),
+    Second(Y),
+}
+
+struct TaggedVisitor<X, Y> {
+    first: AlgorithmId,
+    second: AlgorithmId,
+    phantom_data: PhantomData<fn() -> (X, Y)>,
+}
+
+impl<'de, X: Deserialize<'de>, Y: Deserialize<'de>> Visitor<'de> for TaggedVisitor<X, Y> {
+    type Value = Tagged<X, Y>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a value of {} or {}", self.first, self.second)
+    }
+
+    fn visit_seq<S: SeqAccess<'de>>(self, mut seq: S) -> Result<Self::Value, S::Error> {
+        let algorithm: AlgorithmId = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let value = if algorithm == self.first {
+            seq.next_element()?.map(Tagged::First)
+        } else if algorithm == self.second {
+            seq.next_element()?.map(Tagged::Second)
+        } else {
+            return Err(de::Error::custom(format_args!(
+                "unsupported algorithm {}",
+                algorithm
+            )));
+        };
+        value.ok_or_else(|| de::Error::invalid_length(1, &self))
+    }
+}
+
+fn deserialize_tagged<'de, D, X, Y>(
+    deserializer: D,
+    first: AlgorithmId,
+    second: AlgorithmId,
+) -> Result<Tagged<X, Y>, D::Error>
+where
+    D: Deserializer<'de>,
+    X: Deserialize<'de>,
+    Y: Deserialize<'de>,
+{
+    deserializer.deserialize_tuple(
+        2,
+        TaggedVisitor {
+            first,
+            second,
+            phantom_data: PhantomData,
+        },
+    )
+}
+
+/// A signature made with either backend of a [UsigMux]
+///
+/// Serialized as the [AlgorithmId] of its backend followed by the signature, so nodes
+/// whose muxes list the same backends in a different order understand each other.
+#[derive(Derivative)]
+#[derivative(
+    Debug(bound = "A::Signature: fmt::Debug, B::Signature: fmt::Debug"),
+    Clone(bound = "A::Signature: Clone, B::Signature: Clone")
+)]
+pub enum MuxSignature<A: Usig, B: Usig> {
+    First(A::Signature),
+    Second(B::Signature),
+}
+
+impl<A, B> MuxSignature<A, B>
+where
+    A: Usig + AlgorithmIdentifier,
+    B: Usig + AlgorithmIdentifier,
+{
+    /// The algorithm of the backend that made the signature
+    pub fn algorithm(&self) -> AlgorithmId {
+        match self {
+            MuxSignature::First(_) => A::ALGORITHM_ID,
+            MuxSignature::Second(_) => B::ALGORITHM_ID,
+        }
+    }
+}
+
+impl<A: Usig, B: Usig> Counter for MuxSignature<A, B> {
+    fn counter(&self) -> Count {
+        match self {
+            MuxSignature::First(signature) => signature.counter(),
+            MuxSignature::Second(signature) => signature.counter(),
+        }
+    }
+}
+
+impl<A, B> Serialize for MuxSignature<A, B>
+where
+    A: Usig + AlgorithmIdentifier,
+    B: Usig + AlgorithmIdentifier,
+    A::Signature: Serialize,
+    B::Signature: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            MuxSignature::First(signature) => (A::ALGORITHM_ID, signature).serialize(serializer),
+            MuxSignature::Second(signature) => (B::ALGORITHM_ID, signature).serialize(serializer),
+        }
+    }
+}
+
+impl<'de, A, B> Deserialize<'de> for MuxSignature<A, B>
+where
+    A: Usig + AlgorithmIdentifier,
+    B: Usig + AlgorithmIdentifier,
+    A::Signature: Deserialize<'de>,
+    B::Signature: Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(
+            match deserialize_tagged(deserializer, A::ALGORITHM_ID, B::ALGORITHM_ID)? {
+                Tagged::First(signature) => MuxSignature::First(signature),
+                Tagged::Second(signature) => MuxSignature::Second(signature),
+            },
+        )
+    }
+}
+
+/// An attestation for either backend of a [UsigMux]
+///
+/// Serialized like [MuxSignature], tagged with the [AlgorithmId] of its backend.
+#[derive(Derivative)]
+#[derivative(
+    Debug(bound = "A::Attestation: fmt::Debug, B::Attestation: fmt::Debug"),
+    Clone(bound = "A::Attestation: Clone, B::Attestation: Clone")
+)]
+pub enum MuxAttestation<A: Usig, B: Usig> {
+    First(A::Attestation),
+    Second(B::Attestation),
+}
+
+impl<A, B> MuxAttestation<A, B>
+where
+    A: Usig + AlgorithmIdentifier,
+    B: Usig + AlgorithmIdentifier,
+{
+    /// The algorithm of the attested backend
+    pub fn algorithm(&self) -> AlgorithmId {
+        match self {
+            MuxAttestation::First(_) => A::ALGORITHM_ID,
+            MuxAttestation::Second(_) => B::ALGORITHM_ID,
+        }
+    }
+}
+
+impl<A, B> Serialize for MuxAttestation<A, B>
+where
+    A: Usig + AlgorithmIdentifier,
+    B: Usig + AlgorithmIdentifier,
+    A::Attestation: Serialize,
+    B::Attestation: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            MuxAttestation::First(attestation) => {
+                (A::ALGORITHM_ID, attestation).serialize(serializer)
+            }
+            MuxAttestation::Second(attestation) => {
+                (B::ALGORITHM_ID, attestation).serialize(serializer)
+            }
+        }
+    }
+}
+
+impl<'de, A, B> Deserialize<'de> for MuxAttestation<A, B>
+where
+    A: Usig + AlgorithmIdentifier,
+    B: Usig + AlgorithmIdentifier,
+    A::Attestation: Deserialize<'de>,
+    B::Attestation: Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(
+            match deserialize_tagged(deserializer, A::ALGORITHM_ID, B::ALGORITHM_ID)? {
+                Tagged::First(attestation) => MuxAttestation::First(attestation),
+                Tagged::Second(attestation) => MuxAttestation::Second(attestation),
+            },
+        )
+    }
+}
+
+/// The sign half of a [UsigMux], signing with one of its backends at a time
+///
+/// Starts with the first backend, [MuxSignHalf::sign_with] switches, e.g. once a
+/// migration to the second algorithm is complete. The counters of both backends are
+/// independent, so peers have to register the new attestation after a switch.
+#[derive(Derivative)]
+#[derivative(Debug(bound = "A::SignHalf: fmt::Debug, B::SignHalf: fmt::Debug"))]
+pub struct MuxSignHalf<A: Usig, B: Usig> {
+    first: A::SignHalf,
+    second: B::SignHalf,
+    signing: AlgorithmId,
+}
+
+impl<A, B> MuxSignHalf<A, B>
+where
+    A: Usig + AlgorithmIdentifier,
+    B: Usig + AlgorithmIdentifier,
+{
+    /// Sign with `first`, fails if both backends use the same algorithm
+    pub fn new(first: A::SignHalf, second: B::SignHalf) -> Result<Self, UsigError> {
+        if A::ALGORITHM_ID == B::ALGORITHM_ID {
+            return Err(UsigError::DuplicateAlgorithm(A::ALGORITHM_ID));
+        }
+        Ok(Self {
+            first,
+            second,
+            signing: A::ALGORITHM_ID,
+        })
+    }
+
+    /// The algorithm of the backend signing and attesting
+    pub fn signing_algorithm(&self) -> AlgorithmId {
+        self.signing
+    }
+
+    /// Sign with the backend of `algorithm` from now on, false if neither backend uses it
+    pub fn sign_with(&mut self, algorithm: AlgorithmId) -> bool {
+        let known = algorithm == A::ALGORITHM_ID || algorithm == B::ALGORITHM_ID;
+        if known {
+            self.signing = algorithm;
+        }
+        known
+    }
+
+    fn signs_with_first(&self) -> bool {
+        self.signing == A::ALGORITHM_ID
+    }
+}
+
+impl<A, B> SignHalf for MuxSignHalf<A, B>
+where
+    A: Usig + AlgorithmIdentifier,
+    B: Usig + AlgorithmIdentifier,
+{
+    type Signature = MuxSignature<A, B>;
+    type Attestation = MuxAttestation<A, B>;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        if self.signs_with_first() {
+            self.first.sign(message).map(MuxSignature::First)
+        } else {
+            self.second.sign(message).map(MuxSignature::Second)
+        }
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        if self.signs_with_first() {
+            self.first.attest().map(MuxAttestation::First)
+        } else {
+            self.second.attest().map(MuxAttestation::Second)
+        }
+    }
+
+    fn local_id(&self) -> Option<ReplicaId> {
+        if self.signs_with_first() {
+            self.first.local_id()
+        } else {
+            self.second.local_id()
+        }
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        if self.signs_with_first() {
+            self.first.as_any()
+        } else {
+            self.second.as_any()
+        }
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        if self.signs_with_first() {
+            self.first.as_any_mut()
+        } else {
+            self.second.as_any_mut()
+        }
+    }
+}
+
+/// A verify half routing every party to the backend it was registered with
+///
+/// Lets a cluster mix algorithms, e.g. during a migration some peers use HMAC while
+/// others already use Ed25519. Signatures made with a different algorithm than the one
+/// the party is registered with are rejected with [UsigError::InvalidSignature].
+#[derive(Derivative)]
+#[derivative(Debug(bound = "A::VerifyHalf: fmt::Debug, B::VerifyHalf: fmt::Debug"))]
+pub struct MuxVerifyHalf<A: Usig, B: Usig> {
+    first: A::VerifyHalf,
+    second: B::VerifyHalf,
+    routes: HashMap<ReplicaId, AlgorithmId>,
+}
+
+impl<A, B> MuxVerifyHalf<A, B>
+where
+    A: Usig + AlgorithmIdentifier,
+    B: Usig + AlgorithmIdentifier,
+{
+    pub fn new(first: A::VerifyHalf, second: B::VerifyHalf) -> Self {
+        Self {
+            first,
+            second,
+            routes: HashMap::new(),
+        }
+    }
+
+    /// The algorithm `id` is routed to, if it is registered
+    pub fn algorithm(&self, id: ReplicaId) -> Option<AlgorithmId> {
+        self.routes.get(&id).copied()
+    }
+
+    fn route(&self, id: ReplicaId, signature: &MuxSignature<A, B>) -> Result<(), UsigError> {
+        match self.algorithm(id) {
+            None => Err(UsigError::UnknownId(id)),
+            Some(algorithm) if algorithm != signature.algorithm() => {
+                Err(UsigError::InvalidSignature)
+            }
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Remove `id` from the backend of `algorithm`
+    fn remove_from(&mut self, algorithm: AlgorithmId, id: ReplicaId) -> bool {
+        if algorithm == A::ALGORITHM_ID {
+            self.first.remove_remote_party(id)
+        } else {
+            self.second.remove_remote_party(id)
+        }
+    }
+}
+
+impl<A, B> VerifyHalf for MuxVerifyHalf<A, B>
+where
+    A: Usig + AlgorithmIdentifier,
+    B: Usig + AlgorithmIdentifier,
+{
+    type Signature = MuxSignature<A, B>;
+    type Attestation = MuxAttestation<A, B>;
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        self.route(id, signature)?;
+        match signature {
+            MuxSignature::First(signature) => self.first.verify(id, message, signature),
+            MuxSignature::Second(signature) => self.second.verify(id, message, signature),
+        }
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.route(id, signature)?;
+        match signature {
+            MuxSignature::First(signature) => self.first.pre_validate(id, signature),
+            MuxSignature::Second(signature) => self.second.pre_validate(id, signature),
+        }
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        let (first, second) = (self.first.memory_usage(), self.second.memory_usage());
+        MemoryReport {
+            party_registry: first.party_registry
+                + second.party_registry
+                + size_of_val(&self.routes)
+                + self.routes.capacity() * (size_of::<(ReplicaId, AlgorithmId)>() + 1),
+            replay_cache: first.replay_cache + second.replay_cache,
+            evidence: first.evidence + second.evidence,
+        }
+    }
+
+    /// Register `remote_usig_id` with the backend of the attestation
+    ///
+    /// A party registered with the other backend before is removed from it, so its old
+    /// key is no longer kept around.
+    fn try_add_remote_party(
+        &mut self,
+        remote_usig_id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        let algorithm = attestation.algorithm();
+        match attestation {
+            MuxAttestation::First(attestation) => {
+                self.first.try_add_remote_party(remote_usig_id, attestation)
+            }
+            MuxAttestation::Second(attestation) => self
+                .second
+                .try_add_remote_party(remote_usig_id, attestation),
+        }?;
+        if let Some(previous) = self.routes.insert(remote_usig_id, algorithm) {
+            if previous != algorithm {
+                self.remove_from(previous, remote_usig_id);
+            }
+        }
+        Ok(())
+    }
+
+    fn remove_remote_party(&mut self, remote_usig_id: ReplicaId) -> bool {
+        match self.routes.remove(&remote_usig_id) {
+            Some(algorithm) => self.remove_from(algorithm, remote_usig_id),
+            None => false,
+        }
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.routes.keys().copied()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.routes.contains_key(&remote_usig_id)
+    }
+
+    fn last_counter(&self, id: ReplicaId) -> Option<Count> {
+        if self.algorithm(id)? == A::ALGORITHM_ID {
+            self.first.last_counter(id)
+        } else {
+            self.second.last_counter(id)
+        }
+    }
+}
+
+/// A USIG with two backends of different algorithms, signing with one of them at a time
+///
+/// Signatures and attestations are tagged with the [AlgorithmId] of their backend, see
+/// [MuxSignHalf] for switching the signing backend and [MuxVerifyHalf] for how remote
+/// parties are routed.
+#[derive(Derivative)]
+#[derivative(Debug(bound = "MuxSignHalf<A, B>: fmt::Debug, MuxVerifyHalf<A, B>: fmt::Debug"))]
+pub struct UsigMux<A: Usig, B: Usig> {
+    sign_half: MuxSignHalf<A, B>,
+    verify_half: MuxVerifyHalf<A, B>,
+}
+
+impl<A, B> UsigMux<A, B>
+where
+    A: Usig + AlgorithmIdentifier,
+    B: Usig + AlgorithmIdentifier,
+{
+    /// Sign with `first` and verify parties using either backend
+    ///
+    /// Fails if both backends use the same algorithm.
+    pub fn new(first: A, second: B) -> Result<Self, UsigError> {
+        let (first_sign, first_verify) = first.split();
+        let (second_sign, second_verify) = second.split();
+        Ok(Self {
+            sign_half: MuxSignHalf::new(first_sign, second_sign)?,
+            verify_half: MuxVerifyHalf::new(first_verify, second_verify),
+        })
+    }
+
+    /// The algorithm `id` is routed to, if it is registered
+    pub fn algorithm(&self, id: ReplicaId) -> Option<AlgorithmId> {
+        self.verify_half.algorithm(id)
+    }
+
+    /// See [MuxSignHalf::signing_algorithm]
+    pub fn signing_algorithm(&self) -> AlgorithmId {
+        self.sign_half.signing_algorithm()
+    }
+
+    /// See [MuxSignHalf::sign_with]
+    pub fn sign_with(&mut self, algorithm: AlgorithmId) -> bool {
+        self.sign_half.sign_with(algorithm)
+    }
+}
+
+impl<A, B> Usig for UsigMux<A, B>
+where
+    A: Usig + AlgorithmIdentifier,
+    B: Usig + AlgorithmIdentifier,
+{
+    type Signature = MuxSignature<A, B>;
+    type Attestation = MuxAttestation<A, B>;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        self.sign_half.sign(message)
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        self.sign_half.attest()
+    }
+
+    fn local_id(&self) -> Option<ReplicaId> {
+        self.sign_half.local_id()
+    }
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        self.verify_half.verify(id, message, signature)
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.verify_half.pre_validate(id, signature)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        self.verify_half.memory_usage()
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        self.verify_half.try_add_remote_party(id, attestation)
+    }
+
+    fn remove_remote_party(&mut self, id: ReplicaId) -> bool {
+        self.verify_half.remove_remote_party(id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.verify_half.remote_parties()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.verify_half.contains(remote_usig_id)
+    }
+
+    type SignHalf = MuxSignHalf<A, B>;
+    type VerifyHalf = MuxVerifyHalf<A, B>;
+
+    fn split(self) -> (Self::SignHalf, Self::VerifyHalf) {
+        (self.sign_half, self.verify_half)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::Hmac;
+    use sha2::Sha256;
+
+    use super::{MuxAttestation, MuxSignHalf, MuxSignature, UsigMux};
+    use crate as usig;
+    use crate::{
+        hmac::UsigHmac,
+        registry::AlgorithmId,
+        signature::{new_ed25519, UsigEd25519, UsigP256},
+        tests,
+    };
+
+    type HmacUsig = UsigHmac<Hmac<Sha256>>;
+
+    fn new_hmac() -> HmacUsig {
+        HmacUsig::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap()
+    }
+
+    fn new_mux() -> UsigMux<HmacUsig, UsigEd25519> {
+        UsigMux::new(new_hmac(), new_ed25519()).unwrap()
+    }
+
+    tests!(new_mux());
+
+    #[test]
+    fn heterogeneous() {
+        let hmac_id = ReplicaId::from_u64(1);
+        let ed25519_id = ReplicaId::from_u64(2);
+        let mut mux = new_mux();
+        let mut peer = new_ed25519();
+
+        let attestation = mux.attest().unwrap();
+        assert!(mux.add_remote_party(hmac_id, attestation));
+        assert!(mux
+            .try_add_remote_party(ed25519_id, MuxAttestation::Second(peer.attest().unwrap()))
+            .is_ok());
+        assert_eq!(mux.algorithm(hmac_id), Some(AlgorithmId::HMAC_SHA256));
+        assert_eq!(mux.algorithm(ed25519_id), Some(AlgorithmId::ED25519));
+
+        let local = mux.sign(MESSAGE_1).unwrap();
+        let remote = MuxSignature::Second(peer.sign(MESSAGE_1).unwrap());
+        assert!(mux.verify(hmac_id, MESSAGE_1, &local).is_ok());
+        assert!(mux.verify(ed25519_id, MESSAGE_1, &remote).is_ok());
+
+        assert!(matches!(
+            mux.verify(hmac_id, MESSAGE_1, &remote),
+            Err(UsigError::InvalidSignature)
+        ));
+        assert!(matches!(
+            mux.pre_validate(ed25519_id, &local),
+            Err(UsigError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn reroute() {
+        let id = ReplicaId::from_u64(1);
+        let mut mux = new_mux();
+        let mut peer = new_ed25519();
+
+        let attestation = mux.attest().unwrap();
+        assert!(mux.add_remote_party(id, attestation));
+        let local = mux.sign(MESSAGE_1).unwrap();
+        assert!(mux
+            .try_add_remote_party(id, MuxAttestation::Second(peer.attest().unwrap()))
+            .is_ok());
+
+        assert_eq!(mux.algorithm(id), Some(AlgorithmId::ED25519));
+        assert!(!mux.verify_half.first.contains(id));
+        assert!(matches!(
+            mux.verify(id, MESSAGE_1, &local),
+            Err(UsigError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn sign_with_second() {
+        let mut mux = new_mux();
+        assert!(!mux.sign_with(AlgorithmId::P256));
+        assert_eq!(mux.signing_algorithm(), AlgorithmId::HMAC_SHA256);
+        assert!(mux.sign_with(AlgorithmId::ED25519));
+
+        let attestation = mux.attest().unwrap();
+        assert_eq!(attestation.algorithm(), AlgorithmId::ED25519);
+        assert!(mux.add_remote_party(ID, attestation));
+        let signature = mux.sign(MESSAGE_1).unwrap();
+        assert!(matches!(signature, MuxSignature::Second(_)));
+        assert!(mux.verify(ID, MESSAGE_1, &signature).is_ok());
+    }
+
+    #[test]
+    fn opposite_order() {
+        let mut hmac_first = new_mux();
+        let mut ed25519_first =
+            UsigMux::<UsigEd25519, HmacUsig>::new(new_ed25519(), new_hmac()).unwrap();
+
+        let attestation = bincode::serialize(&hmac_first.attest().unwrap()).unwrap();
+        let attestation: MuxAttestation<_, _> = bincode::deserialize(&attestation).unwrap();
+        assert!(matches!(attestation, MuxAttestation::Second(_)));
+        assert!(ed25519_first.add_remote_party(ID, attestation));
+
+        let signature = bincode::serialize(&hmac_first.sign(MESSAGE_1).unwrap()).unwrap();
+        let signature: MuxSignature<_, _> = bincode::deserialize(&signature).unwrap();
+        assert_eq!(signature.algorithm(), AlgorithmId::HMAC_SHA256);
+        assert!(ed25519_first.verify(ID, MESSAGE_1, &signature).is_ok());
+
+        let signature = bincode::serialize(&hmac_first.sign(MESSAGE_1).unwrap()).unwrap();
+        assert!(bincode::deserialize::<MuxSignature<UsigEd25519, UsigP256>>(&signature).is_err());
+    }
+
+    #[test]
+    fn same_algorithm() {
+        assert!(matches!(
+            UsigMux::new(new_hmac(), new_hmac()),
+            Err(UsigError::DuplicateAlgorithm(AlgorithmId::HMAC_SHA256))
+        ));
+        assert!(matches!(
+            MuxSignHalf::<UsigEd25519, UsigEd25519>::new(
+                new_ed25519().split().0,
+                new_ed25519().split().0
+            ),
+            Err(UsigError::DuplicateAlgorithm(AlgorithmId::ED25519))
+        ));
+    }
+}
@@ -1,17 +1,34 @@
-use std::collections::HashSet;
+use std::any::Any;
 
 use serde::{Deserialize, Serialize};
 use shared_ids::ReplicaId;
 
-use crate::{Count, Counter, SignHalf, Usig, UsigError, VerifyHalf};
+use crate::{
+    directory::{MemoryDirectory, PartyDirectory},
+    standby::ResumableSignHalf,
+    Count, Counter, MemoryReport, SignHalf, Usig, UsigError, VerifyHalf,
+};
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub struct Signature(u64);
 
 impl Signature {
     pub fn fake(counter: u64) -> Self {
         Self(counter)
     }
+
+    /// The big endian counter, for transports not using serde
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_be_bytes().to_vec()
+    }
+
+    /// Read a signature written by [Signature::to_bytes]
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<Self, UsigError> {
+        bytes
+            .try_into()
+            .map(|counter| Self(u64::from_be_bytes(counter)))
+            .map_err(|_| UsigError::InvalidSignature)
+    }
 }
 
 impl Counter for Signature {
@@ -20,9 +37,23 @@ impl Counter for Signature {
     }
 }
 
-#[derive(Default, Debug)]
+/// The sign half of [UsigNoOp]
+///
+/// A clone continues at the same counter as the original, so both hand out the same
+/// counters. Only fork sign halves whose original is not used anymore afterwards, e.g. to
+/// explore alternative executions in a simulation.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct UsigNoOpSignHalf {
     counter: u64,
+    local_id: Option<ReplicaId>,
+}
+
+impl UsigNoOpSignHalf {
+    /// Bind the sign half to its own id
+    pub fn with_local_id(mut self, id: ReplicaId) -> Self {
+        self.local_id = Some(id);
+        self
+    }
 }
 
 impl SignHalf for UsigNoOpSignHalf {
@@ -32,21 +63,65 @@ impl SignHalf for UsigNoOpSignHalf {
     fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
         let _ = message.as_ref();
         let counter = self.counter;
-        self.counter += 1;
+        self.counter = counter.checked_add(1).ok_or(UsigError::CounterExhausted)?;
         Ok(Signature(counter))
     }
 
     fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
         Ok(())
     }
+
+    fn local_id(&self) -> Option<ReplicaId> {
+        self.local_id
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        Some(self)
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        Some(self)
+    }
+}
+
+impl ResumableSignHalf for UsigNoOpSignHalf {
+    fn resume_at(&mut self, next: Count) -> Result<(), UsigError> {
+        if next.0 < self.counter {
+            return Err(UsigError::CounterRegression);
+        }
+        self.counter = next.0;
+        Ok(())
+    }
+
+    fn next_counter(&self) -> Count {
+        Count(self.counter)
+    }
 }
 
-#[derive(Default, Debug)]
-pub struct UsigNoOpVerifyHalf {
-    ids: HashSet<ReplicaId>,
+#[derive(Debug, Clone)]
+pub struct UsigNoOpVerifyHalf<D = MemoryDirectory<()>> {
+    ids: D,
 }
 
-impl VerifyHalf for UsigNoOpVerifyHalf {
+impl Default for UsigNoOpVerifyHalf {
+    fn default() -> Self {
+        Self::with_directory(MemoryDirectory::default())
+    }
+}
+
+impl<D: PartyDirectory<()>> UsigNoOpVerifyHalf<D> {
+    pub fn with_directory(directory: D) -> Self {
+        Self { ids: directory }
+    }
+}
+
+impl<D: PartyDirectory<()>> VerifyHalf for UsigNoOpVerifyHalf<D> {
     type Signature = Signature;
     type Attestation = ();
 
@@ -54,29 +129,95 @@ impl VerifyHalf for UsigNoOpVerifyHalf {
         &self,
         id: ReplicaId,
         message: impl AsRef<[u8]>,
-        _signature: &Self::Signature,
+        signature: &Self::Signature,
     ) -> Result<(), UsigError> {
-        if self.ids.contains(&id) {
+        if self.ids.get(id).is_some() {
             let _ = message.as_ref();
+            self.ids.observe(id, signature.counter());
             Ok(())
         } else {
             Err(UsigError::UnknownId(id))
         }
     }
 
-    fn add_remote_party(&mut self, id: ReplicaId, _attestation: Self::Attestation) -> bool {
-        self.ids.insert(id);
-        true
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.ids.precheck(id, signature.counter())
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        MemoryReport {
+            party_registry: self.ids.memory_usage(),
+            ..Default::default()
+        }
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        self.ids.insert(id, attestation)
+    }
+
+    fn remove_remote_party(&mut self, id: ReplicaId) -> bool {
+        self.ids.remove(id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.ids.ids()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.ids.get(remote_usig_id).is_some()
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        Some(self)
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        Some(self)
     }
 }
 
-#[derive(Default, Debug)]
-pub struct UsigNoOp {
+/// A USIG without any cryptography, for tests and simulations
+///
+/// Cloning forks the replica state: the clone has the same counter and remote parties,
+/// see [UsigNoOpSignHalf] for the counter duplication this entails.
+#[derive(Debug, Clone)]
+pub struct UsigNoOp<D = MemoryDirectory<()>> {
     sign_half: UsigNoOpSignHalf,
-    verify_half: UsigNoOpVerifyHalf,
+    verify_half: UsigNoOpVerifyHalf<D>,
 }
 
-impl Usig for UsigNoOp {
+impl Default for UsigNoOp {
+    fn default() -> Self {
+        Self::with_directory(MemoryDirectory::default())
+    }
+}
+
+impl<D: PartyDirectory<()>> UsigNoOp<D> {
+    pub fn with_directory(directory: D) -> Self {
+        Self {
+            sign_half: UsigNoOpSignHalf::default(),
+            verify_half: UsigNoOpVerifyHalf::with_directory(directory),
+        }
+    }
+
+    /// Bind the USIG to its own id
+    pub fn with_local_id(mut self, id: ReplicaId) -> Self {
+        self.sign_half = self.sign_half.with_local_id(id);
+        self
+    }
+}
+
+impl<D: PartyDirectory<()>> Usig for UsigNoOp<D> {
     type Signature = Signature;
     type Attestation = ();
 
@@ -88,6 +229,10 @@ impl Usig for UsigNoOp {
         self.sign_half.attest()
     }
 
+    fn local_id(&self) -> Option<ReplicaId> {
+        self.sign_half.local_id()
+    }
+
     fn verify(
         &self,
         id: ReplicaId,
@@ -97,12 +242,36 @@ impl Usig for UsigNoOp {
         self.verify_half.verify(id, message, signature)
     }
 
-    fn add_remote_party(&mut self, id: ReplicaId, attestation: Self::Attestation) -> bool {
-        self.verify_half.add_remote_party(id, attestation)
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.verify_half.pre_validate(id, signature)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        self.verify_half.memory_usage()
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        self.verify_half.try_add_remote_party(id, attestation)
+    }
+
+    fn remove_remote_party(&mut self, id: ReplicaId) -> bool {
+        self.verify_half.remove_remote_party(id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.verify_half.remote_parties()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.verify_half.contains(remote_usig_id)
     }
 
     type SignHalf = UsigNoOpSignHalf;
-    type VerifyHalf = UsigNoOpVerifyHalf;
+    type VerifyHalf = UsigNoOpVerifyHalf<D>;
 
     fn split(self) -> (Self::SignHalf, Self::VerifyHalf) {
         (self.sign_half, self.verify_half)
@@ -452,4 +621,31 @@ mod tests {
             Err(UsigError::UnknownId(id)) if id == ReplicaId::from_u64(0)
         ));
     }
+
+    #[test]
+    fn bytes_roundtrip() {
+        let signature = Signature::fake(7);
+        assert_eq!(signature.to_bytes(), 7u64.to_be_bytes());
+        let signature = Signature::try_from_bytes(&signature.to_bytes()).unwrap();
+        assert_eq!(signature.counter(), Count(7));
+        assert!(matches!(
+            Signature::try_from_bytes(&[0; 9]),
+            Err(UsigError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn fork() {
+        let mut usig = new_usig();
+        assert!(usig.add_remote_party(ID, ()));
+        usig.sign(MESSAGE_1).unwrap();
+        let mut fork = usig.clone();
+        let signature = fork.sign(MESSAGE_2).unwrap();
+        assert_eq!(signature, usig.sign(MESSAGE_2).unwrap());
+        assert!(fork.verify(ID, MESSAGE_2, &signature).is_ok());
+
+        let (sign, _) = usig.split();
+        assert_eq!(sign.clone(), sign);
+        assert_eq!(sign.next_counter(), Count(2));
+    }
 }
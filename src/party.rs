@@ -0,0 +1,180 @@
+use std::{
+    any::Any,
+    collections::{hash_map::Entry, HashMap},
+    fmt::Debug,
+};
+
+use shared_ids::ReplicaId;
+
+use crate::{Count, MemoryReport, PartyId, UsigError, VerifyHalf};
+
+/// A verify half identifying parties by `Id` on top of one identifying them by [ReplicaId]
+///
+/// Every party added gets the next unused [ReplicaId] of the inner verify half, so any
+/// backend and wrapper can be used in systems identifying their peers by e.g. the hash of a
+/// public key or a UUID. Unknown parties are rejected with [UsigError::UnknownParty],
+/// errors of the inner verify half still name the [ReplicaId], see [PartyVerifyHalf::party].
+#[derive(Debug)]
+pub struct PartyVerifyHalf<V, Id> {
+    inner: V,
+    ids: HashMap<Id, ReplicaId>,
+    next: u64,
+}
+
+impl<V: VerifyHalf, Id: PartyId> PartyVerifyHalf<V, Id> {
+    pub fn new(inner: V) -> Self {
+        Self {
+            inner,
+            ids: HashMap::new(),
+            next: 0,
+        }
+    }
+
+    /// The [ReplicaId] `id` is known as to the inner verify half
+    pub fn replica_id(&self, id: &Id) -> Option<ReplicaId> {
+        self.ids.get(id).copied()
+    }
+
+    /// The party known as `replica_id` to the inner verify half
+    pub fn party(&self, replica_id: ReplicaId) -> Option<&Id> {
+        self.ids
+            .iter()
+            .find(|(_, &known)| known == replica_id)
+            .map(|(id, _)| id)
+    }
+
+    pub fn into_inner(self) -> V {
+        self.inner
+    }
+
+    fn known(&self, id: &Id) -> Result<ReplicaId, UsigError> {
+        self.replica_id(id)
+            .ok_or_else(|| UsigError::UnknownParty(format!("{:?}", id)))
+    }
+}
+
+impl<V: VerifyHalf, Id: PartyId> VerifyHalf<Id> for PartyVerifyHalf<V, Id> {
+    type Signature = V::Signature;
+    type Attestation = V::Attestation;
+
+    fn verify(
+        &self,
+        id: Id,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        self.inner.verify(self.known(&id)?, message, signature)
+    }
+
+    fn pre_validate(&self, id: Id, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.inner.pre_validate(self.known(&id)?, signature)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        let mut report = self.inner.memory_usage();
+        report.party_registry +=
+            size_of_val(&self.ids) + self.ids.capacity() * (size_of::<(Id, ReplicaId)>() + 1);
+        report
+    }
+
+    /// A party added again keeps its [ReplicaId]
+    fn try_add_remote_party(
+        &mut self,
+        id: Id,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        let replica_id = self
+            .replica_id(&id)
+            .unwrap_or(ReplicaId::from_u64(self.next));
+        self.inner.try_add_remote_party(replica_id, attestation)?;
+        if let Entry::Vacant(entry) = self.ids.entry(id) {
+            entry.insert(replica_id);
+            self.next += 1;
+        }
+        Ok(())
+    }
+
+    /// The [ReplicaId] of the party is not reused
+    fn remove_remote_party(&mut self, id: Id) -> bool {
+        match self.ids.remove(&id) {
+            Some(replica_id) => self.inner.remove_remote_party(replica_id),
+            None => false,
+        }
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = Id> + '_ {
+        self.ids.keys().cloned()
+    }
+
+    fn contains(&self, id: Id) -> bool {
+        self.ids.contains_key(&id)
+    }
+
+    fn last_counter(&self, id: Id) -> Option<Count> {
+        self.inner.last_counter(self.replica_id(&id)?)
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any()
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::Hmac;
+    use sha2::Sha256;
+
+    use super::*;
+    use crate::{hmac::UsigHmac, SignHalf, Usig};
+
+    type HmacUsig = UsigHmac<Hmac<Sha256>>;
+
+    /// Peers identified by the hash of their public key
+    type KeyHash = [u8; 32];
+
+    const MESSAGE: &[u8] = b"message";
+
+    fn usig() -> HmacUsig {
+        HmacUsig::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap()
+    }
+
+    #[test]
+    fn key_hashes() {
+        let (alice, bob): (KeyHash, KeyHash) = ([1; 32], [2; 32]);
+        let (mut sign_alice, verify) = usig().split();
+        let (mut sign_bob, _) = usig().split();
+        let mut verify = PartyVerifyHalf::new(verify.enforce_monotonicity());
+        assert!(verify.add_remote_party(alice, sign_alice.attest().unwrap()));
+        assert!(verify.add_remote_party(bob, sign_bob.attest().unwrap()));
+        assert_eq!(verify.replica_id(&bob), Some(ReplicaId::from_u64(1)));
+        assert_eq!(verify.party(ReplicaId::from_u64(1)), Some(&bob));
+
+        let signature = sign_bob.sign(MESSAGE).unwrap();
+        assert!(verify.verify(bob, MESSAGE, &signature).is_ok());
+        assert_eq!(verify.last_counter(bob), Some(Count(0)));
+        assert!(matches!(
+            verify.verify(alice, MESSAGE, &signature),
+            Err(UsigError::InvalidSignature)
+        ));
+        assert!(matches!(
+            verify.verify([3; 32], MESSAGE, &signature),
+            Err(UsigError::UnknownParty(_))
+        ));
+
+        assert!(verify.add_remote_party(alice, sign_alice.attest().unwrap()));
+        assert_eq!(verify.replica_id(&alice), Some(ReplicaId::from_u64(0)));
+        assert!(verify.remove_remote_party(alice));
+        assert!(!verify.contains(alice));
+        assert_eq!(verify.remote_parties().collect::<Vec<_>>(), [bob]);
+    }
+}
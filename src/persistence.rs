@@ -0,0 +1,822 @@
+use std::{
+    any::Any,
+    collections::VecDeque,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use derivative::Derivative;
+use shared_ids::ReplicaId;
+
+use crate::{
+    clock::{Clock, SystemClock},
+    standby::ResumableSignHalf,
+    Count, MemoryReport, SignHalf, Usig, UsigError, VerifyHalf,
+};
+
+/// Durable storage of the next counter value of a sign half
+pub trait CounterStorage {
+    /// Load the stored counter, [None] if nothing was stored yet
+    fn load(&self) -> io::Result<Option<Count>>;
+
+    /// Stage `next` as the counter to resume at, it is durable after [CounterStorage::flush]
+    fn store(&mut self, next: Count) -> io::Result<()>;
+
+    /// Make the last stored counter durable
+    fn flush(&mut self) -> io::Result<()>;
+}
+
+/// Stores the counter in a file as eight big-endian bytes
+///
+/// Flushing replaces the file atomically and syncs it and its directory to disk.
+#[derive(Debug)]
+pub struct FileCounterStorage {
+    path: PathBuf,
+    staged: Option<Count>,
+}
+
+impl FileCounterStorage {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_owned(),
+            staged: None,
+        }
+    }
+}
+
+impl CounterStorage for FileCounterStorage {
+    fn load(&self) -> io::Result<Option<Count>> {
+        match fs::read(&self.path) {
+            Ok(bytes) => {
+                let bytes = bytes.try_into().map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidData, "malformed counter file")
+                })?;
+                Ok(Some(Count(u64::from_be_bytes(bytes))))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn store(&mut self, next: Count) -> io::Result<()> {
+        self.staged = Some(next);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let Some(next) = self.staged else {
+            return Ok(());
+        };
+        let tmp = self.path.with_extension("tmp");
+        let mut file = File::create(&tmp)?;
+        file.write_all(&next.0.to_be_bytes())?;
+        file.sync_all()?;
+        fs::rename(tmp, &self.path)?;
+        // the rename itself is only durable once the directory is synced
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            File::open(parent)?.sync_all()?;
+        }
+        self.staged = None;
+        Ok(())
+    }
+}
+
+/// How many of the latest flushes a [PersistenceLatency] covers
+const LATENCY_WINDOW: usize = 128;
+
+/// The latency of the durable counter writes of a [PersistentSignHalf]
+///
+/// The percentile and the maximum cover the latest flushes only, so they recover once the
+/// storage does. Failed flushes are not counted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PersistenceLatency {
+    /// Flushes since the sign half was opened
+    pub flushes: u64,
+    /// The 99th percentile of the latest flushes
+    pub p99: Duration,
+    /// The slowest of the latest flushes
+    pub max: Duration,
+    /// Whether the sign half is in group commit, see [DegradePolicy]
+    pub degraded: bool,
+}
+
+/// A handle on the [PersistenceLatency] of a [PersistentSignHalf]
+///
+/// Clones share the same latencies, e.g. to report them from another thread while the sign
+/// half signs, or through [crate::remote::UsigServer::with_persistence].
+#[derive(Debug, Clone, Default)]
+pub struct PersistenceMonitor {
+    latencies: Arc<Mutex<Latencies>>,
+}
+
+#[derive(Debug, Default)]
+struct Latencies {
+    latest: VecDeque<Duration>,
+    flushes: u64,
+    degraded: bool,
+}
+
+impl Latencies {
+    fn report(&self) -> PersistenceLatency {
+        let mut latest: Vec<_> = self.latest.iter().copied().collect();
+        latest.sort_unstable();
+        let p99 = latest
+            .len()
+            .checked_sub(1)
+            .map(|last| latest[(last * 99).div_ceil(100)])
+            .unwrap_or_default();
+        PersistenceLatency {
+            flushes: self.flushes,
+            p99,
+            max: latest.last().copied().unwrap_or_default(),
+            degraded: self.degraded,
+        }
+    }
+}
+
+impl PersistenceMonitor {
+    /// The latency of the latest flushes
+    pub fn latency(&self) -> PersistenceLatency {
+        self.latencies.lock().unwrap().report()
+    }
+
+    fn record(&self, latency: Duration) -> PersistenceLatency {
+        let mut latencies = self.latencies.lock().unwrap();
+        if latencies.latest.len() == LATENCY_WINDOW {
+            latencies.latest.pop_front();
+        }
+        latencies.latest.push_back(latency);
+        latencies.flushes += 1;
+        latencies.report()
+    }
+
+    fn set_degraded(&self, degraded: bool) {
+        self.latencies.lock().unwrap().degraded = degraded;
+    }
+}
+
+/// When a [PersistentSignHalf] switches to group commit
+///
+/// A degrading storage device, e.g. a failing disk or a saturated network volume, stalls
+/// every signature on its flush and with it the consensus protocol. While the p99 of the
+/// latest flushes is above `slow_flush`, every flush reserves `group_commit` counters, so
+/// only one in `group_commit` signatures waits for the storage, at the cost of skipping up
+/// to `group_commit` counters on restart. Once the p99 is back below `slow_flush`, the sign
+/// half returns to its own reservation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DegradePolicy {
+    /// The flush latency the storage is considered degraded above
+    pub slow_flush: Duration,
+    /// The counters reserved with every flush while degraded
+    pub group_commit: u64,
+}
+
+/// Called with the latency whenever a [PersistentSignHalf] enters or leaves group commit
+pub type DegradeAlert = Box<dyn FnMut(&PersistenceLatency) + Send>;
+
+/// A sign half that survives restarts without reusing counter values
+///
+/// Counters are reserved on the storage before they are issued, so after a crash, even
+/// on power loss, the sign half resumes behind every signature that may have left it.
+/// By default every signature reserves one counter, i.e. costs one flush. A larger
+/// reservation (see [PersistentSignHalf::with_reservation]) flushes once per block, at the
+/// cost of skipping the unused rest of the block on restart.
+/// Signing fails with [UsigError::Io] if the reservation can not be persisted.
+///
+/// [PersistentSignHalf::close] hands the unused rest of the block back, so a restart
+/// resumes at the next counter. Dropping does the same on a best effort basis, a sign half
+/// that is leaked or whose process dies only skips the rest of the block.
+///
+/// The latency of every flush is measured with the clock, see [PersistentSignHalf::monitor]
+/// and [PersistentSignHalf::with_degrade_policy].
+#[derive(Derivative)]
+#[derivative(Debug(bound = "S: std::fmt::Debug, C: std::fmt::Debug, K: std::fmt::Debug"))]
+pub struct PersistentSignHalf<S, C: CounterStorage, K = SystemClock> {
+    inner: S,
+    counters: Reservation<C>,
+    reservation: u64,
+    clock: K,
+    monitor: PersistenceMonitor,
+    policy: Option<DegradePolicy>,
+    #[derivative(Debug = "ignore")]
+    alert: Option<DegradeAlert>,
+}
+
+/// The storage of a [PersistentSignHalf] with the counters issued and reserved on it
+#[derive(Debug)]
+struct Reservation<C: CounterStorage> {
+    /// Only taken by [PersistentSignHalf::into_inner]
+    storage: Option<C>,
+    next: Count,
+    reserved: Count,
+}
+
+impl<C: CounterStorage> Reservation<C> {
+    fn storage(&mut self) -> &mut C {
+        self.storage
+            .as_mut()
+            .expect("storage is only taken when consumed")
+    }
+
+    /// Store the next counter as the one to resume at, unless it already is
+    fn release(&mut self) -> io::Result<()> {
+        if let Some(storage) = &mut self.storage {
+            if self.next != self.reserved {
+                storage.store(self.next)?;
+                storage.flush()?;
+                self.reserved = self.next;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<C: CounterStorage> Drop for Reservation<C> {
+    fn drop(&mut self) {
+        // a reservation that can not be released is skipped on restart, which is safe
+        let _ = self.release();
+    }
+}
+
+impl<S: ResumableSignHalf, C: CounterStorage> PersistentSignHalf<S, C> {
+    /// Resume `inner` at the counter found in `storage`
+    ///
+    /// Fails with [UsigError::CounterRegression] if `inner` is already past the stored counter.
+    pub fn open(mut inner: S, storage: C) -> Result<Self, UsigError> {
+        let next = storage.load().map_err(UsigError::Io)?.unwrap_or_default();
+        inner.resume_at(next)?;
+        Ok(Self {
+            inner,
+            counters: Reservation {
+                storage: Some(storage),
+                next,
+                reserved: next,
+            },
+            reservation: 1,
+            clock: SystemClock,
+            monitor: PersistenceMonitor::default(),
+            policy: None,
+            alert: None,
+        })
+    }
+}
+
+impl<S: ResumableSignHalf, C: CounterStorage, K: Clock> PersistentSignHalf<S, C, K> {
+    /// Measure the flushes with `clock` instead of the system clock
+    pub fn with_clock<L: Clock>(self, clock: L) -> PersistentSignHalf<S, C, L> {
+        PersistentSignHalf {
+            inner: self.inner,
+            counters: self.counters,
+            reservation: self.reservation,
+            clock,
+            monitor: self.monitor,
+            policy: self.policy,
+            alert: self.alert,
+        }
+    }
+
+    /// Switch to group commit while the storage is degraded, and call `alert` on every switch
+    pub fn with_degrade_policy(
+        mut self,
+        policy: DegradePolicy,
+        alert: impl FnMut(&PersistenceLatency) + Send + 'static,
+    ) -> Self {
+        self.policy = Some(policy);
+        self.alert = Some(Box::new(alert));
+        self
+    }
+
+    /// A handle on the flush latency, it stays valid when the sign half is moved
+    pub fn monitor(&self) -> PersistenceMonitor {
+        self.monitor.clone()
+    }
+
+    /// Reserve `size` counters with every flush, at least one
+    pub fn with_reservation(mut self, size: u64) -> Self {
+        self.reservation = size.max(1);
+        self
+    }
+
+    /// The counter the next signature will have
+    pub fn next_counter(&self) -> Count {
+        self.counters.next
+    }
+
+    /// The first counter not covered by the durable reservation
+    pub fn reserved_until(&self) -> Count {
+        self.counters.reserved
+    }
+
+    /// Hand the unused reservation back to the storage and stop signing
+    ///
+    /// Fails with [UsigError::Io] if the storage fails, the sign half resumes behind the
+    /// reservation on restart then.
+    pub fn close(mut self) -> Result<(), UsigError> {
+        self.counters.release().map_err(UsigError::Io)
+    }
+
+    /// Keeps the reservation, a sign half opened on the storage resumes behind it
+    pub fn into_inner(mut self) -> (S, C) {
+        let storage = self.counters.storage.take();
+        (
+            self.inner,
+            storage.expect("storage is only taken when consumed"),
+        )
+    }
+
+    /// The counters to reserve with the next flush
+    fn block(&self) -> u64 {
+        match self.policy {
+            Some(policy) if self.monitor.latency().degraded => {
+                policy.group_commit.max(self.reservation)
+            }
+            _ => self.reservation,
+        }
+    }
+
+    /// Enter or leave group commit according to the latest flushes
+    fn observe(&mut self, latency: PersistenceLatency) {
+        let Some(policy) = self.policy else {
+            return;
+        };
+        let degraded = latency.p99 > policy.slow_flush;
+        if degraded != latency.degraded {
+            self.monitor.set_degraded(degraded);
+            if let Some(alert) = &mut self.alert {
+                alert(&PersistenceLatency {
+                    degraded,
+                    ..latency
+                });
+            }
+        }
+    }
+}
+
+impl<S: ResumableSignHalf, C: CounterStorage, K: Clock> SignHalf for PersistentSignHalf<S, C, K> {
+    type Signature = S::Signature;
+    type Attestation = S::Attestation;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        if self.counters.next >= self.counters.reserved {
+            let reserved = self
+                .counters
+                .next
+                .checked_add(self.block())
+                .ok_or(UsigError::CounterExhausted)?;
+            let start = self.clock.now();
+            let storage = self.counters.storage();
+            storage
+                .store(reserved)
+                .and_then(|()| storage.flush())
+                .map_err(UsigError::Io)?;
+            self.counters.reserved = reserved;
+            let latency = self.monitor.record(self.clock.now().saturating_sub(start));
+            self.observe(latency);
+        }
+        let signature = self.inner.sign(message)?;
+        self.counters.next += 1;
+        Ok(signature)
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        self.inner.attest()
+    }
+
+    fn local_id(&self) -> Option<ReplicaId> {
+        self.inner.local_id()
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any()
+    }
+
+    /// `None`, signing with the backend directly would skip persisting the counter
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        None
+    }
+}
+
+/// Advancing is not persisted by itself, the next signature reserves from the new counter
+impl<S: ResumableSignHalf, C: CounterStorage, K: Clock> ResumableSignHalf
+    for PersistentSignHalf<S, C, K>
+{
+    fn resume_at(&mut self, next: Count) -> Result<(), UsigError> {
+        if next < self.counters.next {
+            return Err(UsigError::CounterRegression);
+        }
+        self.inner.resume_at(next)?;
+        self.counters.next = next;
+        Ok(())
+    }
+
+    fn next_counter(&self) -> Count {
+        self.counters.next
+    }
+}
+
+/// A USIG signing with a [PersistentSignHalf], e.g. to serve it with [crate::remote::UsigServer]
+#[derive(Derivative)]
+#[derivative(Debug(
+    bound = "PersistentSignHalf<U::SignHalf, C, K>: std::fmt::Debug, U::VerifyHalf: std::fmt::Debug"
+))]
+pub struct PersistentUsig<U: Usig, C: CounterStorage, K = SystemClock> {
+    sign_half: PersistentSignHalf<U::SignHalf, C, K>,
+    verify_half: U::VerifyHalf,
+}
+
+impl<U, C, K> PersistentUsig<U, C, K>
+where
+    U: Usig,
+    U::SignHalf: ResumableSignHalf,
+    C: CounterStorage,
+    K: Clock,
+{
+    /// Join the halves of a split `U`, after wrapping its sign half
+    pub fn new(
+        sign_half: PersistentSignHalf<U::SignHalf, C, K>,
+        verify_half: U::VerifyHalf,
+    ) -> Self {
+        Self {
+            sign_half,
+            verify_half,
+        }
+    }
+
+    pub fn sign_half(&self) -> &PersistentSignHalf<U::SignHalf, C, K> {
+        &self.sign_half
+    }
+
+    /// See [PersistentSignHalf::close]
+    pub fn close(self) -> Result<(), UsigError> {
+        self.sign_half.close()
+    }
+}
+
+impl<U, C, K> Usig for PersistentUsig<U, C, K>
+where
+    U: Usig,
+    U::SignHalf: ResumableSignHalf,
+    C: CounterStorage,
+    K: Clock,
+{
+    type Signature = U::Signature;
+    type Attestation = U::Attestation;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        self.sign_half.sign(message)
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        self.sign_half.attest()
+    }
+
+    fn local_id(&self) -> Option<ReplicaId> {
+        self.sign_half.local_id()
+    }
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        self.verify_half.verify(id, message, signature)
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.verify_half.pre_validate(id, signature)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        self.verify_half.memory_usage()
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        self.verify_half.try_add_remote_party(id, attestation)
+    }
+
+    fn remove_remote_party(&mut self, id: ReplicaId) -> bool {
+        self.verify_half.remove_remote_party(id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.verify_half.remote_parties()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.verify_half.contains(remote_usig_id)
+    }
+
+    type SignHalf = PersistentSignHalf<U::SignHalf, C, K>;
+    type VerifyHalf = U::VerifyHalf;
+
+    fn split(self) -> (Self::SignHalf, Self::VerifyHalf) {
+        (self.sign_half, self.verify_half)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::Hmac;
+    use sha2::Sha256;
+
+    use super::*;
+    use crate::{
+        clock::ManualClock,
+        hmac::{UsigHmac, UsigHmacSignHalf},
+        Counter,
+    };
+
+    const ID: ReplicaId = ReplicaId::first();
+    const KEY: [u8; 16] = [5; 16];
+
+    fn sign_half() -> UsigHmacSignHalf<Hmac<Sha256>> {
+        UsigHmac::<Hmac<Sha256>>::try_new(Box::new(KEY))
+            .unwrap()
+            .split()
+            .0
+    }
+
+    struct FailingStorage;
+
+    impl CounterStorage for FailingStorage {
+        fn load(&self) -> io::Result<Option<Count>> {
+            Ok(None)
+        }
+
+        fn store(&mut self, _: Count) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Err(io::Error::other("disk full"))
+        }
+    }
+
+    /// Every flush takes `delay` on `clock`
+    struct SlowStorage {
+        clock: ManualClock,
+        delay: Arc<Mutex<Duration>>,
+    }
+
+    impl CounterStorage for SlowStorage {
+        fn load(&self) -> io::Result<Option<Count>> {
+            Ok(None)
+        }
+
+        fn store(&mut self, _: Count) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.clock.advance(*self.delay.lock().unwrap());
+            Ok(())
+        }
+    }
+
+    type SlowSignHalf =
+        PersistentSignHalf<UsigHmacSignHalf<Hmac<Sha256>>, SlowStorage, ManualClock>;
+
+    fn slow_sign_half(delay: Duration) -> (SlowSignHalf, Arc<Mutex<Duration>>) {
+        let clock = ManualClock::default();
+        let delay = Arc::new(Mutex::new(delay));
+        let storage = SlowStorage {
+            clock: clock.clone(),
+            delay: delay.clone(),
+        };
+        let sign = PersistentSignHalf::open(sign_half(), storage)
+            .unwrap()
+            .with_clock(clock);
+        (sign, delay)
+    }
+
+    #[test]
+    fn file_storage() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("counter");
+        let mut storage = FileCounterStorage::new(&path);
+        assert_eq!(storage.load().unwrap(), None);
+        storage.store(Count(7)).unwrap();
+        assert_eq!(storage.load().unwrap(), None);
+        storage.flush().unwrap();
+        assert_eq!(
+            FileCounterStorage::new(&path).load().unwrap(),
+            Some(Count(7))
+        );
+
+        fs::write(&path, b"short").unwrap();
+        assert_eq!(
+            storage.load().unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("counter");
+        let (_, mut verify) = UsigHmac::<Hmac<Sha256>>::try_new(Box::new(KEY))
+            .unwrap()
+            .split();
+
+        let mut sign =
+            PersistentSignHalf::open(sign_half(), FileCounterStorage::new(&path)).unwrap();
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+        for _ in 0..3 {
+            sign.sign(b"message").unwrap();
+        }
+        drop(sign);
+
+        let mut sign =
+            PersistentSignHalf::open(sign_half(), FileCounterStorage::new(&path)).unwrap();
+        assert_eq!(sign.next_counter(), Count(3));
+        let signature = sign.sign(b"message").unwrap();
+        assert_eq!(signature.counter(), Count(3));
+        assert!(verify.verify(ID, b"message", &signature).is_ok());
+    }
+
+    #[test]
+    fn reservation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("counter");
+
+        let mut sign = PersistentSignHalf::open(sign_half(), FileCounterStorage::new(&path))
+            .unwrap()
+            .with_reservation(10);
+        sign.sign(b"message").unwrap();
+        assert_eq!(sign.reserved_until(), Count(10));
+        assert_eq!(
+            FileCounterStorage::new(&path).load().unwrap(),
+            Some(Count(10))
+        );
+        for _ in 1..10 {
+            sign.sign(b"message").unwrap();
+        }
+        assert_eq!(sign.reserved_until(), Count(10));
+        sign.sign(b"message").unwrap();
+        assert_eq!(sign.reserved_until(), Count(20));
+        // power loss after 11 signatures, the rest of the block is skipped
+        std::mem::forget(sign);
+
+        let mut sign =
+            PersistentSignHalf::open(sign_half(), FileCounterStorage::new(&path)).unwrap();
+        assert_eq!(sign.sign(b"message").unwrap().counter(), Count(20));
+    }
+
+    #[test]
+    fn close() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("counter");
+        let open = || {
+            PersistentSignHalf::open(sign_half(), FileCounterStorage::new(&path))
+                .unwrap()
+                .with_reservation(10)
+        };
+
+        let mut sign = open();
+        sign.sign(b"message").unwrap();
+        sign.close().unwrap();
+        assert_eq!(
+            FileCounterStorage::new(&path).load().unwrap(),
+            Some(Count(1))
+        );
+
+        // dropping releases the reservation as well
+        let mut sign = open();
+        assert_eq!(sign.sign(b"message").unwrap().counter(), Count(1));
+        drop(sign);
+        let mut sign = open();
+        assert_eq!(sign.sign(b"message").unwrap().counter(), Count(2));
+
+        // a leaked sign half does not keep the next one from starting behind it
+        std::mem::forget(sign);
+        assert_eq!(open().next_counter(), Count(12));
+    }
+
+    #[test]
+    fn close_failure() {
+        let mut sign = PersistentSignHalf::open(sign_half(), FailingStorage).unwrap();
+        sign.resume_at(Count(3)).unwrap();
+        assert!(matches!(sign.close(), Err(UsigError::Io(_))));
+    }
+
+    #[test]
+    fn storage_failure() {
+        let mut sign = PersistentSignHalf::open(sign_half(), FailingStorage).unwrap();
+        assert!(matches!(sign.sign(b"message"), Err(UsigError::Io(_))));
+        assert_eq!(sign.next_counter(), Count(0));
+    }
+
+    #[test]
+    fn advance() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("counter");
+
+        let mut sign = PersistentSignHalf::open(sign_half(), FileCounterStorage::new(&path))
+            .unwrap()
+            .with_reservation(10);
+        sign.sign(b"message").unwrap();
+        assert_eq!(sign.advance_to(Count(25)).unwrap(), 24);
+        assert_eq!(sign.sign(b"message").unwrap().counter(), Count(25));
+        assert_eq!(sign.reserved_until(), Count(35));
+        std::mem::forget(sign);
+
+        let sign = PersistentSignHalf::open(sign_half(), FileCounterStorage::new(&path)).unwrap();
+        assert_eq!(sign.next_counter(), Count(35));
+    }
+
+    #[test]
+    fn latency() {
+        let (mut sign, delay) = slow_sign_half(Duration::from_millis(1));
+        let monitor = sign.monitor();
+        assert_eq!(monitor.latency(), PersistenceLatency::default());
+        for _ in 0..100 {
+            sign.sign(b"message").unwrap();
+        }
+        *delay.lock().unwrap() = Duration::from_millis(50);
+        sign.sign(b"message").unwrap();
+        let latency = monitor.latency();
+        assert_eq!(latency.flushes, 101);
+        assert_eq!(latency.p99, Duration::from_millis(1));
+        assert_eq!(latency.max, Duration::from_millis(50));
+        assert!(!latency.degraded);
+
+        sign.sign(b"message").unwrap();
+        assert_eq!(monitor.latency().p99, Duration::from_millis(50));
+    }
+
+    #[test]
+    fn degrade() {
+        let (sign, delay) = slow_sign_half(Duration::from_millis(20));
+        let alerts = Arc::new(Mutex::new(Vec::new()));
+        let policy = DegradePolicy {
+            slow_flush: Duration::from_millis(10),
+            group_commit: 8,
+        };
+        let mut sign = sign.with_degrade_policy(policy, {
+            let alerts = alerts.clone();
+            move |latency| alerts.lock().unwrap().push(latency.degraded)
+        });
+
+        sign.sign(b"message").unwrap();
+        assert_eq!(*alerts.lock().unwrap(), [true]);
+        assert_eq!(sign.reserved_until(), Count(1));
+        // group commit, one flush for the next 8 signatures
+        sign.sign(b"message").unwrap();
+        assert_eq!(sign.reserved_until(), Count(9));
+
+        // the storage recovers once the slow flushes left the window
+        *delay.lock().unwrap() = Duration::from_millis(1);
+        while sign.monitor().latency().degraded {
+            sign.sign(b"message").unwrap();
+        }
+        assert_eq!(*alerts.lock().unwrap(), [true, false]);
+        while sign.next_counter() < sign.reserved_until() {
+            sign.sign(b"message").unwrap();
+        }
+        sign.sign(b"message").unwrap();
+        assert_eq!(sign.reserved_until(), sign.next_counter());
+    }
+
+    #[test]
+    fn usig() {
+        let dir = tempfile::tempdir().unwrap();
+        let (sign, verify) = UsigHmac::<Hmac<Sha256>>::try_new(Box::new(KEY))
+            .unwrap()
+            .split();
+        let sign =
+            PersistentSignHalf::open(sign, FileCounterStorage::new(dir.path().join("c"))).unwrap();
+        let mut usig = PersistentUsig::<UsigHmac<Hmac<Sha256>>, _>::new(sign, verify);
+        let attestation = usig.attest().unwrap();
+        assert!(usig.add_remote_party(ID, attestation));
+        let signature = usig.sign(b"message").unwrap();
+        assert!(usig.verify(ID, b"message", &signature).is_ok());
+        assert_eq!(usig.sign_half().monitor().latency().flushes, 1);
+        usig.close().unwrap();
+    }
+
+    #[test]
+    fn inner_ahead_of_storage() {
+        let mut inner = sign_half();
+        inner.resume_at(Count(10)).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let storage = FileCounterStorage::new(dir.path().join("counter"));
+        assert!(matches!(
+            PersistentSignHalf::open(inner, storage),
+            Err(UsigError::CounterRegression)
+        ));
+    }
+}
@@ -0,0 +1,352 @@
+use std::{any::Any, collections::HashMap, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use shared_ids::ReplicaId;
+
+use crate::{
+    clock::{Clock, SystemClock},
+    encoding::with_context,
+    Count, Counter, SignHalf, UsigError, VerifyHalf,
+};
+
+/// Context of the timestamp a [ProgressAttestation] signs
+pub const PROGRESS_CONTEXT: &[u8] = b"usig progress";
+
+/// When a [ProgressSignHalf] emits a [ProgressAttestation]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProgressPolicy {
+    /// After this many signatures since the last progress attestation
+    pub every_signatures: Option<u64>,
+    /// After this much time since the last progress attestation
+    pub every: Option<Duration>,
+}
+
+/// A self-signed statement that the USIG reached a counter value at some time
+///
+/// The counter of the statement is the counter of its USIG signature.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgressAttestation<S> {
+    /// When the statement was made, according to the clock of the signer
+    pub timestamp: Duration,
+    pub signature: S,
+}
+
+impl<S: Counter> ProgressAttestation<S> {
+    pub fn counter(&self) -> Count {
+        self.signature.counter()
+    }
+}
+
+fn progress_message(timestamp: Duration) -> Vec<u8> {
+    let mut message = timestamp.as_secs().to_be_bytes().to_vec();
+    message.extend_from_slice(&timestamp.subsec_nanos().to_be_bytes());
+    with_context(PROGRESS_CONTEXT, &message)
+}
+
+/// A sign half that periodically emits [ProgressAttestation]s
+///
+/// Peers archiving them bound how far a later rollback of the counter could go undetected.
+/// The attestations are checked for after every signature and are collected with
+/// [ProgressSignHalf::take_progress]. If one can not be signed, the signature is returned
+/// nevertheless, the error is kept for [ProgressSignHalf::take_progress_error] and the
+/// attestation is retried after the next signature.
+#[derive(Debug)]
+pub struct ProgressSignHalf<S: SignHalf, C = SystemClock> {
+    inner: S,
+    policy: ProgressPolicy,
+    clock: C,
+    since_last: u64,
+    last: Duration,
+    emitted: Vec<ProgressAttestation<S::Signature>>,
+    failed: Option<UsigError>,
+}
+
+impl<S: SignHalf> ProgressSignHalf<S> {
+    pub fn new(inner: S, policy: ProgressPolicy) -> Self {
+        Self::with_clock(inner, policy, SystemClock)
+    }
+}
+
+impl<S: SignHalf, C: Clock> ProgressSignHalf<S, C> {
+    pub fn with_clock(inner: S, policy: ProgressPolicy, clock: C) -> Self {
+        let last = clock.now();
+        Self {
+            inner,
+            policy,
+            clock,
+            since_last: 0,
+            last,
+            emitted: Vec::new(),
+            failed: None,
+        }
+    }
+
+    /// Emit a progress attestation now, regardless of the policy
+    pub fn emit_progress(&mut self) -> Result<(), UsigError> {
+        let timestamp = self.clock.now();
+        let signature = self.inner.sign(progress_message(timestamp))?;
+        self.emitted.push(ProgressAttestation {
+            timestamp,
+            signature,
+        });
+        self.since_last = 0;
+        self.last = timestamp;
+        Ok(())
+    }
+
+    /// Take all progress attestations emitted since the last call
+    pub fn take_progress(&mut self) -> Vec<ProgressAttestation<S::Signature>> {
+        std::mem::take(&mut self.emitted)
+    }
+
+    /// Take the error of the last progress attestation that failed after a signature
+    pub fn take_progress_error(&mut self) -> Option<UsigError> {
+        self.failed.take()
+    }
+
+    fn due(&self) -> bool {
+        let ProgressPolicy {
+            every_signatures,
+            every,
+        } = self.policy;
+        every_signatures.is_some_and(|n| self.since_last >= n)
+            || every.is_some_and(|t| self.clock.now().saturating_sub(self.last) >= t)
+    }
+}
+
+impl<S: SignHalf, C: Clock> SignHalf for ProgressSignHalf<S, C> {
+    type Signature = S::Signature;
+    type Attestation = S::Attestation;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        let signature = self.inner.sign(message)?;
+        self.since_last += 1;
+        if self.due() {
+            if let Err(error) = self.emit_progress() {
+                self.failed = Some(error);
+            }
+        }
+        Ok(signature)
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        self.inner.attest()
+    }
+
+    fn local_id(&self) -> Option<ReplicaId> {
+        self.inner.local_id()
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any()
+    }
+
+    /// `None`, signing with the backend directly would not emit progress
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        None
+    }
+}
+
+/// Archive of the latest verified [ProgressAttestation] of each remote party
+#[derive(Debug, Clone)]
+pub struct ProgressArchive<S> {
+    latest: HashMap<ReplicaId, ProgressAttestation<S>>,
+}
+
+impl<S> Default for ProgressArchive<S> {
+    fn default() -> Self {
+        Self {
+            latest: HashMap::new(),
+        }
+    }
+}
+
+impl<S: Counter> ProgressArchive<S> {
+    /// Verify a progress attestation of `id` and archive it if it is newer than the archived one
+    ///
+    /// Returns whether the archived progress advanced. A statement with a higher timestamp
+    /// but lower counter than the archived one proves a rollback and is reported as
+    /// [UsigError::RemoteRollback].
+    pub fn record<V: VerifyHalf<Signature = S>>(
+        &mut self,
+        verifier: &V,
+        id: ReplicaId,
+        progress: ProgressAttestation<S>,
+    ) -> Result<bool, UsigError> {
+        verifier.verify(
+            id,
+            progress_message(progress.timestamp),
+            &progress.signature,
+        )?;
+        match self.latest.get(&id) {
+            Some(latest) if progress.counter() <= latest.counter() => {
+                if progress.timestamp > latest.timestamp {
+                    Err(UsigError::RemoteRollback(id))
+                } else {
+                    Ok(false)
+                }
+            }
+            _ => {
+                self.latest.insert(id, progress);
+                Ok(true)
+            }
+        }
+    }
+
+    /// The latest archived progress of `id`
+    pub fn latest(&self, id: ReplicaId) -> Option<&ProgressAttestation<S>> {
+        self.latest.get(&id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::Hmac;
+    use sha2::Sha256;
+
+    use super::*;
+    use crate::{clock::ManualClock, hmac::UsigHmac, Usig};
+
+    const ID: ReplicaId = ReplicaId::first();
+    const MESSAGE: &[u8] = b"message";
+
+    fn new_usig() -> UsigHmac<Hmac<Sha256>> {
+        UsigHmac::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap()
+    }
+
+    #[test]
+    fn every_signatures() {
+        let (sign, mut verify) = new_usig().split();
+        let policy = ProgressPolicy {
+            every_signatures: Some(3),
+            every: None,
+        };
+        let mut sign = ProgressSignHalf::new(sign, policy);
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+
+        for _ in 0..7 {
+            sign.sign(MESSAGE).unwrap();
+        }
+        let progress = sign.take_progress();
+        assert_eq!(progress.len(), 2);
+        assert_eq!(progress[0].counter(), Count(3));
+        assert_eq!(progress[1].counter(), Count(7));
+        assert!(sign.take_progress().is_empty());
+
+        let mut archive = ProgressArchive::default();
+        for p in progress {
+            assert!(archive.record(&verify, ID, p).unwrap());
+        }
+        assert_eq!(archive.latest(ID).unwrap().counter(), Count(7));
+    }
+
+    #[test]
+    fn every_duration() {
+        let (sign, _) = new_usig().split();
+        let clock = ManualClock::new(Duration::from_secs(100));
+        let policy = ProgressPolicy {
+            every_signatures: None,
+            every: Some(Duration::from_secs(10)),
+        };
+        let mut sign = ProgressSignHalf::with_clock(sign, policy, clock.clone());
+        sign.sign(MESSAGE).unwrap();
+        assert!(sign.take_progress().is_empty());
+
+        clock.advance(Duration::from_secs(10));
+        sign.sign(MESSAGE).unwrap();
+        let progress = sign.take_progress();
+        assert_eq!(progress.len(), 1);
+        assert_eq!(progress[0].timestamp, Duration::from_secs(110));
+    }
+
+    #[test]
+    fn failed_progress() {
+        /// Fails to sign progress attestations while `failing`
+        struct Flaky<S> {
+            inner: S,
+            failing: bool,
+        }
+
+        impl<S: SignHalf> SignHalf for Flaky<S> {
+            type Signature = S::Signature;
+            type Attestation = S::Attestation;
+
+            fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+                if self.failing
+                    && message
+                        .as_ref()
+                        .starts_with(&with_context(PROGRESS_CONTEXT, &[]))
+                {
+                    return Err(UsigError::SigningFailed);
+                }
+                self.inner.sign(message)
+            }
+
+            fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+                self.inner.attest()
+            }
+        }
+
+        let (sign, mut verify) = new_usig().split();
+        let policy = ProgressPolicy {
+            every_signatures: Some(1),
+            every: None,
+        };
+        let inner = Flaky {
+            inner: sign,
+            failing: true,
+        };
+        let mut sign = ProgressSignHalf::new(inner, policy);
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+
+        let signature = sign.sign(MESSAGE).unwrap();
+        assert!(verify.verify(ID, MESSAGE, &signature).is_ok());
+        assert!(sign.take_progress().is_empty());
+        assert!(matches!(
+            sign.take_progress_error(),
+            Some(UsigError::SigningFailed)
+        ));
+        assert!(sign.take_progress_error().is_none());
+
+        sign.inner.failing = false;
+        sign.sign(MESSAGE).unwrap();
+        assert_eq!(sign.take_progress().len(), 1);
+        assert!(sign.take_progress_error().is_none());
+    }
+
+    #[test]
+    fn rollback_detected() {
+        let key: Box<[u8]> = Box::new(rand::random::<[u8; 16]>());
+        let new_usig = || UsigHmac::<Hmac<Sha256>>::try_new(key.clone()).unwrap();
+        let (mut sign, mut verify) = new_usig().split();
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+        let clock = ManualClock::new(Duration::from_secs(1));
+
+        let mut sign = ProgressSignHalf::with_clock(sign, Default::default(), &clock);
+        for _ in 0..5 {
+            sign.sign(MESSAGE).unwrap();
+        }
+        sign.emit_progress().unwrap();
+        let mut archive = ProgressArchive::default();
+        let before = sign.take_progress().pop().unwrap();
+        assert!(archive.record(&verify, ID, before.clone()).unwrap());
+        assert!(!archive.record(&verify, ID, before).unwrap());
+
+        // the same key restarted from counter zero later on
+        clock.advance(Duration::from_secs(1));
+        let mut rolled_back =
+            ProgressSignHalf::with_clock(new_usig().split().0, Default::default(), &clock);
+        rolled_back.emit_progress().unwrap();
+        let after = rolled_back.take_progress().pop().unwrap();
+        assert!(matches!(
+            archive.record(&verify, ID, after),
+            Err(UsigError::RemoteRollback(ID))
+        ));
+    }
+}
@@ -0,0 +1,229 @@
+use std::{fmt, ops::RangeInclusive};
+
+use hmac::Hmac;
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Sha512};
+use signature::{Signer, Verifier};
+
+use crate::{
+    cmac::UsigCmac,
+    detached::Algorithm,
+    directory::PartyDirectory,
+    hmac::{UsigHmac, VerifyKey},
+    noop::UsigNoOp,
+    persistence::{CounterStorage, PersistentUsig},
+    signature::UsigSignature,
+    siphash::{self, UsigSipHash},
+    tee::UsigSimulatedTee,
+    Usig,
+};
+
+/// The identifier of a USIG algorithm on the wire
+///
+/// Identifiers of the backends of this crate are assigned here. Out-of-tree backends
+/// pick theirs from [AlgorithmId::PRIVATE], which this crate never assigns.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(transparent)]
+pub struct AlgorithmId(pub u16);
+
+impl AlgorithmId {
+    pub const NOOP: Self = Self(0x0001);
+
+    pub const HMAC_SHA256: Self = Self(0x0100);
+    pub const HMAC_SHA512: Self = Self(0x0101);
+    pub const CMAC_AES128: Self = Self(0x0110);
+    pub const CMAC_AES256: Self = Self(0x0111);
+    pub const SIPHASH: Self = Self(0x0120);
+
+    pub const ED25519: Self = Self(0x0200);
+    pub const ED448: Self = Self(0x0201);
+    pub const SECP256K1: Self = Self(0x0210);
+    pub const P256: Self = Self(0x0211);
+    pub const BLS12_381: Self = Self(0x0220);
+
+    /// Identifiers reserved for backends outside of this crate
+    pub const PRIVATE: RangeInclusive<u16> = 0x8000..=0xffff;
+
+    /// All identifiers assigned by this crate with their names
+    pub const ASSIGNED: [(Self, &'static str); 11] = [
+        (Self::NOOP, "noop"),
+        (Self::HMAC_SHA256, "hmac-sha256"),
+        (Self::HMAC_SHA512, "hmac-sha512"),
+        (Self::CMAC_AES128, "cmac-aes128"),
+        (Self::CMAC_AES256, "cmac-aes256"),
+        (Self::SIPHASH, "siphash-2-4"),
+        (Self::ED25519, "ed25519"),
+        (Self::ED448, "ed448"),
+        (Self::SECP256K1, "ecdsa-secp256k1"),
+        (Self::P256, "ecdsa-p256"),
+        (Self::BLS12_381, "bls12-381"),
+    ];
+
+    /// An identifier for an out-of-tree backend, [None] outside of [AlgorithmId::PRIVATE]
+    pub const fn private(id: u16) -> Option<Self> {
+        if id >= *Self::PRIVATE.start() {
+            Some(Self(id))
+        } else {
+            None
+        }
+    }
+
+    pub fn is_private(self) -> bool {
+        Self::PRIVATE.contains(&self.0)
+    }
+
+    /// The name of an identifier assigned by this crate
+    pub fn name(self) -> Option<&'static str> {
+        Self::ASSIGNED
+            .iter()
+            .find(|(id, _)| *id == self)
+            .map(|(_, name)| *name)
+    }
+}
+
+impl fmt::Display for AlgorithmId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "{:#06x}", self.0),
+        }
+    }
+}
+
+/// Backends with an identifier in the [AlgorithmId] registry
+pub trait AlgorithmIdentifier {
+    const ALGORITHM_ID: AlgorithmId;
+}
+
+impl<D> AlgorithmIdentifier for UsigNoOp<D> {
+    const ALGORITHM_ID: AlgorithmId = AlgorithmId::NOOP;
+}
+
+impl<D: PartyDirectory<VerifyKey<Hmac<Sha256>>>> AlgorithmIdentifier for UsigHmac<Hmac<Sha256>, D> {
+    const ALGORITHM_ID: AlgorithmId = AlgorithmId::HMAC_SHA256;
+}
+
+impl<D: PartyDirectory<VerifyKey<Hmac<Sha512>>>> AlgorithmIdentifier for UsigHmac<Hmac<Sha512>, D> {
+    const ALGORITHM_ID: AlgorithmId = AlgorithmId::HMAC_SHA512;
+}
+
+impl<D> AlgorithmIdentifier for UsigCmac<aes::Aes128, D> {
+    const ALGORITHM_ID: AlgorithmId = AlgorithmId::CMAC_AES128;
+}
+
+impl<D> AlgorithmIdentifier for UsigCmac<aes::Aes256, D> {
+    const ALGORITHM_ID: AlgorithmId = AlgorithmId::CMAC_AES256;
+}
+
+impl<D: PartyDirectory<siphash::VerifyKey>> AlgorithmIdentifier for UsigSipHash<D> {
+    const ALGORITHM_ID: AlgorithmId = AlgorithmId::SIPHASH;
+}
+
+macro_rules! signature_algorithm_id {
+    ($signature:ty, $id:expr) => {
+        impl<S, V, D> AlgorithmIdentifier for UsigSignature<$signature, S, V, D>
+        where
+            S: Signer<$signature> + fmt::Debug,
+            V: Verifier<$signature> + Clone + fmt::Debug + for<'a> Deserialize<'a> + Serialize,
+        {
+            const ALGORITHM_ID: AlgorithmId = $id;
+        }
+    };
+}
+
+signature_algorithm_id!(ed25519_dalek::Signature, AlgorithmId::ED25519);
+signature_algorithm_id!(ed448_goldilocks_plus::Signature, AlgorithmId::ED448);
+signature_algorithm_id!(k256::ecdsa::Signature, AlgorithmId::SECP256K1);
+signature_algorithm_id!(p256::ecdsa::Signature, AlgorithmId::P256);
+#[cfg(feature = "bls")]
+signature_algorithm_id!(blst::min_pk::Signature, AlgorithmId::BLS12_381);
+
+/// The simulated TEE signs with plain Ed25519 signatures
+impl AlgorithmIdentifier for UsigSimulatedTee {
+    const ALGORITHM_ID: AlgorithmId = AlgorithmId::ED25519;
+}
+
+/// Persisting the counter does not change the signatures
+impl<U, C, K> AlgorithmIdentifier for PersistentUsig<U, C, K>
+where
+    U: Usig + AlgorithmIdentifier,
+    C: CounterStorage,
+{
+    const ALGORITHM_ID: AlgorithmId = U::ALGORITHM_ID;
+}
+
+impl Algorithm {
+    pub fn id(self) -> AlgorithmId {
+        match self {
+            Algorithm::Ed25519 => AlgorithmId::ED25519,
+            Algorithm::Ed448 => AlgorithmId::ED448,
+            Algorithm::Secp256k1 => AlgorithmId::SECP256K1,
+            Algorithm::P256 => AlgorithmId::P256,
+            #[cfg(feature = "bls")]
+            Algorithm::Bls => AlgorithmId::BLS12_381,
+        }
+    }
+
+    pub fn from_id(id: AlgorithmId) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|algorithm| algorithm.id() == id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::{
+        cmac::{UsigCmacAes128, UsigCmacAes256},
+        signature::UsigEd25519,
+    };
+
+    #[test]
+    fn assigned_ids_are_unique_and_public() {
+        let ids: HashSet<_> = AlgorithmId::ASSIGNED.iter().map(|(id, _)| *id).collect();
+        let names: HashSet<_> = AlgorithmId::ASSIGNED
+            .iter()
+            .map(|(_, name)| *name)
+            .collect();
+        assert_eq!(ids.len(), AlgorithmId::ASSIGNED.len());
+        assert_eq!(names.len(), AlgorithmId::ASSIGNED.len());
+        assert!(ids.iter().all(|id| !id.is_private()));
+    }
+
+    #[test]
+    fn private_range() {
+        assert_eq!(AlgorithmId::private(0x7fff), None);
+        let id = AlgorithmId::private(0x8001).unwrap();
+        assert!(id.is_private());
+        assert_eq!(id.name(), None);
+        assert_eq!(id.to_string(), "0x8001");
+        assert_eq!(AlgorithmId::ED25519.to_string(), "ed25519");
+    }
+
+    #[test]
+    fn backend_ids() {
+        assert_eq!(<UsigNoOp>::ALGORITHM_ID, AlgorithmId::NOOP);
+        assert_eq!(
+            <UsigHmac<Hmac<Sha256>>>::ALGORITHM_ID,
+            AlgorithmId::HMAC_SHA256
+        );
+        assert_eq!(UsigCmacAes128::ALGORITHM_ID, AlgorithmId::CMAC_AES128);
+        assert_eq!(UsigCmacAes256::ALGORITHM_ID, AlgorithmId::CMAC_AES256);
+        assert_eq!(<UsigSipHash>::ALGORITHM_ID, AlgorithmId::SIPHASH);
+        assert_eq!(UsigEd25519::ALGORITHM_ID, AlgorithmId::ED25519);
+        #[cfg(feature = "bls")]
+        assert_eq!(crate::bls::UsigBls::ALGORITHM_ID, AlgorithmId::BLS12_381);
+    }
+
+    #[test]
+    fn detached_algorithms() {
+        for &algorithm in Algorithm::ALL {
+            assert_eq!(Algorithm::from_id(algorithm.id()), Some(algorithm));
+        }
+        assert_eq!(Algorithm::from_id(AlgorithmId::HMAC_SHA256), None);
+    }
+}
@@ -0,0 +1,1173 @@
+//! A USIG running in a separate process or machine, reached over gRPC
+//!
+//! [UsigServer] exposes any [Usig] as the services of `proto/usig.proto`, [RemoteUsig]
+//! is the client implementing [Usig] by calling them. Signatures and attestations are
+//! transferred in the [wire] format, so a client of another backend is rejected with
+//! [UsigError::UnsupportedWireFormat].
+//!
+//! Both services are only served over TLS with client certificates, each accepting the
+//! clients of its own CA: `UsigService` signs and verifies for the replica, `UsigRegistry`
+//! adds and removes parties, so a signing client can not change the registered parties.
+//! One server can host several USIGs, each selected by the certificates of its clients,
+//! see [UsigServer::with_tenant].
+//!
+//! A server of a [crate::persistence::PersistentUsig] reports the latency of its counter writes, see
+//! [UsigServer::with_persistence] and [RemoteSignHalf::try_persistence_latency].
+
+use std::{
+    any::Any, collections::HashMap, fmt::Debug, future::Future, marker::PhantomData, pin::Pin,
+    sync::Arc, sync::Mutex, time::Duration,
+};
+
+use derivative::Derivative;
+use shared_ids::ReplicaId;
+use tokio::{net::TcpListener, runtime::Runtime};
+use tokio_stream::{wrappers::TcpListenerStream, Stream, StreamExt};
+use tonic::{
+    codegen::Bytes,
+    transport::{
+        Certificate, CertificateDer, Channel, ClientTlsConfig, Endpoint, Identity, Server,
+        ServerTlsConfig,
+    },
+    Code, Request, Response, Status, Streaming,
+};
+
+use crate::{
+    persistence::{PersistenceLatency, PersistenceMonitor},
+    registry::AlgorithmIdentifier,
+    wire::{self, WireFormat},
+    Counter, MemoryReport, SignHalf, Usig, UsigError, VerifyHalf,
+};
+
+use proto::{
+    usig_registry_client::UsigRegistryClient,
+    usig_registry_server::{UsigRegistry, UsigRegistryServer},
+    usig_service_client::UsigServiceClient,
+    usig_service_server::{UsigService, UsigServiceServer},
+    AddRemotePartyRequest, AttestResponse, Empty, MemoryUsageResponse, PersistenceLatencyResponse,
+    PreValidateRequest, RemotePartiesResponse, RemoveRemotePartyRequest, RemoveRemotePartyResponse,
+    SignRequest, SignResponse, VerifyRequest,
+};
+
+/// The messages and services generated from `proto/usig.proto`
+pub mod proto {
+    tonic::include_proto!("usig");
+}
+
+/// The status a [UsigError] is reported with, see [from_status] for the way back
+///
+/// The id of an unknown party is sent along in the details.
+fn to_status(error: UsigError) -> Status {
+    let message = error.to_string();
+    match error {
+        UsigError::UnknownId(id) => Status::with_details(
+            Code::NotFound,
+            message,
+            Bytes::copy_from_slice(&id.as_u64().to_be_bytes()),
+        ),
+        UsigError::UnknownParty(_) => Status::not_found(message),
+        UsigError::InvalidSignature => Status::invalid_argument(message),
+        UsigError::RemoteAttestationFailed
+        | UsigError::ParameterMismatch { .. }
+        | UsigError::IdentityMismatch { .. } => Status::failed_precondition(message),
+        UsigError::CounterExhausted => Status::resource_exhausted(message),
+        _ => Status::internal(message),
+    }
+}
+
+/// The error of a failed call, as precise as the status code allows
+fn from_status(status: Status) -> UsigError {
+    match status.code() {
+        Code::NotFound => match <[u8; 8]>::try_from(status.details()) {
+            Ok(id) => UsigError::UnknownId(ReplicaId::from_u64(u64::from_be_bytes(id))),
+            Err(_) => UsigError::UnknownParty(status.message().to_owned()),
+        },
+        Code::InvalidArgument => UsigError::InvalidSignature,
+        Code::FailedPrecondition => UsigError::RemoteAttestationFailed,
+        Code::ResourceExhausted => UsigError::CounterExhausted,
+        _ => UsigError::Backend(Box::new(status)),
+    }
+}
+
+/// The TLS configuration of a [UsigServer]
+#[derive(Debug, Clone)]
+pub struct ServerTls {
+    /// The certificate and key the server authenticates with
+    pub identity: Identity,
+    /// The CA of the clients allowed to sign and verify
+    pub signing_clients: Certificate,
+    /// The CA of the clients allowed to add and remove parties
+    pub registry_clients: Certificate,
+}
+
+/// A [Usig] served by a [UsigServer] with the monitor of its counter writes
+#[derive(Derivative)]
+#[derivative(Debug(bound = "U: Debug"), Clone(bound = ""))]
+struct Tenant<U> {
+    usig: Arc<Mutex<U>>,
+    persistence: Option<PersistenceMonitor>,
+}
+
+impl<U> Tenant<U> {
+    fn new(usig: U, persistence: Option<PersistenceMonitor>) -> Self {
+        Self {
+            usig: Arc::new(Mutex::new(usig)),
+            persistence,
+        }
+    }
+}
+
+/// The tenants of a [UsigServer], by the certificate of their clients
+#[derive(Derivative)]
+#[derivative(Debug(bound = "U: Debug"))]
+struct Tenants<U> {
+    default: Option<Tenant<U>>,
+    by_client: HashMap<CertificateDer<'static>, Tenant<U>>,
+}
+
+impl<U> Tenants<U> {
+    /// The tenant of the client certificate of `request`, else the default one
+    // The error of every call is dictated by tonic
+    #[allow(clippy::result_large_err)]
+    fn select<T>(&self, request: &Request<T>) -> Result<&Tenant<U>, Status> {
+        request
+            .peer_certs()
+            .and_then(|chain| chain.first().and_then(|leaf| self.by_client.get(leaf)))
+            .or(self.default.as_ref())
+            .ok_or_else(|| Status::permission_denied("no USIG for the client certificate"))
+    }
+}
+
+/// Serves a [Usig] over gRPC
+///
+/// Calls are served one at a time per USIG, so counters are assigned in the order the
+/// signing requests are handled.
+///
+/// A server can host several independent USIGs (tenants), each with its own keys, counter
+/// and persistence, see [UsigServer::with_tenant]. The certificate a client presents selects
+/// its tenant on both services, clients of no tenant are served by the USIG passed to
+/// [UsigServer::new], or refused by a [UsigServer::multi_tenant] server.
+#[derive(Derivative)]
+#[derivative(Debug(bound = "U: Debug"))]
+pub struct UsigServer<U> {
+    tenants: Tenants<U>,
+}
+
+impl<U> UsigServer<U>
+where
+    U: Usig + AlgorithmIdentifier + Send + 'static,
+    U::Signature: WireFormat,
+    U::Attestation: WireFormat,
+{
+    pub fn new(usig: U) -> Self {
+        Self {
+            tenants: Tenants {
+                default: Some(Tenant::new(usig, None)),
+                by_client: HashMap::new(),
+            },
+        }
+    }
+
+    /// A server only serving the clients of its tenants
+    pub fn multi_tenant() -> Self {
+        Self {
+            tenants: Tenants {
+                default: None,
+                by_client: HashMap::new(),
+            },
+        }
+    }
+
+    /// Report the latency of the counter writes of the USIG passed to [UsigServer::new]
+    ///
+    /// `monitor` is the one of its sign half, see [crate::persistence::PersistentSignHalf::monitor].
+    /// Does nothing on a [UsigServer::multi_tenant] server.
+    pub fn with_persistence(mut self, monitor: PersistenceMonitor) -> Self {
+        if let Some(tenant) = &mut self.tenants.default {
+            tenant.persistence = Some(monitor);
+        }
+        self
+    }
+
+    /// Serve `usig` to the clients presenting one of the certificates `clients`
+    ///
+    /// The certificates still have to be issued by the CA of the service they call, list
+    /// the ones of the signing clients and of the administrators of the tenant. A client
+    /// already selecting another tenant is moved to this one. `persistence` is reported
+    /// like with [UsigServer::with_persistence].
+    pub fn with_tenant(
+        mut self,
+        usig: U,
+        persistence: Option<PersistenceMonitor>,
+        clients: impl IntoIterator<Item = CertificateDer<'static>>,
+    ) -> Self {
+        let tenant = Tenant::new(usig, persistence);
+        for client in clients {
+            self.tenants.by_client.insert(client, tenant.clone());
+        }
+        self
+    }
+
+    /// Serve `UsigService` on `signing` and `UsigRegistry` on `registry` until one fails
+    ///
+    /// Clients without a certificate of the CA of the respective service are refused.
+    pub async fn serve(
+        self,
+        signing: TcpListener,
+        registry: TcpListener,
+        tls: ServerTls,
+    ) -> Result<(), tonic::transport::Error> {
+        let tenants = Arc::new(self.tenants);
+        let signing = Server::builder()
+            .tls_config(
+                ServerTlsConfig::new()
+                    .identity(tls.identity.clone())
+                    .client_ca_root(tls.signing_clients),
+            )?
+            .add_service(UsigServiceServer::new(SigningService {
+                tenants: tenants.clone(),
+            }))
+            .serve_with_incoming(TcpListenerStream::new(signing));
+        let registry = Server::builder()
+            .tls_config(
+                ServerTlsConfig::new()
+                    .identity(tls.identity)
+                    .client_ca_root(tls.registry_clients),
+            )?
+            .add_service(UsigRegistryServer::new(RegistryService { tenants }))
+            .serve_with_incoming(TcpListenerStream::new(registry));
+        tokio::try_join!(signing, registry)?;
+        Ok(())
+    }
+}
+
+/// The `UsigService` of a [UsigServer]
+struct SigningService<U> {
+    tenants: Arc<Tenants<U>>,
+}
+
+/// The `UsigRegistry` of a [UsigServer]
+struct RegistryService<U> {
+    tenants: Arc<Tenants<U>>,
+}
+
+#[tonic::async_trait]
+impl<U> UsigService for SigningService<U>
+where
+    U: Usig + AlgorithmIdentifier + Send + 'static,
+    U::Signature: WireFormat,
+    U::Attestation: WireFormat,
+{
+    async fn attest(&self, request: Request<Empty>) -> Result<Response<AttestResponse>, Status> {
+        let tenant = self.tenants.select(&request)?;
+        let attestation = tenant.usig.lock().unwrap().attest().map_err(to_status)?;
+        Ok(Response::new(AttestResponse {
+            attestation: wire::encode_attestation::<U>(&attestation),
+        }))
+    }
+
+    async fn sign(&self, request: Request<SignRequest>) -> Result<Response<SignResponse>, Status> {
+        let tenant = self.tenants.select(&request)?;
+        let signature = tenant
+            .usig
+            .lock()
+            .unwrap()
+            .sign(request.into_inner().message)
+            .map_err(to_status)?;
+        Ok(Response::new(SignResponse {
+            signature: wire::encode_signature::<U>(&signature),
+        }))
+    }
+
+    type SignBatchStream = Pin<Box<dyn Stream<Item = Result<SignResponse, Status>> + Send>>;
+
+    /// Receive the whole batch first, then sign it under one lock
+    async fn sign_batch(
+        &self,
+        request: Request<Streaming<SignRequest>>,
+    ) -> Result<Response<Self::SignBatchStream>, Status> {
+        let tenant = self.tenants.select(&request)?;
+        let mut requests = request.into_inner();
+        let mut messages = Vec::new();
+        while let Some(request) = requests.next().await {
+            messages.push(request?.message);
+        }
+        let signatures = tenant
+            .usig
+            .lock()
+            .unwrap()
+            .sign_batch(&messages)
+            .map_err(to_status)?;
+        // The stream items are dictated by tonic
+        #[allow(clippy::result_large_err)]
+        let responses: Vec<_> = signatures
+            .iter()
+            .map(|signature| {
+                Ok(SignResponse {
+                    signature: wire::encode_signature::<U>(signature),
+                })
+            })
+            .collect();
+        Ok(Response::new(Box::pin(tokio_stream::iter(responses))))
+    }
+
+    async fn verify(&self, request: Request<VerifyRequest>) -> Result<Response<Empty>, Status> {
+        let tenant = self.tenants.select(&request)?;
+        let request = request.into_inner();
+        let signature = wire::decode_signature::<U>(&request.signature).map_err(to_status)?;
+        tenant
+            .usig
+            .lock()
+            .unwrap()
+            .verify(
+                ReplicaId::from_u64(request.replica),
+                request.message,
+                &signature,
+            )
+            .map_err(to_status)?;
+        Ok(Response::new(Empty {}))
+    }
+
+    async fn pre_validate(
+        &self,
+        request: Request<PreValidateRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        let tenant = self.tenants.select(&request)?;
+        let request = request.into_inner();
+        let signature = wire::decode_signature::<U>(&request.signature).map_err(to_status)?;
+        tenant
+            .usig
+            .lock()
+            .unwrap()
+            .pre_validate(ReplicaId::from_u64(request.replica), &signature)
+            .map_err(to_status)?;
+        Ok(Response::new(Empty {}))
+    }
+
+    async fn remote_parties(
+        &self,
+        request: Request<Empty>,
+    ) -> Result<Response<RemotePartiesResponse>, Status> {
+        let tenant = self.tenants.select(&request)?;
+        let replicas = tenant
+            .usig
+            .lock()
+            .unwrap()
+            .remote_parties()
+            .map(|id| id.as_u64())
+            .collect();
+        Ok(Response::new(RemotePartiesResponse { replicas }))
+    }
+
+    async fn memory_usage(
+        &self,
+        request: Request<Empty>,
+    ) -> Result<Response<MemoryUsageResponse>, Status> {
+        let tenant = self.tenants.select(&request)?;
+        let report = tenant.usig.lock().unwrap().memory_usage();
+        Ok(Response::new(MemoryUsageResponse {
+            party_registry: report.party_registry as u64,
+            replay_cache: report.replay_cache as u64,
+            evidence: report.evidence as u64,
+        }))
+    }
+
+    async fn persistence_latency(
+        &self,
+        request: Request<Empty>,
+    ) -> Result<Response<PersistenceLatencyResponse>, Status> {
+        let latency = self
+            .tenants
+            .select(&request)?
+            .persistence
+            .as_ref()
+            .ok_or_else(|| Status::unimplemented("the counter is not persisted"))?
+            .latency();
+        let nanos = |duration: Duration| duration.as_nanos().try_into().unwrap_or(u64::MAX);
+        Ok(Response::new(PersistenceLatencyResponse {
+            flushes: latency.flushes,
+            p99_nanos: nanos(latency.p99),
+            max_nanos: nanos(latency.max),
+            degraded: latency.degraded,
+        }))
+    }
+}
+
+#[tonic::async_trait]
+impl<U> UsigRegistry for RegistryService<U>
+where
+    U: Usig + AlgorithmIdentifier + Send + 'static,
+    U::Attestation: WireFormat,
+{
+    async fn add_remote_party(
+        &self,
+        request: Request<AddRemotePartyRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        let tenant = self.tenants.select(&request)?;
+        let request = request.into_inner();
+        let attestation = wire::decode_attestation::<U>(&request.attestation).map_err(to_status)?;
+        tenant
+            .usig
+            .lock()
+            .unwrap()
+            .try_add_remote_party(ReplicaId::from_u64(request.replica), attestation)
+            .map_err(to_status)?;
+        Ok(Response::new(Empty {}))
+    }
+
+    async fn remove_remote_party(
+        &self,
+        request: Request<RemoveRemotePartyRequest>,
+    ) -> Result<Response<RemoveRemotePartyResponse>, Status> {
+        let tenant = self.tenants.select(&request)?;
+        let removed = tenant
+            .usig
+            .lock()
+            .unwrap()
+            .remove_remote_party(ReplicaId::from_u64(request.into_inner().replica));
+        Ok(Response::new(RemoveRemotePartyResponse { removed }))
+    }
+}
+
+/// The TLS configuration of a connection of a [RemoteUsig]
+#[derive(Debug, Clone)]
+pub struct ClientTls {
+    /// The CA the certificate of the server is checked against
+    pub server_ca: Certificate,
+    /// The certificate and key the client authenticates with
+    pub identity: Identity,
+    /// The name the certificate of the server is issued for
+    pub domain: String,
+}
+
+/// A connection to a service of a [UsigServer] with the runtime driving it
+#[derive(Debug, Clone)]
+struct Connection {
+    runtime: Arc<Runtime>,
+    channel: Channel,
+}
+
+impl Connection {
+    fn open(
+        runtime: Arc<Runtime>,
+        endpoint: String,
+        tls: ClientTls,
+    ) -> Result<Self, tonic::transport::Error> {
+        let endpoint = Endpoint::from_shared(endpoint)?.tls_config(
+            ClientTlsConfig::new()
+                .ca_certificate(tls.server_ca)
+                .identity(tls.identity)
+                .domain_name(tls.domain),
+        )?;
+        let channel = runtime.block_on(endpoint.connect())?;
+        Ok(Self { runtime, channel })
+    }
+
+    fn call<T, F: Future<Output = Result<Response<T>, Status>>>(
+        &self,
+        call: impl FnOnce(Channel) -> F,
+    ) -> Result<T, UsigError> {
+        self.runtime
+            .block_on(call(self.channel.clone()))
+            .map(Response::into_inner)
+            .map_err(from_status)
+    }
+}
+
+/// The signing half of a [RemoteUsig]
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct RemoteSignHalf<U> {
+    connection: Connection,
+    #[derivative(Debug = "ignore")]
+    phantom_data: PhantomData<fn() -> U>,
+}
+
+impl<U> RemoteSignHalf<U> {
+    /// The latency of the counter writes of the server
+    ///
+    /// Fails with [UsigError::Backend] if the server does not persist its counter.
+    pub fn try_persistence_latency(&self) -> Result<PersistenceLatency, UsigError> {
+        let response = self.connection.call(|channel| async move {
+            UsigServiceClient::new(channel)
+                .persistence_latency(Empty {})
+                .await
+        })?;
+        Ok(PersistenceLatency {
+            flushes: response.flushes,
+            p99: Duration::from_nanos(response.p99_nanos),
+            max: Duration::from_nanos(response.max_nanos),
+            degraded: response.degraded,
+        })
+    }
+}
+
+impl<U> SignHalf for RemoteSignHalf<U>
+where
+    U: Usig + AlgorithmIdentifier,
+    U::Signature: WireFormat,
+    U::Attestation: WireFormat,
+{
+    type Signature = U::Signature;
+    type Attestation = U::Attestation;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        let request = SignRequest {
+            message: message.as_ref().to_vec(),
+        };
+        let response = self
+            .connection
+            .call(|channel| async move { UsigServiceClient::new(channel).sign(request).await })?;
+        wire::decode_signature::<U>(&response.signature)
+    }
+
+    /// Sign all messages in one streaming call, the server signs them at once
+    ///
+    /// Like [SignHalf::sign_batch], the signatures received before a failure are returned.
+    fn sign_batch(
+        &mut self,
+        messages: &[impl AsRef<[u8]>],
+    ) -> Result<Vec<Self::Signature>, UsigError> {
+        let requests: Vec<_> = messages
+            .iter()
+            .map(|message| SignRequest {
+                message: message.as_ref().to_vec(),
+            })
+            .collect();
+        let mut responses = self.connection.call(|channel| async move {
+            UsigServiceClient::new(channel)
+                .sign_batch(tokio_stream::iter(requests))
+                .await
+        })?;
+        let mut signatures: Vec<U::Signature> = Vec::with_capacity(messages.len());
+        self.connection.runtime.block_on(async {
+            while let Some(response) = responses.next().await {
+                let signature = match response
+                    .map_err(from_status)
+                    .and_then(|response| wire::decode_signature::<U>(&response.signature))
+                {
+                    Ok(signature) => signature,
+                    Err(error) if signatures.is_empty() => return Err(error),
+                    Err(_) => break,
+                };
+                if let Some(last) = signatures.last() {
+                    if last.counter().checked_add(1) != Some(signature.counter()) {
+                        break;
+                    }
+                }
+                signatures.push(signature);
+            }
+            Ok(())
+        })?;
+        signatures.truncate(messages.len());
+        Ok(signatures)
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        let response = self.connection.call(|channel| async move {
+            UsigServiceClient::new(channel).attest(Empty {}).await
+        })?;
+        wire::decode_attestation::<U>(&response.attestation)
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        Some(self)
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        Some(self)
+    }
+}
+
+/// The verifying half of a [RemoteUsig]
+///
+/// Signatures are verified by the server. Remote parties are registered with the server
+/// through the registry connection, see [RemoteUsig::with_registry].
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct RemoteVerifyHalf<U> {
+    connection: Connection,
+    registry: Option<Connection>,
+    #[derivative(Debug = "ignore")]
+    phantom_data: PhantomData<fn() -> U>,
+}
+
+impl<U> RemoteVerifyHalf<U> {
+    fn registry(&self) -> Result<&Connection, UsigError> {
+        self.registry
+            .as_ref()
+            .ok_or_else(|| UsigError::Backend("no connection to the registry".into()))
+    }
+
+    /// The parties registered with the server
+    pub fn try_remote_parties(&self) -> Result<Vec<ReplicaId>, UsigError> {
+        let response = self.connection.call(|channel| async move {
+            UsigServiceClient::new(channel)
+                .remote_parties(Empty {})
+                .await
+        })?;
+        Ok(response
+            .replicas
+            .into_iter()
+            .map(ReplicaId::from_u64)
+            .collect())
+    }
+
+    /// The memory used by the server
+    pub fn try_memory_usage(&self) -> Result<MemoryReport, UsigError> {
+        let report = self.connection.call(|channel| async move {
+            UsigServiceClient::new(channel).memory_usage(Empty {}).await
+        })?;
+        Ok(MemoryReport {
+            party_registry: report.party_registry as usize,
+            replay_cache: report.replay_cache as usize,
+            evidence: report.evidence as usize,
+        })
+    }
+
+    /// Remove `id` from the server, returns whether it was registered
+    pub fn try_remove_remote_party(&mut self, id: ReplicaId) -> Result<bool, UsigError> {
+        let request = RemoveRemotePartyRequest {
+            replica: id.as_u64(),
+        };
+        let response = self.registry()?.call(|channel| async move {
+            UsigRegistryClient::new(channel)
+                .remove_remote_party(request)
+                .await
+        })?;
+        Ok(response.removed)
+    }
+}
+
+impl<U> VerifyHalf for RemoteVerifyHalf<U>
+where
+    U: Usig + AlgorithmIdentifier,
+    U::Signature: WireFormat,
+    U::Attestation: WireFormat,
+{
+    type Signature = U::Signature;
+    type Attestation = U::Attestation;
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        let request = VerifyRequest {
+            replica: id.as_u64(),
+            message: message.as_ref().to_vec(),
+            signature: wire::encode_signature::<U>(signature),
+        };
+        self.connection
+            .call(|channel| async move { UsigServiceClient::new(channel).verify(request).await })?;
+        Ok(())
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        let request = PreValidateRequest {
+            replica: id.as_u64(),
+            signature: wire::encode_signature::<U>(signature),
+        };
+        self.connection.call(|channel| async move {
+            UsigServiceClient::new(channel).pre_validate(request).await
+        })?;
+        Ok(())
+    }
+
+    /// [MemoryReport::default] if the server can not be reached, see
+    /// [RemoteVerifyHalf::try_memory_usage] to tell apart
+    fn memory_usage(&self) -> MemoryReport {
+        self.try_memory_usage().unwrap_or_default()
+    }
+
+    /// Fails if the verify half has no connection to the registry
+    fn try_add_remote_party(
+        &mut self,
+        id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        let request = AddRemotePartyRequest {
+            replica: id.as_u64(),
+            attestation: wire::encode_attestation::<U>(&attestation),
+        };
+        self.registry()?.call(|channel| async move {
+            UsigRegistryClient::new(channel)
+                .add_remote_party(request)
+                .await
+        })?;
+        Ok(())
+    }
+
+    /// `false` if the registry can not be reached, see
+    /// [RemoteVerifyHalf::try_remove_remote_party] to tell apart
+    fn remove_remote_party(&mut self, id: ReplicaId) -> bool {
+        self.try_remove_remote_party(id).unwrap_or(false)
+    }
+
+    /// No parties if the server can not be reached, see
+    /// [RemoteVerifyHalf::try_remote_parties] to tell apart
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.try_remote_parties().unwrap_or_default().into_iter()
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        Some(self)
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        Some(self)
+    }
+}
+
+/// A client of a [UsigServer] serving the backend `U`
+///
+/// Every call blocks on a runtime owned by the client, so it must not be used from
+/// within an async context.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct RemoteUsig<U> {
+    sign_half: RemoteSignHalf<U>,
+    verify_half: RemoteVerifyHalf<U>,
+}
+
+impl<U> RemoteUsig<U> {
+    /// Connect to `UsigService` at `endpoint`, e.g. `https://usig.example:50051`
+    ///
+    /// Parties can only be added or removed after [RemoteUsig::with_registry].
+    pub fn connect(
+        endpoint: impl Into<String>,
+        tls: ClientTls,
+    ) -> Result<Self, tonic::transport::Error> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .expect("runtime can be created");
+        let connection = Connection::open(Arc::new(runtime), endpoint.into(), tls)?;
+        Ok(Self {
+            sign_half: RemoteSignHalf {
+                connection: connection.clone(),
+                phantom_data: PhantomData,
+            },
+            verify_half: RemoteVerifyHalf {
+                connection,
+                registry: None,
+                phantom_data: PhantomData,
+            },
+        })
+    }
+
+    /// Also connect to `UsigRegistry` at `endpoint`, with the identity of an administrator
+    pub fn with_registry(
+        mut self,
+        endpoint: impl Into<String>,
+        tls: ClientTls,
+    ) -> Result<Self, tonic::transport::Error> {
+        let runtime = self.verify_half.connection.runtime.clone();
+        self.verify_half.registry = Some(Connection::open(runtime, endpoint.into(), tls)?);
+        Ok(self)
+    }
+}
+
+impl<U> Usig for RemoteUsig<U>
+where
+    U: Usig + AlgorithmIdentifier,
+    U::Signature: WireFormat,
+    U::Attestation: WireFormat,
+{
+    type Signature = U::Signature;
+    type Attestation = U::Attestation;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        self.sign_half.sign(message)
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        self.sign_half.attest()
+    }
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        self.verify_half.verify(id, message, signature)
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.verify_half.pre_validate(id, signature)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        self.verify_half.memory_usage()
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        self.verify_half.try_add_remote_party(id, attestation)
+    }
+
+    fn remove_remote_party(&mut self, id: ReplicaId) -> bool {
+        self.verify_half.remove_remote_party(id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.verify_half.remote_parties()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.verify_half.contains(remote_usig_id)
+    }
+
+    type SignHalf = RemoteSignHalf<U>;
+    type VerifyHalf = RemoteVerifyHalf<U>;
+
+    fn split(self) -> (Self::SignHalf, Self::VerifyHalf) {
+        (self.sign_half, self.verify_half)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rcgen::{BasicConstraints, CertificateParams, IsCa, KeyPair};
+    use tonic::transport::{Certificate, CertificateDer, Identity};
+
+    use crate as usig;
+    use crate::{
+        persistence::{FileCounterStorage, PersistentSignHalf, PersistentUsig},
+        registry::AlgorithmIdentifier,
+        signature::{new_ed25519, UsigEd25519},
+        tee::UsigSimulatedTee,
+        tests,
+        wire::WireFormat,
+    };
+
+    use super::{ClientTls, RemoteUsig, ServerTls, UsigServer};
+
+    type RemoteEd25519 = RemoteUsig<UsigEd25519>;
+
+    /// A CA and a function issuing certificates signed by it
+    struct Ca {
+        certificate: rcgen::Certificate,
+        key: KeyPair,
+    }
+
+    impl Ca {
+        fn new() -> Self {
+            let mut params = CertificateParams::new(Vec::new()).unwrap();
+            params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            let key = KeyPair::generate().unwrap();
+            let certificate = params.self_signed(&key).unwrap();
+            Self { certificate, key }
+        }
+
+        fn root(&self) -> Certificate {
+            Certificate::from_pem(self.certificate.pem())
+        }
+
+        /// An identity for `name` and its certificate
+        fn issue(&self, name: &str) -> (Identity, CertificateDer<'static>) {
+            let key = KeyPair::generate().unwrap();
+            let certificate = CertificateParams::new(vec![name.to_owned()])
+                .unwrap()
+                .signed_by(&key, &self.certificate, &self.key)
+                .unwrap();
+            let identity = Identity::from_pem(certificate.pem(), key.serialize_pem());
+            (identity, CertificateDer::from(certificate.der().to_vec()))
+        }
+    }
+
+    /// The CAs of the server and of the clients of both services
+    struct Pki {
+        server: Ca,
+        signing: Ca,
+        registry: Ca,
+    }
+
+    impl Pki {
+        fn new() -> Self {
+            Self {
+                server: Ca::new(),
+                signing: Ca::new(),
+                registry: Ca::new(),
+            }
+        }
+
+        /// The TLS configuration of a client issued by `ca`, and its certificate
+        fn client(&self, ca: &Ca, name: &str) -> (ClientTls, CertificateDer<'static>) {
+            let (identity, certificate) = ca.issue(name);
+            let tls = ClientTls {
+                server_ca: self.server.root(),
+                identity,
+                domain: "localhost".to_owned(),
+            };
+            (tls, certificate)
+        }
+    }
+
+    /// The endpoints of a served USIG and the TLS configurations of its clients
+    struct Served {
+        signing: String,
+        registry: String,
+        signer: ClientTls,
+        administrator: ClientTls,
+    }
+
+    /// Serve a fresh USIG on a background thread
+    fn serve() -> Served {
+        serve_usig(&Pki::new(), UsigServer::new(new_ed25519()))
+    }
+
+    /// Serve `server` on a background thread
+    fn serve_usig<U>(pki: &Pki, server: UsigServer<U>) -> Served
+    where
+        U: usig::Usig + AlgorithmIdentifier + Send + 'static,
+        U::Signature: WireFormat,
+        U::Attestation: WireFormat,
+    {
+        let tls = ServerTls {
+            identity: pki.server.issue("localhost").0,
+            signing_clients: pki.signing.root(),
+            registry_clients: pki.registry.root(),
+        };
+        let bind = || {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.set_nonblocking(true).unwrap();
+            listener
+        };
+        let (signing, registry) = (bind(), bind());
+        let endpoint = |listener: &std::net::TcpListener| {
+            format!(
+                "https://localhost:{}",
+                listener.local_addr().unwrap().port()
+            )
+        };
+        let served = Served {
+            signing: endpoint(&signing),
+            registry: endpoint(&registry),
+            signer: pki.client(&pki.signing, "signer").0,
+            administrator: pki.client(&pki.registry, "administrator").0,
+        };
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            runtime.block_on(async {
+                let signing = tokio::net::TcpListener::from_std(signing).unwrap();
+                let registry = tokio::net::TcpListener::from_std(registry).unwrap();
+                server.serve(signing, registry, tls).await.unwrap();
+            });
+        });
+        served
+    }
+
+    /// Serve a fresh USIG and connect to both of its services
+    fn new_remote() -> RemoteEd25519 {
+        let served = serve();
+        RemoteUsig::connect(served.signing, served.signer)
+            .unwrap()
+            .with_registry(served.registry, served.administrator)
+            .unwrap()
+    }
+
+    tests!(new_remote());
+
+    #[test]
+    fn sign_batch() {
+        let (mut sign, mut verify) = new_remote().split();
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+        let messages = [MESSAGE_1, MESSAGE_2, MESSAGE_EMPTY];
+        let signatures = sign.sign_batch(&messages).unwrap();
+        assert_eq!(signatures.len(), 3);
+        for (i, (message, signature)) in messages.iter().zip(&signatures).enumerate() {
+            assert_eq!(signature.counter().0, i as u64);
+            assert!(verify.verify(ID, message, signature).is_ok());
+        }
+    }
+
+    #[test]
+    fn unknown_id() {
+        let (mut sign, verify) = new_remote().split();
+        let other = ReplicaId::from_u64(7);
+        let signature = sign.sign(MESSAGE_1).unwrap();
+        assert!(matches!(
+            verify.verify(other, MESSAGE_1, &signature),
+            Err(UsigError::UnknownId(id)) if id == other
+        ));
+    }
+
+    #[test]
+    fn signer_can_not_change_registry() {
+        let served = serve();
+        let mut usig = RemoteEd25519::connect(&served.signing, served.signer.clone()).unwrap();
+        let attestation = usig.attest().unwrap();
+        assert!(matches!(
+            usig.try_add_remote_party(ID, attestation.clone()),
+            Err(UsigError::Backend(_))
+        ));
+
+        // The registry refuses the certificate of a signing client, during the handshake
+        // or at the first call
+        if let Ok(mut usig) = usig.with_registry(&served.registry, served.signer) {
+            assert!(usig.try_add_remote_party(ID, attestation).is_err());
+            assert!(!usig.contains(ID));
+        }
+    }
+
+    #[test]
+    fn administrator_can_not_sign() {
+        let served = serve();
+        if let Ok(mut usig) = RemoteEd25519::connect(&served.signing, served.administrator) {
+            assert!(usig.sign(MESSAGE_1).is_err());
+        }
+    }
+
+    #[test]
+    fn fallible_registry_calls() {
+        let (_, mut verify) = new_remote().split();
+        assert!(verify.try_remote_parties().unwrap().is_empty());
+        assert!(!verify.try_remove_remote_party(ID).unwrap());
+        assert!(verify.try_memory_usage().is_ok());
+    }
+
+    #[test]
+    fn rejects_other_backend() {
+        let served = serve();
+        let mut usig =
+            RemoteUsig::<crate::signature::UsigP256>::connect(&served.signing, served.signer)
+                .unwrap();
+        assert!(matches!(
+            usig.sign(MESSAGE_1),
+            Err(UsigError::UnsupportedWireFormat {
+                algorithm: usig::registry::AlgorithmId::ED25519,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn persistence_latency() {
+        let dir = tempfile::tempdir().unwrap();
+        let (sign, verify) = new_ed25519().split();
+        let sign =
+            PersistentSignHalf::open(sign, FileCounterStorage::new(dir.path().join("counter")))
+                .unwrap();
+        let monitor = sign.monitor();
+        let usig = PersistentUsig::<UsigEd25519, _>::new(sign, verify);
+        let served = serve_usig(&Pki::new(), UsigServer::new(usig).with_persistence(monitor));
+        let (mut sign, _) = RemoteUsig::<PersistentUsig<UsigEd25519, FileCounterStorage>>::connect(
+            served.signing,
+            served.signer,
+        )
+        .unwrap()
+        .split();
+        sign.sign(MESSAGE_1).unwrap();
+        sign.sign(MESSAGE_2).unwrap();
+        let latency = sign.try_persistence_latency().unwrap();
+        assert_eq!(latency.flushes, 2);
+        assert!(latency.max >= latency.p99);
+        assert!(!latency.degraded);
+    }
+
+    #[test]
+    fn no_persistence_latency() {
+        let (sign, _) = new_remote().split();
+        assert!(matches!(
+            sign.try_persistence_latency(),
+            Err(UsigError::Backend(_))
+        ));
+    }
+
+    #[test]
+    fn tenants() {
+        let pki = Pki::new();
+        let (signer_a, cert_a) = pki.client(&pki.signing, "signer a");
+        let (admin_a, admin_cert_a) = pki.client(&pki.registry, "administrator a");
+        let (signer_b, cert_b) = pki.client(&pki.signing, "signer b");
+        let server = UsigServer::multi_tenant()
+            .with_tenant(new_ed25519(), None, [cert_a, admin_cert_a])
+            .with_tenant(new_ed25519(), None, [cert_b]);
+        let served = serve_usig(&pki, server);
+
+        let mut a = RemoteEd25519::connect(&served.signing, signer_a)
+            .unwrap()
+            .with_registry(&served.registry, admin_a)
+            .unwrap();
+        let mut b = RemoteEd25519::connect(&served.signing, signer_b).unwrap();
+
+        // every tenant has its own key and counter
+        let attestation_a = a.attest().unwrap();
+        assert_ne!(
+            bincode::serialize(&attestation_a).unwrap(),
+            bincode::serialize(&b.attest().unwrap()).unwrap()
+        );
+        assert_eq!(a.sign(MESSAGE_1).unwrap().counter().0, 0);
+        assert_eq!(a.sign(MESSAGE_1).unwrap().counter().0, 1);
+        let signature_b = b.sign(MESSAGE_1).unwrap();
+        assert_eq!(signature_b.counter().0, 0);
+
+        // and its own parties
+        assert!(a.add_remote_party(ID, attestation_a));
+        assert!(a.contains(ID));
+        assert!(!b.contains(ID));
+        assert!(a.verify(ID, MESSAGE_1, &signature_b).is_err());
+
+        // clients of no tenant are refused
+        assert!(matches!(
+            RemoteEd25519::connect(&served.signing, served.signer)
+                .unwrap()
+                .sign(MESSAGE_1),
+            Err(UsigError::Backend(_))
+        ));
+    }
+
+    #[test]
+    fn simulated_tee_tenants() {
+        let dir = tempfile::tempdir().unwrap();
+        let (dir_a, dir_b) = (dir.path().join("a"), dir.path().join("b"));
+        let (key_a, key_b) = (rand::random(), rand::random());
+        let pki = Pki::new();
+        let (signer_a, cert_a) = pki.client(&pki.signing, "signer a");
+        let (signer_b, cert_b) = pki.client(&pki.signing, "signer b");
+        let server = UsigServer::multi_tenant()
+            .with_tenant(
+                UsigSimulatedTee::open(&dir_a, &key_a).unwrap(),
+                None,
+                [cert_a],
+            )
+            .with_tenant(
+                UsigSimulatedTee::open(&dir_b, &key_b).unwrap(),
+                None,
+                [cert_b],
+            );
+        let served = serve_usig(&pki, server);
+
+        let mut a = RemoteUsig::<UsigSimulatedTee>::connect(&served.signing, signer_a).unwrap();
+        let mut b = RemoteUsig::<UsigSimulatedTee>::connect(&served.signing, signer_b).unwrap();
+        a.sign(MESSAGE_1).unwrap();
+        a.sign(MESSAGE_1).unwrap();
+        b.sign(MESSAGE_1).unwrap();
+
+        // every tenant sealed its own counter with its own key
+        let mut a = UsigSimulatedTee::open(&dir_a, &key_a).unwrap();
+        assert_eq!(a.sign(MESSAGE_1).unwrap().counter().0, 2);
+        let mut b = UsigSimulatedTee::open(&dir_b, &key_b).unwrap();
+        assert_eq!(b.sign(MESSAGE_1).unwrap().counter().0, 1);
+        assert!(UsigSimulatedTee::open(&dir_b, &key_a).is_err());
+    }
+}
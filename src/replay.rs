@@ -0,0 +1,352 @@
+use std::{
+    any::Any,
+    fmt::Debug,
+    fs::File,
+    io::{self, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use shared_ids::ReplicaId;
+
+use crate::{Counter, MemoryReport, SignHalf, Usig, UsigError, VerifyHalf};
+
+/// SHA-256 of a signed message
+pub type MessageDigest = [u8; 32];
+
+/// One recorded signature together with the digest of the message it was created for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedSignature<S> {
+    pub digest: MessageDigest,
+    pub signature: S,
+}
+
+/// The attestations and signatures a sign half produced during one run, in order
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Recording<S, A> {
+    pub attestations: Vec<A>,
+    pub signatures: Vec<RecordedSignature<S>>,
+}
+
+impl<S, A> Default for Recording<S, A> {
+    fn default() -> Self {
+        Self {
+            attestations: Vec::new(),
+            signatures: Vec::new(),
+        }
+    }
+}
+
+impl<S: Serialize, A: Serialize> Recording<S, A> {
+    /// Store the recording as a fixture file
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        bincode::serialize_into(&mut writer, self).map_err(io::Error::other)?;
+        writer.flush()
+    }
+}
+
+impl<S: DeserializeOwned, A: DeserializeOwned> Recording<S, A> {
+    /// Load a fixture file stored with [Recording::save]
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        bincode::deserialize_from(BufReader::new(File::open(path)?))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// A sign half recording everything its backend produces
+#[derive(Debug)]
+pub struct RecordingSignHalf<S: SignHalf> {
+    inner: S,
+    recording: Recording<S::Signature, S::Attestation>,
+}
+
+impl<S: SignHalf> RecordingSignHalf<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            recording: Recording::default(),
+        }
+    }
+
+    pub fn recording(&self) -> &Recording<S::Signature, S::Attestation> {
+        &self.recording
+    }
+
+    pub fn into_recording(self) -> Recording<S::Signature, S::Attestation> {
+        self.recording
+    }
+}
+
+impl<S: SignHalf> SignHalf for RecordingSignHalf<S>
+where
+    S::Signature: Clone,
+    S::Attestation: Clone,
+{
+    type Signature = S::Signature;
+    type Attestation = S::Attestation;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        let digest = Sha256::digest(message.as_ref()).into();
+        let signature = self.inner.sign(message)?;
+        self.recording.signatures.push(RecordedSignature {
+            digest,
+            signature: signature.clone(),
+        });
+        Ok(signature)
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        let attestation = self.inner.attest()?;
+        self.recording.attestations.push(attestation.clone());
+        Ok(attestation)
+    }
+
+    fn local_id(&self) -> Option<ReplicaId> {
+        self.inner.local_id()
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any()
+    }
+
+    /// `None`, signatures of the backend would be missing from the recording
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        None
+    }
+}
+
+/// A sign half handing out the signatures of a [Recording] instead of computing them
+///
+/// Gives downstream protocol fuzzers bit-identical executions across runs, even if the
+/// recorded backend is nondeterministic. Signing a different message than the one
+/// recorded at this position, or more messages than recorded, fails with
+/// [UsigError::SigningFailed] because the execution diverged from the recorded one.
+#[derive(Debug)]
+pub struct ReplaySignHalf<S, A> {
+    recording: Recording<S, A>,
+    next_signature: usize,
+    next_attestation: usize,
+}
+
+impl<S, A> ReplaySignHalf<S, A> {
+    pub fn new(recording: Recording<S, A>) -> Self {
+        Self {
+            recording,
+            next_signature: 0,
+            next_attestation: 0,
+        }
+    }
+
+    /// The number of recorded signatures not handed out yet
+    pub fn remaining(&self) -> usize {
+        self.recording.signatures.len() - self.next_signature
+    }
+}
+
+impl<S: Counter + Clone, A: Clone> SignHalf for ReplaySignHalf<S, A> {
+    type Signature = S;
+    type Attestation = A;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        let recorded = self
+            .recording
+            .signatures
+            .get(self.next_signature)
+            .ok_or(UsigError::SigningFailed)?;
+        if recorded.digest != <[u8; 32]>::from(Sha256::digest(message.as_ref())) {
+            return Err(UsigError::SigningFailed);
+        }
+        self.next_signature += 1;
+        Ok(recorded.signature.clone())
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        let attestation = self
+            .recording
+            .attestations
+            .get(self.next_attestation)
+            .ok_or(UsigError::SigningFailed)?;
+        self.next_attestation += 1;
+        Ok(attestation.clone())
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        Some(self)
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        Some(self)
+    }
+}
+
+/// A USIG replaying a [Recording], see [ReplaySignHalf]
+///
+/// Verification is done for real by the verify half of the recorded backend.
+#[derive(Debug)]
+pub struct ReplayUsig<S, A, V> {
+    sign_half: ReplaySignHalf<S, A>,
+    verify_half: V,
+}
+
+impl<S, A, V: VerifyHalf<Signature = S, Attestation = A>> ReplayUsig<S, A, V> {
+    pub fn new(recording: Recording<S, A>, verify_half: V) -> Self {
+        Self {
+            sign_half: ReplaySignHalf::new(recording),
+            verify_half,
+        }
+    }
+}
+
+impl<S, A, V> Usig for ReplayUsig<S, A, V>
+where
+    S: Debug + Counter + Clone,
+    A: Debug + Clone,
+    V: VerifyHalf<Signature = S, Attestation = A>,
+{
+    type Signature = S;
+    type Attestation = A;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        self.sign_half.sign(message)
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        self.sign_half.attest()
+    }
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        self.verify_half.verify(id, message, signature)
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.verify_half.pre_validate(id, signature)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        self.verify_half.memory_usage()
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        self.verify_half.try_add_remote_party(id, attestation)
+    }
+
+    fn remove_remote_party(&mut self, id: ReplicaId) -> bool {
+        self.verify_half.remove_remote_party(id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.verify_half.remote_parties()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.verify_half.contains(remote_usig_id)
+    }
+
+    type SignHalf = ReplaySignHalf<S, A>;
+    type VerifyHalf = V;
+
+    fn split(self) -> (Self::SignHalf, Self::VerifyHalf) {
+        (self.sign_half, self.verify_half)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature::{new_ed25519, UsigEd25519};
+
+    const ID: ReplicaId = ReplicaId::first();
+    const MESSAGES: [&[u8]; 3] = [b"one", b"two", b"three"];
+
+    type Signature = <UsigEd25519 as Usig>::Signature;
+    type Attestation = <UsigEd25519 as Usig>::Attestation;
+    type Ed25519Recording = Recording<Signature, Attestation>;
+    type ReplayEd25519 = ReplayUsig<Signature, Attestation, <UsigEd25519 as Usig>::VerifyHalf>;
+
+    fn record() -> Ed25519Recording {
+        let (sign, _) = new_ed25519().split();
+        let mut sign = RecordingSignHalf::new(sign);
+        sign.attest().unwrap();
+        for message in MESSAGES {
+            sign.sign(message).unwrap();
+        }
+        sign.into_recording()
+    }
+
+    fn replay(recording: Ed25519Recording) -> ReplayEd25519 {
+        ReplayUsig::new(recording, new_ed25519().split().1)
+    }
+
+    #[test]
+    fn identical_runs() {
+        let recording = record();
+        let runs: Vec<Vec<_>> = (0..2)
+            .map(|_| {
+                let mut usig = replay(recording.clone());
+                let attestation = usig.attest().unwrap();
+                assert!(usig.add_remote_party(ID, attestation));
+                MESSAGES
+                    .iter()
+                    .map(|message| {
+                        let signature = usig.sign(message).unwrap();
+                        assert!(usig.verify(ID, message, &signature).is_ok());
+                        bincode::serialize(&signature).unwrap()
+                    })
+                    .collect()
+            })
+            .collect();
+        assert_eq!(runs[0], runs[1]);
+    }
+
+    #[test]
+    fn divergence() {
+        let mut usig = replay(record());
+        usig.sign(MESSAGES[0]).unwrap();
+        assert!(matches!(
+            usig.sign(b"unexpected"),
+            Err(UsigError::SigningFailed)
+        ));
+        usig.sign(MESSAGES[1]).unwrap();
+        usig.sign(MESSAGES[2]).unwrap();
+        assert_eq!(usig.sign_half.remaining(), 0);
+        assert!(matches!(
+            usig.sign(MESSAGES[0]),
+            Err(UsigError::SigningFailed)
+        ));
+    }
+
+    #[test]
+    fn fixture_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("recording");
+        let recording = record();
+        recording.save(&path).unwrap();
+        assert_eq!(
+            bincode::serialize(&Ed25519Recording::load(&path).unwrap()).unwrap(),
+            bincode::serialize(&recording).unwrap()
+        );
+    }
+}
@@ -0,0 +1,341 @@
+use std::{
+    any::Any,
+    collections::{HashMap, HashSet},
+};
+
+use serde::{Deserialize, Serialize};
+use shared_ids::ReplicaId;
+
+use crate::{
+    encoding::with_context, Count, Counter, MemoryReport, SignHalf, UsigError, VerifyHalf,
+};
+
+/// Context of the empty statement a [RevocationNotice] signs
+pub const REVOCATION_CONTEXT: &[u8] = b"usig revocation";
+
+fn revocation_message() -> Vec<u8> {
+    with_context(REVOCATION_CONTEXT, &[])
+}
+
+/// The last statement of a compromised key, declaring all its later signatures invalid
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevocationNotice<S> {
+    pub signature: S,
+}
+
+impl<S: Counter> RevocationNotice<S> {
+    /// The counter burnt by the revocation
+    pub fn final_counter(&self) -> Count {
+        self.signature.counter()
+    }
+}
+
+/// A sign half whose key can be marked as compromised
+///
+/// Once revoked it refuses to sign with [UsigError::Revoked].
+#[derive(Debug)]
+pub struct RevocableSignHalf<S> {
+    inner: S,
+    revoked: bool,
+}
+
+impl<S: SignHalf> RevocableSignHalf<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            revoked: false,
+        }
+    }
+
+    pub fn is_revoked(&self) -> bool {
+        self.revoked
+    }
+
+    /// Mark the key as compromised and produce the notice for the peers
+    pub fn revoke(&mut self) -> Result<RevocationNotice<S::Signature>, UsigError> {
+        if self.revoked {
+            return Err(UsigError::Revoked);
+        }
+        let signature = self.inner.sign(revocation_message())?;
+        self.revoked = true;
+        Ok(RevocationNotice { signature })
+    }
+}
+
+impl<S: SignHalf> SignHalf for RevocableSignHalf<S> {
+    type Signature = S::Signature;
+    type Attestation = S::Attestation;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        if self.revoked {
+            return Err(UsigError::Revoked);
+        }
+        self.inner.sign(message)
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        if self.revoked {
+            return Err(UsigError::Revoked);
+        }
+        self.inner.attest()
+    }
+
+    fn local_id(&self) -> Option<ReplicaId> {
+        self.inner.local_id()
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any()
+    }
+
+    /// `None`, the backend would keep signing after the revocation
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        None
+    }
+}
+
+/// A verify half that honors [RevocationNotice]s
+///
+/// Signatures of a revoked party with a counter after the one of its notice are rejected
+/// with [UsigError::PartyRevoked], the earlier ones are still accepted. The party can not
+/// be registered again until the revocation is lifted out of band with
+/// [RevocationVerifyHalf::lift], and never again with a revoked key.
+#[derive(Debug)]
+pub struct RevocationVerifyHalf<V: VerifyHalf> {
+    inner: V,
+    /// The notices of all revoked keys of each party, the latest last
+    revoked: HashMap<ReplicaId, Vec<RevocationNotice<V::Signature>>>,
+    /// The parties whose latest revocation was lifted
+    lifted: HashSet<ReplicaId>,
+}
+
+impl<V: VerifyHalf> RevocationVerifyHalf<V> {
+    pub fn new(inner: V) -> Self {
+        Self {
+            inner,
+            revoked: HashMap::new(),
+            lifted: HashSet::new(),
+        }
+    }
+
+    /// Verify the revocation notice of `id` and record it
+    pub fn revoke(
+        &mut self,
+        id: ReplicaId,
+        notice: RevocationNotice<V::Signature>,
+    ) -> Result<(), UsigError> {
+        if self.revocation(id).is_some() {
+            return Ok(());
+        }
+        self.inner
+            .verify(id, revocation_message(), &notice.signature)?;
+        self.revoked.entry(id).or_default().push(notice);
+        self.lifted.remove(&id);
+        Ok(())
+    }
+
+    /// Lift the revocation of `id`, e.g. once its operator replaced the compromised key
+    ///
+    /// The revoked key is forgotten, signatures of `id` are only accepted again once a new
+    /// attestation is registered, which is rejected if it attests a revoked key.
+    /// Returns `false` if `id` was not revoked.
+    pub fn lift(&mut self, id: ReplicaId) -> bool {
+        if self.revocation(id).is_none() {
+            return false;
+        }
+        self.inner.remove_remote_party(id);
+        self.lifted.insert(id)
+    }
+
+    /// The recorded revocation of `id`, if its key is revoked and the revocation not lifted
+    pub fn revocation(&self, id: ReplicaId) -> Option<&RevocationNotice<V::Signature>> {
+        if self.lifted.contains(&id) {
+            return None;
+        }
+        self.revoked.get(&id)?.last()
+    }
+
+    fn check_revoked(&self, id: ReplicaId, signature: &V::Signature) -> Result<(), UsigError> {
+        match self.revocation(id) {
+            Some(notice) if signature.counter() > notice.final_counter() => {
+                Err(UsigError::PartyRevoked(id))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<V: VerifyHalf> VerifyHalf for RevocationVerifyHalf<V> {
+    type Signature = V::Signature;
+    type Attestation = V::Attestation;
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        self.check_revoked(id, signature)?;
+        self.inner.verify(id, message, signature)
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.check_revoked(id, signature)?;
+        self.inner.pre_validate(id, signature)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        let mut report = self.inner.memory_usage();
+        report.evidence += size_of_val(&self.revoked)
+            + self.revoked.capacity()
+                * (size_of::<(ReplicaId, Vec<RevocationNotice<V::Signature>>)>() + 1)
+            + self
+                .revoked
+                .values()
+                .map(|notices| notices.capacity() * size_of::<RevocationNotice<V::Signature>>())
+                .sum::<usize>()
+            + size_of_val(&self.lifted)
+            + self.lifted.capacity() * (size_of::<ReplicaId>() + 1);
+        report
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        remote_usig_id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        if self.revocation(remote_usig_id).is_some() {
+            return Err(UsigError::PartyRevoked(remote_usig_id));
+        }
+        self.inner
+            .try_add_remote_party(remote_usig_id, attestation)?;
+        // A key verifying one of the notices of the party is one of its revoked keys
+        let revoked_key = self
+            .revoked
+            .get(&remote_usig_id)
+            .into_iter()
+            .flatten()
+            .any(|notice| {
+                self.inner
+                    .verify(remote_usig_id, revocation_message(), &notice.signature)
+                    .is_ok()
+            });
+        if revoked_key {
+            self.inner.remove_remote_party(remote_usig_id);
+            return Err(UsigError::PartyRevoked(remote_usig_id));
+        }
+        Ok(())
+    }
+
+    /// Revocations are kept, so the party can not be registered again with its revoked key
+    fn remove_remote_party(&mut self, remote_usig_id: ReplicaId) -> bool {
+        self.inner.remove_remote_party(remote_usig_id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.inner.remote_parties()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.inner.contains(remote_usig_id)
+    }
+
+    fn last_counter(&self, id: ReplicaId) -> Option<Count> {
+        self.inner.last_counter(id)
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any()
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{signature::new_ed25519, Usig};
+
+    const ID: ReplicaId = ReplicaId::first();
+    const MESSAGE: &[u8] = b"message";
+
+    #[test]
+    fn revoke() {
+        let (sign, verify) = new_ed25519().split();
+        let mut sign = RevocableSignHalf::new(sign);
+        let mut verify = RevocationVerifyHalf::new(verify);
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+
+        let before = sign.sign(MESSAGE).unwrap();
+        let attestation = sign.attest().unwrap();
+        let notice = sign.revoke().unwrap();
+        assert_eq!(notice.final_counter(), Count(1));
+        assert!(sign.is_revoked());
+        assert!(matches!(sign.sign(MESSAGE), Err(UsigError::Revoked)));
+        assert!(matches!(sign.revoke(), Err(UsigError::Revoked)));
+        assert_eq!(UsigError::Revoked.kind(), crate::ErrorKind::Fatal);
+
+        verify.revoke(ID, notice).unwrap();
+        assert_eq!(verify.revocation(ID).unwrap().final_counter(), Count(1));
+        assert!(verify.verify(ID, MESSAGE, &before).is_ok());
+
+        // Signatures after the notice are rejected before they are verified
+        let (mut other, _) = new_ed25519().split();
+        let after = other.sign_batch(&[MESSAGE; 3]).unwrap().pop().unwrap();
+        assert!(matches!(
+            verify.pre_validate(ID, &after),
+            Err(UsigError::PartyRevoked(ID))
+        ));
+        // Whoever holds the compromised key can not lift the revocation by re-attesting
+        assert!(matches!(
+            verify.try_add_remote_party(ID, attestation.clone()),
+            Err(UsigError::PartyRevoked(ID))
+        ));
+
+        assert!(verify.lift(ID));
+        assert!(verify.revocation(ID).is_none());
+        assert!(!verify.contains(ID));
+        assert!(matches!(
+            verify.try_add_remote_party(ID, attestation),
+            Err(UsigError::PartyRevoked(ID))
+        ));
+        assert!(!verify.contains(ID));
+
+        let (mut replacement, _) = new_ed25519().split();
+        assert!(verify.add_remote_party(ID, replacement.attest().unwrap()));
+        let signature = replacement.sign(MESSAGE).unwrap();
+        assert!(verify.verify(ID, MESSAGE, &signature).is_ok());
+    }
+
+    #[test]
+    fn forged_notice() {
+        let (mut sign, verify) = new_ed25519().split();
+        let (mut other, _) = new_ed25519().split();
+        let mut verify = RevocationVerifyHalf::new(verify);
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+
+        let forged = RevocationNotice {
+            signature: other.sign(revocation_message()).unwrap(),
+        };
+        assert!(matches!(
+            verify.revoke(ID, forged),
+            Err(UsigError::InvalidSignature)
+        ));
+        let signature = sign.sign(MESSAGE).unwrap();
+        assert!(verify.verify(ID, MESSAGE, &signature).is_ok());
+    }
+}
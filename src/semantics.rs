@@ -0,0 +1,117 @@
+use std::cmp::Ordering;
+
+use crate::Count;
+
+/// What counter may follow the last accepted counter of a remote party
+///
+/// Consulted by the verify-side duplicate detection, see [crate::watermarks::HighWatermarks::observe_with].
+/// The first counter of a party is always accepted, the verifier may have joined late.
+pub trait CounterSemantics {
+    fn is_valid_next(&self, last: Count, next: Count) -> bool;
+}
+
+/// Any counter above the last one, gaps are allowed
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Monotonic;
+
+impl CounterSemantics for Monotonic {
+    fn is_valid_next(&self, last: Count, next: Count) -> bool {
+        next > last
+    }
+}
+
+/// Exactly the counter after the last one, for USIGs that never skip a value
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StrictIncrement;
+
+impl CounterSemantics for StrictIncrement {
+    fn is_valid_next(&self, last: Count, next: Count) -> bool {
+        last.checked_add(1) == Some(next)
+    }
+}
+
+/// A counter above the last one, skipping at most one lease
+///
+/// Matches sign halves reserving blocks of `lease` counters, which skip the unused rest of
+/// a block on restart, see [crate::persistence::PersistentSignHalf::with_reservation].
+#[derive(Debug, Clone, Copy)]
+pub struct Leased {
+    pub lease: u64,
+}
+
+impl CounterSemantics for Leased {
+    fn is_valid_next(&self, last: Count, next: Count) -> bool {
+        next > last && next.distance(last) <= self.lease
+    }
+}
+
+/// Counters carrying an epoch in their upper `epoch_bits` bits
+///
+/// Within an epoch the counter increments strictly, a higher epoch may start anywhere,
+/// e.g. when a secondary USIG takes over, see [crate::failover::SECONDARY_EPOCH].
+#[derive(Debug, Clone, Copy)]
+pub struct EpochEmbedded {
+    pub epoch_bits: u32,
+}
+
+impl EpochEmbedded {
+    pub fn epoch(&self, counter: Count) -> u64 {
+        counter
+            .0
+            .checked_shr(u64::BITS.saturating_sub(self.epoch_bits))
+            .unwrap_or(0)
+    }
+}
+
+impl CounterSemantics for EpochEmbedded {
+    fn is_valid_next(&self, last: Count, next: Count) -> bool {
+        match self.epoch(next).cmp(&self.epoch(last)) {
+            Ordering::Greater => true,
+            Ordering::Equal => StrictIncrement.is_valid_next(last, next),
+            Ordering::Less => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::failover::SECONDARY_EPOCH;
+
+    #[test]
+    fn monotonic() {
+        assert!(Monotonic.is_valid_next(Count(3), Count(4)));
+        assert!(Monotonic.is_valid_next(Count(3), Count(10)));
+        assert!(!Monotonic.is_valid_next(Count(3), Count(3)));
+        assert!(!Monotonic.is_valid_next(Count(3), Count(2)));
+    }
+
+    #[test]
+    fn strict_increment() {
+        assert!(StrictIncrement.is_valid_next(Count(3), Count(4)));
+        assert!(!StrictIncrement.is_valid_next(Count(3), Count(5)));
+        assert!(!StrictIncrement.is_valid_next(Count(3), Count(3)));
+        assert!(!StrictIncrement.is_valid_next(Count(u64::MAX), Count(0)));
+    }
+
+    #[test]
+    fn leased() {
+        let leased = Leased { lease: 10 };
+        assert!(leased.is_valid_next(Count(3), Count(4)));
+        assert!(leased.is_valid_next(Count(3), Count(13)));
+        assert!(!leased.is_valid_next(Count(3), Count(14)));
+        assert!(!leased.is_valid_next(Count(3), Count(3)));
+    }
+
+    #[test]
+    fn epoch_embedded() {
+        let epochs = EpochEmbedded { epoch_bits: 1 };
+        assert_eq!(epochs.epoch(Count(SECONDARY_EPOCH + 5)), 1);
+        assert!(epochs.is_valid_next(Count(3), Count(4)));
+        assert!(!epochs.is_valid_next(Count(3), Count(5)));
+        assert!(epochs.is_valid_next(Count(3), Count(SECONDARY_EPOCH)));
+        assert!(epochs.is_valid_next(Count(SECONDARY_EPOCH), Count(SECONDARY_EPOCH + 1)));
+        assert!(!epochs.is_valid_next(Count(SECONDARY_EPOCH), Count(4)));
+        assert_eq!(EpochEmbedded { epoch_bits: 0 }.epoch(Count(u64::MAX)), 0);
+    }
+}
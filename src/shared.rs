@@ -0,0 +1,257 @@
+use std::{
+    any::Any,
+    sync::{Arc, Mutex, RwLock},
+};
+
+use shared_ids::ReplicaId;
+
+use crate::{Count, MemoryReport, SignHalf, UsigError, VerifyHalf};
+
+/// A [SignHalf] that can be moved to a signing thread
+pub trait SendSignHalf: SignHalf<Signature: Send, Attestation: Send> + Send {}
+
+impl<S: SignHalf<Signature: Send, Attestation: Send> + Send> SendSignHalf for S {}
+
+/// A [VerifyHalf] that can be shared between verification threads, see [SharedVerifyHalf]
+pub trait SyncVerifyHalf:
+    VerifyHalf<Signature: Send + Sync, Attestation: Send> + Send + Sync
+{
+}
+
+impl<V: VerifyHalf<Signature: Send + Sync, Attestation: Send> + Send + Sync> SyncVerifyHalf for V {}
+
+/// A sign half several protocol tasks sign with concurrently
+///
+/// [SignHalf] is implemented for `&SharedSignHalf`, so tasks only need a shared reference,
+/// e.g. through an [Arc]. Signing requests are served one at a time, so every counter is
+/// still handed out once. A batch holds the lock, its counters stay consecutive.
+#[derive(Debug)]
+pub struct SharedSignHalf<S> {
+    inner: Mutex<S>,
+}
+
+impl<S: SendSignHalf> SharedSignHalf<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner: Mutex::new(inner),
+        }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner.into_inner().unwrap()
+    }
+}
+
+impl<S: SendSignHalf> SignHalf for &SharedSignHalf<S> {
+    type Signature = S::Signature;
+    type Attestation = S::Attestation;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        self.inner.lock().unwrap().sign(message)
+    }
+
+    fn sign_batch(
+        &mut self,
+        messages: &[impl AsRef<[u8]>],
+    ) -> Result<Vec<Self::Signature>, UsigError> {
+        self.inner.lock().unwrap().sign_batch(messages)
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        self.inner.lock().unwrap().attest()
+    }
+
+    fn local_id(&self) -> Option<ReplicaId> {
+        self.inner.lock().unwrap().local_id()
+    }
+}
+
+/// A verify half shared by several verification workers
+///
+/// Clones refer to the same verify half. Signatures are verified concurrently, adding or
+/// removing a party waits for the running verifications and is seen by all clones.
+#[derive(Debug)]
+pub struct SharedVerifyHalf<V> {
+    inner: Arc<RwLock<V>>,
+}
+
+impl<V> Clone for SharedVerifyHalf<V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<V: SyncVerifyHalf> SharedVerifyHalf<V> {
+    pub fn new(inner: V) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(inner)),
+        }
+    }
+
+    /// The verify half, if no other clone is left
+    pub fn try_into_inner(self) -> Result<V, Self> {
+        Arc::try_unwrap(self.inner)
+            .map(|inner| inner.into_inner().unwrap())
+            .map_err(|inner| Self { inner })
+    }
+}
+
+impl<V: SyncVerifyHalf> VerifyHalf for SharedVerifyHalf<V> {
+    type Signature = V::Signature;
+    type Attestation = V::Attestation;
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        self.inner.read().unwrap().verify(id, message, signature)
+    }
+
+    fn verify_batch(
+        &self,
+        batch: &[(ReplicaId, &[u8], &Self::Signature)],
+    ) -> Vec<Result<(), UsigError>> {
+        self.inner.read().unwrap().verify_batch(batch)
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.inner.read().unwrap().pre_validate(id, signature)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        self.inner.read().unwrap().memory_usage()
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        remote_usig_id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        self.inner
+            .write()
+            .unwrap()
+            .try_add_remote_party(remote_usig_id, attestation)
+    }
+
+    fn remove_remote_party(&mut self, remote_usig_id: ReplicaId) -> bool {
+        self.inner
+            .write()
+            .unwrap()
+            .remove_remote_party(remote_usig_id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        let parties: Vec<_> = self.inner.read().unwrap().remote_parties().collect();
+        parties.into_iter()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.inner.read().unwrap().contains(remote_usig_id)
+    }
+
+    fn last_counter(&self, id: ReplicaId) -> Option<Count> {
+        self.inner.read().unwrap().last_counter(id)
+    }
+
+    /// Only while no other clone is left, the verify half is behind a lock otherwise
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        Arc::get_mut(&mut self.inner)?
+            .get_mut()
+            .unwrap()
+            .as_any_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use hmac::Hmac;
+    use sha2::Sha256;
+
+    use super::*;
+    use crate::{
+        hmac::UsigHmac,
+        noop::UsigNoOp,
+        signature::{new_ed25519, UsigEd25519},
+        standby::ResumableSignHalf,
+        Counter, Usig,
+    };
+
+    type HmacUsig = UsigHmac<Hmac<Sha256>>;
+
+    const ID: ReplicaId = ReplicaId::first();
+    const MESSAGE: &[u8] = b"message";
+
+    fn assert_thread_safe<U: Usig<SignHalf: SendSignHalf, VerifyHalf: SyncVerifyHalf>>(_: U) {}
+
+    #[test]
+    fn backends_are_thread_safe() {
+        assert_thread_safe(HmacUsig::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap());
+        assert_thread_safe::<UsigEd25519>(new_ed25519());
+        assert_thread_safe(UsigNoOp::default());
+    }
+
+    #[test]
+    fn workers() {
+        let usig = HmacUsig::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap();
+        let (mut sign, verify) = usig.split();
+        let mut verify = SharedVerifyHalf::new(verify);
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+        let signatures: Vec<_> = (0..4)
+            .map(|i| sign.sign([i]).map(|signature| (i, signature)).unwrap())
+            .collect();
+
+        thread::scope(|scope| {
+            for (i, signature) in &signatures {
+                let verify = verify.clone();
+                scope.spawn(move || assert!(verify.verify(ID, [*i], signature).is_ok()));
+            }
+        });
+
+        let other = verify.clone();
+        assert!(verify.remove_remote_party(ID));
+        assert!(!other.contains(ID));
+        assert!(verify.try_into_inner().is_err());
+        assert!(other.try_into_inner().is_ok());
+    }
+
+    #[test]
+    fn concurrent_signers() {
+        let usig = HmacUsig::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap();
+        let (sign, mut verify) = usig.split();
+        let sign = SharedSignHalf::new(sign);
+        assert!(verify.add_remote_party(ID, (&sign).attest().unwrap()));
+
+        let mut signatures: Vec<_> = thread::scope(|scope| {
+            let tasks: Vec<_> = (0..4)
+                .map(|_| {
+                    let mut sign = &sign;
+                    scope.spawn(move || {
+                        let mut signatures = vec![sign.sign(MESSAGE).unwrap()];
+                        signatures.extend(sign.sign_batch(&[MESSAGE, MESSAGE]).unwrap());
+                        assert_eq!(signatures[2].counter(), signatures[1].counter() + 1);
+                        signatures
+                    })
+                })
+                .collect();
+            tasks
+                .into_iter()
+                .flat_map(|task| task.join().unwrap())
+                .collect()
+        });
+        signatures.sort_by_key(Counter::counter);
+        for (i, signature) in signatures.iter().enumerate() {
+            assert_eq!(signature.counter(), Count(i as u64));
+            assert!(verify.verify(ID, MESSAGE, signature).is_ok());
+        }
+        assert_eq!(sign.into_inner().next_counter(), Count(12));
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    ffi::OsString,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use shared_ids::ReplicaId;
+
+use crate::{
+    encoding::with_context,
+    lazy::{digest, AttestationDigest},
+    AlgorithmParameters, Attestation, Count, Counter, SignHalf, UsigError, VerifyHalf,
+};
+
+/// Context of the file digest a [Sidecar] signs
+pub const SIDECAR_CONTEXT: &[u8] = b"usig sidecar";
+
+/// Extension appended to the path of a signed file to get the path of its sidecar
+pub const SIDECAR_EXTENSION: &str = "usig";
+
+fn sidecar_message(contents: &[u8]) -> Vec<u8> {
+    with_context(SIDECAR_CONTEXT, &Sha256::digest(contents))
+}
+
+/// The path of the sidecar of `path`, e.g. `config.toml.usig` for `config.toml`
+pub fn sidecar_path(path: impl AsRef<Path>) -> PathBuf {
+    let mut sidecar = OsString::from(path.as_ref().as_os_str());
+    sidecar.push(".");
+    sidecar.push(SIDECAR_EXTENSION);
+    sidecar.into()
+}
+
+/// A detached USIG signature over the contents of a file
+///
+/// Carries everything needed to find the signer's attestation and check it is the
+/// one the signature was made with. The file contents are signed as their SHA-256 digest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sidecar<S> {
+    pub parameters: AlgorithmParameters,
+    pub replica: ReplicaId,
+    pub counter: Count,
+    /// Digest of the signer's attestation
+    pub fingerprint: AttestationDigest,
+    pub signature: S,
+}
+
+impl<S: Counter> Sidecar<S> {
+    /// Sign `contents` as `replica`
+    pub fn create<H: SignHalf<Signature = S, Attestation = Attestation<P>>, P: Serialize>(
+        sign_half: &mut H,
+        replica: ReplicaId,
+        contents: impl AsRef<[u8]>,
+    ) -> Result<Self, UsigError> {
+        let attestation = sign_half.attest()?;
+        let signature = sign_half.sign(sidecar_message(contents.as_ref()))?;
+        Ok(Self {
+            parameters: attestation.parameters.clone(),
+            replica,
+            counter: signature.counter(),
+            fingerprint: digest(&attestation),
+            signature,
+        })
+    }
+
+    /// Check the sidecar against `contents` and the attestation the verifier knows `replica` by
+    pub fn verify<V: VerifyHalf<Signature = S>, P: Serialize>(
+        &self,
+        verifier: &V,
+        attestation: &Attestation<P>,
+        contents: impl AsRef<[u8]>,
+    ) -> Result<(), UsigError> {
+        attestation.check_parameters(&self.parameters)?;
+        if digest(attestation) != self.fingerprint {
+            return Err(UsigError::RemoteAttestationFailed);
+        }
+        if self.signature.counter() != self.counter {
+            return Err(UsigError::InvalidSignature);
+        }
+        verifier.verify(
+            self.replica,
+            sidecar_message(contents.as_ref()),
+            &self.signature,
+        )
+    }
+}
+
+fn invalid_data(error: UsigError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+/// Sign the file at `path` and write its sidecar next to it
+pub fn sign_file<H: SignHalf<Attestation = Attestation<P>>, P: Serialize>(
+    sign_half: &mut H,
+    replica: ReplicaId,
+    path: impl AsRef<Path>,
+) -> io::Result<Sidecar<H::Signature>>
+where
+    H::Signature: Serialize,
+{
+    let contents = fs::read(&path)?;
+    let sidecar = Sidecar::create(sign_half, replica, contents).map_err(invalid_data)?;
+    let bytes = bincode::serialize(&sidecar).map_err(io::Error::other)?;
+    fs::write(sidecar_path(path), bytes)?;
+    Ok(sidecar)
+}
+
+/// Read the sidecar of the file at `path` without verifying it
+pub fn read_sidecar<S: DeserializeOwned>(path: impl AsRef<Path>) -> io::Result<Sidecar<S>> {
+    let bytes = fs::read(sidecar_path(path))?;
+    bincode::deserialize(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Verify the file at `path` against its sidecar
+///
+/// Returns the sidecar so the caller can check the signer and counter.
+/// Verification failures are reported as [io::ErrorKind::InvalidData].
+pub fn verify_file<V: VerifyHalf, P: Serialize>(
+    verifier: &V,
+    attestation: &Attestation<P>,
+    path: impl AsRef<Path>,
+) -> io::Result<Sidecar<V::Signature>>
+where
+    V::Signature: DeserializeOwned,
+{
+    let sidecar = read_sidecar(&path)?;
+    let contents = fs::read(&path)?;
+    sidecar
+        .verify(verifier, attestation, contents)
+        .map_err(invalid_data)?;
+    Ok(sidecar)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{signature::new_ed25519, Usig};
+
+    const ID: ReplicaId = ReplicaId::first();
+
+    #[test]
+    fn path() {
+        assert_eq!(
+            sidecar_path("dir/config.toml"),
+            PathBuf::from("dir/config.toml.usig")
+        );
+    }
+
+    #[test]
+    fn roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("checkpoint");
+        fs::write(&path, b"state").unwrap();
+
+        let (mut sign, mut verify) = new_ed25519().split();
+        let attestation = sign.attest().unwrap();
+        assert!(verify.add_remote_party(ID, attestation.clone()));
+        sign.sign(b"message").unwrap();
+
+        let written = sign_file(&mut sign, ID, &path).unwrap();
+        assert_eq!(written.counter, Count(1));
+        let read = verify_file(&verify, &attestation, &path).unwrap();
+        assert_eq!(read.counter, Count(1));
+        assert_eq!(read.replica, ID);
+
+        fs::write(&path, b"tampered").unwrap();
+        let error = verify_file(&verify, &attestation, &path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn wrong_attestation() {
+        let (mut sign, mut verify) = new_ed25519().split();
+        let (mut other, _) = new_ed25519().split();
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+
+        let sidecar = Sidecar::create(&mut sign, ID, b"state").unwrap();
+        assert!(matches!(
+            sidecar.verify(&verify, &other.attest().unwrap(), b"state"),
+            Err(UsigError::RemoteAttestationFailed)
+        ));
+
+        let mut moved = sidecar;
+        moved.counter = Count(5);
+        assert!(matches!(
+            moved.verify(&verify, &sign.attest().unwrap(), b"state"),
+            Err(UsigError::InvalidSignature)
+        ));
+    }
+}
@@ -1,16 +1,166 @@
-use std::{collections::HashMap, fmt::Debug, marker::PhantomData};
+use std::{any::Any, fmt::Debug, marker::PhantomData};
 
 use derivative::Derivative;
+use ed25519_dalek::hazmat::{raw_sign, ExpandedSecretKey};
 use rand::rngs::OsRng;
 use serde::{Deserialize, Serialize};
 
+use sha2::Sha512;
 use shared_ids::ReplicaId;
-use signature::{Signer, Verifier};
+use signature::{SignatureEncoding, Signer, Verifier};
 use trait_alias_macro::pub_trait_alias_macro;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
-use crate::{Count, Counter, SignHalf, Usig, UsigError, VerifyHalf};
+use crate::{
+    directory::{MemoryDirectory, PartyDirectory},
+    encoding::CounterEncoding,
+    export::{self, ExportKey, ImportLog},
+    standby::ResumableSignHalf,
+    AlgorithmParameters, Attestation, Count, Counter, MemoryReport, SignHalf, Usig, UsigError,
+    VerifyHalf,
+};
 
-pub_trait_alias_macro!(SignatureType = for<'a> Deserialize<'a> + Serialize + Clone + Debug);
+/// Signature types that can describe the [AlgorithmParameters] of their scheme
+pub trait SignatureParameters {
+    fn parameters() -> AlgorithmParameters;
+}
+
+impl SignatureParameters for ed25519_dalek::Signature {
+    fn parameters() -> AlgorithmParameters {
+        AlgorithmParameters {
+            scheme: "Ed25519".to_owned(),
+            output_length: ed25519_dalek::SIGNATURE_LENGTH,
+            pre_hash: None,
+            counter_encoding: CounterEncoding::default(),
+        }
+    }
+}
+
+impl SignatureParameters for k256::ecdsa::Signature {
+    fn parameters() -> AlgorithmParameters {
+        AlgorithmParameters {
+            scheme: "ECDSA secp256k1".to_owned(),
+            output_length: 64,
+            pre_hash: Some("Sha256".to_owned()),
+            counter_encoding: CounterEncoding::default(),
+        }
+    }
+}
+
+impl SignatureParameters for p256::ecdsa::Signature {
+    fn parameters() -> AlgorithmParameters {
+        AlgorithmParameters {
+            scheme: "ECDSA P-256".to_owned(),
+            output_length: 64,
+            pre_hash: Some("Sha256".to_owned()),
+            counter_encoding: CounterEncoding::default(),
+        }
+    }
+}
+
+impl SignatureParameters for ed448_goldilocks_plus::Signature {
+    fn parameters() -> AlgorithmParameters {
+        AlgorithmParameters {
+            scheme: "Ed448".to_owned(),
+            output_length: ed448_goldilocks_plus::SIGNATURE_LENGTH,
+            pre_hash: None,
+            counter_encoding: CounterEncoding::default(),
+        }
+    }
+}
+
+/// An Ed25519 signing key expanding its secret once instead of for every signature
+///
+/// Signs exactly like [ed25519_dalek::SigningKey], which hashes the secret again on every
+/// call. Both the key and its expansion are wiped from memory on drop.
+pub struct Ed25519SigningKey {
+    key: ed25519_dalek::SigningKey,
+    expanded: ExpandedSecretKey,
+}
+
+impl Ed25519SigningKey {
+    pub fn generate() -> Self {
+        ed25519_dalek::SigningKey::generate(&mut OsRng).into()
+    }
+
+    pub fn from_bytes(secret: &ed25519_dalek::SecretKey) -> Self {
+        ed25519_dalek::SigningKey::from_bytes(secret).into()
+    }
+
+    pub fn to_bytes(&self) -> ed25519_dalek::SecretKey {
+        self.key.to_bytes()
+    }
+
+    pub fn verifying_key(&self) -> ed25519_dalek::VerifyingKey {
+        self.key.verifying_key()
+    }
+}
+
+impl From<ed25519_dalek::SigningKey> for Ed25519SigningKey {
+    fn from(key: ed25519_dalek::SigningKey) -> Self {
+        Self {
+            expanded: ExpandedSecretKey::from(key.as_bytes()),
+            key,
+        }
+    }
+}
+
+impl Debug for Ed25519SigningKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Ed25519SigningKey")
+            .field("verifying_key", &self.key.verifying_key())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Signer<ed25519_dalek::Signature> for Ed25519SigningKey {
+    fn try_sign(&self, message: &[u8]) -> Result<ed25519_dalek::Signature, signature::Error> {
+        Ok(raw_sign::<Sha512>(
+            &self.expanded,
+            message,
+            &self.key.verifying_key(),
+        ))
+    }
+}
+
+/// Both fields wipe themselves on drop
+impl ZeroizeOnDrop for Ed25519SigningKey {}
+
+/// Verifying keys that can verify many signatures at once
+///
+/// The default verifies one signature after the other, keys of schemes with a batch
+/// verification override it.
+pub trait BatchVerifier<Q>: Verifier<Q> + Sized {
+    /// Whether all signatures of the batch are valid, without telling which ones are not
+    fn verify_all(batch: &[(&Self, &[u8], &Q)]) -> bool {
+        batch
+            .iter()
+            .all(|(key, message, signature)| key.verify(message, signature).is_ok())
+    }
+}
+
+/// Checks the whole batch with a single multiscalar multiplication
+///
+/// The batch equation is cofactored, so it may accept signatures crafted with small order
+/// components which a single verification rejects. Honest signatures pass either way.
+impl BatchVerifier<ed25519_dalek::Signature> for ed25519_dalek::VerifyingKey {
+    fn verify_all(batch: &[(&Self, &[u8], &ed25519_dalek::Signature)]) -> bool {
+        let messages: Vec<_> = batch.iter().map(|(_, message, _)| *message).collect();
+        let signatures: Vec<_> = batch.iter().map(|(_, _, signature)| **signature).collect();
+        let keys: Vec<_> = batch.iter().map(|(key, _, _)| **key).collect();
+        ed25519_dalek::verify_batch(&messages, &signatures, &keys).is_ok()
+    }
+}
+
+impl BatchVerifier<k256::ecdsa::Signature> for k256::ecdsa::VerifyingKey {}
+
+impl BatchVerifier<p256::ecdsa::Signature> for p256::ecdsa::VerifyingKey {}
+
+impl BatchVerifier<ed448_goldilocks_plus::Signature> for ed448_goldilocks_plus::VerifyingKey {}
+
+pub_trait_alias_macro!(
+    SignatureType = for<'a> Deserialize<'a> + Serialize + Clone + Debug + SignatureParameters
+);
 
 #[derive(Derivative, Deserialize, Serialize)]
 #[serde(bound = "")]
@@ -26,6 +176,37 @@ impl<S: SignatureType> Counter for Signature<S> {
     }
 }
 
+impl<S: SignatureType> Signature<S> {
+    /// The signature of the underlying scheme over the encoded counter and message
+    #[cfg(any(test, feature = "bls"))]
+    pub(crate) fn inner(&self) -> &S {
+        &self.signature
+    }
+}
+
+impl<S: SignatureType + SignatureEncoding> Signature<S> {
+    /// The big endian counter followed by the signature of the scheme, for transports
+    /// not using serde
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let signature = self.signature.to_bytes();
+        let mut bytes = Vec::with_capacity(8 + signature.as_ref().len());
+        bytes.extend_from_slice(&self.counter.to_be_bytes());
+        bytes.extend_from_slice(signature.as_ref());
+        bytes
+    }
+
+    /// Read a signature written by [Signature::to_bytes]
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<Self, UsigError> {
+        let (counter, signature) = bytes
+            .split_first_chunk::<8>()
+            .ok_or(UsigError::InvalidSignature)?;
+        Ok(Self {
+            counter: u64::from_be_bytes(*counter),
+            signature: S::try_from(signature).map_err(|_| UsigError::InvalidSignature)?,
+        })
+    }
+}
+
 #[derive(Derivative)]
 #[derivative(Debug(bound = ""))]
 pub struct UsigSignatureSignHalf<
@@ -36,6 +217,8 @@ pub struct UsigSignatureSignHalf<
     counter: u64,
     private_key: S,
     public_key: V,
+    local_id: Option<ReplicaId>,
+    encoding: CounterEncoding,
     phantom_data: PhantomData<Q>,
 }
 
@@ -50,9 +233,23 @@ impl<
             counter: 0,
             private_key,
             public_key,
-            phantom_data: PhantomData::default(),
+            local_id: None,
+            encoding: CounterEncoding::default(),
+            phantom_data: PhantomData,
         }
     }
+
+    /// Bind the sign half to its own id, which is embedded in its attestation
+    pub fn with_local_id(mut self, id: ReplicaId) -> Self {
+        self.local_id = Some(id);
+        self
+    }
+
+    /// Mix the counter into the signed data as `encoding` describes
+    pub fn with_counter_encoding(mut self, encoding: CounterEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
 }
 
 impl<
@@ -62,39 +259,219 @@ impl<
     > SignHalf for UsigSignatureSignHalf<Q, S, V>
 {
     type Signature = Signature<Q>;
-    type Attestation = V;
+    type Attestation = Attestation<V>;
 
     fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
         let counter = self.counter;
-        self.counter += 1;
-        let mut data = Vec::<u8>::new();
-        data.extend_from_slice(&counter.to_be_bytes());
-        data.extend_from_slice(message.as_ref());
-        let signature = self.private_key.sign(&data);
+        self.counter = counter.checked_add(1).ok_or(UsigError::CounterExhausted)?;
+        let signature = self
+            .private_key
+            .sign(&self.encoding.preimage(counter, message.as_ref()));
         Ok(Signature { counter, signature })
     }
 
+    /// Assigns all counters at once, signing with `S` can not fail
+    fn sign_batch(
+        &mut self,
+        messages: &[impl AsRef<[u8]>],
+    ) -> Result<Vec<Self::Signature>, UsigError> {
+        let first = self.counter;
+        self.counter = first
+            .checked_add(messages.len() as u64)
+            .ok_or(UsigError::CounterExhausted)?;
+        Ok((first..)
+            .zip(messages)
+            .map(|(counter, message)| Signature {
+                counter,
+                signature: self
+                    .private_key
+                    .sign(&self.encoding.preimage(counter, message.as_ref())),
+            })
+            .collect())
+    }
+
     fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
-        Ok(self.public_key.clone())
+        let mut attestation = Attestation {
+            parameters: AlgorithmParameters {
+                counter_encoding: self.encoding,
+                ..Q::parameters()
+            },
+            signer: self.local_id,
+            payload: self.public_key.clone(),
+            binding: Vec::new(),
+        };
+        let binding = self
+            .private_key
+            .try_sign(&attestation.bound_message()?)
+            .map_err(|_| UsigError::SigningFailed)?;
+        attestation.binding = bincode::serialize(&binding)?;
+        Ok(attestation)
+    }
+
+    fn local_id(&self) -> Option<ReplicaId> {
+        self.local_id
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        Some(self)
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        Some(self)
     }
 }
 
-#[derive(Derivative)]
-#[derivative(Debug(bound = ""), Default(bound = ""))]
+/// The exported state of an Ed25519 [UsigSignatureSignHalf]
+#[derive(Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+struct Ed25519State {
+    secret: [u8; 32],
+    counter: u64,
+    #[zeroize(skip)]
+    local_id: Option<ReplicaId>,
+    #[zeroize(skip)]
+    encoding: CounterEncoding,
+}
+
+impl
+    UsigSignatureSignHalf<ed25519_dalek::Signature, Ed25519SigningKey, ed25519_dalek::VerifyingKey>
+{
+    /// Encrypt the key and counter with `key` to move the sign half to another host
+    ///
+    /// The sign half is consumed, so it can not sign with a counter the imported one will
+    /// use again. The export carries a random [export::TransferId], so it can only be
+    /// imported once per [ImportLog].
+    pub fn export_state(self, key: &ExportKey) -> Result<Vec<u8>, UsigError> {
+        let state = Ed25519State {
+            secret: self.private_key.to_bytes(),
+            counter: self.counter,
+            local_id: self.local_id,
+            encoding: self.encoding,
+        };
+        export::seal(key, &ed25519_dalek::Signature::parameters().scheme, &state)
+    }
+
+    /// Restore a sign half exported with [UsigSignatureSignHalf::export_state]
+    ///
+    /// Fails with [UsigError::Io] if the state was not exported with `key` for Ed25519
+    /// or `log` recorded its import before.
+    pub fn import_state(
+        state: &[u8],
+        key: &ExportKey,
+        log: &mut impl ImportLog,
+    ) -> Result<Self, UsigError> {
+        let state: Ed25519State = export::open(
+            key,
+            &ed25519_dalek::Signature::parameters().scheme,
+            state,
+            log,
+        )?;
+        let private_key = Ed25519SigningKey::from_bytes(&state.secret);
+        Ok(Self {
+            counter: state.counter,
+            public_key: private_key.verifying_key(),
+            private_key,
+            local_id: state.local_id,
+            encoding: state.encoding,
+            phantom_data: PhantomData,
+        })
+    }
+}
+
+impl<
+        Q: SignatureType,
+        S: Signer<Q> + Debug,
+        V: Verifier<Q> + Clone + Debug + for<'a> Deserialize<'a> + Serialize,
+    > ResumableSignHalf for UsigSignatureSignHalf<Q, S, V>
+{
+    fn resume_at(&mut self, next: Count) -> Result<(), UsigError> {
+        if next.0 < self.counter {
+            return Err(UsigError::CounterRegression);
+        }
+        self.counter = next.0;
+        Ok(())
+    }
+
+    fn next_counter(&self) -> Count {
+        Count(self.counter)
+    }
+}
 
+#[derive(Derivative)]
+#[derivative(Debug(bound = "D: Debug"))]
 pub struct UsigSignatureVerifyHalf<
     Q: SignatureType,
     V: Verifier<Q> + Clone + Debug + for<'a> Deserialize<'a> + Serialize,
+    D = MemoryDirectory<V>,
 > {
-    other_keys: HashMap<ReplicaId, V>,
-    phantom_data: PhantomData<Q>,
+    other_keys: D,
+    encoding: CounterEncoding,
+    require_signer: bool,
+    phantom_data: PhantomData<(Q, V)>,
+}
+
+impl<Q: SignatureType, V: Verifier<Q> + Clone + Debug + for<'a> Deserialize<'a> + Serialize> Default
+    for UsigSignatureVerifyHalf<Q, V>
+{
+    fn default() -> Self {
+        Self::with_directory(MemoryDirectory::default())
+    }
 }
 
-impl<Q: SignatureType, V: Verifier<Q> + Clone + Debug + for<'a> Deserialize<'a> + Serialize>
-    VerifyHalf for UsigSignatureVerifyHalf<Q, V>
+impl<
+        Q: SignatureType,
+        V: Verifier<Q> + Clone + Debug + for<'a> Deserialize<'a> + Serialize,
+        D: PartyDirectory<V>,
+    > UsigSignatureVerifyHalf<Q, V, D>
+{
+    pub fn with_directory(directory: D) -> Self {
+        Self {
+            other_keys: directory,
+            encoding: CounterEncoding::default(),
+            require_signer: false,
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Expect the counter encoding remote parties are attested with
+    pub fn with_counter_encoding(mut self, encoding: CounterEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Reject attestations that are not bound to the id they are added under
+    ///
+    /// Remote parties have to be constructed with their local id then.
+    pub fn require_signer(mut self) -> Self {
+        self.require_signer = true;
+        self
+    }
+
+    #[cfg(feature = "bls")]
+    pub(crate) fn counter_encoding(&self) -> CounterEncoding {
+        self.encoding
+    }
+
+    /// The public key registered for `id`
+    #[cfg(feature = "bls")]
+    pub(crate) fn remote_key(&self, id: ReplicaId) -> Option<std::borrow::Cow<'_, V>> {
+        self.other_keys.get(id)
+    }
+}
+
+impl<
+        Q: SignatureType,
+        V: BatchVerifier<Q> + Clone + Debug + for<'a> Deserialize<'a> + Serialize,
+        D: PartyDirectory<V>,
+    > VerifyHalf for UsigSignatureVerifyHalf<Q, V, D>
 {
     type Signature = Signature<Q>;
-    type Attestation = V;
+    type Attestation = Attestation<V>;
 
     fn verify(
         &self,
@@ -102,35 +479,126 @@ impl<Q: SignatureType, V: Verifier<Q> + Clone + Debug + for<'a> Deserialize<'a>
         message: impl AsRef<[u8]>,
         signature: &Self::Signature,
     ) -> Result<(), UsigError> {
-        if let Some(key) = self.other_keys.get(&id) {
-            let mut data = Vec::<u8>::new();
-            data.extend_from_slice(&signature.counter.to_be_bytes());
-            data.extend_from_slice(message.as_ref());
-
+        if let Some(key) = self.other_keys.get(id) {
+            let data = self.encoding.preimage(signature.counter, message.as_ref());
             key.verify(&data, &signature.signature)
-                .is_ok()
-                .then_some(())
-                .ok_or(UsigError::InvalidSignature)
+                .map_err(|_| UsigError::InvalidSignature)?;
+            self.other_keys.observe(id, signature.counter());
+            Ok(())
         } else {
             Err(UsigError::UnknownId(id))
         }
     }
 
-    fn add_remote_party(&mut self, id: ReplicaId, attestation: Self::Attestation) -> bool {
-        self.other_keys.insert(id, attestation);
-        true
+    fn verify_batch(
+        &self,
+        batch: &[(ReplicaId, &[u8], &Self::Signature)],
+    ) -> Vec<Result<(), UsigError>> {
+        let keys: Vec<_> = batch
+            .iter()
+            .map(|(id, _, _)| self.other_keys.get(*id))
+            .collect();
+        let data: Vec<_> = batch
+            .iter()
+            .map(|(_, message, signature)| self.encoding.preimage(signature.counter, message))
+            .collect();
+        let known: Vec<_> = batch
+            .iter()
+            .zip(&keys)
+            .zip(&data)
+            .filter_map(|(((_, _, signature), key), data)| {
+                Some((key.as_deref()?, data.as_slice(), &signature.signature))
+            })
+            .collect();
+        if !V::verify_all(&known) {
+            // verify one after the other to find the invalid signatures
+            return batch
+                .iter()
+                .map(|(id, message, signature)| self.verify(*id, message, signature))
+                .collect();
+        }
+        batch
+            .iter()
+            .zip(&keys)
+            .map(|((id, _, signature), key)| {
+                key.as_ref().ok_or(UsigError::UnknownId(*id))?;
+                self.other_keys.observe(*id, signature.counter());
+                Ok(())
+            })
+            .collect()
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.other_keys.precheck(id, signature.counter())
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        MemoryReport {
+            party_registry: self.other_keys.memory_usage(),
+            ..Default::default()
+        }
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        attestation.check_parameters(&AlgorithmParameters {
+            counter_encoding: self.encoding,
+            ..Q::parameters()
+        })?;
+        let binding: Q = bincode::deserialize(&attestation.binding)
+            .map_err(|_| UsigError::RemoteAttestationFailed)?;
+        attestation
+            .payload
+            .verify(&attestation.bound_message()?, &binding)
+            .map_err(|_| UsigError::RemoteAttestationFailed)?;
+        if self.require_signer {
+            attestation.check_bound_signer(id)?;
+        } else {
+            attestation.check_signer(id)?;
+        }
+        self.other_keys.insert(id, attestation.payload)
+    }
+
+    fn remove_remote_party(&mut self, id: ReplicaId) -> bool {
+        self.other_keys.remove(id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.other_keys.ids()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.other_keys.get(remote_usig_id).is_some()
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        Some(self)
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        Some(self)
     }
 }
 
 #[derive(Derivative)]
-#[derivative(Debug(bound = ""))]
+#[derivative(Debug(bound = "D: Debug"))]
 pub struct UsigSignature<
     Q: SignatureType,
     S: Signer<Q> + Debug,
     V: Verifier<Q> + Clone + Debug + for<'a> Deserialize<'a> + Serialize,
+    D = MemoryDirectory<V>,
 > {
     sign_half: UsigSignatureSignHalf<Q, S, V>,
-    verify_half: UsigSignatureVerifyHalf<Q, V>,
+    verify_half: UsigSignatureVerifyHalf<Q, V, D>,
 }
 
 impl<
@@ -151,10 +619,45 @@ impl<
         Q: SignatureType,
         S: Signer<Q> + Debug,
         V: Verifier<Q> + Clone + Debug + for<'a> Deserialize<'a> + Serialize,
-    > Usig for UsigSignature<Q, S, V>
+        D: PartyDirectory<V>,
+    > UsigSignature<Q, S, V, D>
+{
+    pub fn with_directory(private_key: S, public_key: V, directory: D) -> Self {
+        Self {
+            sign_half: UsigSignatureSignHalf::new(private_key, public_key),
+            verify_half: UsigSignatureVerifyHalf::with_directory(directory),
+        }
+    }
+
+    /// Bind the USIG to its own id, which is embedded in its attestation
+    pub fn with_local_id(mut self, id: ReplicaId) -> Self {
+        self.sign_half = self.sign_half.with_local_id(id);
+        self
+    }
+
+    /// Use `encoding` for the own signatures and expect it from remote parties
+    pub fn with_counter_encoding(mut self, encoding: CounterEncoding) -> Self {
+        self.sign_half = self.sign_half.with_counter_encoding(encoding);
+        self.verify_half = self.verify_half.with_counter_encoding(encoding);
+        self
+    }
+
+    /// Reject attestations that are not bound to the id they are added under
+    pub fn require_signer(mut self) -> Self {
+        self.verify_half = self.verify_half.require_signer();
+        self
+    }
+}
+
+impl<
+        Q: SignatureType,
+        S: Signer<Q> + Debug,
+        V: BatchVerifier<Q> + Clone + Debug + for<'a> Deserialize<'a> + Serialize,
+        D: PartyDirectory<V>,
+    > Usig for UsigSignature<Q, S, V, D>
 {
     type Signature = Signature<Q>;
-    type Attestation = V;
+    type Attestation = Attestation<V>;
 
     fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
         self.sign_half.sign(message)
@@ -164,6 +667,10 @@ impl<
         self.sign_half.attest()
     }
 
+    fn local_id(&self) -> Option<ReplicaId> {
+        self.sign_half.local_id()
+    }
+
     fn verify(
         &self,
         id: ReplicaId,
@@ -173,12 +680,43 @@ impl<
         self.verify_half.verify(id, message, signature)
     }
 
-    fn add_remote_party(&mut self, id: ReplicaId, attestation: Self::Attestation) -> bool {
-        self.verify_half.add_remote_party(id, attestation)
+    fn verify_batch(
+        &self,
+        batch: &[(ReplicaId, &[u8], &Self::Signature)],
+    ) -> Vec<Result<(), UsigError>> {
+        self.verify_half.verify_batch(batch)
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.verify_half.pre_validate(id, signature)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        self.verify_half.memory_usage()
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        self.verify_half.try_add_remote_party(id, attestation)
+    }
+
+    fn remove_remote_party(&mut self, id: ReplicaId) -> bool {
+        self.verify_half.remove_remote_party(id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.verify_half.remote_parties()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.verify_half.contains(remote_usig_id)
     }
 
     type SignHalf = UsigSignatureSignHalf<Q, S, V>;
-    type VerifyHalf = UsigSignatureVerifyHalf<Q, V>;
+    type VerifyHalf = UsigSignatureVerifyHalf<Q, V, D>;
 
     fn split(self) -> (Self::SignHalf, Self::VerifyHalf) {
         (self.sign_half, self.verify_half)
@@ -186,14 +724,53 @@ impl<
 }
 
 pub type UsigEd25519 =
-    UsigSignature<ed25519_dalek::Signature, ed25519_dalek::SigningKey, ed25519_dalek::VerifyingKey>;
+    UsigSignature<ed25519_dalek::Signature, Ed25519SigningKey, ed25519_dalek::VerifyingKey>;
 
 pub fn new_ed25519() -> UsigEd25519 {
-    let keypair = ed25519_dalek::SigningKey::generate(&mut OsRng::default());
+    let keypair = Ed25519SigningKey::generate();
     let public_key = keypair.verifying_key();
     UsigSignature::new(keypair, public_key)
 }
 
+/// Create an Ed25519 USIG with the keypair derived from `seed`
+///
+/// The same seed always yields the same keypair, for tests and reproducible experiments only.
+pub fn new_ed25519_from_seed(seed: [u8; 32]) -> UsigEd25519 {
+    let keypair = Ed25519SigningKey::from_bytes(&seed);
+    let public_key = keypair.verifying_key();
+    UsigSignature::new(keypair, public_key)
+}
+
+pub type UsigSecp256k1 =
+    UsigSignature<k256::ecdsa::Signature, k256::ecdsa::SigningKey, k256::ecdsa::VerifyingKey>;
+
+pub fn new_secp256k1() -> UsigSecp256k1 {
+    let private_key = k256::ecdsa::SigningKey::random(&mut OsRng);
+    let public_key = *private_key.verifying_key();
+    UsigSignature::new(private_key, public_key)
+}
+
+pub type UsigP256 =
+    UsigSignature<p256::ecdsa::Signature, p256::ecdsa::SigningKey, p256::ecdsa::VerifyingKey>;
+
+pub fn new_p256() -> UsigP256 {
+    let private_key = p256::ecdsa::SigningKey::random(&mut OsRng);
+    let public_key = *private_key.verifying_key();
+    UsigSignature::new(private_key, public_key)
+}
+
+pub type UsigEd448 = UsigSignature<
+    ed448_goldilocks_plus::Signature,
+    ed448_goldilocks_plus::SigningKey,
+    ed448_goldilocks_plus::VerifyingKey,
+>;
+
+pub fn new_ed448() -> UsigEd448 {
+    let private_key = ed448_goldilocks_plus::SigningKey::generate(&mut OsRng);
+    let public_key = private_key.verifying_key();
+    UsigSignature::new(private_key, public_key)
+}
+
 #[cfg(test)]
 mod tests {
     use super::new_ed25519;
@@ -201,4 +778,178 @@ mod tests {
     use crate::tests;
 
     tests!(new_ed25519());
+
+    mod ed25519_from_seed {
+        use crate as usig;
+        use crate::signature::new_ed25519_from_seed;
+        use crate::tests;
+
+        tests!(new_ed25519_from_seed(rand::random()));
+
+        #[test]
+        fn deterministic() {
+            let seed = [7; 32];
+            let mut first = new_ed25519_from_seed(seed);
+            let mut second = new_ed25519_from_seed(seed);
+            let attestation = first.attest().unwrap();
+            assert_eq!(attestation.payload, second.attest().unwrap().payload);
+            assert_eq!(
+                first.sign(MESSAGE_1).unwrap().inner(),
+                second.sign(MESSAGE_1).unwrap().inner()
+            );
+            assert!(second.add_remote_party(ID, attestation));
+        }
+
+        #[test]
+        fn export_import() {
+            use std::collections::HashSet;
+
+            use crate::{signature::UsigSignatureSignHalf, Count};
+
+            let export_key = [5; 32];
+            let (mut sign_half, mut verify_half) = new_ed25519_from_seed(rand::random()).split();
+            assert!(verify_half.add_remote_party(ID, sign_half.attest().unwrap()));
+            sign_half.sign(MESSAGE_1).unwrap();
+            let state = sign_half.export_state(&export_key).unwrap();
+
+            let mut tampered = state.clone();
+            tampered[20] ^= 1;
+            let mut log = HashSet::new();
+            assert!(matches!(
+                UsigSignatureSignHalf::import_state(&tampered, &export_key, &mut log),
+                Err(UsigError::Io(_))
+            ));
+            let mut sign_half =
+                UsigSignatureSignHalf::import_state(&state, &export_key, &mut log).unwrap();
+            let signature = sign_half.sign(MESSAGE_2).unwrap();
+            assert_eq!(signature.counter(), Count(1));
+            assert!(verify_half.verify(ID, MESSAGE_2, &signature).is_ok());
+            assert!(UsigSignatureSignHalf::import_state(&state, &export_key, &mut log).is_err());
+        }
+
+        #[test]
+        fn zeroize_on_drop() {
+            fn assert_zeroize_on_drop<T: zeroize::ZeroizeOnDrop>() {}
+            assert_zeroize_on_drop::<crate::signature::Ed25519SigningKey>();
+        }
+
+        #[test]
+        fn batch_matches_sequential() {
+            let seed = [7; 32];
+            let (mut batched, _) = new_ed25519_from_seed(seed).split();
+            let (mut sequential, _) = new_ed25519_from_seed(seed).split();
+            let messages = [MESSAGE_1, MESSAGE_2];
+            let batch = batched.sign_batch(&messages).unwrap();
+            for (message, signature) in messages.iter().zip(&batch) {
+                assert_eq!(signature.inner(), sequential.sign(message).unwrap().inner());
+            }
+
+            use signature::Signer;
+            let expanded = crate::signature::Ed25519SigningKey::from_bytes(&seed);
+            let plain = ed25519_dalek::SigningKey::from_bytes(&seed);
+            assert_eq!(expanded.sign(MESSAGE_1), plain.sign(MESSAGE_1));
+        }
+
+        #[test]
+        fn bytes_roundtrip() {
+            use crate::signature::Signature;
+
+            let mut usig = new_ed25519_from_seed(rand::random());
+            let attestation = usig.attest().unwrap();
+            assert!(usig.add_remote_party(ID, attestation));
+            usig.sign(MESSAGE_1).unwrap();
+            let bytes = usig.sign(MESSAGE_1).unwrap().to_bytes();
+            assert_eq!(bytes.len(), 8 + 64);
+            assert_eq!(bytes[..8], 1u64.to_be_bytes());
+            let signature = Signature::try_from_bytes(&bytes).unwrap();
+            assert!(usig.verify(ID, MESSAGE_1, &signature).is_ok());
+            assert!(matches!(
+                Signature::<ed25519_dalek::Signature>::try_from_bytes(&bytes[..40]),
+                Err(UsigError::InvalidSignature)
+            ));
+        }
+    }
+
+    mod secp256k1 {
+        use crate as usig;
+        use crate::signature::new_secp256k1;
+        use crate::tests;
+
+        tests!(new_secp256k1());
+    }
+
+    mod ed448 {
+        use crate as usig;
+        use crate::signature::new_ed448;
+        use crate::tests;
+
+        tests!(new_ed448());
+
+        #[test]
+        fn serde_roundtrip() {
+            let mut usig = new_ed448();
+            let attestation = usig.attest().unwrap();
+            let attestation = bincode::deserialize(&bincode::serialize(&attestation).unwrap());
+            assert!(usig.add_remote_party(ID, attestation.unwrap()));
+            let signature = usig.sign(MESSAGE_1).unwrap();
+            let signature = bincode::deserialize(&bincode::serialize(&signature).unwrap()).unwrap();
+            assert!(usig.verify(ID, MESSAGE_1, &signature).is_ok());
+        }
+    }
+
+    mod p256 {
+        use crate as usig;
+        use crate::signature::new_p256;
+        use crate::tests;
+
+        tests!(new_p256());
+
+        #[test]
+        fn serde_roundtrip() {
+            let mut usig = new_p256();
+            let attestation = usig.attest().unwrap();
+            let attestation = bincode::deserialize(&bincode::serialize(&attestation).unwrap());
+            assert!(usig.add_remote_party(ID, attestation.unwrap()));
+            let signature = usig.sign(MESSAGE_1).unwrap();
+            let signature = bincode::deserialize(&bincode::serialize(&signature).unwrap()).unwrap();
+            assert!(usig.verify(ID, MESSAGE_1, &signature).is_ok());
+        }
+    }
+
+    #[test]
+    fn parameter_mismatch() {
+        let mut usig_1 = new_ed25519();
+        let mut usig_2 = new_ed25519();
+        let mut attestation = usig_1.attest().unwrap();
+        attestation.parameters.pre_hash = Some("Sha512".to_owned());
+        assert!(matches!(
+            usig_2.try_add_remote_party(ID, attestation),
+            Err(UsigError::ParameterMismatch { .. })
+        ));
+        let signature = usig_1.sign(MESSAGE_1).unwrap();
+        assert!(matches!(
+            usig_2.verify(ID, MESSAGE_1, &signature),
+            Err(UsigError::UnknownId(ID))
+        ));
+    }
+
+    #[test]
+    fn relabeled_attestation() {
+        let other = ReplicaId::from_u64(1);
+        let mut usig_1 = new_ed25519().with_local_id(ID);
+        let mut usig_2 = new_ed25519().require_signer();
+        let mut attestation = usig_1.attest().unwrap();
+        attestation.signer = Some(other);
+        assert!(matches!(
+            usig_2.try_add_remote_party(other, attestation),
+            Err(UsigError::RemoteAttestationFailed)
+        ));
+        let mut attestation = usig_1.attest().unwrap();
+        attestation.binding[0] ^= 1;
+        assert!(matches!(
+            usig_2.try_add_remote_party(ID, attestation),
+            Err(UsigError::RemoteAttestationFailed)
+        ));
+        assert!(usig_2.add_remote_party(ID, usig_1.attest().unwrap()));
+    }
 }
@@ -0,0 +1,419 @@
+//! A USIG for large-scale simulations, with real key-dependent tags at almost no cost
+//!
+//! SipHash-2-4 is a fast keyed PRF with a 64 bit tag. Tags of other keys or messages
+//! fail to verify just like with the HMAC backend, but the short tag makes it
+//! unsuitable outside of simulations.
+
+use std::{any::Any, hash::Hasher};
+
+use serde::{Deserialize, Serialize};
+use shared_ids::ReplicaId;
+use siphasher::sip::SipHasher24;
+use zeroize::{ZeroizeOnDrop, Zeroizing};
+
+use crate::{
+    directory::{MemoryDirectory, PartyDirectory},
+    encoding::CounterEncoding,
+    standby::ResumableSignHalf,
+    AlgorithmParameters, Attestation, Count, Counter, MemoryReport, SignHalf, Usig, UsigError,
+    VerifyHalf,
+};
+
+pub type Key = [u8; 16];
+
+/// A key as attested and registered for a remote party, wiped from memory on drop
+pub type VerifyKey = Zeroizing<Key>;
+
+/// The algorithm parameters of the SipHash USIG
+pub fn parameters() -> AlgorithmParameters {
+    AlgorithmParameters {
+        scheme: "SipHash-2-4".to_owned(),
+        output_length: 8,
+        pre_hash: None,
+        counter_encoding: CounterEncoding::default(),
+    }
+}
+
+fn tag(key: &Key, encoding: CounterEncoding, counter: u64, message: &[u8]) -> u64 {
+    let mut hasher = SipHasher24::new_with_key(key);
+    encoding.write(counter, message, |bytes| hasher.write(bytes));
+    hasher.finish()
+}
+
+/// The tag binding an attestation to its fields
+fn binding(key: &Key, message: &[u8]) -> Vec<u8> {
+    let mut hasher = SipHasher24::new_with_key(key);
+    hasher.write(message);
+    hasher.finish().to_be_bytes().to_vec()
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Signature {
+    counter: u64,
+    tag: u64,
+}
+
+impl Counter for Signature {
+    fn counter(&self) -> Count {
+        Count(self.counter)
+    }
+}
+
+#[derive(Debug)]
+pub struct UsigSipHashSignHalf {
+    counter: u64,
+    key: Zeroizing<Key>,
+    local_id: Option<ReplicaId>,
+    encoding: CounterEncoding,
+}
+
+impl UsigSipHashSignHalf {
+    /// The key is wiped from memory when the sign half is dropped
+    pub fn new(key: Key) -> Self {
+        Self {
+            counter: 0,
+            key: Zeroizing::new(key),
+            local_id: None,
+            encoding: CounterEncoding::default(),
+        }
+    }
+
+    /// Bind the sign half to its own id, which is embedded in its attestation
+    pub fn with_local_id(mut self, id: ReplicaId) -> Self {
+        self.local_id = Some(id);
+        self
+    }
+
+    /// Mix the counter into the tag input as `encoding` describes
+    pub fn with_counter_encoding(mut self, encoding: CounterEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+}
+
+/// The key wipes itself on drop
+impl ZeroizeOnDrop for UsigSipHashSignHalf {}
+
+impl SignHalf for UsigSipHashSignHalf {
+    type Signature = Signature;
+    type Attestation = Attestation<VerifyKey>;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        let counter = self.counter;
+        self.counter = counter.checked_add(1).ok_or(UsigError::CounterExhausted)?;
+        Ok(Signature {
+            counter,
+            tag: tag(&self.key, self.encoding, counter, message.as_ref()),
+        })
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        let mut attestation = Attestation {
+            parameters: AlgorithmParameters {
+                counter_encoding: self.encoding,
+                ..parameters()
+            },
+            signer: self.local_id,
+            payload: self.key.clone(),
+            binding: Vec::new(),
+        };
+        attestation.binding = binding(&self.key, &attestation.bound_message()?);
+        Ok(attestation)
+    }
+
+    fn local_id(&self) -> Option<ReplicaId> {
+        self.local_id
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        Some(self)
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        Some(self)
+    }
+}
+
+impl ResumableSignHalf for UsigSipHashSignHalf {
+    fn resume_at(&mut self, next: Count) -> Result<(), UsigError> {
+        if next.0 < self.counter {
+            return Err(UsigError::CounterRegression);
+        }
+        self.counter = next.0;
+        Ok(())
+    }
+
+    fn next_counter(&self) -> Count {
+        Count(self.counter)
+    }
+}
+
+#[derive(Debug)]
+pub struct UsigSipHashVerifyHalf<D = MemoryDirectory<VerifyKey>> {
+    keys: D,
+    encoding: CounterEncoding,
+    require_signer: bool,
+}
+
+impl Default for UsigSipHashVerifyHalf {
+    fn default() -> Self {
+        Self::with_directory(MemoryDirectory::default())
+    }
+}
+
+impl<D: PartyDirectory<VerifyKey>> UsigSipHashVerifyHalf<D> {
+    pub fn with_directory(directory: D) -> Self {
+        Self {
+            keys: directory,
+            encoding: CounterEncoding::default(),
+            require_signer: false,
+        }
+    }
+
+    /// Expect the counter encoding remote parties are attested with
+    pub fn with_counter_encoding(mut self, encoding: CounterEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Reject attestations that are not bound to the id they are added under
+    ///
+    /// Remote parties have to be constructed with their local id then.
+    pub fn require_signer(mut self) -> Self {
+        self.require_signer = true;
+        self
+    }
+}
+
+impl<D: PartyDirectory<VerifyKey>> VerifyHalf for UsigSipHashVerifyHalf<D> {
+    type Signature = Signature;
+    type Attestation = Attestation<VerifyKey>;
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        let key = self.keys.get(id).ok_or(UsigError::UnknownId(id))?;
+        if tag(&key, self.encoding, signature.counter, message.as_ref()) == signature.tag {
+            self.keys.observe(id, signature.counter());
+            Ok(())
+        } else {
+            Err(UsigError::InvalidSignature)
+        }
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.keys.precheck(id, signature.counter())
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        MemoryReport {
+            party_registry: self.keys.memory_usage(),
+            ..Default::default()
+        }
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        attestation.check_parameters(&AlgorithmParameters {
+            counter_encoding: self.encoding,
+            ..parameters()
+        })?;
+        if binding(&attestation.payload, &attestation.bound_message()?) != attestation.binding {
+            return Err(UsigError::RemoteAttestationFailed);
+        }
+        if self.require_signer {
+            attestation.check_bound_signer(id)?;
+        } else {
+            attestation.check_signer(id)?;
+        }
+        self.keys.insert(id, attestation.payload)
+    }
+
+    fn remove_remote_party(&mut self, id: ReplicaId) -> bool {
+        self.keys.remove(id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.keys.ids()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.keys.get(remote_usig_id).is_some()
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        Some(self)
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        Some(self)
+    }
+}
+
+#[derive(Debug)]
+pub struct UsigSipHash<D = MemoryDirectory<VerifyKey>> {
+    sign_half: UsigSipHashSignHalf,
+    verify_half: UsigSipHashVerifyHalf<D>,
+}
+
+impl UsigSipHash {
+    pub fn new(key: Key) -> Self {
+        Self::with_directory(key, MemoryDirectory::default())
+    }
+}
+
+impl<D: PartyDirectory<VerifyKey>> UsigSipHash<D> {
+    pub fn with_directory(key: Key, directory: D) -> Self {
+        Self {
+            sign_half: UsigSipHashSignHalf::new(key),
+            verify_half: UsigSipHashVerifyHalf::with_directory(directory),
+        }
+    }
+
+    /// Bind the USIG to its own id, which is embedded in its attestation
+    pub fn with_local_id(mut self, id: ReplicaId) -> Self {
+        self.sign_half = self.sign_half.with_local_id(id);
+        self
+    }
+
+    /// Use `encoding` for the own tags and expect it from remote parties
+    pub fn with_counter_encoding(mut self, encoding: CounterEncoding) -> Self {
+        self.sign_half = self.sign_half.with_counter_encoding(encoding);
+        self.verify_half = self.verify_half.with_counter_encoding(encoding);
+        self
+    }
+
+    /// Reject attestations that are not bound to the id they are added under
+    pub fn require_signer(mut self) -> Self {
+        self.verify_half = self.verify_half.require_signer();
+        self
+    }
+}
+
+impl<D: PartyDirectory<VerifyKey>> Usig for UsigSipHash<D> {
+    type Signature = Signature;
+    type Attestation = Attestation<VerifyKey>;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        self.sign_half.sign(message)
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        self.sign_half.attest()
+    }
+
+    fn local_id(&self) -> Option<ReplicaId> {
+        self.sign_half.local_id()
+    }
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        self.verify_half.verify(id, message, signature)
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.verify_half.pre_validate(id, signature)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        self.verify_half.memory_usage()
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        self.verify_half.try_add_remote_party(id, attestation)
+    }
+
+    fn remove_remote_party(&mut self, id: ReplicaId) -> bool {
+        self.verify_half.remove_remote_party(id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.verify_half.remote_parties()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.verify_half.contains(remote_usig_id)
+    }
+
+    type SignHalf = UsigSipHashSignHalf;
+    type VerifyHalf = UsigSipHashVerifyHalf<D>;
+
+    fn split(self) -> (Self::SignHalf, Self::VerifyHalf) {
+        (self.sign_half, self.verify_half)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests;
+
+    use crate as usig;
+
+    use super::UsigSipHash;
+
+    tests!(UsigSipHash::new(rand::random()));
+
+    #[test]
+    fn other_key() {
+        let mut usig_1 = UsigSipHash::new(rand::random());
+        let mut usig_2 = UsigSipHash::new(rand::random());
+        let attestation = usig_1.attest().unwrap();
+        assert!(usig_2.add_remote_party(ID, attestation));
+
+        let mut impostor = UsigSipHash::new(rand::random());
+        let signature = impostor.sign(MESSAGE_1).unwrap();
+        assert!(matches!(
+            usig_2.verify(ID, MESSAGE_1, &signature),
+            Err(UsigError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn relabeled_attestation() {
+        let other = ReplicaId::from_u64(1);
+        let mut usig_1 = UsigSipHash::new(rand::random()).with_local_id(ID);
+        let mut usig_2 = UsigSipHash::new(rand::random());
+        let mut attestation = usig_1.attest().unwrap();
+        attestation.signer = Some(other);
+        assert!(matches!(
+            usig_2.try_add_remote_party(other, attestation),
+            Err(UsigError::RemoteAttestationFailed)
+        ));
+        assert!(usig_2.add_remote_party(ID, usig_1.attest().unwrap()));
+    }
+
+    #[test]
+    fn zeroize_on_drop() {
+        fn assert_zeroize_on_drop<T: zeroize::ZeroizeOnDrop>(_: &T) {}
+        let mut usig = UsigSipHash::new(rand::random());
+        assert_zeroize_on_drop(&usig.attest().unwrap().payload);
+        let (sign_half, _) = usig.split();
+        assert_zeroize_on_drop(&sign_half);
+    }
+}
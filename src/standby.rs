@@ -0,0 +1,316 @@
+use std::any::Any;
+
+use serde::{Deserialize, Serialize};
+use shared_ids::ReplicaId;
+
+use crate::{encoding::with_context, Count, Counter, SignHalf, UsigError, VerifyHalf};
+
+/// Context of the handoff the primary signs to hand its counter over to a standby
+pub const HANDOFF_CONTEXT: &[u8] = b"usig standby handoff";
+
+/// Sign halves that can continue a counter sequence started by another instance
+pub trait ResumableSignHalf: SignHalf {
+    /// Continue signing at counter `next`
+    ///
+    /// Fails with [UsigError::CounterRegression] if `next` is below the current counter.
+    fn resume_at(&mut self, next: Count) -> Result<(), UsigError>;
+
+    /// The counter the next signature will have
+    fn next_counter(&self) -> Count;
+
+    /// Skip forward to counter `next`, e.g. to recover from partial loss of the counter state
+    ///
+    /// Never moves backwards, so no counter value is used twice.
+    /// Returns the number of counter values skipped.
+    fn advance_to(&mut self, next: Count) -> Result<u64, UsigError> {
+        let current = self.next_counter();
+        if next < current {
+            return Err(UsigError::CounterRegression);
+        }
+        self.resume_at(next)?;
+        Ok(next.distance(current))
+    }
+}
+
+/// Produced by the primary on [StandbySignHalf::deactivate], consumed by the standby on [StandbySignHalf::activate]
+///
+/// The handoff is a regular USIG signature over the epoch and the last counter of the
+/// primary, which is the counter of the handoff itself. Every handoff starts a new epoch,
+/// so a replayed token is older than the last one seen.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferToken<S> {
+    pub epoch: u64,
+    pub last: Count,
+    pub handoff: S,
+}
+
+fn handoff_message(epoch: u64, last: Count) -> Vec<u8> {
+    let mut message = epoch.to_be_bytes().to_vec();
+    message.extend_from_slice(&last.0.to_be_bytes());
+    with_context(HANDOFF_CONTEXT, &message)
+}
+
+/// A sign half that can hand its counter over to a warm standby holding the same key
+///
+/// Only an active instance signs. The primary deactivates itself by signing a handoff
+/// message, the standby continues right after the counter of that signature, so no
+/// counter is ever used by both. Tokens of an epoch not after the last one seen are
+/// refused with [UsigError::Outdated], so the epoch has to survive restarts, see
+/// [StandbySignHalf::with_epoch].
+#[derive(Debug)]
+pub struct StandbySignHalf<S> {
+    inner: S,
+    active: bool,
+    epoch: u64,
+}
+
+impl<S: ResumableSignHalf> StandbySignHalf<S> {
+    /// The instance currently responsible for signing
+    pub fn active(inner: S) -> Self {
+        Self {
+            inner,
+            active: true,
+            epoch: 0,
+        }
+    }
+
+    /// A standby instance holding the key but no counter lease
+    pub fn standby(inner: S) -> Self {
+        Self {
+            inner,
+            active: false,
+            epoch: 0,
+        }
+    }
+
+    /// Continue from the epoch of the last token this instance issued or accepted
+    pub fn with_epoch(mut self, epoch: u64) -> Self {
+        self.epoch = epoch;
+        self
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// The epoch of the last token this instance issued or accepted
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Stop signing and produce the token that lets a standby take over
+    pub fn deactivate(&mut self) -> Result<TransferToken<S::Signature>, UsigError> {
+        if !self.active {
+            return Err(UsigError::Inactive);
+        }
+        let epoch = self
+            .epoch
+            .checked_add(1)
+            .ok_or(UsigError::CounterExhausted)?;
+        let last = self.inner.next_counter();
+        let handoff = self.inner.sign(handoff_message(epoch, last))?;
+        if handoff.counter() != last {
+            return Err(UsigError::SigningFailed);
+        }
+        self.active = false;
+        self.epoch = epoch;
+        Ok(TransferToken {
+            epoch,
+            last,
+            handoff,
+        })
+    }
+
+    /// Take over signing from the primary identified by `primary`
+    ///
+    /// The token is checked with `verifier`, which must know the attestation of the primary.
+    /// Fails with [UsigError::Outdated] if its epoch is not after the last one seen.
+    pub fn activate<V: VerifyHalf<Signature = S::Signature>>(
+        &mut self,
+        token: TransferToken<S::Signature>,
+        verifier: &V,
+        primary: ReplicaId,
+    ) -> Result<(), UsigError> {
+        if self.active {
+            return Ok(());
+        }
+        if token.epoch <= self.epoch {
+            return Err(UsigError::Outdated);
+        }
+        if token.handoff.counter() != token.last {
+            return Err(UsigError::InvalidSignature);
+        }
+        verifier.verify(
+            primary,
+            handoff_message(token.epoch, token.last),
+            &token.handoff,
+        )?;
+        let next = token
+            .last
+            .checked_add(1)
+            .ok_or(UsigError::CounterExhausted)?;
+        self.inner.resume_at(next)?;
+        self.active = true;
+        self.epoch = token.epoch;
+        Ok(())
+    }
+}
+
+impl<S: ResumableSignHalf> SignHalf for StandbySignHalf<S> {
+    type Signature = S::Signature;
+    type Attestation = S::Attestation;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        if self.active {
+            self.inner.sign(message)
+        } else {
+            Err(UsigError::Inactive)
+        }
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        self.inner.attest()
+    }
+
+    fn local_id(&self) -> Option<ReplicaId> {
+        self.inner.local_id()
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any()
+    }
+
+    /// `None`, the backend could sign while on standby
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::Hmac;
+    use sha2::Sha256;
+
+    use super::*;
+    use crate::hmac::{UsigHmacSignHalf, UsigHmacVerifyHalf};
+
+    type HmacSignHalf = UsigHmacSignHalf<Hmac<Sha256>>;
+
+    const ID: ReplicaId = ReplicaId::first();
+    const MESSAGE: &[u8] = b"message";
+
+    fn setup() -> (
+        StandbySignHalf<HmacSignHalf>,
+        StandbySignHalf<HmacSignHalf>,
+        UsigHmacVerifyHalf<Hmac<Sha256>>,
+    ) {
+        let key: Box<[u8]> = Box::new(rand::random::<[u8; 16]>());
+        let mut primary = StandbySignHalf::active(HmacSignHalf::try_new(key.clone()).unwrap());
+        let standby = StandbySignHalf::standby(HmacSignHalf::try_new(key).unwrap());
+        let mut verify = UsigHmacVerifyHalf::default();
+        assert!(verify.add_remote_party(ID, primary.attest().unwrap()));
+        (primary, standby, verify)
+    }
+
+    #[test]
+    fn advance_to() {
+        let (primary, _, verify) = setup();
+        let mut sign = primary.inner;
+        sign.sign(MESSAGE).unwrap();
+        assert_eq!(sign.advance_to(Count(10)).unwrap(), 9);
+        assert_eq!(sign.advance_to(Count(10)).unwrap(), 0);
+        assert!(matches!(
+            sign.advance_to(Count(9)),
+            Err(UsigError::CounterRegression)
+        ));
+        let signature = sign.sign(MESSAGE).unwrap();
+        assert_eq!(signature.counter(), Count(10));
+        assert!(verify.verify(ID, MESSAGE, &signature).is_ok());
+    }
+
+    #[test]
+    fn handoff() {
+        let (mut primary, mut standby, verify) = setup();
+        for _ in 0..5 {
+            primary.sign(MESSAGE).unwrap();
+        }
+        assert!(matches!(standby.sign(MESSAGE), Err(UsigError::Inactive)));
+
+        let token = primary.deactivate().unwrap();
+        assert_eq!((token.epoch, token.last), (1, Count(5)));
+        assert!(!primary.is_active());
+        assert!(matches!(primary.sign(MESSAGE), Err(UsigError::Inactive)));
+
+        standby.activate(token, &verify, ID).unwrap();
+        assert_eq!(standby.epoch(), 1);
+        let signature = standby.sign(MESSAGE).unwrap();
+        assert_eq!(signature.counter(), Count(6));
+        assert!(verify.verify(ID, MESSAGE, &signature).is_ok());
+    }
+
+    #[test]
+    fn forged_token() {
+        let (mut primary, mut standby, verify) = setup();
+        let (mut other, _, _) = setup();
+        primary.sign(MESSAGE).unwrap();
+        let token = other.deactivate().unwrap();
+        assert!(matches!(
+            standby.activate(token, &verify, ID),
+            Err(UsigError::InvalidSignature)
+        ));
+        assert!(!standby.is_active());
+    }
+
+    #[test]
+    fn stale_token() {
+        let (mut primary, mut standby, verify) = setup();
+        let stale = primary.deactivate().unwrap();
+        standby.activate(stale.clone(), &verify, ID).unwrap();
+        standby.sign(MESSAGE).unwrap();
+        let token = standby.deactivate().unwrap();
+
+        primary.activate(token, &verify, ID).unwrap();
+        let fresh = primary.deactivate().unwrap();
+        standby.activate(fresh, &verify, ID).unwrap();
+        standby.deactivate().unwrap();
+
+        assert!(matches!(
+            standby.activate(stale.clone(), &verify, ID),
+            Err(UsigError::Outdated)
+        ));
+
+        // A restarted standby remembers the epoch
+        let (_, restarted, _) = setup();
+        let mut restarted = restarted.with_epoch(standby.epoch());
+        assert!(matches!(
+            restarted.activate(stale, &verify, ID),
+            Err(UsigError::Outdated)
+        ));
+        assert!(!restarted.is_active());
+    }
+
+    #[test]
+    fn tampered_token() {
+        let (mut primary, mut standby, verify) = setup();
+        primary.sign(MESSAGE).unwrap();
+        let mut token = primary.deactivate().unwrap();
+        token.epoch = 7;
+        assert!(matches!(
+            standby.activate(token.clone(), &verify, ID),
+            Err(UsigError::InvalidSignature)
+        ));
+        token.epoch = 1;
+        token.last = Count(0);
+        assert!(matches!(
+            standby.activate(token, &verify, ID),
+            Err(UsigError::InvalidSignature)
+        ));
+        assert!(!standby.is_active());
+    }
+}
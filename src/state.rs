@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    monotonic::MonotonicVerifyHalf, semantics::CounterSemantics, standby::ResumableSignHalf,
+    watermarks::HighWatermarks, Count, UsigError, VerifyHalf,
+};
+
+/// The counter state of a replica, for state transfer and checkpoints
+///
+/// Holds the next counter of the sign half and the last accepted counter of every party of
+/// the verify half. Restoring never moves a counter backwards, so a stale state can not
+/// make the sign half reuse a counter or the verify half accept a duplicate.
+///
+/// Only restore states this replica captured itself. The watermarks in the state of a
+/// peer are not vouched for by anyone else, a Byzantine peer could raise them to make the
+/// verify half reject honest counters; merge those with [MonotonicVerifyHalf::merge].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CounterState {
+    pub next: Count,
+    pub watermarks: HighWatermarks,
+}
+
+impl CounterState {
+    pub fn capture<S: ResumableSignHalf, V: VerifyHalf, M: CounterSemantics>(
+        sign_half: &S,
+        verify_half: &MonotonicVerifyHalf<V, M>,
+    ) -> Self {
+        Self {
+            next: sign_half.next_counter(),
+            watermarks: verify_half.watermarks(),
+        }
+    }
+
+    /// Fails with [UsigError::CounterRegression] if the sign half is already past the state,
+    /// the verify half is restored nevertheless
+    pub fn restore<S: ResumableSignHalf, V: VerifyHalf, M: CounterSemantics>(
+        &self,
+        sign_half: &mut S,
+        verify_half: &MonotonicVerifyHalf<V, M>,
+    ) -> Result<(), UsigError> {
+        verify_half.merge_trusted(&self.watermarks);
+        sign_half.resume_at(self.next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::Hmac;
+    use sha2::Sha256;
+    use shared_ids::ReplicaId;
+
+    use super::*;
+    use crate::{hmac::UsigHmac, Counter, SignHalf, Usig};
+
+    type HmacUsig = UsigHmac<Hmac<Sha256>>;
+
+    const ID: ReplicaId = ReplicaId::first();
+    const KEY: [u8; 16] = [9; 16];
+    const MESSAGE: &[u8] = b"message";
+
+    fn replica() -> (
+        <HmacUsig as Usig>::SignHalf,
+        MonotonicVerifyHalf<<HmacUsig as Usig>::VerifyHalf>,
+    ) {
+        let (mut sign, verify) = HmacUsig::try_new(Box::new(KEY)).unwrap().split();
+        let mut verify = verify.enforce_monotonicity();
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+        (sign, verify)
+    }
+
+    #[test]
+    fn transfer() {
+        let (mut sign, verify) = replica();
+        let signatures: Vec<_> = (0..3).map(|_| sign.sign(MESSAGE).unwrap()).collect();
+        assert!(verify.verify(ID, MESSAGE, &signatures[1]).is_ok());
+
+        let state = CounterState::capture(&sign, &verify);
+        let encoded = bincode::serialize(&state).unwrap();
+        let state: CounterState = bincode::deserialize(&encoded).unwrap();
+        assert_eq!(state.next, Count(3));
+
+        let (mut sign, verify) = replica();
+        state.restore(&mut sign, &verify).unwrap();
+        assert_eq!(sign.sign(MESSAGE).unwrap().counter(), Count(3));
+        assert!(matches!(
+            verify.verify(ID, MESSAGE, &signatures[1]),
+            Err(UsigError::StaleCounter { .. })
+        ));
+        assert!(verify.verify(ID, MESSAGE, &signatures[2]).is_ok());
+    }
+
+    #[test]
+    fn stale_state() {
+        let (mut sign, verify) = replica();
+        let state = CounterState::capture(&sign, &verify);
+        let signature = sign.sign(MESSAGE).unwrap();
+        assert!(verify.verify(ID, MESSAGE, &signature).is_ok());
+
+        assert!(matches!(
+            state.restore(&mut sign, &verify),
+            Err(UsigError::CounterRegression)
+        ));
+        assert_eq!(sign.next_counter(), Count(1));
+        assert_eq!(verify.last_counter(ID), Some(Count(0)));
+    }
+}
@@ -0,0 +1,192 @@
+use std::{any::Any, collections::BTreeMap, sync::Mutex};
+
+use shared_ids::ReplicaId;
+
+use crate::{Count, Counter, MemoryReport, UsigError, VerifyHalf};
+
+/// Verification outcomes of a single party
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PartyStats {
+    /// Signatures verified successfully
+    pub verified: u64,
+    /// Signatures rejected with [UsigError::InvalidSignature]
+    pub invalid: u64,
+    /// The highest counter of a successfully verified signature
+    ///
+    /// Counters of invalid signatures are not taken into account, they could be forged.
+    pub highest: Option<Count>,
+}
+
+/// A verify half counting the verification outcomes of every party
+///
+/// Lets operators spot misbehaving peers, e.g. a party with many invalid signatures or one
+/// whose counter lags behind the others.
+#[derive(Debug)]
+pub struct StatsVerifyHalf<V> {
+    inner: V,
+    stats: Mutex<BTreeMap<ReplicaId, PartyStats>>,
+}
+
+impl<V: VerifyHalf> StatsVerifyHalf<V> {
+    pub fn new(inner: V) -> Self {
+        Self {
+            inner,
+            stats: Mutex::default(),
+        }
+    }
+
+    /// The statistics of all parties a signature was verified for
+    pub fn stats(&self) -> BTreeMap<ReplicaId, PartyStats> {
+        self.stats.lock().unwrap().clone()
+    }
+
+    pub fn party_stats(&self, id: ReplicaId) -> PartyStats {
+        self.stats
+            .lock()
+            .unwrap()
+            .get(&id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Start counting from zero again
+    pub fn reset(&self) {
+        self.stats.lock().unwrap().clear();
+    }
+
+    pub fn into_inner(self) -> V {
+        self.inner
+    }
+}
+
+impl<V: VerifyHalf> VerifyHalf for StatsVerifyHalf<V> {
+    type Signature = V::Signature;
+    type Attestation = V::Attestation;
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        let result = self.inner.verify(id, message, signature);
+        match result {
+            Ok(()) => {
+                let mut stats = self.stats.lock().unwrap();
+                let party = stats.entry(id).or_default();
+                party.verified += 1;
+                party.highest = party.highest.max(Some(signature.counter()));
+            }
+            Err(UsigError::InvalidSignature) => {
+                self.stats.lock().unwrap().entry(id).or_default().invalid += 1;
+            }
+            Err(_) => {}
+        }
+        result
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.inner.pre_validate(id, signature)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        let stats = self.stats.lock().unwrap();
+        let mut report = self.inner.memory_usage();
+        report.evidence += stats.len() * size_of::<(ReplicaId, PartyStats)>();
+        report
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        remote_usig_id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        self.inner.try_add_remote_party(remote_usig_id, attestation)
+    }
+
+    fn remove_remote_party(&mut self, remote_usig_id: ReplicaId) -> bool {
+        self.stats.get_mut().unwrap().remove(&remote_usig_id);
+        self.inner.remove_remote_party(remote_usig_id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.inner.remote_parties()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.inner.contains(remote_usig_id)
+    }
+
+    fn last_counter(&self, id: ReplicaId) -> Option<Count> {
+        self.inner.last_counter(id)
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any()
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::Hmac;
+    use sha2::Sha256;
+
+    use super::*;
+    use crate::{hmac::UsigHmac, SignHalf, Usig};
+
+    type HmacUsig = UsigHmac<Hmac<Sha256>>;
+
+    const MESSAGE: &[u8] = b"message";
+
+    #[test]
+    fn per_party() {
+        let (honest, byzantine) = (ReplicaId::from_u64(0), ReplicaId::from_u64(1));
+        let usig = || HmacUsig::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap();
+        let (mut sign_0, verify) = usig().split();
+        let (mut sign_1, _) = usig().split();
+        let mut verify = StatsVerifyHalf::new(verify);
+        assert!(verify.add_remote_party(honest, sign_0.attest().unwrap()));
+        assert!(verify.add_remote_party(byzantine, sign_1.attest().unwrap()));
+
+        for _ in 0..3 {
+            let signature = sign_0.sign(MESSAGE).unwrap();
+            assert!(verify.verify(honest, MESSAGE, &signature).is_ok());
+            let signature = sign_1.sign(MESSAGE).unwrap();
+            assert!(verify.verify(byzantine, b"forged", &signature).is_err());
+        }
+        let signature = sign_0.sign(MESSAGE).unwrap();
+        assert!(verify
+            .verify(ReplicaId::from_u64(2), MESSAGE, &signature)
+            .is_err());
+
+        assert_eq!(
+            verify.party_stats(honest),
+            PartyStats {
+                verified: 3,
+                invalid: 0,
+                highest: Some(Count(2)),
+            }
+        );
+        assert_eq!(
+            verify.party_stats(byzantine),
+            PartyStats {
+                verified: 0,
+                invalid: 3,
+                highest: None,
+            }
+        );
+        assert_eq!(verify.stats().len(), 2);
+        verify.reset();
+        assert!(verify.stats().is_empty());
+    }
+}
@@ -0,0 +1,142 @@
+use sha2::{Digest, Sha256};
+
+use crate::UsigError;
+
+/// Prefix of the signed data of a streamed message
+///
+/// Keeps the signature of a streamed message from being valid for a message signed in one
+/// piece which happens to equal the digest.
+const DOMAIN: &[u8] = b"usig stream sha256";
+
+fn signed_data(hasher: Sha256) -> Vec<u8> {
+    [DOMAIN, hasher.finalize().as_slice()].concat()
+}
+
+/// A message passed to the signer in parts, see [crate::Usig::begin_sign]
+///
+/// Only a SHA-256 digest of the parts is kept, so the message never has to be in memory
+/// as a whole. The counter is assigned when the message is finished.
+pub struct SignContext<'a, T, S> {
+    signer: &'a mut T,
+    sign: fn(&mut T, &[u8]) -> Result<S, UsigError>,
+    hasher: Sha256,
+}
+
+impl<'a, T, S> SignContext<'a, T, S> {
+    pub(crate) fn new(signer: &'a mut T, sign: fn(&mut T, &[u8]) -> Result<S, UsigError>) -> Self {
+        Self {
+            signer,
+            sign,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// Append a part to the message
+    pub fn update(&mut self, part: impl AsRef<[u8]>) -> &mut Self {
+        self.hasher.update(part);
+        self
+    }
+
+    /// Sign the message made up of all parts
+    pub fn finish(self) -> Result<S, UsigError> {
+        (self.sign)(self.signer, &signed_data(self.hasher))
+    }
+}
+
+/// A message passed to the verifier in parts, see [crate::Usig::begin_verify]
+pub struct VerifyContext<'a, T, Id, S> {
+    verifier: &'a T,
+    verify: fn(&T, Id, &[u8], &S) -> Result<(), UsigError>,
+    remote_usig_id: Id,
+    hasher: Sha256,
+}
+
+impl<'a, T, Id, S> VerifyContext<'a, T, Id, S> {
+    pub(crate) fn new(
+        verifier: &'a T,
+        verify: fn(&T, Id, &[u8], &S) -> Result<(), UsigError>,
+        remote_usig_id: Id,
+    ) -> Self {
+        Self {
+            verifier,
+            verify,
+            remote_usig_id,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// Append a part to the message
+    pub fn update(&mut self, part: impl AsRef<[u8]>) -> &mut Self {
+        self.hasher.update(part);
+        self
+    }
+
+    /// Verify the signature of the message made up of all parts
+    pub fn finish(self, signature: &S) -> Result<(), UsigError> {
+        (self.verify)(
+            self.verifier,
+            self.remote_usig_id,
+            &signed_data(self.hasher),
+            signature,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::Hmac;
+    use sha2::Sha256;
+    use shared_ids::ReplicaId;
+
+    use crate::{hmac::UsigHmac, Count, Counter, SignHalf, Usig, UsigError, VerifyHalf};
+
+    type HmacUsig = UsigHmac<Hmac<Sha256>>;
+
+    const ID: ReplicaId = ReplicaId::first();
+
+    #[test]
+    fn parts() {
+        let checkpoint = vec![7; 1 << 20];
+        let mut usig = HmacUsig::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap();
+        let attestation = usig.attest().unwrap();
+        assert!(usig.add_remote_party(ID, attestation));
+
+        let mut context = usig.begin_sign();
+        for chunk in checkpoint.chunks(4096) {
+            context.update(chunk);
+        }
+        let signature = context.finish().unwrap();
+        assert_eq!(signature.counter(), Count(0));
+
+        let mut context = usig.begin_verify(ID);
+        context
+            .update(&checkpoint[..1000])
+            .update(&checkpoint[1000..]);
+        assert!(context.finish(&signature).is_ok());
+
+        let mut context = usig.begin_verify(ID);
+        context.update(&checkpoint[1..]);
+        assert!(matches!(
+            context.finish(&signature),
+            Err(UsigError::InvalidSignature)
+        ));
+        assert!(usig.verify(ID, &checkpoint, &signature).is_err());
+    }
+
+    #[test]
+    fn parts_split() {
+        let usig = HmacUsig::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap();
+        let (mut sign, mut verify) = usig.split();
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+        sign.sign(b"first").unwrap();
+
+        let mut context = sign.begin_sign();
+        context.update(b"multi").update(b"part");
+        let signature = context.finish().unwrap();
+        assert_eq!(signature.counter(), Count(1));
+
+        let mut context = verify.begin_verify(ID);
+        context.update(b"multipart");
+        assert!(context.finish(&signature).is_ok());
+    }
+}
@@ -0,0 +1,394 @@
+use std::{
+    any::Any,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, Payload},
+    Aes256Gcm, Nonce,
+};
+use derivative::Derivative;
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use shared_ids::ReplicaId;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+use crate::{
+    signature::{UsigSignatureSignHalf, UsigSignatureVerifyHalf},
+    standby::ResumableSignHalf,
+    Count, MemoryReport, SignHalf, Usig, UsigError, VerifyHalf,
+};
+
+/// Name of the file holding the sealed key and counter
+pub const SEALED_FILE: &str = "sealed";
+
+/// Name of the file emulating the monotonic version counter of the TEE
+///
+/// It is plain text next to the sealed state, so it is no protection against an attacker
+/// who can write the directory, see [SimulatedTeeSignHalf].
+pub const VERSION_FILE: &str = "version";
+
+/// Associated data binding sealed states to this backend
+const SEALING_DOMAIN: &[u8] = b"usig simulated tee";
+
+/// The key sealed states are encrypted with, the stand-in for a hardware-bound key
+pub type SealingKey = [u8; 32];
+
+/// Wiped from memory on drop, like every other buffer holding the secret key
+#[derive(Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+struct SealedState {
+    secret: [u8; 32],
+    next: u64,
+    version: u64,
+}
+
+fn invalid_data(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+/// Write `contents` to `path` so that it either fully replaces the file or not at all
+fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    let mut file = File::create(&tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    fs::rename(tmp, path)
+}
+
+fn read_optional(path: &Path) -> io::Result<Option<Vec<u8>>> {
+    match fs::read(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+type Ed25519SignHalf = UsigSignatureSignHalf<ed25519_dalek::Signature, SigningKey, VerifyingKey>;
+type Ed25519VerifyHalf = UsigSignatureVerifyHalf<ed25519_dalek::Signature, VerifyingKey>;
+
+/// The sign half of a TEE emulated in software
+///
+/// The Ed25519 key and the counter are sealed to an encrypted file that is rewritten
+/// before every signature, so a restarted instance never reuses a counter. Every seal
+/// bumps a version number that is also kept in a separate file, standing in for the
+/// monotonic counter of real hardware. A sealed state older than that version was
+/// rolled back and is refused on [SimulatedTeeSignHalf::open].
+///
+/// This only simulates the hardware counter. The version file is neither sealed nor kept
+/// apart from the sealed state, so rolling back both files together, e.g. by restoring a
+/// backup or snapshot of the directory, goes undetected and counters are reused.
+/// Only meant for integration tests of persistence and attestation without hardware.
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct SimulatedTeeSignHalf {
+    inner: Ed25519SignHalf,
+    directory: PathBuf,
+    #[derivative(Debug = "ignore")]
+    cipher: Aes256Gcm,
+    #[derivative(Debug = "ignore")]
+    secret: Zeroizing<[u8; 32]>,
+    next: u64,
+    version: u64,
+}
+
+impl SimulatedTeeSignHalf {
+    /// Unseal the state stored in `directory`, or create a new key if there is none yet
+    ///
+    /// Fails with [io::ErrorKind::InvalidData] if the state can not be unsealed with `key`
+    /// or was rolled back, the latter wrapping [UsigError::CounterRegression].
+    pub fn open(directory: impl AsRef<Path>, key: &SealingKey) -> io::Result<Self> {
+        let directory = directory.as_ref().to_owned();
+        fs::create_dir_all(&directory)?;
+        let cipher = Aes256Gcm::new(key.into());
+
+        let version = match read_optional(&directory.join(VERSION_FILE))? {
+            Some(bytes) => u64::from_be_bytes(
+                bytes
+                    .try_into()
+                    .map_err(|_| invalid_data("malformed version file"))?,
+            ),
+            None => 0,
+        };
+        let state = match read_optional(&directory.join(SEALED_FILE))? {
+            Some(sealed) => Some(Self::unseal(&cipher, &sealed)?),
+            None => None,
+        };
+
+        let mut tee = match state {
+            Some(state) if state.version < version => {
+                return Err(invalid_data(UsigError::CounterRegression))
+            }
+            Some(state) => Self::from_state(directory, cipher, state),
+            None if version > 0 => return Err(invalid_data(UsigError::CounterRegression)),
+            None => {
+                let state = SealedState {
+                    secret: SigningKey::generate(&mut OsRng).to_bytes(),
+                    next: 0,
+                    version: 0,
+                };
+                let mut tee = Self::from_state(directory, cipher, state);
+                tee.seal(0)?;
+                tee
+            }
+        };
+        tee.inner.resume_at(Count(tee.next)).map_err(invalid_data)?;
+        Ok(tee)
+    }
+
+    fn from_state(directory: PathBuf, cipher: Aes256Gcm, state: SealedState) -> Self {
+        let private_key = SigningKey::from_bytes(&state.secret);
+        let public_key = private_key.verifying_key();
+        Self {
+            inner: UsigSignatureSignHalf::new(private_key, public_key),
+            directory,
+            cipher,
+            secret: Zeroizing::new(state.secret),
+            next: state.next,
+            version: state.version,
+        }
+    }
+
+    pub fn with_local_id(mut self, id: ReplicaId) -> Self {
+        self.inner = self.inner.with_local_id(id);
+        self
+    }
+
+    fn unseal(cipher: &Aes256Gcm, sealed: &[u8]) -> io::Result<SealedState> {
+        if sealed.len() < 12 {
+            return Err(invalid_data("sealed state is truncated"));
+        }
+        let (nonce, ciphertext) = sealed.split_at(12);
+        let plaintext = Zeroizing::new(
+            cipher
+                .decrypt(
+                    Nonce::from_slice(nonce),
+                    Payload {
+                        msg: ciphertext,
+                        aad: SEALING_DOMAIN,
+                    },
+                )
+                .map_err(|_| invalid_data("sealed state can not be unsealed"))?,
+        );
+        bincode::deserialize(&plaintext).map_err(invalid_data)
+    }
+
+    /// Seal the state with counter `next` under a new version
+    fn seal(&mut self, next: u64) -> io::Result<()> {
+        let version = self.version + 1;
+        let state = SealedState {
+            secret: *self.secret,
+            next,
+            version,
+        };
+        let plaintext = Zeroizing::new(bincode::serialize(&state).map_err(io::Error::other)?);
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: &plaintext,
+                    aad: SEALING_DOMAIN,
+                },
+            )
+            .map_err(|_| io::Error::other("sealing failed"))?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+
+        // the sealed state goes first, a crash in between leaves it ahead of the version
+        write_atomic(&self.directory.join(SEALED_FILE), &sealed)?;
+        write_atomic(&self.directory.join(VERSION_FILE), &version.to_be_bytes())?;
+        self.version = version;
+        self.next = next;
+        Ok(())
+    }
+}
+
+impl SignHalf for SimulatedTeeSignHalf {
+    type Signature = <Ed25519SignHalf as SignHalf>::Signature;
+    type Attestation = <Ed25519SignHalf as SignHalf>::Attestation;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        let next = self
+            .next
+            .checked_add(1)
+            .ok_or(UsigError::CounterExhausted)?;
+        self.seal(next)?;
+        self.inner.sign(message)
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        self.inner.attest()
+    }
+
+    fn local_id(&self) -> Option<ReplicaId> {
+        self.inner.local_id()
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        Some(self)
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        Some(self)
+    }
+}
+
+/// A USIG backed by a [SimulatedTeeSignHalf], verifying like [crate::signature::UsigEd25519]
+#[derive(Debug)]
+pub struct UsigSimulatedTee {
+    sign_half: SimulatedTeeSignHalf,
+    verify_half: Ed25519VerifyHalf,
+}
+
+impl UsigSimulatedTee {
+    /// See [SimulatedTeeSignHalf::open]
+    pub fn open(directory: impl AsRef<Path>, key: &SealingKey) -> io::Result<Self> {
+        Ok(Self {
+            sign_half: SimulatedTeeSignHalf::open(directory, key)?,
+            verify_half: UsigSignatureVerifyHalf::default(),
+        })
+    }
+
+    pub fn with_local_id(mut self, id: ReplicaId) -> Self {
+        self.sign_half = self.sign_half.with_local_id(id);
+        self
+    }
+}
+
+impl Usig for UsigSimulatedTee {
+    type Signature = <SimulatedTeeSignHalf as SignHalf>::Signature;
+    type Attestation = <SimulatedTeeSignHalf as SignHalf>::Attestation;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        self.sign_half.sign(message)
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        self.sign_half.attest()
+    }
+
+    fn local_id(&self) -> Option<ReplicaId> {
+        self.sign_half.local_id()
+    }
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        self.verify_half.verify(id, message, signature)
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.verify_half.pre_validate(id, signature)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        self.verify_half.memory_usage()
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        self.verify_half.try_add_remote_party(id, attestation)
+    }
+
+    fn remove_remote_party(&mut self, id: ReplicaId) -> bool {
+        self.verify_half.remove_remote_party(id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.verify_half.remote_parties()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.verify_half.contains(remote_usig_id)
+    }
+
+    type SignHalf = SimulatedTeeSignHalf;
+    type VerifyHalf = Ed25519VerifyHalf;
+
+    fn split(self) -> (Self::SignHalf, Self::VerifyHalf) {
+        (self.sign_half, self.verify_half)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io};
+
+    use super::{SealingKey, UsigSimulatedTee, SEALED_FILE};
+    use crate as usig;
+    use crate::tests;
+
+    const KEY: SealingKey = [3; 32];
+
+    fn is_rollback(error: &io::Error) -> bool {
+        error.kind() == io::ErrorKind::InvalidData
+            && matches!(
+                error.get_ref().and_then(|e| e.downcast_ref::<UsigError>()),
+                Some(UsigError::CounterRegression)
+            )
+    }
+
+    tests!(UsigSimulatedTee::open(tempfile::tempdir().unwrap().keep(), &KEY).unwrap());
+
+    #[test]
+    fn restart_continues_counter() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tee = UsigSimulatedTee::open(dir.path(), &KEY).unwrap();
+        let attestation = tee.attest().unwrap();
+        let first = tee.sign(MESSAGE_1).unwrap();
+        tee.sign(MESSAGE_1).unwrap();
+        drop(tee);
+
+        let mut tee = UsigSimulatedTee::open(dir.path(), &KEY).unwrap();
+        assert_eq!(tee.attest().unwrap().payload, attestation.payload);
+        let third = tee.sign(MESSAGE_2).unwrap();
+        assert_eq!(third.counter(), first.counter() + 2);
+        assert!(tee.add_remote_party(ID, attestation));
+        assert!(tee.verify(ID, MESSAGE_2, &third).is_ok());
+    }
+
+    #[test]
+    fn rollback_detected() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tee = UsigSimulatedTee::open(dir.path(), &KEY).unwrap();
+        tee.sign(MESSAGE_1).unwrap();
+        let snapshot = fs::read(dir.path().join(SEALED_FILE)).unwrap();
+        tee.sign(MESSAGE_1).unwrap();
+        drop(tee);
+
+        fs::write(dir.path().join(SEALED_FILE), snapshot).unwrap();
+        assert!(is_rollback(
+            &UsigSimulatedTee::open(dir.path(), &KEY).unwrap_err()
+        ));
+
+        fs::remove_file(dir.path().join(SEALED_FILE)).unwrap();
+        assert!(is_rollback(
+            &UsigSimulatedTee::open(dir.path(), &KEY).unwrap_err()
+        ));
+    }
+
+    #[test]
+    fn wrong_sealing_key() {
+        let dir = tempfile::tempdir().unwrap();
+        drop(UsigSimulatedTee::open(dir.path(), &KEY).unwrap());
+        let error = UsigSimulatedTee::open(dir.path(), &[4; 32]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(!is_rollback(&error));
+    }
+}
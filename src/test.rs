@@ -2,6 +2,7 @@
 macro_rules! tests {
     ($new_usig:expr) => {
         use usig::{
+            clock::{Clock as _, ManualClock, SystemClock},
             Counter as _, ReplicaId, SignHalf as _, Usig as _, UsigError,
             VerifyHalf as _,
         };
@@ -11,6 +12,12 @@ macro_rules! tests {
         const MESSAGE_2: &'static [u8] = b"message two";
         const ID: ReplicaId = ReplicaId::first();
 
+        #[test]
+        fn conformance() {
+            let report = usig::conformance::run_conformance(|| $new_usig);
+            assert!(report.passed(), "{}", report);
+        }
+
         #[test]
         fn as_ref() {
             struct Input<F: Fn()>(F);
@@ -46,6 +53,21 @@ macro_rules! tests {
             assert!(usig.verify(ID, MESSAGE_1, &signature).is_ok());
         }
 
+        #[test]
+        fn remove_party() {
+            let mut usig = $new_usig;
+            let attestation = usig.attest().unwrap();
+            assert!(!usig.remove_remote_party(ID));
+            assert!(usig.add_remote_party(ID, attestation));
+            let signature = usig.sign(MESSAGE_1).unwrap();
+            assert!(usig.remove_remote_party(ID));
+            assert!(!usig.remove_remote_party(ID));
+            assert!(matches!(
+                usig.verify(ID, MESSAGE_1, &signature),
+                Err(UsigError::UnknownId(ID))
+            ));
+        }
+
         #[test]
         fn empty_msg() {
             let mut usig = $new_usig;
@@ -55,6 +77,20 @@ macro_rules! tests {
             assert!(usig.verify(ID, MESSAGE_EMPTY, &signature).is_ok());
         }
 
+        #[test]
+        fn sign_receipt() {
+            let mut usig = $new_usig;
+            let attestation = usig.attest().unwrap();
+            assert!(usig.add_remote_party(ID, attestation));
+            let signature = usig.sign(MESSAGE_1).unwrap();
+            let receipt = usig
+                .sign_with_receipt(MESSAGE_2, &SystemClock, SystemClock.now())
+                .unwrap();
+            assert_eq!(signature.counter() + 1, receipt.signature.counter());
+            assert!(receipt.total_time() >= receipt.sign_time);
+            assert!(usig.verify(ID, MESSAGE_2, &receipt.signature).is_ok());
+        }
+
         #[test]
         fn double_sig() {
             let mut usig = $new_usig;
@@ -176,6 +212,35 @@ macro_rules! tests {
             ));
         }
 
+        #[test]
+        fn memory_usage() {
+            let mut usig = $new_usig;
+            let empty = usig.memory_usage();
+            for id in 0..10 {
+                let attestation = usig.attest().unwrap();
+                assert!(usig.add_remote_party(ReplicaId::from_u64(id), attestation));
+            }
+            let report = usig.memory_usage();
+            assert!(report.party_registry > empty.party_registry);
+            assert!(report.total() >= report.party_registry);
+        }
+
+        #[test]
+        fn pre_validate() {
+            let mut usig = $new_usig;
+            let signature = usig.sign(MESSAGE_1).unwrap();
+            assert!(matches!(
+                usig.pre_validate(ID, &signature),
+                Err(UsigError::UnknownId(ID))
+            ));
+            let attestation = usig.attest().unwrap();
+            assert!(usig.add_remote_party(ID, attestation));
+            assert!(usig.pre_validate(ID, &signature).is_ok());
+
+            let (_, verify_half) = usig.split();
+            assert!(verify_half.pre_validate(ID, &signature).is_ok());
+        }
+
         #[test]
         fn no_id() {
             let mut usig = $new_usig;
@@ -271,6 +336,38 @@ macro_rules! tests {
             assert!(verify.verify(ID, MESSAGE_1, &signature).is_ok());
         }
 
+        #[test]
+        fn remove_party_split() {
+            let (mut sign, mut verify) = $new_usig.split();
+            let attestation = sign.attest().unwrap();
+            assert!(verify.add_remote_party(ID, attestation));
+            let signature = sign.sign(MESSAGE_1).unwrap();
+            assert!(verify.remove_remote_party(ID));
+            assert!(matches!(
+                verify.verify(ID, MESSAGE_1, &signature),
+                Err(UsigError::UnknownId(ID))
+            ));
+            assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+            assert!(verify.verify(ID, MESSAGE_1, &signature).is_ok());
+        }
+
+        #[test]
+        fn remote_parties_split() {
+            let (mut sign, mut verify) = $new_usig.split();
+            let other = ReplicaId::from_u64(3);
+            assert_eq!(verify.remote_parties().count(), 0);
+            assert!(!verify.contains(ID));
+            assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+            assert!(verify.add_remote_party(other, sign.attest().unwrap()));
+            let mut parties: Vec<_> = verify.remote_parties().collect();
+            parties.sort();
+            assert_eq!(parties, [ID, other]);
+            assert!(verify.contains(other));
+            assert!(verify.remove_remote_party(other));
+            assert!(!verify.contains(other));
+            assert_eq!(verify.remote_parties().collect::<Vec<_>>(), [ID]);
+        }
+
         #[test]
         fn empty_msg_split() {
             let (mut sign, mut verify) = $new_usig.split();
@@ -280,6 +377,75 @@ macro_rules! tests {
             assert!(verify.verify(ID, MESSAGE_EMPTY, &signature).is_ok());
         }
 
+        #[test]
+        fn sign_batch_split() {
+            let (mut sign, mut verify) = $new_usig.split();
+            let attestation = sign.attest().unwrap();
+            assert!(verify.add_remote_party(ID, attestation));
+            let messages = [MESSAGE_1, MESSAGE_2, MESSAGE_EMPTY];
+            let signatures = sign.sign_batch(&messages).unwrap();
+            assert_eq!(signatures.len(), messages.len());
+            for (message, signature) in messages.iter().zip(&signatures) {
+                assert!(verify.verify(ID, message, signature).is_ok());
+            }
+            for pair in signatures.windows(2) {
+                assert_eq!(pair[1].counter(), pair[0].counter() + 1);
+            }
+            let next = sign.sign(MESSAGE_1).unwrap();
+            assert_eq!(next.counter(), signatures[2].counter() + 1);
+            assert!(sign.sign_batch(&[] as &[&[u8]]).unwrap().is_empty());
+        }
+
+        #[test]
+        fn verify_batch_split() {
+            let (mut sign, mut verify) = $new_usig.split();
+            let attestation = sign.attest().unwrap();
+            assert!(verify.add_remote_party(ID, attestation));
+            let signature_1 = sign.sign(MESSAGE_1).unwrap();
+            let signature_2 = sign.sign(MESSAGE_2).unwrap();
+            let valid = verify.verify_batch(&[(ID, MESSAGE_1, &signature_1), (ID, MESSAGE_2, &signature_2)]);
+            assert!(valid.iter().all(Result::is_ok));
+            let mixed = verify.verify_batch(&[
+                (ID, MESSAGE_1, &signature_1),
+                (ID, MESSAGE_1, &signature_2),
+                (ReplicaId::from_u64(2), MESSAGE_2, &signature_2),
+            ]);
+            assert_eq!(mixed.len(), 3);
+            assert!(mixed[0].is_ok());
+            assert!(mixed[1].is_err());
+            assert!(mixed[2].is_err());
+            assert!(verify.verify_batch(&[]).is_empty());
+        }
+
+        #[test]
+        fn context_split() {
+            let (mut sign, mut verify) = $new_usig.split();
+            let attestation = sign.attest().unwrap();
+            assert!(verify.add_remote_party(ID, attestation));
+            let signature = sign.sign_with_context(b"prepare", MESSAGE_1).unwrap();
+            assert!(verify
+                .verify_with_context(ID, b"prepare", MESSAGE_1, &signature)
+                .is_ok());
+            assert!(verify
+                .verify_with_context(ID, b"commit", MESSAGE_1, &signature)
+                .is_err());
+            assert!(verify.verify(ID, MESSAGE_1, &signature).is_err());
+        }
+
+        #[test]
+        fn sign_receipt_split() {
+            let (mut sign, mut verify) = $new_usig.split();
+            let attestation = sign.attest().unwrap();
+            assert!(verify.add_remote_party(ID, attestation));
+            let clock = ManualClock::new(::std::time::Duration::from_secs(3));
+            let enqueued_at = ::std::time::Duration::from_secs(1);
+            let receipt = sign.sign_with_receipt(MESSAGE_1, &clock, enqueued_at).unwrap();
+            assert_eq!(receipt.enqueued_at, enqueued_at);
+            assert_eq!(receipt.queue_time, ::std::time::Duration::from_secs(2));
+            assert_eq!(receipt.sign_time, ::std::time::Duration::ZERO);
+            assert!(verify.verify(ID, MESSAGE_1, &receipt.signature).is_ok());
+        }
+
         #[test]
         fn double_sig_split() {
             let (mut sign, mut verify) = $new_usig.split();
@@ -0,0 +1,372 @@
+use std::{any::Any, collections::HashMap, fmt::Debug, time::Duration};
+
+use derivative::Derivative;
+use serde::{Deserialize, Serialize};
+use shared_ids::ReplicaId;
+
+use crate::{
+    clock::{Clock, SystemClock},
+    Count, MemoryReport, SignHalf, UsigError, VerifyHalf,
+};
+
+/// Context of the nonce a challenged party signs, see [respond]
+pub const CHALLENGE_CONTEXT: &[u8] = b"usig attestation challenge";
+
+/// How long a challenge can be answered after it was issued
+pub const CHALLENGE_LIFETIME: Duration = Duration::from_secs(60);
+
+/// Most challenges outstanding at once, issuing more drops the oldest one
+pub const MAX_PENDING_CHALLENGES: usize = 1024;
+
+/// Answer a challenge issued with [TranscriptVerifyHalf::challenge]
+///
+/// The response is a USIG signature over the nonce, so it can only be produced with the
+/// key of the attestation after the challenge was issued.
+pub fn respond<S: SignHalf>(
+    sign_half: &mut S,
+    nonce: &[u8; 32],
+) -> Result<S::Signature, UsigError> {
+    sign_half.sign_with_context(CHALLENGE_CONTEXT, nonce)
+}
+
+/// Whether an attestation was accepted
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TranscriptOutcome {
+    Admitted,
+    /// The attestation was rejected, with the error it was rejected with
+    Rejected(String),
+}
+
+/// Everything a verify half saw and decided when a party presented an attestation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttestationTranscript {
+    /// When the attestation was checked, according to the clock of the verifier
+    pub timestamp: Duration,
+    /// The challenge answered with the attestation, if it was presented with
+    /// [TranscriptVerifyHalf::try_add_challenged_party]
+    pub nonce: Option<[u8; 32]>,
+    /// The bincode serialization of the presented attestation
+    pub evidence: Vec<u8>,
+    /// The bincode serialization of the signature over the nonce, see [respond]
+    pub response: Option<Vec<u8>>,
+    pub outcome: TranscriptOutcome,
+}
+
+/// A verify half recording an [AttestationTranscript] for every attestation it is given
+///
+/// Security audits can reconstruct from the transcripts why each party was admitted.
+/// Attestations added with [TranscriptVerifyHalf::try_add_challenged_party] are proven
+/// fresh, the transcript holds the nonce and the signature over it.
+#[derive(Derivative)]
+#[derivative(Debug(bound = "V: Debug, C: Debug"))]
+pub struct TranscriptVerifyHalf<V, C = SystemClock> {
+    inner: V,
+    clock: C,
+    /// The outstanding nonce of each party and when it was issued
+    challenges: HashMap<ReplicaId, ([u8; 32], Duration)>,
+    transcripts: HashMap<ReplicaId, Vec<AttestationTranscript>>,
+}
+
+impl<V: VerifyHalf> TranscriptVerifyHalf<V>
+where
+    V::Attestation: Serialize,
+{
+    pub fn new(inner: V) -> Self {
+        Self::with_clock(inner, SystemClock)
+    }
+}
+
+impl<V: VerifyHalf, C: Clock> TranscriptVerifyHalf<V, C>
+where
+    V::Attestation: Serialize,
+{
+    pub fn with_clock(inner: V, clock: C) -> Self {
+        Self {
+            inner,
+            clock,
+            challenges: HashMap::new(),
+            transcripts: HashMap::new(),
+        }
+    }
+
+    /// Issue a fresh nonce for the next attestation of `id`
+    ///
+    /// The party has to answer with [respond] within [CHALLENGE_LIFETIME], the nonce
+    /// replaces any earlier one of `id`. At most [MAX_PENDING_CHALLENGES] are kept.
+    pub fn challenge(&mut self, id: ReplicaId) -> [u8; 32] {
+        let now = self.clock.now();
+        self.challenges
+            .retain(|_, (_, issued)| now.saturating_sub(*issued) <= CHALLENGE_LIFETIME);
+        if self.challenges.len() >= MAX_PENDING_CHALLENGES && !self.challenges.contains_key(&id) {
+            let oldest = self
+                .challenges
+                .iter()
+                .min_by_key(|(_, (_, issued))| *issued)
+                .map(|(id, _)| *id);
+            if let Some(oldest) = oldest {
+                self.challenges.remove(&oldest);
+            }
+        }
+        let nonce = rand::random();
+        self.challenges.insert(id, (nonce, now));
+        nonce
+    }
+
+    /// Add a party that answered the challenge of [TranscriptVerifyHalf::challenge]
+    ///
+    /// The attestation is accepted only if `response` is a valid signature over the
+    /// outstanding nonce of `id` under the attested key. Otherwise the party is not added,
+    /// or removed if it was known before, and the challenge has to be issued again.
+    pub fn try_add_challenged_party(
+        &mut self,
+        remote_usig_id: ReplicaId,
+        attestation: V::Attestation,
+        response: &V::Signature,
+    ) -> Result<(), UsigError>
+    where
+        V::Signature: Serialize,
+    {
+        let evidence = bincode::serialize(&attestation)?;
+        let encoded_response = bincode::serialize(response)?;
+        let now = self.clock.now();
+        let nonce = self
+            .challenges
+            .remove(&remote_usig_id)
+            .filter(|(_, issued)| now.saturating_sub(*issued) <= CHALLENGE_LIFETIME)
+            .map(|(nonce, _)| nonce);
+        let result = match nonce {
+            None => Err(UsigError::RemoteAttestationFailed),
+            Some(nonce) => self
+                .inner
+                .try_add_remote_party(remote_usig_id, attestation)
+                .and_then(|()| {
+                    self.inner
+                        .verify_with_context(remote_usig_id, CHALLENGE_CONTEXT, nonce, response)
+                        .inspect_err(|_| {
+                            self.inner.remove_remote_party(remote_usig_id);
+                        })
+                }),
+        };
+        self.record(
+            remote_usig_id,
+            nonce,
+            evidence,
+            Some(encoded_response),
+            &result,
+        );
+        result
+    }
+
+    fn record(
+        &mut self,
+        id: ReplicaId,
+        nonce: Option<[u8; 32]>,
+        evidence: Vec<u8>,
+        response: Option<Vec<u8>>,
+        result: &Result<(), UsigError>,
+    ) {
+        let outcome = match result {
+            Ok(()) => TranscriptOutcome::Admitted,
+            Err(error) => TranscriptOutcome::Rejected(error.to_string()),
+        };
+        self.transcripts
+            .entry(id)
+            .or_default()
+            .push(AttestationTranscript {
+                timestamp: self.clock.now(),
+                nonce,
+                evidence,
+                response,
+                outcome,
+            });
+    }
+
+    /// All transcripts of `id`, oldest first
+    pub fn transcripts(&self, id: ReplicaId) -> &[AttestationTranscript] {
+        self.transcripts.get(&id).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn into_transcripts(self) -> HashMap<ReplicaId, Vec<AttestationTranscript>> {
+        self.transcripts
+    }
+}
+
+impl<V: VerifyHalf, C: Clock> VerifyHalf for TranscriptVerifyHalf<V, C>
+where
+    V::Attestation: Serialize,
+{
+    type Signature = V::Signature;
+    type Attestation = V::Attestation;
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        self.inner.verify(id, message, signature)
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.inner.pre_validate(id, signature)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        let mut report = self.inner.memory_usage();
+        report.evidence += self
+            .transcripts
+            .values()
+            .flatten()
+            .map(|transcript| {
+                size_of_val(transcript)
+                    + transcript.evidence.capacity()
+                    + transcript.response.as_ref().map_or(0, Vec::capacity)
+            })
+            .sum::<usize>();
+        report
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        remote_usig_id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        let evidence = bincode::serialize(&attestation)?;
+        let result = self.inner.try_add_remote_party(remote_usig_id, attestation);
+        self.record(remote_usig_id, None, evidence, None, &result);
+        result
+    }
+
+    fn remove_remote_party(&mut self, remote_usig_id: ReplicaId) -> bool {
+        self.challenges.remove(&remote_usig_id);
+        self.inner.remove_remote_party(remote_usig_id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.inner.remote_parties()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.inner.contains(remote_usig_id)
+    }
+
+    fn last_counter(&self, id: ReplicaId) -> Option<Count> {
+        self.inner.last_counter(id)
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any()
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        clock::ManualClock,
+        signature::{new_ed25519, new_p256},
+        Attestation, SignHalf, Usig,
+    };
+
+    const ID: ReplicaId = ReplicaId::first();
+
+    #[test]
+    fn records_admission_and_rejection() {
+        let clock = ManualClock::new(Duration::from_secs(5));
+        let (mut sign, verify) = new_ed25519().split();
+        let mut verify = TranscriptVerifyHalf::with_clock(verify, &clock);
+
+        let mut attestation = sign.attest().unwrap();
+        attestation.parameters = new_p256().attest().unwrap().parameters;
+        assert!(!verify.add_remote_party(ID, attestation));
+
+        clock.advance(Duration::from_secs(1));
+        let nonce = verify.challenge(ID);
+        let attestation = sign.attest().unwrap();
+        let response = respond(&mut sign, &nonce).unwrap();
+        verify
+            .try_add_challenged_party(ID, attestation.clone(), &response)
+            .unwrap();
+
+        let transcripts = verify.transcripts(ID);
+        assert_eq!(transcripts.len(), 2);
+        assert!(matches!(
+            &transcripts[0].outcome,
+            TranscriptOutcome::Rejected(reason) if reason.contains("ECDSA P-256")
+        ));
+        assert_eq!(transcripts[0].nonce, None);
+        assert_eq!(transcripts[1].outcome, TranscriptOutcome::Admitted);
+        assert_eq!(transcripts[1].nonce, Some(nonce));
+        assert_eq!(transcripts[1].timestamp, Duration::from_secs(6));
+        let evidence: Attestation<ed25519_dalek::VerifyingKey> =
+            bincode::deserialize(&transcripts[1].evidence).unwrap();
+        assert_eq!(evidence.payload, attestation.payload);
+        assert!(transcripts[1].response.is_some());
+
+        assert!(verify.transcripts(ReplicaId::from_u64(1)).is_empty());
+    }
+
+    #[test]
+    fn rejects_unfresh_response() {
+        let clock = ManualClock::new(Duration::ZERO);
+        let (mut sign, verify) = new_ed25519().split();
+        let mut verify = TranscriptVerifyHalf::with_clock(verify, &clock);
+        let attestation = sign.attest().unwrap();
+
+        // a response to an earlier challenge
+        let old = verify.challenge(ID);
+        let response = respond(&mut sign, &old).unwrap();
+        verify.challenge(ID);
+        assert!(matches!(
+            verify.try_add_challenged_party(ID, attestation.clone(), &response),
+            Err(UsigError::InvalidSignature)
+        ));
+        assert!(!verify.contains(ID));
+
+        // the challenge was consumed by the failed attempt
+        assert!(matches!(
+            verify.try_add_challenged_party(ID, attestation.clone(), &response),
+            Err(UsigError::RemoteAttestationFailed)
+        ));
+
+        let nonce = verify.challenge(ID);
+        let response = respond(&mut sign, &nonce).unwrap();
+        clock.advance(CHALLENGE_LIFETIME + Duration::from_secs(1));
+        assert!(verify
+            .try_add_challenged_party(ID, attestation, &response)
+            .is_err());
+        assert!(!verify.contains(ID));
+        assert_eq!(verify.transcripts(ID).len(), 3);
+    }
+
+    #[test]
+    fn bounded_challenges() {
+        let clock = ManualClock::new(Duration::ZERO);
+        let (mut sign, verify) = new_ed25519().split();
+        let mut verify = TranscriptVerifyHalf::with_clock(verify, &clock);
+        let first = verify.challenge(ID);
+        for id in 1..=MAX_PENDING_CHALLENGES as u64 {
+            clock.advance(Duration::from_millis(1));
+            verify.challenge(ReplicaId::from_u64(id));
+        }
+        assert_eq!(verify.challenges.len(), MAX_PENDING_CHALLENGES);
+        let response = respond(&mut sign, &first).unwrap();
+        assert!(verify
+            .try_add_challenged_party(ID, sign.attest().unwrap(), &response)
+            .is_err());
+
+        clock.advance(CHALLENGE_LIFETIME * 2);
+        verify.challenge(ID);
+        assert_eq!(verify.challenges.len(), 1);
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use shared_ids::ReplicaId;
+
+use crate::{
+    encoding::with_context,
+    semantics::{CounterSemantics, Monotonic},
+    Count, SignHalf, UsigError, VerifyHalf,
+};
+
+/// Context of the watermarks a [WatermarkSnapshot] signs
+pub const SNAPSHOT_CONTEXT: &[u8] = b"usig watermark snapshot";
+
+/// The highest verified counter of each remote party
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HighWatermarks {
+    watermarks: BTreeMap<ReplicaId, Count>,
+}
+
+impl HighWatermarks {
+    pub fn get(&self, id: ReplicaId) -> Option<Count> {
+        self.watermarks.get(&id).copied()
+    }
+
+    /// Record a verified counter of `id`
+    ///
+    /// Returns `false` if the counter is not above the watermark, i.e. it is a duplicate or was reordered.
+    pub fn observe(&mut self, id: ReplicaId, counter: Count) -> bool {
+        self.observe_with(&Monotonic, id, counter)
+    }
+
+    /// Record a verified counter of `id` if `semantics` allow it to follow the watermark
+    pub fn observe_with(
+        &mut self,
+        semantics: &impl CounterSemantics,
+        id: ReplicaId,
+        counter: Count,
+    ) -> bool {
+        match self.watermarks.get_mut(&id) {
+            Some(watermark) if !semantics.is_valid_next(*watermark, counter) => false,
+            Some(watermark) => {
+                *watermark = counter;
+                true
+            }
+            None => {
+                self.watermarks.insert(id, counter);
+                true
+            }
+        }
+    }
+
+    /// Forget the watermark of `id`, e.g. when the party is removed
+    pub fn remove(&mut self, id: ReplicaId) -> Option<Count> {
+        self.watermarks.remove(&id)
+    }
+
+    /// Raise every watermark to the highest counter at least `f + 1` of `vouched` reached
+    ///
+    /// `vouched` are the verified watermarks of distinct peers, e.g. from
+    /// [WatermarkSnapshot]s. With at most `f` Byzantine peers one of them is correct, so a
+    /// Byzantine peer can not raise a watermark to make the honest counters of a party stale.
+    pub fn merge(&mut self, vouched: &BTreeMap<ReplicaId, HighWatermarks>, f: usize) {
+        let mut counters = BTreeMap::<_, Vec<_>>::new();
+        for (id, counter) in vouched.values().flat_map(HighWatermarks::iter) {
+            counters.entry(id).or_default().push(counter);
+        }
+        for (id, mut counters) in counters {
+            if counters.len() > f {
+                counters.sort_unstable_by(|a, b| b.cmp(a));
+                self.observe(id, counters[f]);
+            }
+        }
+    }
+
+    /// Raise every watermark to the one in `other` if that is higher, only for watermarks
+    /// this replica verified itself
+    pub(crate) fn merge_trusted(&mut self, other: &HighWatermarks) {
+        for (&id, &counter) in &other.watermarks {
+            self.observe(id, counter);
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (ReplicaId, Count)> + '_ {
+        self.watermarks.iter().map(|(&id, &counter)| (id, counter))
+    }
+
+    /// Sign the watermarks with the USIG of this replica so a peer can import them
+    pub fn snapshot<S: SignHalf>(
+        &self,
+        sign_half: &mut S,
+    ) -> Result<WatermarkSnapshot<S::Signature>, UsigError> {
+        let signature = sign_half.sign(snapshot_message(self))?;
+        Ok(WatermarkSnapshot {
+            watermarks: self.clone(),
+            signature,
+        })
+    }
+}
+
+fn snapshot_message(watermarks: &HighWatermarks) -> Vec<u8> {
+    let watermarks = bincode::serialize(watermarks).expect("serialization to memory does not fail");
+    with_context(SNAPSHOT_CONTEXT, &watermarks)
+}
+
+/// [HighWatermarks] signed by the peer that verified them
+///
+/// Lets a freshly joined replica start duplicate and equivocation detection from the
+/// baselines of its peers instead of counter zero, see [HighWatermarks::merge].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatermarkSnapshot<S> {
+    pub watermarks: HighWatermarks,
+    pub signature: S,
+}
+
+impl<S> WatermarkSnapshot<S> {
+    /// Check the snapshot was signed by `peer` and return its watermarks
+    pub fn verify<V: VerifyHalf<Signature = S>>(
+        self,
+        verifier: &V,
+        peer: ReplicaId,
+    ) -> Result<HighWatermarks, UsigError> {
+        verifier.verify(peer, snapshot_message(&self.watermarks), &self.signature)?;
+        Ok(self.watermarks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::Hmac;
+    use sha2::Sha256;
+
+    use super::*;
+    use crate::{hmac::UsigHmac, semantics::StrictIncrement, Usig};
+
+    const PEER: ReplicaId = ReplicaId::first();
+
+    #[test]
+    fn observe() {
+        let id = ReplicaId::from_u64(1);
+        let mut watermarks = HighWatermarks::default();
+        assert_eq!(watermarks.get(id), None);
+        assert!(watermarks.observe(id, Count(3)));
+        assert!(!watermarks.observe(id, Count(3)));
+        assert!(!watermarks.observe(id, Count(2)));
+        assert!(watermarks.observe(id, Count(4)));
+        assert_eq!(watermarks.get(id), Some(Count(4)));
+    }
+
+    #[test]
+    fn observe_with() {
+        let id = ReplicaId::from_u64(1);
+        let mut watermarks = HighWatermarks::default();
+        assert!(watermarks.observe_with(&StrictIncrement, id, Count(3)));
+        assert!(!watermarks.observe_with(&StrictIncrement, id, Count(5)));
+        assert!(watermarks.observe_with(&StrictIncrement, id, Count(4)));
+        assert_eq!(watermarks.get(id), Some(Count(4)));
+    }
+
+    #[test]
+    fn warm_start() {
+        let (id_1, id_2) = (ReplicaId::from_u64(1), ReplicaId::from_u64(2));
+        let usig = UsigHmac::<Hmac<Sha256>>::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap();
+        let (mut sign, mut verify) = usig.split();
+        assert!(verify.add_remote_party(PEER, sign.attest().unwrap()));
+
+        let mut peer = HighWatermarks::default();
+        peer.observe(id_1, Count(10));
+        peer.observe(id_2, Count(20));
+        let snapshot = peer.snapshot(&mut sign).unwrap();
+
+        let mut other = HighWatermarks::default();
+        other.observe(id_1, Count(12));
+        other.observe(id_2, Count(18));
+        let vouched = BTreeMap::from([
+            (PEER, snapshot.verify(&verify, PEER).unwrap()),
+            (ReplicaId::from_u64(3), other),
+        ]);
+
+        let mut joined = HighWatermarks::default();
+        joined.observe(id_1, Count(15));
+        joined.merge(&vouched, 1);
+        assert_eq!(joined.get(id_1), Some(Count(15)));
+        assert_eq!(joined.get(id_2), Some(Count(18)));
+        assert!(!joined.observe(id_2, Count(5)));
+        assert!(joined.observe(id_2, Count(19)));
+    }
+
+    #[test]
+    fn merge_needs_f_plus_one() {
+        let id = ReplicaId::from_u64(1);
+        let mut byzantine = HighWatermarks::default();
+        byzantine.observe(id, Count(u64::MAX));
+        let mut correct = HighWatermarks::default();
+        correct.observe(id, Count(3));
+
+        let mut watermarks = HighWatermarks::default();
+        watermarks.merge(&BTreeMap::from([(PEER, byzantine.clone())]), 1);
+        assert_eq!(watermarks.get(id), None);
+
+        watermarks.merge(&BTreeMap::from([(PEER, byzantine), (id, correct)]), 1);
+        assert_eq!(watermarks.get(id), Some(Count(3)));
+        assert!(watermarks.observe(id, Count(4)));
+    }
+
+    #[test]
+    fn tampered_snapshot() {
+        let usig = UsigHmac::<Hmac<Sha256>>::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap();
+        let (mut sign, mut verify) = usig.split();
+        assert!(verify.add_remote_party(PEER, sign.attest().unwrap()));
+
+        let mut snapshot = HighWatermarks::default().snapshot(&mut sign).unwrap();
+        snapshot
+            .watermarks
+            .observe(ReplicaId::from_u64(1), Count(u64::MAX));
+        assert!(matches!(
+            snapshot.verify(&verify, PEER),
+            Err(UsigError::InvalidSignature)
+        ));
+    }
+}
@@ -0,0 +1,347 @@
+use std::{any::Any, collections::HashMap, fmt};
+
+use serde::{Deserialize, Serialize};
+use shared_ids::ReplicaId;
+
+use crate::{
+    encoding::with_context, Count, Counter, MemoryReport, SignHalf, UsigError, VerifyHalf,
+};
+
+/// Context under which an [EpochSignHalf] signs the epoch and the message
+pub const EPOCH_CONTEXT: &[u8] = b"usig epoch";
+
+/// A 128-bit counter value, an epoch in the upper and a [Count] in the lower half
+///
+/// For deployments outliving the 64-bit counter of a single key.
+#[repr(transparent)]
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, Ord, Eq, PartialEq, PartialOrd, Default, Hash,
+)]
+pub struct Count128(pub u128);
+
+impl fmt::Display for Count128 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({0})", self.0)
+    }
+}
+
+impl Count128 {
+    pub const fn from_parts(epoch: u64, count: Count) -> Self {
+        Self((epoch as u128) << 64 | count.0 as u128)
+    }
+
+    pub const fn epoch(self) -> u64 {
+        (self.0 >> 64) as u64
+    }
+
+    pub const fn count(self) -> Count {
+        Count(self.0 as u64)
+    }
+
+    /// The counter `rhs` values later, [None] on overflow
+    pub const fn checked_add(self, rhs: u128) -> Option<Count128> {
+        match self.0.checked_add(rhs) {
+            Some(counter) => Some(Count128(counter)),
+            None => None,
+        }
+    }
+}
+
+/// A count of epoch zero
+impl From<Count> for Count128 {
+    fn from(count: Count) -> Self {
+        Self::from_parts(0, count)
+    }
+}
+
+/// Retrieval of the full 128-bit counter value of a signature
+pub trait WideCounter: Counter {
+    fn wide_counter(&self) -> Count128;
+}
+
+/// A signature made in an epoch of an [EpochSignHalf]
+///
+/// [Counter::counter] is the counter within the epoch, [WideCounter::wide_counter] orders
+/// signatures across epochs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EpochSignature<S> {
+    pub epoch: u64,
+    pub signature: S,
+}
+
+impl<S: Counter> Counter for EpochSignature<S> {
+    fn counter(&self) -> Count {
+        self.signature.counter()
+    }
+}
+
+impl<S: Counter> WideCounter for EpochSignature<S> {
+    fn wide_counter(&self) -> Count128 {
+        Count128::from_parts(self.epoch, self.signature.counter())
+    }
+}
+
+/// The attestation of the backend of an epoch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EpochAttestation<A> {
+    pub epoch: u64,
+    pub attestation: A,
+}
+
+fn epoch_message(epoch: u64, message: &[u8]) -> Vec<u8> {
+    with_context(EPOCH_CONTEXT, &[&epoch.to_be_bytes(), message].concat())
+}
+
+/// A sign half with 128-bit counters made of an epoch and the counter of its backend
+///
+/// Once the counter of the backend is exhausted, [EpochSignHalf::rotate] continues with a
+/// fresh backend in the next epoch. Peers have to add the new attestation to keep verifying.
+/// The epoch is part of the signed message, so a signature can not be moved to another epoch.
+#[derive(Debug)]
+pub struct EpochSignHalf<S> {
+    inner: S,
+    epoch: u64,
+}
+
+impl<S: SignHalf> EpochSignHalf<S> {
+    pub fn new(inner: S, epoch: u64) -> Self {
+        Self { inner, epoch }
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Continue with `inner` in the next epoch and return the backend of the current one
+    pub fn rotate(&mut self, inner: S) -> Result<S, UsigError> {
+        self.epoch = self
+            .epoch
+            .checked_add(1)
+            .ok_or(UsigError::CounterExhausted)?;
+        Ok(std::mem::replace(&mut self.inner, inner))
+    }
+}
+
+impl<S: SignHalf> SignHalf for EpochSignHalf<S> {
+    type Signature = EpochSignature<S::Signature>;
+    type Attestation = EpochAttestation<S::Attestation>;
+
+    fn sign(&mut self, message: impl AsRef<[u8]>) -> Result<Self::Signature, UsigError> {
+        let signature = self
+            .inner
+            .sign(epoch_message(self.epoch, message.as_ref()))?;
+        Ok(EpochSignature {
+            epoch: self.epoch,
+            signature,
+        })
+    }
+
+    fn attest(&mut self) -> Result<Self::Attestation, UsigError> {
+        Ok(EpochAttestation {
+            epoch: self.epoch,
+            attestation: self.inner.attest()?,
+        })
+    }
+
+    fn local_id(&self) -> Option<ReplicaId> {
+        self.inner.local_id()
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any()
+    }
+
+    /// `None`, the backend would sign without the epoch, burning counters peers never verify
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        None
+    }
+}
+
+/// Verifies the signatures of [EpochSignHalf]s in the latest attested epoch of each party
+///
+/// Signatures of earlier epochs are rejected with [UsigError::InvalidSignature], the key
+/// of the epoch was replaced. Attestations of earlier epochs are rejected with
+/// [UsigError::Outdated].
+#[derive(Debug)]
+pub struct EpochVerifyHalf<V> {
+    inner: V,
+    epochs: HashMap<ReplicaId, u64>,
+}
+
+impl<V: VerifyHalf> EpochVerifyHalf<V> {
+    pub fn new(inner: V) -> Self {
+        Self {
+            inner,
+            epochs: HashMap::new(),
+        }
+    }
+
+    /// The latest attested epoch of `id`
+    pub fn epoch(&self, id: ReplicaId) -> Option<u64> {
+        self.epochs.get(&id).copied()
+    }
+
+    fn check_epoch(&self, id: ReplicaId, epoch: u64) -> Result<(), UsigError> {
+        match self.epochs.get(&id) {
+            None => Err(UsigError::UnknownId(id)),
+            Some(&current) if current != epoch => Err(UsigError::InvalidSignature),
+            Some(_) => Ok(()),
+        }
+    }
+}
+
+impl<V: VerifyHalf> VerifyHalf for EpochVerifyHalf<V> {
+    type Signature = EpochSignature<V::Signature>;
+    type Attestation = EpochAttestation<V::Attestation>;
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        self.check_epoch(id, signature.epoch)?;
+        self.inner.verify(
+            id,
+            epoch_message(signature.epoch, message.as_ref()),
+            &signature.signature,
+        )
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.check_epoch(id, signature.epoch)?;
+        self.inner.pre_validate(id, &signature.signature)
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        let mut report = self.inner.memory_usage();
+        report.party_registry += size_of_val(&self.epochs)
+            + self.epochs.capacity() * (size_of::<(ReplicaId, u64)>() + 1);
+        report
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        if self
+            .epoch(id)
+            .is_some_and(|epoch| attestation.epoch < epoch)
+        {
+            return Err(UsigError::Outdated);
+        }
+        self.inner
+            .try_add_remote_party(id, attestation.attestation)?;
+        self.epochs.insert(id, attestation.epoch);
+        Ok(())
+    }
+
+    fn remove_remote_party(&mut self, remote_usig_id: ReplicaId) -> bool {
+        self.epochs.remove(&remote_usig_id);
+        self.inner.remove_remote_party(remote_usig_id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.inner.remote_parties()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.inner.contains(remote_usig_id)
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any()
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::Hmac;
+    use sha2::Sha256;
+
+    use super::*;
+    use crate::{hmac::UsigHmac, standby::ResumableSignHalf, Usig};
+
+    type HmacUsig = UsigHmac<Hmac<Sha256>>;
+
+    const ID: ReplicaId = ReplicaId::first();
+    const MESSAGE: &[u8] = b"message";
+
+    fn usig() -> HmacUsig {
+        HmacUsig::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap()
+    }
+
+    #[test]
+    fn count128() {
+        let counter = Count128::from_parts(3, Count(7));
+        assert_eq!(counter.epoch(), 3);
+        assert_eq!(counter.count(), Count(7));
+        assert!(Count128::from_parts(2, Count(u64::MAX)) < counter);
+        assert_eq!(Count128::from(Count(7)), Count128(7));
+        assert_eq!(Count128(u128::MAX).checked_add(1), None);
+    }
+
+    #[test]
+    fn rotation() {
+        let (sign, verify) = usig().split();
+        let mut sign = EpochSignHalf::new(sign, 0);
+        let mut verify = EpochVerifyHalf::new(verify);
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+        sign.inner.resume_at(Count(u64::MAX - 1)).unwrap();
+
+        let last = sign.sign(MESSAGE).unwrap();
+        assert!(verify.verify(ID, MESSAGE, &last).is_ok());
+        assert!(matches!(
+            sign.sign(MESSAGE),
+            Err(UsigError::CounterExhausted)
+        ));
+
+        let stale = sign.attest().unwrap();
+        sign.rotate(usig().split().0).unwrap();
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+        assert!(matches!(
+            verify.try_add_remote_party(ID, stale),
+            Err(UsigError::Outdated)
+        ));
+        let first = sign.sign(MESSAGE).unwrap();
+        assert!(first.wide_counter() > last.wide_counter());
+        assert_eq!(first.wide_counter(), Count128::from_parts(1, Count(0)));
+        assert!(verify.verify(ID, MESSAGE, &first).is_ok());
+        assert!(matches!(
+            verify.verify(ID, MESSAGE, &last),
+            Err(UsigError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn moved_epoch() {
+        let (sign, verify) = usig().split();
+        let mut sign = EpochSignHalf::new(sign, 5);
+        let mut verify = EpochVerifyHalf::new(verify);
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+
+        let mut signature = sign.sign(MESSAGE).unwrap();
+        signature.epoch = 6;
+        assert!(matches!(
+            verify.verify(ID, MESSAGE, &signature),
+            Err(UsigError::InvalidSignature)
+        ));
+    }
+}
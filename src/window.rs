@@ -0,0 +1,283 @@
+use std::{
+    any::Any,
+    collections::{BTreeSet, HashMap},
+    sync::Mutex,
+};
+
+use shared_ids::ReplicaId;
+
+use crate::{Count, Counter, MemoryReport, UsigError, VerifyHalf};
+
+/// The counters of a party accepted at or above its low watermark
+#[derive(Debug, Default)]
+struct Window {
+    low: Count,
+    accepted: BTreeSet<Count>,
+}
+
+impl Window {
+    /// Move the low watermark over the accepted counters directly following it
+    fn slide(&mut self) {
+        while self.accepted.first() == Some(&self.low) {
+            self.accepted.pop_first();
+            self.low += 1;
+        }
+    }
+}
+
+/// A verify half only accepting counters within a window of each party, like PBFT watermarks
+///
+/// The window of a party starts at the first counter verified for it, so a verifier that
+/// restarts or joins late accepts the current counters, like [crate::monotonic]. From then
+/// on counters below the low watermark or at and above `low + size` are rejected with
+/// [UsigError::OutsideWindow] before the signature is verified. Within the window counters
+/// may arrive in any order, each at most once. The low watermark slides over the accepted
+/// counters directly following it or is moved by [WindowVerifyHalf::advance], e.g. on a
+/// stable checkpoint, so at most `size` counters are remembered per party.
+#[derive(Debug)]
+pub struct WindowVerifyHalf<V> {
+    inner: V,
+    size: u64,
+    windows: Mutex<HashMap<ReplicaId, Window>>,
+}
+
+impl<V: VerifyHalf> WindowVerifyHalf<V> {
+    pub fn new(inner: V, size: u64) -> Self {
+        Self {
+            inner,
+            size,
+            windows: Mutex::default(),
+        }
+    }
+
+    /// The low watermark of `id`, zero until a counter of `id` was accepted or it was advanced
+    pub fn low_watermark(&self, id: ReplicaId) -> Count {
+        self.windows
+            .lock()
+            .unwrap()
+            .get(&id)
+            .map_or(Count(0), |window| window.low)
+    }
+
+    /// Move the low watermark of `id` to `low`, never backwards
+    pub fn advance(&self, id: ReplicaId, low: Count) {
+        let mut windows = self.windows.lock().unwrap();
+        let window = windows.entry(id).or_default();
+        if low > window.low {
+            window.low = low;
+            window.accepted = window.accepted.split_off(&low);
+            window.slide();
+        }
+    }
+
+    pub fn into_inner(self) -> V {
+        self.inner
+    }
+
+    fn check(
+        &self,
+        window: Option<&Window>,
+        id: ReplicaId,
+        counter: Count,
+    ) -> Result<(), UsigError> {
+        let Some(window) = window else {
+            return Ok(());
+        };
+        let low = window.low;
+        let high = low.checked_add(self.size).unwrap_or(Count(u64::MAX));
+        if counter < low || counter >= high {
+            return Err(UsigError::OutsideWindow {
+                id,
+                counter,
+                low,
+                high,
+            });
+        }
+        if window.accepted.contains(&counter) {
+            return Err(UsigError::StaleCounter {
+                id,
+                last: counter,
+                counter,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl<V: VerifyHalf> VerifyHalf for WindowVerifyHalf<V> {
+    type Signature = V::Signature;
+    type Attestation = V::Attestation;
+
+    fn verify(
+        &self,
+        id: ReplicaId,
+        message: impl AsRef<[u8]>,
+        signature: &Self::Signature,
+    ) -> Result<(), UsigError> {
+        let counter = signature.counter();
+        self.pre_validate(id, signature)?;
+        self.inner.verify(id, message, signature)?;
+        let mut windows = self.windows.lock().unwrap();
+        // the window may have moved or accepted the counter in the meantime
+        self.check(windows.get(&id), id, counter)?;
+        let window = windows.entry(id).or_insert_with(|| Window {
+            low: counter,
+            accepted: BTreeSet::new(),
+        });
+        window.accepted.insert(counter);
+        window.slide();
+        Ok(())
+    }
+
+    fn pre_validate(&self, id: ReplicaId, signature: &Self::Signature) -> Result<(), UsigError> {
+        self.check(
+            self.windows.lock().unwrap().get(&id),
+            id,
+            signature.counter(),
+        )?;
+        self.inner.pre_validate(id, signature)
+    }
+
+    fn last_counter(&self, id: ReplicaId) -> Option<Count> {
+        let windows = self.windows.lock().unwrap();
+        let window = windows.get(&id)?;
+        window
+            .accepted
+            .last()
+            .copied()
+            .or_else(|| window.low.checked_sub(1))
+    }
+
+    fn memory_usage(&self) -> MemoryReport {
+        let windows = self.windows.lock().unwrap();
+        let mut report = self.inner.memory_usage();
+        report.evidence += windows
+            .values()
+            .map(|window| {
+                size_of::<(ReplicaId, Window)>() + window.accepted.len() * size_of::<Count>()
+            })
+            .sum::<usize>();
+        report
+    }
+
+    fn try_add_remote_party(
+        &mut self,
+        remote_usig_id: ReplicaId,
+        attestation: Self::Attestation,
+    ) -> Result<(), UsigError> {
+        self.inner.try_add_remote_party(remote_usig_id, attestation)
+    }
+
+    fn remove_remote_party(&mut self, remote_usig_id: ReplicaId) -> bool {
+        self.windows.get_mut().unwrap().remove(&remote_usig_id);
+        self.inner.remove_remote_party(remote_usig_id)
+    }
+
+    fn remote_parties(&self) -> impl Iterator<Item = ReplicaId> + '_ {
+        self.inner.remote_parties()
+    }
+
+    fn contains(&self, remote_usig_id: ReplicaId) -> bool {
+        self.inner.contains(remote_usig_id)
+    }
+
+    fn as_any(&self) -> Option<&dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any()
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any>
+    where
+        Self: 'static,
+    {
+        self.inner.as_any_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::Hmac;
+    use sha2::Sha256;
+
+    use super::*;
+    use crate::{hmac::UsigHmac, SignHalf, Usig};
+
+    type HmacUsig = UsigHmac<Hmac<Sha256>>;
+
+    const ID: ReplicaId = ReplicaId::first();
+    const MESSAGE: &[u8] = b"message";
+
+    #[test]
+    fn window() {
+        let usig = HmacUsig::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap();
+        let (mut sign, verify) = usig.split();
+        let mut verify = WindowVerifyHalf::new(verify, 4);
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+        let signatures: Vec<_> = (0..8).map(|_| sign.sign(MESSAGE).unwrap()).collect();
+
+        assert!(verify.verify(ID, MESSAGE, &signatures[0]).is_ok());
+        assert!(matches!(
+            verify.pre_validate(ID, &signatures[5]),
+            Err(UsigError::OutsideWindow {
+                low: Count(1),
+                high: Count(5),
+                ..
+            })
+        ));
+        assert!(verify.verify(ID, MESSAGE, &signatures[2]).is_ok());
+        assert!(matches!(
+            verify.verify(ID, MESSAGE, &signatures[2]),
+            Err(UsigError::StaleCounter { .. })
+        ));
+        assert_eq!(verify.low_watermark(ID), Count(1));
+        assert_eq!(verify.last_counter(ID), Some(Count(2)));
+
+        assert!(verify.verify(ID, MESSAGE, &signatures[1]).is_ok());
+        assert_eq!(verify.low_watermark(ID), Count(3));
+        assert!(verify.verify(ID, MESSAGE, &signatures[6]).is_ok());
+    }
+
+    #[test]
+    fn advance() {
+        let usig = HmacUsig::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap();
+        let (mut sign, verify) = usig.split();
+        let mut verify = WindowVerifyHalf::new(verify, 4);
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+        let signatures: Vec<_> = (0..8).map(|_| sign.sign(MESSAGE).unwrap()).collect();
+
+        assert!(verify.verify(ID, MESSAGE, &signatures[0]).is_ok());
+        assert!(verify.verify(ID, MESSAGE, &signatures[3]).is_ok());
+        verify.advance(ID, Count(2));
+        assert_eq!(verify.low_watermark(ID), Count(2));
+        verify.advance(ID, Count(1));
+        assert_eq!(verify.low_watermark(ID), Count(2));
+        assert!(matches!(
+            verify.verify(ID, MESSAGE, &signatures[1]),
+            Err(UsigError::OutsideWindow { .. })
+        ));
+        assert!(verify.verify(ID, MESSAGE, &signatures[2]).is_ok());
+        assert_eq!(verify.low_watermark(ID), Count(4));
+        assert_eq!(verify.last_counter(ID), Some(Count(3)));
+        assert!(verify.verify(ID, MESSAGE, &signatures[7]).is_ok());
+    }
+
+    #[test]
+    fn late_join() {
+        let usig = HmacUsig::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap();
+        let (mut sign, verify) = usig.split();
+        let mut verify = WindowVerifyHalf::new(verify, 4);
+        assert!(verify.add_remote_party(ID, sign.attest().unwrap()));
+        let signatures: Vec<_> = (0..12).map(|_| sign.sign(MESSAGE).unwrap()).collect();
+
+        assert!(verify.pre_validate(ID, &signatures[10]).is_ok());
+        assert!(verify.verify(ID, MESSAGE, &signatures[9]).is_ok());
+        assert_eq!(verify.low_watermark(ID), Count(10));
+        assert!(verify.verify(ID, MESSAGE, &signatures[11]).is_ok());
+        assert!(matches!(
+            verify.verify(ID, MESSAGE, &signatures[8]),
+            Err(UsigError::OutsideWindow { .. })
+        ));
+    }
+}
@@ -0,0 +1,262 @@
+use bincode::Options;
+use serde::{de::DeserializeOwned, Serialize};
+use zeroize::Zeroizing;
+
+#[cfg(feature = "bls")]
+use crate::bls::BlsVerifyingKey;
+use crate::{
+    hmac, noop,
+    registry::{AlgorithmId, AlgorithmIdentifier},
+    signature, siphash, Attestation, Usig, UsigError,
+};
+
+/// The first byte of every value in the wire format
+pub const MAGIC: u8 = 0xA5;
+
+/// Length of the header preceding the payload: magic, algorithm and version
+const HEADER_LENGTH: usize = 4;
+
+/// Signatures and attestations with a canonical, versioned binary encoding
+///
+/// A value is encoded as [MAGIC], the [AlgorithmId] of its backend in big endian, the
+/// version and the bincode serialization of the value as payload. The version is bumped
+/// whenever the layout of the payload changes, so a party receiving a value of another
+/// algorithm or a newer layout fails with [UsigError::UnsupportedWireFormat] instead of
+/// misinterpreting it. Values of backends sharing a type, e.g. the MACs, are told apart
+/// by the algorithm, see [encode_signature].
+pub trait WireFormat: Serialize + DeserializeOwned {
+    const VERSION: u8;
+
+    /// Returned for values that are not in the wire format or whose payload is corrupt
+    const MALFORMED: UsigError;
+}
+
+fn payload_options() -> impl Options {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .reject_trailing_bytes()
+}
+
+fn encode<T: WireFormat>(algorithm: AlgorithmId, value: &T) -> Vec<u8> {
+    let mut bytes = vec![MAGIC];
+    bytes.extend_from_slice(&algorithm.0.to_be_bytes());
+    bytes.push(T::VERSION);
+    payload_options()
+        .serialize_into(&mut bytes, value)
+        .expect("serialization to memory does not fail");
+    bytes
+}
+
+fn decode<T: WireFormat>(expected: AlgorithmId, bytes: &[u8]) -> Result<T, UsigError> {
+    let (&[magic, high, low, version], payload) = bytes
+        .split_first_chunk::<HEADER_LENGTH>()
+        .ok_or(T::MALFORMED)?;
+    if magic != MAGIC {
+        return Err(T::MALFORMED);
+    }
+    let algorithm = AlgorithmId(u16::from_be_bytes([high, low]));
+    if algorithm != expected || version != T::VERSION {
+        return Err(UsigError::UnsupportedWireFormat { algorithm, version });
+    }
+    payload_options()
+        .deserialize(payload)
+        .map_err(|_| T::MALFORMED)
+}
+
+/// Encode a signature of the backend `U` in the wire format
+pub fn encode_signature<U>(signature: &U::Signature) -> Vec<u8>
+where
+    U: Usig + AlgorithmIdentifier,
+    U::Signature: WireFormat,
+{
+    encode(U::ALGORITHM_ID, signature)
+}
+
+/// Decode a signature written by [encode_signature] for the same backend
+///
+/// Fails with [UsigError::InvalidSignature] if the bytes are malformed or followed by
+/// anything else.
+pub fn decode_signature<U>(bytes: &[u8]) -> Result<U::Signature, UsigError>
+where
+    U: Usig + AlgorithmIdentifier,
+    U::Signature: WireFormat,
+{
+    decode(U::ALGORITHM_ID, bytes)
+}
+
+/// Encode an attestation of the backend `U` in the wire format
+pub fn encode_attestation<U>(attestation: &U::Attestation) -> Vec<u8>
+where
+    U: Usig + AlgorithmIdentifier,
+    U::Attestation: WireFormat,
+{
+    encode(U::ALGORITHM_ID, attestation)
+}
+
+/// Decode an attestation written by [encode_attestation] for the same backend
+///
+/// Fails with [UsigError::RemoteAttestationFailed] if the bytes are malformed or followed
+/// by anything else.
+pub fn decode_attestation<U>(bytes: &[u8]) -> Result<U::Attestation, UsigError>
+where
+    U: Usig + AlgorithmIdentifier,
+    U::Attestation: WireFormat,
+{
+    decode(U::ALGORITHM_ID, bytes)
+}
+
+macro_rules! wire_format {
+    ($signature:ty, $attestation:ty) => {
+        impl WireFormat for $signature {
+            const VERSION: u8 = 1;
+            const MALFORMED: UsigError = UsigError::InvalidSignature;
+        }
+
+        /// Version 2 added the binding of the attestation
+        impl WireFormat for $attestation {
+            const VERSION: u8 = 2;
+            const MALFORMED: UsigError = UsigError::RemoteAttestationFailed;
+        }
+    };
+}
+
+impl<L: generic_array::ArrayLength<u8>> WireFormat for hmac::Signature<L> {
+    const VERSION: u8 = 1;
+    const MALFORMED: UsigError = UsigError::InvalidSignature;
+}
+
+/// Version 2 added the binding of the attestation
+impl WireFormat for Attestation<Zeroizing<Box<[u8]>>> {
+    const VERSION: u8 = 2;
+    const MALFORMED: UsigError = UsigError::RemoteAttestationFailed;
+}
+
+impl WireFormat for noop::Signature {
+    const VERSION: u8 = 1;
+    const MALFORMED: UsigError = UsigError::InvalidSignature;
+}
+
+impl WireFormat for () {
+    const VERSION: u8 = 1;
+    const MALFORMED: UsigError = UsigError::RemoteAttestationFailed;
+}
+
+wire_format!(siphash::Signature, Attestation<siphash::VerifyKey>);
+wire_format!(
+    signature::Signature<ed25519_dalek::Signature>,
+    Attestation<ed25519_dalek::VerifyingKey>
+);
+wire_format!(
+    signature::Signature<k256::ecdsa::Signature>,
+    Attestation<k256::ecdsa::VerifyingKey>
+);
+wire_format!(
+    signature::Signature<p256::ecdsa::Signature>,
+    Attestation<p256::ecdsa::VerifyingKey>
+);
+wire_format!(
+    signature::Signature<ed448_goldilocks_plus::Signature>,
+    Attestation<ed448_goldilocks_plus::VerifyingKey>
+);
+#[cfg(feature = "bls")]
+wire_format!(
+    signature::Signature<blst::min_pk::Signature>,
+    Attestation<BlsVerifyingKey>
+);
+
+#[cfg(test)]
+mod tests {
+    use ::hmac::Hmac;
+    use sha2::{Sha256, Sha512};
+    use shared_ids::ReplicaId;
+
+    use super::*;
+    use crate::{
+        cmac::{UsigCmacAes128, UsigCmacAes256},
+        hmac::UsigHmac,
+        signature::{new_ed25519, UsigEd25519},
+        Count, Counter,
+    };
+
+    const ID: ReplicaId = ReplicaId::first();
+    const MESSAGE: &[u8] = b"message";
+
+    type HmacSha256 = UsigHmac<Hmac<Sha256>>;
+
+    #[test]
+    fn roundtrip() {
+        let mut usig = new_ed25519();
+        let bytes = encode_attestation::<UsigEd25519>(&usig.attest().unwrap());
+        assert_eq!(bytes[..HEADER_LENGTH], [MAGIC, 0x02, 0x00, 2]);
+        let attestation = decode_attestation::<UsigEd25519>(&bytes).unwrap();
+        usig.try_add_remote_party(ID, attestation).unwrap();
+        let bytes = encode_signature::<UsigEd25519>(&usig.sign(MESSAGE).unwrap());
+        assert_eq!(bytes[..HEADER_LENGTH], [MAGIC, 0x02, 0x00, 1]);
+        let signature = decode_signature::<UsigEd25519>(&bytes).unwrap();
+        assert_eq!(signature.counter(), Count(0));
+        assert!(usig.verify(ID, MESSAGE, &signature).is_ok());
+    }
+
+    #[test]
+    fn rejects_foreign() {
+        let mut usig = HmacSha256::try_new(Box::new(rand::random::<[u8; 16]>())).unwrap();
+        let mut bytes = encode_signature::<HmacSha256>(&usig.sign(MESSAGE).unwrap());
+        assert!(matches!(
+            decode_signature::<UsigEd25519>(&bytes),
+            Err(UsigError::UnsupportedWireFormat {
+                algorithm: AlgorithmId::HMAC_SHA256,
+                version: 1
+            })
+        ));
+
+        bytes[3] = 2;
+        assert!(matches!(
+            decode_signature::<HmacSha256>(&bytes),
+            Err(UsigError::UnsupportedWireFormat {
+                algorithm: AlgorithmId::HMAC_SHA256,
+                version: 2
+            })
+        ));
+        bytes[0] = 0;
+        assert!(matches!(
+            decode_signature::<HmacSha256>(&bytes),
+            Err(UsigError::InvalidSignature)
+        ));
+        assert!(matches!(
+            decode_attestation::<HmacSha256>(&[MAGIC, 1, 0]),
+            Err(UsigError::RemoteAttestationFailed)
+        ));
+    }
+
+    #[test]
+    fn distinct_macs() {
+        let key: Box<[u8]> = Box::new(rand::random::<[u8; 16]>());
+        let mut usig = UsigCmacAes128::try_new(key).unwrap();
+        let attestation = encode_attestation::<UsigCmacAes128>(&usig.attest().unwrap());
+        assert!(decode_attestation::<UsigCmacAes128>(&attestation).is_ok());
+        for result in [
+            decode_attestation::<UsigCmacAes256>(&attestation),
+            decode_attestation::<HmacSha256>(&attestation),
+            decode_attestation::<UsigHmac<Hmac<Sha512>>>(&attestation),
+        ] {
+            assert!(matches!(
+                result,
+                Err(UsigError::UnsupportedWireFormat {
+                    algorithm: AlgorithmId::CMAC_AES128,
+                    ..
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn rejects_trailing_bytes() {
+        let mut usig = new_ed25519();
+        let mut bytes = encode_signature::<UsigEd25519>(&usig.sign(MESSAGE).unwrap());
+        bytes.push(0);
+        assert!(matches!(
+            decode_signature::<UsigEd25519>(&bytes),
+            Err(UsigError::InvalidSignature)
+        ));
+    }
+}